@@ -1,4 +1,7 @@
+use std::fmt::Write;
+use std::path::Path;
 use std::process::Command;
+use std::{env, fs};
 
 fn main() {
     let hash = Command::new("git")
@@ -22,4 +25,35 @@ fn main() {
     } else {
         println!("cargo:rustc-env=VERSION={}", env!("CARGO_PKG_VERSION"));
     }
+
+    generate_block_docs();
+}
+
+/// Collects the doc comments of all blocks for `--explain`
+fn generate_block_docs() {
+    let mut docs: Vec<(String, String)> = fs::read_dir("src/blocks")
+        .unwrap()
+        .map(Result::unwrap)
+        .filter(|entry| entry.file_type().unwrap().is_file())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = path.file_stem()?.to_str()?.to_owned();
+            let doc: String = fs::read_to_string(&path)
+                .unwrap()
+                .lines()
+                .map_while(|line| line.strip_prefix("//!"))
+                .map(|line| format!("{}\n", line.strip_prefix(' ').unwrap_or(line)))
+                .collect();
+            (!doc.is_empty()).then_some((name, doc))
+        })
+        .collect();
+    docs.sort_unstable();
+
+    let mut out = String::from("&[\n");
+    for (name, doc) in docs {
+        writeln!(out, "    ({name:?}, {doc:?}),").unwrap();
+    }
+    out.push(']');
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("block_docs.rs");
+    fs::write(out_path, out).unwrap();
 }
@@ -6,12 +6,25 @@
 //! ----|-------------|----------
 //! `block` | Name of the i3status-rs block you want to use. See [modules](#modules) below for valid block names. | -
 //! `signal` | Signal value that causes an update for this block with `0` corresponding to `-SIGRTMIN+0` and the largest value being `-SIGRTMAX` | None
+//! `toggle_signal` | Signal value that hides or shows this block. Hidden blocks keep running and retain their state. | None
+//! `hidden` | Whether the block is hidden on start (see `toggle_signal` and the `toggle_visibility` action) | `false`
 //! `if_command` | Only display the block if the supplied command returns 0 on startup. | None
 //! `merge_with_next` | If true this will group the block with the next one, so rendering such as alternating_tint will apply to the whole group | `false`
+//! `priority` | Blocks with lower priority are degraded to narrower `formats` and collapsed first if the bar is wider than `max_width` | `0`
+//! `formats` | A list of formats from the widest to the narrowest, using the same placeholders as `format`, e.g. `[" $icon $title ", " $icon "]`. The first one replaces the block's main `format`, other formats such as `format_alt` are not affected. If the bar is wider than `max_width`, blocks switch to their next narrower format, starting with the lowest `priority`, before any block is collapsed. | `[]`
+//! `urgent` | Mark the block as urgent in the critical state (`true`), or in a list of states such as `["warning", "critical"]`. Bars draw urgent blocks in their urgent colors. | `false`
+//! `short_format` | A format for the short text of the block, using the same placeholders as `format`. It overrides the short text of the block's main `format`, if any. `i3bar` and `swaybar` show the short texts of blocks when the bar runs out of space. | None
+//! `scroll_interval` | Overrides global `scroll_interval` | None
+//! `scroll_acceleration` | Overrides global `scroll_acceleration` | None
 //! `icons_format` | Overrides global `icons_format` | None
 //! `error_format` | Overrides global `error_format` | None
 //! `error_fullscreen_format` | Overrides global `error_fullscreen_format` | None
 //! `error_interval` | How long to wait until restarting the block after an error occurred. | `5`
+//! `restart_policy` | Whether to restart the block automatically after an error occurred: `"on-failure"` or `"never"`. Blocks can always be restarted by clicking on the error or by signals. | `"on-failure"`
+//! `max_restarts` | How many times in a row to restart the block automatically. Failures count as consecutive if the block fails within a minute of being restarted. | None (unlimited)
+//! `error_backoff` | The factor by which `error_interval` is multiplied after each consecutive failure (at least `1`). The interval never exceeds an hour. | `1`
+//! `watchdog_secs` | If the block does not update for this many seconds, it is considered hung and an error is displayed until its next update. Only useful for blocks which update periodically. | None
+//! `watchdog_restart` | Restart a hung block (see `watchdog_secs`) as if it failed, instead of waiting for its next update | `false`
 //! `[block.theme_overrides]` | Same as the top-level config option, but for this block only. Refer to `Themes and Icons` below. | None
 //! `[block.icons_overrides]` | Same as the top-level config option, but for this block only. Refer to `Themes and Icons` below. | None
 //! `[[block.click]]` | Set or override click action for the block. See below for details. | Block default / None
@@ -22,23 +35,32 @@
 //! ----|-------------|----------
 //! `button` | `left`, `middle`, `right`, `up`/`wheel_up`, `down`/`wheel_down`, `wheel_left`, `wheel_right`, `forward`, `back` or [`double_left`](MouseButton). | -
 //! `widget` | To which part of the block this entry applies (accepts regex) | `"block"`
+//! `modifiers` | The modifier keys which must be held, e.g. `["Shift"]`: `Shift`, `Control`/`Ctrl`, `Mod1`/`Alt`, `Mod3`, `Mod4`/`Super` or `Mod5`. Clicks with modifiers which no entry matches are handled by the entry without modifiers. Note that `swaybar` does not report modifiers. | `[]`
 //! `cmd` | Command to run when the mouse button event is detected. | None
-//! `action` | Which block action to trigger | None
+//! `action` | Which block action to trigger. The built-in `toggle_visibility` action hides the block, and `pause_updates` suspends the block until the action is used again: its interval timers and update requests wait, so it stops polling, and it keeps showing what it showed when it was paused (marked with the `paused` icon and in the idle colors). | None
 //! `sync` | Whether to wait for command to exit or not. | `false`
 //! `update` | Whether to update the block on click. | `false`
+//! `output_duration` | If set, the output of `cmd` is shown for this many seconds as the `$cmd_output` placeholder, which can be used in the `format` of any block, e.g. <code>\" {$cmd_output\|$icon $volume} \"</code>. The command runs in the background, `sync` has no effect. | None
+//!
+//! There is no option for long presses (such as a `press_duration_ms`): `i3bar` and `swaybar` only
+//! send click events when a button is pressed, never when it is released, so holding a button
+//! cannot be told apart from a short click. To trigger a distinct action, e.g. one which should be
+//! hard to trigger by accident, use `double_left` or `modifiers` instead.
 
 mod prelude;
 
 use futures::future::FutureExt;
 use futures::stream::FuturesUnordered;
 use serde::de::{self, Deserialize};
-use tokio::sync::{mpsc, Notify};
+use tokio::sync::{mpsc, watch, Notify};
 
 use std::borrow::Cow;
+use std::future::Future;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crate::click::MouseButton;
+use crate::config::RestartPolicy;
 use crate::errors::*;
 use crate::widget::Widget;
 use crate::{BoxedFuture, Request, RequestCmd};
@@ -47,6 +69,7 @@ macro_rules! define_blocks {
     {
         $(
             $(#[cfg(feature = $feat: literal)])?
+            $(#[cfg(target_os = $os: literal)])?
             $(#[deprecated($($dep_k: ident = $dep_v: literal),+)])?
             $block: ident $(,)?
         )*
@@ -54,6 +77,7 @@ macro_rules! define_blocks {
         $(
             $(#[cfg(feature = $feat)])?
             $(#[cfg_attr(docsrs, doc(cfg(feature = $feat)))])?
+            $(#[cfg(target_os = $os)])?
             $(#[deprecated($($dep_k = $dep_v),+)])?
             pub mod $block;
         )*
@@ -62,6 +86,7 @@ macro_rules! define_blocks {
         pub enum BlockConfig {
             $(
                 $(#[cfg(feature = $feat)])?
+                $(#[cfg(target_os = $os)])?
                 #[allow(non_camel_case_types)]
                 #[allow(deprecated)]
                 $block($block::Config),
@@ -74,6 +99,7 @@ macro_rules! define_blocks {
                 match self {
                     $(
                         $(#[cfg(feature = $feat)])?
+                        $(#[cfg(target_os = $os)])?
                         Self::$block { .. } => stringify!($block),
                     )*
                     Self::Err(name, _err) => name,
@@ -84,17 +110,10 @@ macro_rules! define_blocks {
                 match self {
                     $(
                         $(#[cfg(feature = $feat)])?
+                        $(#[cfg(target_os = $os)])?
                         #[allow(deprecated)]
                         Self::$block(config) => futures.push(async move {
-                            while let Err(err) = $block::run(&config, &api).await {
-                                if api.set_error(err).is_err() {
-                                    return;
-                                }
-                                tokio::select! {
-                                    _ = tokio::time::sleep(api.error_interval) => (),
-                                    _ = api.wait_for_update_request() => (),
-                                }
-                            }
+                            api.run_with_restarts(|| $block::run(&config, &api)).await;
                         }.boxed_local()),
                     )*
                     Self::Err(_name, err) => {
@@ -117,10 +136,12 @@ macro_rules! define_blocks {
                 let mut table = toml::Table::deserialize(deserializer)?;
                 let block_name = table.remove("block").ok_or_else(|| D::Error::missing_field("block"))?;
                 let block_name = block_name.as_str().ok_or_else(|| D::Error::custom("block must be a string"))?;
+                crate::config::apply_format_options(&mut table).map_err(D::Error::custom)?;
 
                 match block_name {
                     $(
                         $(#[cfg(feature = $feat)])?
+                        $(#[cfg(target_os = $os)])?
                         #[allow(deprecated)]
                         stringify!($block) => match $block::Config::deserialize(table) {
                             Ok(config) => Ok(BlockConfig::$block(config)),
@@ -128,11 +149,19 @@ macro_rules! define_blocks {
                         }
                         $(
                             #[cfg(not(feature = $feat))]
-                            stringify!($block) => Err(D::Error::custom(format!(
+                            stringify!($block) => Ok(BlockConfig::Err(stringify!($block), crate::errors::Error::new(format!(
                                 "block {} is behind a feature gate '{}' which must be enabled at compile time",
                                 stringify!($block),
                                 $feat,
-                            ))),
+                            )))),
+                        )?
+                        $(
+                            #[cfg(not(target_os = $os))]
+                            stringify!($block) => Ok(BlockConfig::Err(stringify!($block), crate::errors::Error::new(format!(
+                                "block {} is only available on {}",
+                                stringify!($block),
+                                $os,
+                            )))),
                         )?
                     )*
                     other => Err(D::Error::custom(format!("unknown block '{other}'")))
@@ -144,49 +173,89 @@ macro_rules! define_blocks {
 
 define_blocks!(
     amd_gpu,
+    #[cfg(target_os = "linux")]
     backlight,
     battery,
+    #[cfg(target_os = "linux")]
+    binding_mode,
     bluetooth,
     calendar,
     cpu,
     custom,
     custom_dbus,
+    #[cfg(target_os = "linux")]
+    default_app,
+    disk_iostats,
     disk_space,
     docker,
     external_ip,
+    failed_units,
     focused_window,
+    fortune,
+    #[cfg(target_os = "linux")]
+    git,
     github,
+    group,
     hueshift,
+    idle,
+    idle_inhibitor,
+    journal,
     kdeconnect,
+    kubernetes,
+    llm_server,
     load,
     #[cfg(feature = "maildir")]
     maildir,
     menu,
     memory,
+    #[cfg(feature = "pulseaudio")]
+    mic_level,
     music,
+    #[cfg(target_os = "linux")]
     net,
+    #[cfg(feature = "net_top")]
+    net_top,
+    nix,
     notify,
     #[cfg(feature = "notmuch")]
     notmuch,
     nvidia_gpu,
+    openrgb,
     packages,
     pomodoro,
+    power_profile,
+    #[cfg(target_os = "linux")]
     privacy,
+    progress,
+    reclaimable,
+    river_tags,
     rofication,
     service_status,
+    #[cfg(target_os = "linux")]
     scratchpad,
+    #[cfg(feature = "rhai")]
+    script,
+    slurm,
     sound,
     speedtest,
     keyboard_layout,
+    #[cfg(target_os = "linux")]
     taskwarrior,
     temperature,
+    thermald,
     time,
     tea_timer,
     toggle,
     uptime,
+    user_services,
     vpn,
+    #[cfg(feature = "wasm")]
+    #[cfg(target_os = "linux")]
+    wasm,
+    #[cfg(target_os = "linux")]
     watson,
     weather,
+    workspaces,
     xrandr,
 );
 
@@ -201,43 +270,238 @@ pub struct BlockError {
 
 pub type BlockAction = Cow<'static, str>;
 
+/// The documentation of each block, extracted from the doc comments by the build script
+const BLOCK_DOCS: &[(&str, &str)] = include!(concat!(env!("OUT_DIR"), "/block_docs.rs"));
+
+/// Returns the names of all documented blocks
+pub fn block_names() -> impl Iterator<Item = &'static str> {
+    BLOCK_DOCS.iter().map(|(name, _)| *name)
+}
+
+/// Returns the documentation of a block
+pub(crate) fn doc(block: &str) -> Option<&'static str> {
+    BLOCK_DOCS
+        .iter()
+        .find(|(name, _)| *name == block)
+        .map(|(_, doc)| *doc)
+}
+
+/// Returns the actions of a block, as listed in the action table of its documentation, or `None`
+/// if the block accepts any action or does not list its actions
+pub(crate) fn actions(block: &str) -> Option<Vec<&'static str>> {
+    // The actions are passed on to the script or module
+    if matches!(block, "script" | "wasm") {
+        return None;
+    }
+    let mut lines = doc(block)?
+        .lines()
+        .skip_while(|line| !line.starts_with("Action "));
+    lines.next()?;
+    Some(
+        lines
+            .skip(1)
+            .map_while(|line| line.strip_prefix('`')?.split_once('`'))
+            .map(|(name, _)| name)
+            .collect(),
+    )
+}
+
+/// Describes a block for `--explain`: its config keys, placeholders, actions and icons. Examples
+/// are left out and the markup used for the manpage is removed.
+pub fn explain(block: &str) -> Option<String> {
+    let doc = doc(block)?;
+    let mut out = String::new();
+    let mut in_examples = false;
+    for line in doc.lines() {
+        if let Some(heading) = line.strip_prefix("# ") {
+            in_examples = heading.starts_with("Example");
+        }
+        if in_examples {
+            continue;
+        }
+        let line = line
+            .replace("<code>\\\"", "\"")
+            .replace("\\\"</code>", "\"")
+            .replace("\\|", "|");
+        out.push_str(&line);
+        out.push('\n');
+    }
+    Some(out.trim_end().to_owned())
+}
+
+impl BlockConfig {
+    /// Like [`BlockConfig::spawn`], but the block shows fake values instead of running (see
+    /// `--demo`). Groups and blocks with configuration errors are spawned as usual.
+    pub(crate) fn spawn_demo(
+        self,
+        format: Option<toml::Value>,
+        api: CommonApi,
+        futures: &mut FuturesUnordered<BoxedFuture<()>>,
+    ) {
+        if matches!(self, Self::group(_) | Self::Err(..)) {
+            return self.spawn(api, futures);
+        }
+        let name = self.name();
+        futures.push(
+            async move {
+                api.run_with_restarts(|| crate::demo::run(name, format.as_ref(), &api))
+                    .await;
+            }
+            .boxed_local(),
+        );
+    }
+}
+
+/// A block which ran for this long before failing is considered to have recovered in between
+const RECOVERY_TIME: Duration = Duration::from_secs(60);
+/// The upper bound of the delay before restarting a block
+const MAX_ERROR_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// When to restart a failed block
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Restart {
+    pub policy: RestartPolicy,
+    pub max_restarts: Option<u32>,
+    pub backoff: f64,
+}
+
+impl Restart {
+    /// How long to wait before restarting a block which has failed `failures` times in a row, or
+    /// `None` if it should not be restarted automatically. `interval` is the delay after the first
+    /// failure.
+    fn delay(&self, interval: Duration, failures: u32) -> Option<Duration> {
+        if self.policy == RestartPolicy::Never || self.max_restarts.is_some_and(|m| failures > m) {
+            return None;
+        }
+        let factor = self
+            .backoff
+            .max(1.0)
+            .powf(failures.saturating_sub(1) as f64);
+        let secs = interval.as_secs_f64() * factor;
+        Some(Duration::from_secs_f64(
+            secs.min(MAX_ERROR_INTERVAL.as_secs_f64()),
+        ))
+    }
+}
+
 #[derive(Clone)]
 pub struct CommonApi {
     pub(crate) id: usize,
     pub(crate) update_request: Arc<Notify>,
     pub(crate) request_sender: mpsc::UnboundedSender<Request>,
     pub(crate) error_interval: Duration,
+    pub(crate) restart: Restart,
+    /// Notified whenever the block updates its widget
+    pub(crate) updated: Arc<Notify>,
+    pub(crate) watchdog: Option<Duration>,
+    pub(crate) watchdog_restart: bool,
+    /// Whether the block is paused by the `pause_updates` action
+    pub(crate) paused: watch::Receiver<bool>,
+}
+
+tokio::task_local! {
+    /// Whether the running block is paused, for the timers of the block
+    static PAUSED: watch::Receiver<bool>;
+}
+
+/// Waits until the running block is resumed, if it is paused by the `pause_updates` action.
+/// Returns whether it was paused.
+pub(crate) async fn wait_while_paused() -> bool {
+    let Ok(mut paused) = PAUSED.try_with(Clone::clone) else {
+        return false;
+    };
+    let was_paused = *paused.borrow_and_update();
+    let _ = paused.wait_for(|paused| !paused).await;
+    was_paused
 }
 
 impl CommonApi {
-    /// Sends the widget to be displayed.
-    pub fn set_widget(&self, widget: Widget) -> Result<()> {
+    /// Runs a block until it finishes, restarting it after errors according to its restart policy.
+    async fn run_with_restarts<F>(&self, mut run: impl FnMut() -> F)
+    where
+        F: Future<Output = Result<()>>,
+    {
+        let mut failures = 0;
+        loop {
+            let started = Instant::now();
+            let run = PAUSED.scope(self.paused.clone(), run());
+            tokio::pin!(run);
+            let result = loop {
+                tokio::select! {
+                    result = &mut run => break result,
+                    () = self.watchdog() => {
+                        let error = Error::new("Block is not responding");
+                        if self.watchdog_restart {
+                            break Err(error);
+                        }
+                        // Keep the block running, its next update replaces the error
+                        if self.send(RequestCmd::SetError(error)).is_err() {
+                            return;
+                        }
+                    }
+                }
+            };
+            let Err(err) = result else {
+                return;
+            };
+            if self.send(RequestCmd::SetError(err)).is_err() {
+                return;
+            }
+            if started.elapsed() >= RECOVERY_TIME {
+                failures = 0;
+            }
+            failures += 1;
+            match self.restart.delay(self.error_interval, failures) {
+                Some(delay) => tokio::select! {
+                    _ = tokio::time::sleep(delay) => (),
+                    _ = self.wait_for_update_request() => failures = 0,
+                },
+                None => {
+                    self.wait_for_update_request().await;
+                    failures = 0;
+                }
+            }
+        }
+    }
+
+    /// Completes when the block has not updated its widget for `watchdog`. Never completes if
+    /// the watchdog is disabled. Paused blocks are not expected to update.
+    async fn watchdog(&self) {
+        let Some(timeout) = self.watchdog else {
+            return std::future::pending().await;
+        };
+        while tokio::time::timeout(timeout, self.updated.notified())
+            .await
+            .is_ok()
+            || *self.paused.borrow()
+        {}
+    }
+
+    fn send(&self, cmd: RequestCmd) -> Result<()> {
         self.request_sender
             .send(Request {
                 block_id: self.id,
-                cmd: RequestCmd::SetWidget(widget),
+                cmd,
             })
             .error("Failed to send Request")
     }
 
+    /// Sends the widget to be displayed.
+    pub fn set_widget(&self, widget: Widget) -> Result<()> {
+        self.updated.notify_one();
+        self.send(RequestCmd::SetWidget(widget))
+    }
+
     /// Hides the block. Send new widget to make it visible again.
     pub fn hide(&self) -> Result<()> {
-        self.request_sender
-            .send(Request {
-                block_id: self.id,
-                cmd: RequestCmd::UnsetWidget,
-            })
-            .error("Failed to send Request")
+        self.updated.notify_one();
+        self.send(RequestCmd::UnsetWidget)
     }
 
     /// Sends the error to be displayed.
     pub fn set_error(&self, error: Error) -> Result<()> {
-        self.request_sender
-            .send(Request {
-                block_id: self.id,
-                cmd: RequestCmd::SetError(error),
-            })
-            .error("Failed to send Request")
+        self.updated.notify_one();
+        self.send(RequestCmd::SetError(error))
     }
 
     pub fn set_default_actions(
@@ -263,7 +527,55 @@ impl CommonApi {
         Ok(rx)
     }
 
+    /// Shows or hides the blocks of this group. Only meaningful for the `group` block.
+    pub fn set_expanded(&self, expanded: bool) -> Result<()> {
+        self.request_sender
+            .send(Request {
+                block_id: self.id,
+                cmd: RequestCmd::SetExpanded(expanded),
+            })
+            .error("Failed to send Request")
+    }
+
+    /// The location of the computer from GeoClue
+    pub async fn get_location(&self) -> Result<crate::geoclue::Location> {
+        crate::geoclue::location().await
+    }
+
+    /// Waits for a request to update the block, e.g. a click. Requests of a paused block are held
+    /// back until it is resumed.
     pub async fn wait_for_update_request(&self) {
         self.update_request.notified().await;
+        let _ = self.paused.clone().wait_for(|paused| !paused).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restart_delay() {
+        let restart = Restart {
+            policy: RestartPolicy::OnFailure,
+            max_restarts: Some(3),
+            backoff: 2.0,
+        };
+        let interval = Duration::from_secs(5);
+        assert_eq!(restart.delay(interval, 1), Some(Duration::from_secs(5)));
+        assert_eq!(restart.delay(interval, 3), Some(Duration::from_secs(20)));
+        assert_eq!(restart.delay(interval, 4), None);
+
+        let restart = Restart {
+            max_restarts: None,
+            ..restart
+        };
+        assert_eq!(restart.delay(interval, 100), Some(MAX_ERROR_INTERVAL));
+
+        let restart = Restart {
+            policy: RestartPolicy::Never,
+            ..restart
+        };
+        assert_eq!(restart.delay(interval, 1), None);
     }
 }
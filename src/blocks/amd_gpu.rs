@@ -94,7 +94,7 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
 
         loop {
             select! {
-                _ = sleep(config.interval.0) => break,
+                _ = config.interval.sleep() => break,
                 _ = api.wait_for_update_request() => break,
                 Some(action) = actions.recv() => match action.as_ref() {
                     "toggle_format" => {
@@ -24,17 +24,39 @@
 //! `invert_icons` | Invert icons' ordering, useful if you have colorful emoji | `false`
 //! `ddcci_sleep_multiplier` | [See ddcutil documentation](https://www.ddcutil.com/performance_options/#option-sleep-multiplier) | `1.0`
 //! `ddcci_max_tries_write_read` | The maximum number of times to attempt writing to  or reading from a ddcci monitor | `10`
+//! `automation` | Adjust the brightness automatically during the day. See [below](#automation). | None
 //!
 //! Placeholder  | Value                                     | Type   | Unit
 //! -------------|-------------------------------------------|--------|---------------
 //! `icon`       | Icon based on backlight's state           | Icon   | -
 //! `brightness` | Current brightness                        | Number | %
+//! `target`     | Brightness set by the automation. Absent if the automation is not enabled or is overridden. | Number | %
 //!
-//! Action            | Default button
-//! ------------------|---------------
-//! `cycle`           | Left
-//! `brightness_up`   | Wheel Up
-//! `brightness_down` | Wheel Down
+//! Action              | Default button
+//! --------------------|---------------
+//! `cycle`             | Left
+//! `brightness_up`     | Wheel Up
+//! `brightness_down`   | Wheel Down
+//! `toggle_automation` | Right
+//!
+//! # Automation
+//!
+//! With an `[block.automation]` table, the block sets the brightness to `day` during the day and to
+//! `night` during the night, with linear transitions centered on sunrise and sunset. Sunrise and
+//! sunset are either calculated from `coordinates` (or the location from GeoClue with
+//! `autolocate`) or set to fixed times with `sunrise` and `sunset`. The `toggle_automation` action suspends the automation (e.g. to set the brightness
+//! manually) and resumes it.
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `coordinates` | GPS latitude longitude coordinates as a tuple, example: `["39.2362","9.3317"]` | Required if `sunrise` and `sunset` are not set
+//! `autolocate` | Use the location from [GeoClue](https://gitlab.freedesktop.org/geoclue/geoclue) instead of `coordinates`. GeoClue has to allow i3status-rs to access the location, e.g. with an entry `[i3status-rs]` with `allowed=true`, `system=false` and `users=` in `/etc/geoclue/geoclue.conf`. The location is queried once when the block starts. If that fails, `coordinates` is used instead. | `false`
+//! `sunrise` | Fixed time of sunrise in `HH:MM` format | None
+//! `sunset` | Fixed time of sunset in `HH:MM` format | None
+//! `day` | Brightness during the day, in percent | `100`
+//! `night` | Brightness during the night, in percent | `30`
+//! `transition` | Duration of the transitions, in seconds | `3600`
+//! `interval` | How often to update the brightness, in seconds | `60`
 //!
 //! # Example
 //!
@@ -44,6 +66,17 @@
 //! device = "intel_backlight"
 //! ```
 //!
+//! Dim the screen after sunset, showing the automated target brightness:
+//!
+//! ```toml
+//! [[block]]
+//! block = "backlight"
+//! format = " $icon $brightness{ ($target)|} "
+//! [block.automation]
+//! coordinates = ["52.52", "13.40"]
+//! night = 20
+//! ```
+//!
 //! Hide missing backlight:
 //!
 //! ```toml
@@ -81,8 +114,12 @@
 use std::sync::Arc;
 
 use calibright::{CalibrightBuilder, CalibrightConfig, CalibrightError, DeviceConfig};
+use chrono::{Local, NaiveTime, Timelike};
 
 use super::prelude::*;
+use super::weather::calculate_sunrise_sunset;
+
+make_log_macro!(warn, "backlight");
 
 #[derive(Deserialize, Debug, SmartDefault)]
 #[serde(deny_unknown_fields, default)]
@@ -102,6 +139,79 @@ pub struct Config {
     pub root_scaling: Option<f64>,
     pub ddcci_sleep_multiplier: Option<f64>,
     pub ddcci_max_tries_write_read: Option<u8>,
+    pub automation: Option<Automation>,
+}
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Automation {
+    pub coordinates: Option<(String, String)>,
+    pub autolocate: bool,
+    pub sunrise: Option<String>,
+    pub sunset: Option<String>,
+    #[default(100.0)]
+    pub day: f64,
+    #[default(30.0)]
+    pub night: f64,
+    #[default(3600.into())]
+    pub transition: Seconds,
+    #[default(60.into())]
+    pub interval: Seconds,
+}
+
+impl Automation {
+    /// The coordinates to calculate sunrise and sunset from, either from GeoClue or `coordinates`
+    async fn coordinates(&self, api: &CommonApi) -> Result<Option<(f64, f64)>> {
+        if self.autolocate {
+            match api.get_location().await {
+                Ok(location) => return Ok(Some((location.latitude, location.longitude))),
+                Err(err) => {
+                    warn!("Failed to get the location, using 'coordinates' instead: {err}");
+                }
+            }
+        }
+        match &self.coordinates {
+            Some((lat, lon)) => Ok(Some((
+                lat.parse().error("Invalid latitude")?,
+                lon.parse().error("Invalid longitude")?,
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// The local times of sunrise and sunset
+    fn sun_times(&self, coordinates: Option<(f64, f64)>) -> Result<(NaiveTime, NaiveTime)> {
+        let parse_time = |time: &str| {
+            NaiveTime::parse_from_str(time, "%H:%M")
+                .or_error(|| format!("Invalid time '{time}', expected HH:MM"))
+        };
+        match (&self.sunrise, &self.sunset, coordinates) {
+            (Some(sunrise), Some(sunset), _) => Ok((parse_time(sunrise)?, parse_time(sunset)?)),
+            (None, None, Some((lat, lon))) => {
+                let (sunrise, sunset) = calculate_sunrise_sunset(lat, lon, None)?;
+                Ok((
+                    sunrise.with_timezone(&Local).time(),
+                    sunset.with_timezone(&Local).time(),
+                ))
+            }
+            _ => Err(Error::new(
+                "automation requires either 'coordinates', 'autolocate' or both 'sunrise' and 'sunset'",
+            )),
+        }
+    }
+
+    /// The brightness (in the range 0 to 1) the automation should set right now
+    fn target(&self, coordinates: Option<(f64, f64)>) -> Result<f64> {
+        let (sunrise, sunset) = self.sun_times(coordinates)?;
+        Ok(scheduled_brightness(
+            Local::now().time(),
+            sunrise,
+            sunset,
+            self.transition.0.as_secs_f64(),
+            self.day / 100.0,
+            self.night / 100.0,
+        ))
+    }
 }
 
 pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
@@ -110,6 +220,7 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         (MouseButton::Left, None, "cycle"),
         (MouseButton::WheelUp, None, "brightness_up"),
         (MouseButton::WheelDown, None, "brightness_down"),
+        (MouseButton::Right, None, "toggle_automation"),
     ])?;
 
     let format = config.format.with_default(" $icon $brightness ")?;
@@ -165,6 +276,15 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         .map_err(|e| block_error = Some(e))
         .unwrap_or_default();
 
+    let coordinates = match &config.automation {
+        Some(automation) => automation.coordinates(api).await?,
+        None => None,
+    };
+    let mut automatic = config.automation.is_some();
+    let mut target: Option<f64> = None;
+    let automation_timer = sleep(Duration::ZERO);
+    tokio::pin!(automation_timer);
+
     loop {
         match block_error {
             Some(CalibrightError::NoDevices) => {
@@ -187,7 +307,8 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
                 }
                 widget.set_values(map! {
                     "icon" => Value::icon_progression("backlight", icon_value),
-                    "brightness" => Value::percents((brightness * 100.0).round())
+                    "brightness" => Value::percents((brightness * 100.0).round()),
+                    [if let Some(target) = target] "target" => Value::percents((target * 100.0).round()),
                 });
                 api.set_widget(widget)?;
             }
@@ -205,6 +326,31 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
 
                     break;
                 },
+                _ = &mut automation_timer, if automatic => {
+                    let automation = config.automation.as_ref().unwrap();
+                    automation_timer.as_mut().reset(tokio::time::Instant::now() + automation.interval.0);
+                    let new_target = match automation.target(coordinates) {
+                        Ok(new_target) => new_target.clamp(minimum, maximum),
+                        Err(err) => {
+                            warn!("Suspending the automation: {err}");
+                            automatic = false;
+                            continue;
+                        }
+                    };
+                    target = Some(new_target);
+                    // Avoid needlessly writing to slow (e.g. ddcci) devices
+                    block_error = if (new_target - brightness).abs() >= 0.005 {
+                        brightness = new_target;
+                        calibright.set_brightness(brightness).await.err()
+                    } else {
+                        calibright
+                            .get_brightness()
+                            .await
+                            .map(|new_brightness| {brightness = new_brightness;})
+                            .err()
+                    };
+                    break;
+                }
                 Some(action) = actions.recv() => match action.as_ref() {
                     "cycle" => {
                         if let Some(cycle_brightness) = cycle.next() {
@@ -233,9 +379,106 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
                             .err();
                         break;
                     }
+                    "toggle_automation" if config.automation.is_some() => {
+                        automatic = !automatic;
+                        target = None;
+                        // Apply the target right away when resuming
+                        automation_timer.as_mut().reset(tokio::time::Instant::now());
+                        block_error = calibright
+                            .get_brightness()
+                            .await
+                            .map(|new_brightness| {brightness = new_brightness;})
+                            .err();
+                        break;
+                    }
                     _ => (),
                 }
             }
         }
     }
 }
+
+/// Brightness at time `now`: `day` between sunrise and sunset and `night` otherwise, with linear
+/// transitions of `transition` seconds centered on sunrise and sunset.
+fn scheduled_brightness(
+    now: NaiveTime,
+    sunrise: NaiveTime,
+    sunset: NaiveTime,
+    transition: f64,
+    day: f64,
+    night: f64,
+) -> f64 {
+    const DAY_SECS: f64 = 86_400.0;
+    let secs = |t: NaiveTime| t.num_seconds_from_midnight() as f64;
+    // Signed time from `t` to `now`, wrapping around midnight
+    let since = |t: NaiveTime| {
+        let d = (secs(now) - secs(t)).rem_euclid(DAY_SECS);
+        if d > DAY_SECS / 2.0 {
+            d - DAY_SECS
+        } else {
+            d
+        }
+    };
+
+    let half = transition / 2.0;
+    let since_sunrise = since(sunrise);
+    if since_sunrise.abs() < half {
+        return night + (day - night) * (since_sunrise + half) / transition;
+    }
+    let since_sunset = since(sunset);
+    if since_sunset.abs() < half {
+        return day + (night - day) * (since_sunset + half) / transition;
+    }
+
+    let is_day = if sunrise < sunset {
+        now >= sunrise && now < sunset
+    } else {
+        now >= sunrise || now < sunset
+    };
+    if is_day {
+        day
+    } else {
+        night
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule() {
+        let t = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let b = |now| scheduled_brightness(now, t(7, 0), t(19, 0), 3600.0, 1.0, 0.5);
+        assert_eq!(b(t(3, 0)), 0.5);
+        assert_eq!(b(t(6, 30)), 0.5);
+        assert_eq!(b(t(7, 0)), 0.75);
+        assert_eq!(b(t(12, 0)), 1.0);
+        assert_eq!(b(t(19, 0)), 0.75);
+        assert_eq!(b(t(23, 0)), 0.5);
+
+        // Transition across midnight
+        let b = scheduled_brightness(t(0, 15), t(0, 30), t(12, 0), 3600.0, 1.0, 0.0);
+        assert_eq!(b, 0.25);
+    }
+
+    #[test]
+    fn sun_times() {
+        let t = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let fixed = Automation {
+            sunrise: Some("06:30".into()),
+            sunset: Some("20:15".into()),
+            ..default()
+        };
+        assert_eq!(fixed.sun_times(None).unwrap(), (t(6, 30), t(20, 15)));
+        assert_eq!(
+            fixed.sun_times(Some((52.52, 13.40))).unwrap(),
+            (t(6, 30), t(20, 15))
+        );
+
+        // Without a location, e.g. if GeoClue failed, there is nothing to schedule
+        let located = Automation::default();
+        assert!(located.sun_times(None).is_err());
+        assert!(located.sun_times(Some((52.52, 13.40))).is_ok());
+    }
+}
@@ -1,16 +1,18 @@
 //! Information about the internal power supply
 //!
 //! This block can display the current battery state (Full, Charging or Discharging), percentage
-//! charged and estimate time until (dis)charged for an internal power supply.
+//! charged and estimate time until (dis)charged for an internal power supply, or for a UPS
+//! monitored by apcupsd (`apc_ups` driver) or [Network UPS Tools](https://networkupstools.org/)
+//! (`nut` driver).
 //!
 //! # Configuration
 //!
 //! Key | Values | Default
 //! ----|--------|--------
-//! `device` | sysfs/UPower: The device in `/sys/class/power_supply/` to read from (can also be "DisplayDevice" for UPower, which is a single logical power source representing all physical power sources. This is for example useful if your system has multiple batteries, in which case the DisplayDevice behaves as if you had a single larger battery.). apc_ups: IPv4Address:port or hostname:port | sysfs: the first battery device found in /sys/class/power_supply, with "BATx" or "CMBx" entries taking precedence. apc_ups: "localhost:3551". upower: `DisplayDevice`
-//! `driver` | One of `"sysfs"`, `"apc_ups"`, or `"upower"` | `"sysfs"`
+//! `device` | sysfs/UPower: The device in `/sys/class/power_supply/` to read from (can also be "DisplayDevice" for UPower, which is a single logical power source representing all physical power sources. This is for example useful if your system has multiple batteries, in which case the DisplayDevice behaves as if you had a single larger battery.). apc_ups: IPv4Address:port or hostname:port. nut: `[ups@]host[:port]`, as in `upsc` | sysfs: the first battery device found in /sys/class/power_supply, with "BATx" or "CMBx" entries taking precedence. apc_ups: "localhost:3551". nut: the first UPS of "localhost:3493". upower: `DisplayDevice`
+//! `driver` | One of `"sysfs"` (Linux only), `"apc_ups"`, `"nut"`, `"upower"` or `"termux"` (requires `termux` feature) | `"termux"` if running under Termux, `"sysfs"` on Linux and `"upower"` otherwise
 //! `model` | If present, the contents of `/sys/class/power_supply/.../model_name` must match this value. Typical use is to select by model name on devices that change their path. | N/A
-//! `interval` | Update interval, in seconds. Only relevant for driver = "sysfs" or "apc_ups". | `10`
+//! `interval` | Update interval, in seconds. Only relevant for driver = "sysfs", "apc_ups", "nut" or "termux". The sysfs driver also updates as soon as the kernel reports a change, e.g. when the charger is plugged in. | `10`
 //! `format` | A string to customise the output of this block. See below for available placeholders. | `" $icon $percentage "`
 //! `full_format` | Same as `format` but for when the battery is full | `" $icon "`
 //! `charging_format` | Same as `format` but for when the battery is charging | Links to `format`
@@ -23,6 +25,8 @@
 //! `critical` | Minimum battery level, where state is set to critical | `15`
 //! `full_threshold` | Percentage above which the battery is considered full (`full_format` shown) | `95`
 //! `empty_threshold` | Percentage below which the battery is considered empty | `7.5`
+//! `critical_cmd` | A shell command which is run once when the battery is discharging and reaches the `critical` level. It runs again only after the battery was charged or rose above `critical`. | None
+//! `full_cmd` | A shell command which is run once when the battery becomes full. It runs again only after the battery dropped below `full_threshold`. | None
 //!
 //! Placeholder  | Value                                                                   | Type              | Unit
 //! -------------|-------------------------------------------------------------------------|-------------------|-----
@@ -31,6 +35,8 @@
 //! `time_remaining`  | Time remaining until (dis)charge is complete. Presented only if battery's status is (dis)charging. | Duration | -
 //! `time`       | Time remaining until (dis)charge is complete. Presented only if battery's status is (dis)charging. | String *DEPRECATED* | -
 //! `power`      | Power consumption by the battery or from the power supply when charging | String or Float   | Watts
+//! `load`       | The load of a UPS. Only for `apc_ups` and `nut`.                         | Number | Percents
+//! `ups_status` | The status of a UPS as reported by its daemon, e.g. `OL CHRG` for `nut` or `ONLINE` for `apc_ups`. Only for `apc_ups` and `nut`. | Text | -
 //!
 //! `time` has been deprecated in favor of `time_remaining`.
 //!
@@ -52,6 +58,29 @@
 //! driver = "upower"
 //! ```
 //!
+//! Suspend at 3% and notify when the battery is full:
+//!
+//! ```toml
+//! [[block]]
+//! block = "battery"
+//! critical = 3
+//! critical_cmd = "systemctl suspend"
+//! full_cmd = "notify-send 'Battery full' 'You can unplug the charger'"
+//! ```
+//!
+//! Show the charge and the load of a UPS monitored by Network UPS Tools, and the remaining time
+//! while it runs on battery:
+//!
+//! ```toml
+//! [[block]]
+//! block = "battery"
+//! driver = "nut"
+//! device = "myups@localhost"
+//! format = " UPS $percentage {$load |}{$power $time_remaining.dur(hms:true, min_unit:m) |}"
+//! full_format = " UPS $percentage "
+//! not_charging_format = " UPS $percentage "
+//! ```
+//!
 //! Hide missing battery:
 //!
 //! ```toml
@@ -70,17 +99,28 @@ use std::convert::Infallible;
 use std::str::FromStr;
 
 use super::prelude::*;
+use crate::subprocess::spawn_shell;
 
 mod apc_ups;
+mod nut;
+#[cfg(target_os = "linux")]
 mod sysfs;
+#[cfg(feature = "termux")]
+mod termux;
 mod upower;
 
 // make_log_macro!(debug, "battery");
+make_log_macro!(warn, "battery");
+
+/// How far (in percent) the battery has to move back from a threshold before its command can run
+/// again, so that a fluctuating reading does not run it repeatedly
+const HYSTERESIS: f64 = 1.0;
 
 #[derive(Deserialize, Debug, SmartDefault)]
 #[serde(deny_unknown_fields, default)]
 pub struct Config {
     pub device: Option<String>,
+    #[default(BatteryDriver::detect())]
     pub driver: BatteryDriver,
     pub model: Option<String>,
     #[default(10.into())]
@@ -103,15 +143,33 @@ pub struct Config {
     pub full_threshold: f64,
     #[default(7.5)]
     pub empty_threshold: f64,
+    pub critical_cmd: Option<String>,
+    pub full_cmd: Option<String>,
 }
 
-#[derive(Deserialize, Debug, SmartDefault)]
+#[derive(Deserialize, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum BatteryDriver {
-    #[default]
+    #[cfg(target_os = "linux")]
     Sysfs,
     ApcUps,
+    Nut,
     Upower,
+    #[cfg(feature = "termux")]
+    Termux,
+}
+
+impl BatteryDriver {
+    fn detect() -> Self {
+        #[cfg(feature = "termux")]
+        if crate::termux::is_termux() {
+            return Self::Termux;
+        }
+        #[cfg(target_os = "linux")]
+        return Self::Sysfs;
+        #[cfg(not(target_os = "linux"))]
+        Self::Upower
+    }
 }
 
 pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
@@ -124,17 +182,25 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
 
     let dev_name = DeviceName::new(config.device.clone())?;
     let mut device: Box<dyn BatteryDevice + Send + Sync> = match config.driver {
+        #[cfg(target_os = "linux")]
         BatteryDriver::Sysfs => Box::new(sysfs::Device::new(
             dev_name,
             config.model.clone(),
             config.interval,
         )),
         BatteryDriver::ApcUps => Box::new(apc_ups::Device::new(dev_name, config.interval).await?),
+        BatteryDriver::Nut => Box::new(nut::Device::new(dev_name, config.interval)),
         BatteryDriver::Upower => {
             Box::new(upower::Device::new(dev_name, config.model.clone()).await?)
         }
+        #[cfg(feature = "termux")]
+        BatteryDriver::Termux => Box::new(termux::Device::new(config.interval)),
     };
 
+    let mut critical_hook = ThresholdHook::new("critical_cmd", config.critical_cmd.as_deref());
+    let mut full_hook = ThresholdHook::new("full_cmd", config.full_cmd.as_deref());
+    let mut first_update = true;
+
     loop {
         let mut info = device.get_info().await?;
 
@@ -146,6 +212,22 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
             {
                 info.status = BatteryStatus::Empty;
             }
+
+            let full = info.status == BatteryStatus::Full;
+            if first_update {
+                // Don't notify about a battery which was already full on startup
+                full_hook.reached = full;
+                first_update = false;
+            }
+            critical_hook.update(
+                info.status != BatteryStatus::Charging && info.capacity <= config.critical,
+                info.status == BatteryStatus::Charging
+                    || info.capacity > config.critical + HYSTERESIS,
+            );
+            full_hook.update(
+                full,
+                !full && info.capacity < config.full_threshold - HYSTERESIS,
+            );
         }
 
         match info {
@@ -165,7 +247,11 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
                 );
 
                 info.power
-                    .map(|p| values.insert("power".into(), Value::watts(p)));
+                    .map(|p| values.insert("power", Value::watts(p)));
+                info.load
+                    .map(|l| values.insert("load", Value::percents(l)));
+                info.ups_status
+                    .map(|s| values.insert("ups_status", Value::text(s)));
                 info.time_remaining.inspect(|&t| {
                     map! { @extend values
                         "time" => Value::text(
@@ -210,7 +296,7 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
                 };
 
                 values.insert(
-                    "icon".into(),
+                    "icon",
                     Value::icon_progression(icon_name, icon_value),
                 );
 
@@ -234,6 +320,36 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
     }
 }
 
+/// A command which runs once when a threshold is reached, and again only after it was left
+struct ThresholdHook<'a> {
+    name: &'static str,
+    cmd: Option<&'a str>,
+    reached: bool,
+}
+
+impl<'a> ThresholdHook<'a> {
+    fn new(name: &'static str, cmd: Option<&'a str>) -> Self {
+        Self {
+            name,
+            cmd,
+            reached: false,
+        }
+    }
+
+    fn update(&mut self, reached: bool, left: bool) {
+        if reached && !self.reached {
+            self.reached = true;
+            if let Some(cmd) = self.cmd {
+                if let Err(err) = spawn_shell(cmd) {
+                    warn!("Failed to run {}: {err}", self.name);
+                }
+            }
+        } else if left {
+            self.reached = false;
+        }
+    }
+}
+
 #[async_trait]
 trait BatteryDevice {
     async fn get_info(&mut self) -> Result<Option<BatteryInfo>>;
@@ -270,7 +386,7 @@ impl DeviceName {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 struct BatteryInfo {
     /// Current status, e.g. "charging", "discharging", etc.
     status: BatteryStatus,
@@ -280,6 +396,10 @@ struct BatteryInfo {
     power: Option<f64>,
     /// Time in seconds
     time_remaining: Option<f64>,
+    /// The load of a UPS in percents
+    load: Option<f64>,
+    /// The status of a UPS as reported by its daemon, e.g. `OL CHRG`
+    ups_status: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, SmartDefault)]
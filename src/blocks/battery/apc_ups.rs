@@ -1,6 +1,5 @@
 use std::str::FromStr;
 use tokio::net::TcpStream;
-use tokio::time::Interval;
 
 use super::{BatteryDevice, BatteryInfo, BatteryStatus, DeviceName};
 use crate::blocks::prelude::*;
@@ -92,7 +91,7 @@ impl ApcConnection {
 
 pub(super) struct Device {
     addr: String,
-    interval: Interval,
+    interval: Timer,
 }
 
 impl Device {
@@ -162,15 +161,12 @@ impl BatteryDevice for Device {
             BatteryStatus::Unknown
         };
 
+        let load = status_data.get_property::<f64>("LOADPCT", "Percent").ok();
         let power = status_data
             .get_property::<f64>("NOMPOWER", "Watts")
             .ok()
-            .and_then(|nominal_power| {
-                status_data
-                    .get_property::<f64>("LOADPCT", "Percent")
-                    .ok()
-                    .map(|load_percent| nominal_power * load_percent / 100.0)
-            });
+            .zip(load)
+            .map(|(nominal_power, load_percent)| nominal_power * load_percent / 100.0);
 
         let time_remaining = status_data
             .get_property::<f64>("TIMELEFT", "Minutes")
@@ -182,6 +178,8 @@ impl BatteryDevice for Device {
             capacity,
             power,
             time_remaining,
+            load,
+            ups_status: Some(status_str.to_string()),
         }))
     }
 
@@ -0,0 +1,239 @@
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+
+use super::{BatteryDevice, BatteryInfo, BatteryStatus, DeviceName};
+use crate::blocks::prelude::*;
+
+make_log_macro!(debug, "battery[nut]");
+
+/// A connection to upsd, the server of [Network UPS Tools](https://networkupstools.org/docs/developer-guide.chunked/net-protocol.html)
+struct NutConnection {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl NutConnection {
+    async fn connect(addr: &str) -> Result<Self> {
+        let (reader, writer) = TcpStream::connect(addr)
+            .await
+            .error("Failed to connect to socket")?
+            .into_split();
+        Ok(Self {
+            lines: BufReader::new(reader).lines(),
+            writer,
+        })
+    }
+
+    /// Sends `LIST <what>` and returns the lines between `BEGIN LIST` and `END LIST`
+    async fn list(&mut self, what: &str) -> Result<Vec<String>> {
+        self.writer
+            .write_all(format!("LIST {what}\n").as_bytes())
+            .await
+            .error("Could not write message to socket")?;
+
+        let mut items = Vec::new();
+        loop {
+            let line = self
+                .lines
+                .next_line()
+                .await
+                .error("Could not read from socket")?
+                .error("upsd closed the connection")?;
+            if let Some(err) = line.strip_prefix("ERR ") {
+                return Err(Error::new(format!("upsd: {err}")));
+            } else if line.starts_with("END LIST") {
+                return Ok(items);
+            } else if !line.starts_with("BEGIN LIST") {
+                items.push(line);
+            }
+        }
+    }
+}
+
+/// Splits `VAR <ups> <name> "<value>"` (or `UPS <ups> "<description>"`) into its words, removing
+/// the quotes and escapes of the last one
+fn split_line(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' => (),
+            '"' => {
+                let mut word = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => word.extend(chars.next()),
+                        c => word.push(c),
+                    }
+                }
+                words.push(word);
+            }
+            c => {
+                let mut word = String::from(c);
+                word.extend(chars.by_ref().take_while(|&c| c != ' '));
+                words.push(word);
+            }
+        }
+    }
+    words
+}
+
+/// Converts the variables of a UPS (see `upsc`) to the battery info
+fn parse_vars(vars: &HashMap<String, String>) -> Option<BatteryInfo> {
+    let var = |name: &str| vars.get(name).and_then(|v| v.parse::<f64>().ok());
+
+    let capacity = var("battery.charge")?;
+    let ups_status = vars.get("ups.status")?;
+    let flags: Vec<&str> = ups_status.split_whitespace().collect();
+
+    let status = if flags.contains(&"OB") {
+        if capacity == 0.0 {
+            BatteryStatus::Empty
+        } else {
+            BatteryStatus::Discharging
+        }
+    } else if flags.contains(&"OL") {
+        if capacity == 100.0 {
+            BatteryStatus::Full
+        } else if flags.contains(&"CHRG") {
+            BatteryStatus::Charging
+        } else {
+            BatteryStatus::NotCharging
+        }
+    } else {
+        BatteryStatus::Unknown
+    };
+
+    let power = var("ups.realpower").or_else(|| {
+        var("ups.realpower.nominal")
+            .zip(var("ups.load"))
+            .map(|(nominal_power, load_percent)| nominal_power * load_percent / 100.0)
+    });
+
+    Some(BatteryInfo {
+        status,
+        capacity,
+        power,
+        // The runtime is the time until the battery is empty, even while charging
+        time_remaining: var("battery.runtime").filter(|_| status == BatteryStatus::Discharging),
+        load: var("ups.load"),
+        ups_status: Some(ups_status.clone()),
+    })
+}
+
+pub(super) struct Device {
+    addr: String,
+    ups: Option<String>,
+    interval: Timer,
+}
+
+impl Device {
+    pub(super) fn new(dev_name: DeviceName, interval: Seconds) -> Self {
+        let device = dev_name.exact().unwrap_or("localhost");
+        let (ups, host) = match device.split_once('@') {
+            Some((ups, host)) => (Some(ups.to_string()), host),
+            None => (None, device),
+        };
+        let addr = if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{host}:3493")
+        };
+        Self {
+            addr,
+            ups,
+            interval: interval.timer(),
+        }
+    }
+
+    async fn get_vars(&mut self) -> Result<HashMap<String, String>> {
+        let mut conn = NutConnection::connect(&self.addr).await?;
+
+        let ups = match &self.ups {
+            Some(ups) => ups.clone(),
+            None => conn
+                .list("UPS")
+                .await?
+                .iter()
+                .find_map(|line| split_line(line).into_iter().nth(1))
+                .error("upsd does not monitor any UPS")?,
+        };
+
+        Ok(conn
+            .list(&format!("VAR {ups}"))
+            .await?
+            .iter()
+            .filter_map(|line| {
+                let mut words = split_line(line).into_iter().skip(2);
+                Some((words.next()?, words.next()?))
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl BatteryDevice for Device {
+    async fn get_info(&mut self) -> Result<Option<BatteryInfo>> {
+        let vars = self
+            .get_vars()
+            .await
+            .map_err(|e| {
+                debug!("{e}");
+                e
+            })
+            .unwrap_or_default();
+
+        Ok(parse_vars(&vars))
+    }
+
+    async fn wait_for_change(&mut self) -> Result<()> {
+        self.interval.tick().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_line() {
+        assert_eq!(
+            split_line(r#"VAR myups ups.mfr "American \"Power\" Conversion""#),
+            ["VAR", "myups", "ups.mfr", r#"American "Power" Conversion"#]
+        );
+        assert_eq!(split_line(r#"UPS myups """#), ["UPS", "myups", ""]);
+    }
+
+    #[test]
+    fn test_parse_vars() {
+        let vars = |status: &str| {
+            HashMap::from(
+                [
+                    ("battery.charge", "80"),
+                    ("battery.runtime", "1200"),
+                    ("ups.load", "25"),
+                    ("ups.realpower.nominal", "600"),
+                    ("ups.status", status),
+                ]
+                .map(|(k, v)| (k.to_string(), v.to_string())),
+            )
+        };
+
+        let info = parse_vars(&vars("OB DISCHRG")).unwrap();
+        assert_eq!(info.status, BatteryStatus::Discharging);
+        assert_eq!(info.capacity, 80.0);
+        assert_eq!(info.power, Some(150.0));
+        assert_eq!(info.time_remaining, Some(1200.0));
+        assert_eq!(info.load, Some(25.0));
+        assert_eq!(info.ups_status.as_deref(), Some("OB DISCHRG"));
+
+        let info = parse_vars(&vars("OL CHRG")).unwrap();
+        assert_eq!(info.status, BatteryStatus::Charging);
+        assert_eq!(info.time_remaining, None);
+
+        assert!(parse_vars(&HashMap::new()).is_none());
+    }
+}
@@ -2,8 +2,10 @@ use std::convert::Infallible;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use neli::consts::socket::NlFamily;
+use neli::socket::NlSocket;
 use tokio::fs::read_dir;
-use tokio::time::Interval;
+use tokio::io::unix::AsyncFd;
 
 use super::{BatteryDevice, BatteryInfo, BatteryStatus, DeviceName};
 use crate::blocks::prelude::*;
@@ -14,6 +16,9 @@ make_log_macro!(debug, "battery");
 /// Path for the power supply devices
 const POWER_SUPPLY_DEVICES_PATH: &str = "/sys/class/power_supply";
 
+/// The multicast group of uevents sent by the kernel (as opposed to those re-sent by udev)
+const UEVENT_KERNEL_GROUP: u32 = 1;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 enum CapacityLevel {
     Full,
@@ -58,16 +63,25 @@ pub(super) struct Device {
     dev_name: DeviceName,
     dev_path: Option<PathBuf>,
     dev_model: Option<String>,
-    interval: Interval,
+    interval: Timer,
+    uevents: Option<PowerSupplyEvents>,
 }
 
 impl Device {
     pub(super) fn new(dev_name: DeviceName, dev_model: Option<String>, interval: Seconds) -> Self {
+        let uevents = match PowerSupplyEvents::new() {
+            Ok(uevents) => Some(uevents),
+            Err(err) => {
+                debug!("failed to listen for uevents, polling only: {err}");
+                None
+            }
+        };
         Self {
             dev_name,
             dev_path: None,
             dev_model,
             interval: interval.timer(),
+            uevents,
         }
     }
 
@@ -271,11 +285,79 @@ impl BatteryDevice for Device {
             capacity,
             power,
             time_remaining,
+            load: None,
+            ups_status: None,
         }))
     }
 
     async fn wait_for_change(&mut self) -> Result<()> {
-        self.interval.tick().await;
+        // Not all batteries send uevents when their capacity changes, so keep polling as well
+        match &mut self.uevents {
+            Some(uevents) => select! {
+                _ = self.interval.tick() => (),
+                res = uevents.next() => {
+                    res?;
+                    self.interval.reset();
+                }
+            },
+            None => {
+                self.interval.tick().await;
+            }
+        }
         Ok(())
     }
 }
+
+/// Kernel uevents of power supply devices, which are sent e.g. when a charger is plugged in or
+/// unplugged, or when a battery changes its status
+struct PowerSupplyEvents(AsyncFd<NlSocket>);
+
+impl PowerSupplyEvents {
+    fn new() -> std::io::Result<Self> {
+        let socket = NlSocket::connect(NlFamily::KobjectUevent, None, &[UEVENT_KERNEL_GROUP])?;
+        socket.nonblock()?;
+        Ok(Self(AsyncFd::new(socket)?))
+    }
+
+    /// Waits for the next event of any power supply device
+    async fn next(&mut self) -> Result<()> {
+        let mut buf = [0; 8192];
+        loop {
+            let mut guard = self
+                .0
+                .readable()
+                .await
+                .error("failed to wait for uevents")?;
+            if let Ok(len) = guard.try_io(|socket| socket.get_ref().recv(&mut buf[..], 0)) {
+                let len = len.error("failed to read uevent")?;
+                if is_power_supply_event(&buf[..len]) {
+                    debug!("power supply uevent");
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Uevents consist of a header (e.g. `change@/devices/...`) followed by NUL-separated `KEY=value`
+/// pairs
+fn is_power_supply_event(msg: &[u8]) -> bool {
+    msg.split(|&b| b == 0)
+        .any(|field| field == b"SUBSYSTEM=power_supply")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn power_supply_event() {
+        assert!(is_power_supply_event(
+            b"change@/devices/LNXSYSTM:00/PNP0C0A:00/power_supply/BAT0\0ACTION=change\0\
+              SUBSYSTEM=power_supply\0POWER_SUPPLY_NAME=BAT0\0"
+        ));
+        assert!(!is_power_supply_event(
+            b"add@/devices/virtual/net/tun0\0ACTION=add\0SUBSYSTEM=net\0"
+        ));
+    }
+}
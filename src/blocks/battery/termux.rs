@@ -0,0 +1,48 @@
+
+use super::{BatteryDevice, BatteryInfo, BatteryStatus};
+use crate::blocks::prelude::*;
+use crate::termux;
+
+pub(super) struct Device {
+    interval: Timer,
+}
+
+impl Device {
+    pub(super) fn new(interval: Seconds) -> Self {
+        Self {
+            interval: interval.timer(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Status {
+    percentage: f64,
+    status: String,
+}
+
+#[async_trait]
+impl BatteryDevice for Device {
+    async fn get_info(&mut self) -> Result<Option<BatteryInfo>> {
+        let status: Status = termux::api("termux-battery-status").await?;
+        Ok(Some(BatteryInfo {
+            status: match status.status.as_str() {
+                "CHARGING" => BatteryStatus::Charging,
+                "DISCHARGING" => BatteryStatus::Discharging,
+                "FULL" => BatteryStatus::Full,
+                "NOT_CHARGING" => BatteryStatus::NotCharging,
+                _ => BatteryStatus::Unknown,
+            },
+            capacity: status.percentage,
+            power: None,
+            time_remaining: None,
+            load: None,
+            ups_status: None,
+        }))
+    }
+
+    async fn wait_for_change(&mut self) -> Result<()> {
+        self.interval.tick().await;
+        Ok(())
+    }
+}
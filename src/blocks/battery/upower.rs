@@ -165,6 +165,8 @@ impl BatteryDevice for Device {
                             capacity,
                             power: Some(power),
                             time_remaining,
+                            load: None,
+                            ups_status: None,
                         }))
                     }
                 }
@@ -0,0 +1,112 @@
+//! The current binding mode of sway or i3
+//!
+//! This block shows the active binding mode (e.g. `resize`) and is hidden in the default mode. Each
+//! mode can be given its own name, icon and state. Icons are looked up in the icon set, so icons
+//! which are not part of it must be defined in `icons_overrides`. To give a mode custom colors,
+//! assign it a state and override the colors of that state in the block's `theme_overrides`.
+//!
+//! Mode names are escaped. Modes which are declared with `mode --pango_markup` in the sway config
+//! can be rendered as markup with `$mode.pango-str()` in `format`.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" {$icon \|}$mode \"</code>
+//! `modes` | A map from mode names to their `name`, `icon` and `state`, all of which are optional | `{}`
+//! `state` | The state of modes which do not set it in `modes` | `"Warning"`
+//! `show_default` | Whether to show the block in the default mode | `false`
+//!
+//! Placeholder | Value                                                      | Type | Unit
+//! ------------|------------------------------------------------------------|------|-----
+//! `mode`      | The name of the mode, or its `name` from `modes` if set     | Text | -
+//! `icon`      | The icon of the mode. Absent if not set in `modes`.        | Icon | -
+//!
+//! # Example
+//!
+//! ```toml
+//! [[block]]
+//! block = "binding_mode"
+//! [block.modes.resize]
+//! name = "RESIZE"
+//! icon = "resize"
+//! [block.modes.passthrough]
+//! state = "Critical"
+//! [block.theme_overrides]
+//! critical_bg = "#8f3f71"
+//! [block.icons_overrides]
+//! resize = "\u2921"
+//! ```
+//!
+//! # Used Icons
+//! - The icons set in `modes`
+
+use swayipc_async::{Connection, Event, EventType};
+
+use super::prelude::*;
+
+const DEFAULT_MODE: &str = "default";
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    pub modes: HashMap<String, ModeConfig>,
+    #[default(State::Warning)]
+    pub state: State,
+    pub show_default: bool,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct ModeConfig {
+    pub name: Option<String>,
+    pub icon: Option<String>,
+    pub state: Option<State>,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let format = config.format.with_default(" {$icon |}$mode ")?;
+
+    let mut connection = Connection::new()
+        .await
+        .error("Failed to open connection with swayipc")?;
+    // Not supported by old versions of i3
+    let mut mode = connection
+        .get_binding_state()
+        .await
+        .unwrap_or_else(|_| DEFAULT_MODE.into());
+    let mut events = connection
+        .subscribe(&[EventType::Mode])
+        .await
+        .error("Could not subscribe to mode events")?;
+
+    loop {
+        if mode == DEFAULT_MODE && !config.show_default {
+            api.hide()?;
+        } else {
+            let mode_config = config.modes.get(&mode);
+            let name = mode_config.and_then(|m| m.name.clone());
+            let icon = mode_config.and_then(|m| m.icon.as_deref());
+            let mut widget = Widget::new().with_format(format.clone());
+            widget.state = mode_config.and_then(|m| m.state).unwrap_or(config.state);
+            widget.set_values(map! {
+                "mode" => Value::text(name.unwrap_or_else(|| mode.clone())),
+                [if let Some(icon) = icon] "icon" => Value::icon(icon),
+            });
+            api.set_widget(widget)?;
+        }
+
+        loop {
+            let event = events
+                .next()
+                .await
+                .error("swayipc channel closed")?
+                .error("bad event")?;
+            if let Event::Mode(event) = event {
+                mode = event.change;
+                break;
+            }
+        }
+    }
+}
@@ -16,6 +16,10 @@
 //! `icon`           | An icon                                                              | Icon   | -
 //! `utilization`    | Average CPU utilization                                              | Number | %
 //! `utilization<N>` | Utilization of Nth logical CPU                                       | Number | %
+//! `user`           | Share of CPU time spent in user space (including niced processes)   | Number | %
+//! `sys`            | Share of CPU time spent in the kernel (including interrupts)         | Number | %
+//! `iowait`         | Share of CPU time spent idle while waiting for I/O                   | Number | %
+//! `steal`          | Share of CPU time stolen by the hypervisor from this virtual machine | Number | %
 //! `barchart`       | Utilization of all logical CPUs presented as a barchart              | Text   | -
 //! `frequency`      | Average CPU frequency (may be absent if CPU is not supported)        | Number | Hz
 //! `frequency<N>`   | Frequency of Nth logical CPU (may be absent if CPU is not supported) | Number | Hz
@@ -26,6 +30,10 @@
 //! ----------------|-------------------------------------------|---------------
 //! `toggle_format` | Toggles between `format` and `format_alt` | Left
 //!
+//! Unless time is stolen, `user` and `sys` add up to `utilization`. A high `iowait` means that the
+//! machine is waiting for disks rather than computing, and a high `steal` that a virtual machine
+//! does not get the CPU time it asks for. `iowait` and `steal` are always zero on FreeBSD.
+//!
 //! # Example
 //!
 //! ```toml
@@ -44,9 +52,12 @@
 //! - `cpu_boost_on`
 //! - `cpu_boost_off`
 
+#[cfg(not(target_os = "freebsd"))]
 use std::str::FromStr;
 
+#[cfg(not(target_os = "freebsd"))]
 use tokio::fs::File;
+#[cfg(not(target_os = "freebsd"))]
 use tokio::io::{AsyncBufReadExt, BufReader};
 
 use super::prelude::*;
@@ -96,6 +107,9 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         // Compute utilizations
         let new_cputime = read_proc_stat().await?;
         let utilization_avg = new_cputime.0.utilization(cputime.0);
+        let share = |field| new_cputime.0.share(cputime.0, field) * 100.;
+        let (user, sys) = (share(|t| t.user), share(|t| t.system));
+        let (iowait, steal) = (share(|t| t.iowait), share(|t| t.steal));
         let mut utilizations = Vec::new();
         if new_cputime.1.len() != cores {
             return Err(Error::new("new cputime length is incorrect"));
@@ -122,16 +136,20 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
             "icon" => Value::icon_progression("cpu", utilization_avg),
             "barchart" => Value::text(barchart),
             "utilization" => Value::percents(utilization_avg * 100.),
+            "user" => Value::percents(user),
+            "sys" => Value::percents(sys),
+            "iowait" => Value::percents(iowait),
+            "steal" => Value::percents(steal),
             [if !freqs.is_empty()] "frequency" => Value::hertz(freqs.iter().sum::<f64>() / (freqs.len() as f64)),
             [if !freqs.is_empty()] "max_frequency" => Value::hertz(freqs.iter().copied().max_by(f64::total_cmp).unwrap()),
         );
-        boost.map(|b| values.insert("boost".into(), Value::icon(b)));
+        boost.map(|b| values.insert("boost", Value::icon(b)));
         for (i, freq) in freqs.iter().enumerate() {
-            values.insert(format!("frequency{}", i + 1).into(), Value::hertz(*freq));
+            values.insert(intern(&format!("frequency{}", i + 1)), Value::hertz(*freq));
         }
         for (i, utilization) in utilizations.iter().enumerate() {
             values.insert(
-                format!("utilization{}", i + 1).into(),
+                intern(&format!("utilization{}", i + 1)),
                 Value::percents(utilization * 100.),
             );
         }
@@ -165,6 +183,7 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
 }
 
 // Read frequencies (read in MHz, store in Hz)
+#[cfg(not(target_os = "freebsd"))]
 async fn read_frequencies() -> Result<Vec<f64>> {
     let mut freqs = Vec::with_capacity(32);
 
@@ -192,13 +211,32 @@ async fn read_frequencies() -> Result<Vec<f64>> {
     Ok(freqs)
 }
 
+// Read frequencies (read in MHz, store in Hz)
+#[cfg(target_os = "freebsd")]
+async fn read_frequencies() -> Result<Vec<f64>> {
+    // Only available if the cpufreq driver is loaded
+    Ok((0..)
+        .map_while(|i| crate::sysctl::read::<libc::c_int>(&format!("dev.cpu.{i}.freq")).ok())
+        .map(|mhz| mhz as f64 * 1e6)
+        .collect())
+}
+
 #[derive(Debug, Clone, Copy)]
 struct CpuTime {
     idle: u64,
     non_idle: u64,
+    /// Part of `non_idle`
+    user: u64,
+    /// Part of `non_idle`
+    system: u64,
+    /// Part of `idle`
+    iowait: u64,
+    /// Counted neither as idle nor as non-idle
+    steal: u64,
 }
 
 impl CpuTime {
+    #[cfg(not(target_os = "freebsd"))]
     fn from_str(s: &str) -> Option<Self> {
         let mut s = s.trim().split_ascii_whitespace();
         let user = u64::from_str(s.next()?).ok()?;
@@ -208,10 +246,16 @@ impl CpuTime {
         let iowait = u64::from_str(s.next()?).ok()?;
         let irq = u64::from_str(s.next()?).ok()?;
         let softirq = u64::from_str(s.next()?).ok()?;
+        // Only reported since Linux 2.6.11
+        let steal = s.next().and_then(|v| u64::from_str(v).ok()).unwrap_or(0);
 
         Some(Self {
             idle: idle + iowait,
             non_idle: user + nice + system + irq + softirq,
+            user: user + nice,
+            system: system + irq + softirq,
+            iowait,
+            steal,
         })
     }
 
@@ -223,8 +267,20 @@ impl CpuTime {
             ((self.non_idle - old.non_idle) as f64 / elapsed as f64).clamp(0., 1.)
         }
     }
+
+    /// The share of `field` in the time elapsed since `old`, including stolen time
+    fn share(&self, old: Self, field: fn(&Self) -> u64) -> f64 {
+        let total = |t: &Self| t.idle + t.non_idle + t.steal;
+        let elapsed = total(self).saturating_sub(total(&old));
+        if elapsed == 0 {
+            0.0
+        } else {
+            (field(self).saturating_sub(field(&old)) as f64 / elapsed as f64).clamp(0., 1.)
+        }
+    }
 }
 
+#[cfg(not(target_os = "freebsd"))]
 async fn read_proc_stat() -> Result<(CpuTime, Vec<CpuTime>)> {
     let mut utilizations = Vec::with_capacity(32);
     let mut total = None;
@@ -254,6 +310,28 @@ async fn read_proc_stat() -> Result<(CpuTime, Vec<CpuTime>)> {
     Ok((total.error("failed to parse /proc/stat")?, utilizations))
 }
 
+#[cfg(target_os = "freebsd")]
+async fn read_proc_stat() -> Result<(CpuTime, Vec<CpuTime>)> {
+    // The ticks spent in user, nice, system, interrupt and idle states
+    fn from_ticks(ticks: &[libc::c_long]) -> CpuTime {
+        CpuTime {
+            idle: ticks[4] as u64,
+            non_idle: (ticks[0] + ticks[1] + ticks[2] + ticks[3]) as u64,
+            user: (ticks[0] + ticks[1]) as u64,
+            system: (ticks[2] + ticks[3]) as u64,
+            iowait: 0,
+            steal: 0,
+        }
+    }
+
+    let total = crate::sysctl::read::<[libc::c_long; 5]>("kern.cp_time")?;
+    let per_cpu = crate::sysctl::read_vec::<libc::c_long>("kern.cp_times")?;
+    Ok((
+        from_ticks(&total),
+        per_cpu.chunks_exact(5).map(from_ticks).collect(),
+    ))
+}
+
 /// Read the cpu turbo boost status from kernel sys interface
 /// or intel pstate interface
 async fn boost_status() -> Option<bool> {
@@ -18,7 +18,7 @@
 //! `cycle` | Commands to execute and change when the button is clicked | `None`
 //! `interval` | Update interval in seconds (or "once" to update only once) | `10`
 //! `json` | Use JSON from command output to format the block. If the JSON is not valid, the block will error out. | `false`
-//! `watch_files` | Watch files to trigger update on file modification. Supports path expansions e.g. `~`. | `None`
+//! `watch_files` | Watch files to trigger update on file modification (Linux only). Supports path expansions e.g. `~`. | `None`
 //! `hide_when_empty` | Hides the block when the command output (or json text field) is empty | `false`
 //! `shell` | Specify the shell to use when running commands | `$SHELL` if set, otherwise fallback to `sh`
 //!
@@ -98,9 +98,10 @@
 use crate::formatting::Format;
 
 use super::prelude::*;
+#[cfg(target_os = "linux")]
 use inotify::{Inotify, WatchMask};
 use std::process::Stdio;
-use tokio::io::{self, AsyncBufReadExt, BufReader};
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 
 #[derive(Deserialize, Debug, SmartDefault)]
@@ -135,7 +136,7 @@ async fn update_bar(
                 text_empty = input.text.is_empty();
                 widget.set_values(map! {
                     "text" => Value::text(input.text),
-                    [if !input.icon.is_empty()] "icon" => Value::icon(input.icon),
+                    [if !input.icon.is_empty()] "icon" => Value::icon_owned(input.icon),
                     [if let Some(t) = input.short_text] "short_text" => Value::text(t)
                 });
                 widget.state = input.state;
@@ -164,9 +165,10 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
 
     let mut timer = config.interval.timer();
 
-    type FileStream = Pin<Box<dyn Stream<Item = io::Result<inotify::EventOwned>> + Send + Sync>>;
+    type FileStream = Pin<Box<dyn Stream<Item = ()> + Send + Sync>>;
     let mut file_updates: FileStream = match config.watch_files.as_slice() {
         [] => Box::pin(futures::stream::pending()),
+        #[cfg(target_os = "linux")]
         files => {
             let notify = Inotify::init().error("Failed to start inotify")?;
             let mut watches = notify.watches();
@@ -185,9 +187,12 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
             Box::pin(
                 notify
                     .into_event_stream([0; 1024])
-                    .error("Failed to create event stream")?,
+                    .error("Failed to create event stream")?
+                    .map(|_| ()),
             )
         }
+        #[cfg(not(target_os = "linux"))]
+        _ => return Err(Error::new("'watch_files' is only supported on Linux")),
     };
 
     let shell = config
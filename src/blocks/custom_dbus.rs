@@ -78,9 +78,9 @@ struct Block {
     short_text: Option<String>,
 }
 
-fn block_values(block: &Block) -> HashMap<Cow<'static, str>, Value> {
+fn block_values(block: &Block) -> Values {
     map! {
-        [if let Some(icon) = &block.icon] "icon" => Value::icon(icon.to_string()),
+        [if let Some(icon) = &block.icon] "icon" => Value::icon_owned(icon.clone()),
         [if let Some(text) = &block.text] "text" => Value::text(text.to_string()),
         [if let Some(short_text) = &block.short_text] "short_text" => Value::text(short_text.to_string()),
     }
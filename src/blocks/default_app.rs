@@ -0,0 +1,259 @@
+//! The default application for a MIME type, e.g. the default web browser
+//!
+//! This block uses `xdg-mime` to query and change the default application. Clicking the block
+//! makes the next or the previous installed application which can open the MIME type the default.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" $icon {$name\|none} \"</code>
+//! `mime_types` | The MIME types to change the default application of. The first one is displayed and the applications to cycle through must support it. | `["x-scheme-handler/http", "x-scheme-handler/https", "text/html"]`
+//! `apps` | The desktop files of the applications to cycle through, e.g. `["firefox.desktop", "chromium.desktop"]`. If empty, all installed applications which support the first MIME type are used. | `[]`
+//! `interval` | Update interval in seconds. The block is also updated when `mimeapps.list` changes. | `60`
+//!
+//! Placeholder | Value                                                                   | Type | Unit
+//! ------------|-------------------------------------------------------------------------|------|-----
+//! `icon`      | A static icon                                                           | Icon | -
+//! `name`      | The name of the default application. Absent if there is none.           | Text | -
+//! `app`       | The desktop file of the default application. Absent if there is none.   | Text | -
+//!
+//! Action     | Default button
+//! -----------|---------------
+//! `next_app` | Left
+//! `prev_app` | Right
+//!
+//! # Examples
+//!
+//! Switch the default browser between Firefox and Chromium
+//!
+//! ```toml
+//! [[block]]
+//! block = "default_app"
+//! apps = ["firefox.desktop", "chromium.desktop"]
+//! ```
+//!
+//! Show the default PDF viewer
+//!
+//! ```toml
+//! [[block]]
+//! block = "default_app"
+//! format = " PDF: $name "
+//! mime_types = ["application/pdf"]
+//! ```
+//!
+//! # Icons Used
+//! - `browser`
+
+use std::path::PathBuf;
+
+use dirs::{config_dir, data_dir};
+use inotify::{Inotify, WatchMask};
+use tokio::process::Command;
+
+use super::prelude::*;
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    #[default(vec![
+        "x-scheme-handler/http".into(),
+        "x-scheme-handler/https".into(),
+        "text/html".into(),
+    ])]
+    pub mime_types: Vec<String>,
+    pub apps: Vec<String>,
+    #[default(60.into())]
+    pub interval: Seconds,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+    api.set_default_actions(&[
+        (MouseButton::Left, None, "next_app"),
+        (MouseButton::Right, None, "prev_app"),
+    ])?;
+
+    let format = config.format.with_default(" $icon {$name|none} ")?;
+    let mime_type = config.mime_types.first().error("mime_types is empty")?;
+
+    let config_dir = config_dir().error("xdg config directory not found")?;
+    let notify = Inotify::init().error("Failed to start inotify")?;
+    notify
+        .watches()
+        .add(
+            &config_dir,
+            WatchMask::CREATE | WatchMask::MOVED_TO | WatchMask::CLOSE_WRITE,
+        )
+        .error("Failed to watch the config directory")?;
+    let mut updates = notify
+        .into_event_stream([0; 1024])
+        .error("Failed to create event stream")?;
+
+    let mut timer = config.interval.timer();
+
+    loop {
+        let current = xdg_mime(&["query", "default", mime_type]).await?;
+        let current = current.trim();
+        let apps = installed_apps(mime_type).await;
+
+        let name = (!current.is_empty()).then(|| {
+            apps.iter()
+                .find(|app| app.id == current)
+                .map_or_else(|| current.trim_end_matches(".desktop"), |app| &app.name)
+        });
+
+        let mut widget = Widget::new().with_format(format.clone());
+        widget.set_values(map! {
+            "icon" => Value::icon("browser"),
+            [if let Some(name) = name] "name" => Value::text(name.into()),
+            [if !current.is_empty()] "app" => Value::text(current.into()),
+        });
+        api.set_widget(widget)?;
+
+        loop {
+            select! {
+                _ = timer.tick() => break,
+                _ = api.wait_for_update_request() => break,
+                Some(update) = updates.next() => {
+                    let update = update.error("Bad inotify update")?;
+                    if update.name.is_some_and(|name| name == "mimeapps.list") {
+                        break;
+                    }
+                }
+                Some(action) = actions.recv() => {
+                    let step = match action.as_ref() {
+                        "next_app" => 1,
+                        "prev_app" => -1,
+                        _ => continue,
+                    };
+                    let ids: Vec<&str> = if config.apps.is_empty() {
+                        apps.iter().map(|app| app.id.as_str()).collect()
+                    } else {
+                        config.apps.iter().map(String::as_str).collect()
+                    };
+                    if ids.is_empty() {
+                        continue;
+                    }
+                    let i = ids
+                        .iter()
+                        .position(|id| *id == current)
+                        .map_or(0, |i| (i as isize + step).rem_euclid(ids.len() as isize) as usize);
+                    let mut args = vec!["default", ids[i]];
+                    args.extend(config.mime_types.iter().map(String::as_str));
+                    xdg_mime(&args).await?;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+async fn xdg_mime(args: &[&str]) -> Result<String> {
+    let output = Command::new("xdg-mime")
+        .args(args)
+        .output()
+        .await
+        .error("Failed to run 'xdg-mime'")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::new(format!("'xdg-mime' failed: {}", stderr.trim())));
+    }
+    String::from_utf8(output.stdout).error("'xdg-mime' produced non-UTF8 output")
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct App {
+    /// The name of the desktop file
+    id: String,
+    name: String,
+}
+
+/// Returns the visible applications which support `mime_type`, sorted by their names
+async fn installed_apps(mime_type: &str) -> Vec<App> {
+    let mut dirs: Vec<PathBuf> = data_dir().into_iter().collect();
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    dirs.extend(data_dirs.split(':').map(PathBuf::from));
+
+    let mut ids = Vec::new();
+    let mut apps = Vec::new();
+    for dir in dirs {
+        let Ok(mut entries) = tokio::fs::read_dir(dir.join("applications")).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let id = entry.file_name().to_string_lossy().into_owned();
+            // Desktop files in earlier directories take precedence
+            if !id.ends_with(".desktop") || ids.contains(&id) {
+                continue;
+            }
+            let Ok(contents) = tokio::fs::read_to_string(entry.path()).await else {
+                continue;
+            };
+            ids.push(id.clone());
+            if let Some(name) = parse_desktop_entry(&contents, mime_type) {
+                apps.push(App { id, name });
+            }
+        }
+    }
+    apps.sort_by(|a, b| a.name.cmp(&b.name));
+    apps
+}
+
+/// Returns the name of the application if it is visible and supports `mime_type`
+fn parse_desktop_entry(contents: &str, mime_type: &str) -> Option<String> {
+    let mut name = None;
+    let mut supported = false;
+    let mut in_entry = false;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_entry {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match (key.trim(), value.trim()) {
+            ("Name", value) => name = Some(value.to_owned()),
+            ("MimeType", value) => supported = value.split(';').any(|m| m == mime_type),
+            ("NoDisplay" | "Hidden", "true") => return None,
+            _ => (),
+        }
+    }
+    name.filter(|_| supported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn desktop_entry() {
+        let firefox = "[Desktop Entry]\n\
+                       Name=Firefox\n\
+                       Name[de]=Firefox Webbrowser\n\
+                       MimeType=text/html;x-scheme-handler/http;x-scheme-handler/https;\n\
+                       \n\
+                       [Desktop Action new-window]\n\
+                       Name=New Window\n";
+        assert_eq!(
+            parse_desktop_entry(firefox, "x-scheme-handler/http"),
+            Some("Firefox".into())
+        );
+        assert_eq!(parse_desktop_entry(firefox, "application/pdf"), None);
+        assert_eq!(
+            parse_desktop_entry(
+                "[Desktop Entry]\nName=Hidden\nMimeType=text/html;\nNoDisplay=true\n",
+                "text/html"
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Disk I/O throughput
+//!
+//! This block reads `/proc/diskstats` and shows how fast the selected devices are read from and
+//! written to. If several devices are selected, their rates are summed.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `device` | Devices to monitor (as specified in `/proc/diskstats`). Supports regex, e.g. `"^nvme0n1$"` or `"^sd[a-z]$"`. | All disks with a backing device in `/sys/block/` (partitions, loop and device mapper devices are skipped, since their I/O is already counted by the disks)
+//! `interval` | Update interval in seconds | `2`
+//! `format` | A string to customise the output of this block. See below for available placeholders. | `" $icon $speed_read.eng(prefix:K) $speed_write.eng(prefix:K) "`
+//! `missing_format` | Same as `format` but for when no device is found | `" × "`
+//!
+//! Placeholder   | Value                                       | Type   | Unit
+//! --------------|---------------------------------------------|--------|---------------
+//! `icon`        | A static icon                               | Icon   | -
+//! `speed_read`  | Read speed                                  | Number | Bytes per second
+//! `speed_write` | Write speed                                 | Number | Bytes per second
+//! `device`      | The names of the devices, separated by `,`  | Text   | -
+//!
+//! # Examples
+//!
+//! ```toml
+//! [[block]]
+//! block = "disk_iostats"
+//! device = "^nvme0n1$"
+//! format = " $icon R $speed_read.eng(prefix:M) W $speed_write.eng(prefix:M) "
+//! ```
+//!
+//! Show the block only while more than 1MB per second is written:
+//!
+//! ```toml
+//! [[block]]
+//! block = "disk_iostats"
+//! format = " $icon $speed_write.eng(prefix:M,range:1e6..) |"
+//! ```
+//!
+//! # Icons Used
+//! - `disk_drive`
+
+use super::prelude::*;
+use crate::util::read_file;
+use crate::wrappers::SerdeRegex;
+use itertools::Itertools;
+use std::time::Instant;
+
+/// `/proc/diskstats` counts sectors of 512 bytes, regardless of the sector size of the device
+const SECTOR_SIZE: u64 = 512;
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub device: Option<SerdeRegex>,
+    #[default(2.into())]
+    pub interval: Seconds,
+    pub format: FormatConfig,
+    pub missing_format: FormatConfig,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let format = config
+        .format
+        .with_default(" $icon $speed_read.eng(prefix:K) $speed_write.eng(prefix:K) ")?;
+    let missing_format = config.missing_format.with_default(" × ")?;
+
+    let device_re = config.device.as_ref().map(|re| &re.0);
+
+    let mut timer = config.interval.timer();
+
+    // The stats of each device, so that devices which appear or disappear do not cause spikes
+    let mut stats: HashMap<String, Stats> = HashMap::new();
+    let mut stats_timer = Instant::now();
+
+    loop {
+        let diskstats = read_file("/proc/diskstats")
+            .await
+            .error("Failed to read /proc/diskstats")?;
+        let disks = match device_re {
+            Some(_) => Vec::new(),
+            None => physical_disks().await?,
+        };
+        let devices: Vec<(&str, Stats)> = parse_diskstats(&diskstats)
+            .filter(|(name, _)| match &device_re {
+                Some(re) => re.is_match(name),
+                None => disks.iter().any(|disk| disk == name),
+            })
+            .collect();
+
+        let elapsed = stats_timer.elapsed().as_secs_f64();
+        stats_timer = Instant::now();
+        let delta = io_since(&stats, &devices);
+        stats = devices
+            .iter()
+            .map(|(name, stats)| (name.to_string(), *stats))
+            .collect();
+
+        if devices.is_empty() {
+            api.set_widget(Widget::new().with_format(missing_format.clone()))?;
+        } else {
+            // Nothing is known about the speed before the first update
+            let speed = |bytes: u64| if bytes == 0 { 0.0 } else { bytes as f64 / elapsed };
+            let speed_read = speed(delta.read);
+            let speed_write = speed(delta.written);
+
+            let mut widget = Widget::new().with_format(format.clone());
+            widget.set_values(map! {
+                "icon" => Value::icon("disk_drive"),
+                "speed_read" => Value::bytes(speed_read),
+                "speed_write" => Value::bytes(speed_write),
+                "device" => Value::text(devices.iter().map(|(name, _)| *name).join(",")),
+            });
+            api.set_widget(widget)?;
+        }
+
+        select! {
+            _ = timer.tick() => (),
+            _ = api.wait_for_update_request() => (),
+        }
+    }
+}
+
+/// Bytes read from and written to a device since boot
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Stats {
+    read: u64,
+    written: u64,
+}
+
+impl Stats {
+    /// The bytes read and written since `old`. The counters restart if a device is replaced.
+    fn since(self, old: &Self) -> Self {
+        Self {
+            read: self.read.saturating_sub(old.read),
+            written: self.written.saturating_sub(old.written),
+        }
+    }
+}
+
+impl std::ops::Add for Stats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            read: self.read + rhs.read,
+            written: self.written + rhs.written,
+        }
+    }
+}
+
+/// Sums up the I/O of the devices since their previous stats. Devices without previous stats, e.g.
+/// ones which were just plugged in, are skipped.
+fn io_since(old: &HashMap<String, Stats>, devices: &[(&str, Stats)]) -> Stats {
+    devices
+        .iter()
+        .filter_map(|(name, stats)| Some(stats.since(old.get(*name)?)))
+        .fold(Stats::default(), |sum, delta| sum + delta)
+}
+
+/// Parses the lines of `/proc/diskstats`, e.g.
+/// `259 0 nvme0n1 151003 48218 11151630 27843 213435 103021 14307440 164432 0 ...`, where the
+/// 6th and 10th fields are the sectors read and written.
+fn parse_diskstats(diskstats: &str) -> impl Iterator<Item = (&str, Stats)> {
+    diskstats.lines().filter_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let sectors = |i: usize| fields.get(i)?.parse::<u64>().ok();
+        Some((
+            *fields.get(2)?,
+            Stats {
+                read: sectors(5)? * SECTOR_SIZE,
+                written: sectors(9)? * SECTOR_SIZE,
+            },
+        ))
+    })
+}
+
+/// The devices in `/sys/block/` which are backed by hardware
+async fn physical_disks() -> Result<Vec<String>> {
+    let mut disks = Vec::new();
+    let mut entries = tokio::fs::read_dir("/sys/block")
+        .await
+        .error("Failed to read /sys/block")?;
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .error("Failed to read /sys/block")?
+    {
+        if entry.path().join("device").exists() {
+            disks.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    Ok(disks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_diskstats() {
+        let diskstats = "\
+ 259       0 nvme0n1 151003 48218 11151630 27843 213435 103021 14307440 164432 0 96052 206813 0 0 0 0 12871 14537
+ 259       1 nvme0n1p1 321 1024 12190 67 2 0 2 0 0 106 67 0 0 0 0 0 0
+   7       0 loop0 4 0 8 0 0 0 0 0 0 4 0 0 0 0 0 0 0
+   8       0 sda broken";
+        let stats: Vec<_> = parse_diskstats(diskstats).collect();
+        assert_eq!(
+            stats,
+            [
+                (
+                    "nvme0n1",
+                    Stats {
+                        read: 11151630 * 512,
+                        written: 14307440 * 512
+                    }
+                ),
+                (
+                    "nvme0n1p1",
+                    Stats {
+                        read: 12190 * 512,
+                        written: 2 * 512
+                    }
+                ),
+                (
+                    "loop0",
+                    Stats {
+                        read: 8 * 512,
+                        written: 0
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_io_since() {
+        let stats = |read, written| Stats { read, written };
+        let old = HashMap::from([
+            ("nvme0n1".to_string(), stats(1000, 2000)),
+            ("sda".to_string(), stats(500, 500)),
+        ]);
+        // sdb was plugged in and sda was replaced by a device with restarted counters
+        let devices = [
+            ("nvme0n1", stats(1500, 2100)),
+            ("sda", stats(100, 0)),
+            ("sdb", stats(1 << 40, 1 << 40)),
+        ];
+        assert_eq!(io_since(&old, &devices), stats(500, 100));
+    }
+}
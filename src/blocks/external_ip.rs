@@ -168,9 +168,9 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
             "org" => Value::text(info.org),
         };
         info.postal
-            .map(|x| values.insert("postal".into(), Value::text(x)));
+            .map(|x| values.insert("postal", Value::text(x)));
         if info.in_eu {
-            values.insert("in_eu".into(), Value::flag());
+            values.insert("in_eu", Value::flag());
         }
 
         let mut widget = Widget::new().with_format(format.clone());
@@ -178,7 +178,7 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         api.set_widget(widget)?;
 
         select! {
-            _ = sleep(config.interval.0) => (),
+            _ = config.interval.sleep() => (),
             _ = api.wait_for_update_request() => (),
             _ = stream.next() => {
                 // avoid too frequent updates
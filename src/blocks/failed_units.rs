@@ -0,0 +1,211 @@
+//! The number of failed systemd units
+//!
+//! This block shows how many system and user units of systemd are in the failed state, as listed
+//! by `systemctl --failed` and `systemctl --user --failed`. It is updated as soon as systemd
+//! reports a change.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | `" $icon $failed "`
+//! `system` | Whether to count the units of the system manager | `true`
+//! `user` | Whether to count the units of the user manager | `true`
+//! `list_cmd` | The shell command which the `list` action runs, e.g. to open a terminal with the failed units | None
+//!
+//! Placeholder | Value                                                    | Type   | Unit
+//! ------------|----------------------------------------------------------|--------|-----
+//! `icon`      | A static icon                                            | Icon   | -
+//! `failed`    | The number of failed units                               | Number | -
+//! `system`    | The number of failed system units (present if counted)   | Number | -
+//! `user`      | The number of failed user units (present if counted)     | Number | -
+//!
+//! The block is in the critical state if any unit failed.
+//!
+//! Action | Description         | Default button
+//! -------|---------------------|---------------
+//! `list` | Runs `list_cmd`     | Left
+//!
+//! # Example
+//!
+//! ```toml
+//! [[block]]
+//! block = "failed_units"
+//! format = " $icon $system/$user "
+//! list_cmd = "foot -H sh -c 'systemctl --failed; systemctl --user --failed'"
+//! ```
+//!
+//! # Icons Used
+//! - `cogs`
+
+use super::prelude::*;
+use crate::subprocess::spawn_shell;
+
+make_log_macro!(debug, "failed_units");
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    #[default(true)]
+    pub system: bool,
+    #[default(true)]
+    pub user: bool,
+    pub list_cmd: Option<String>,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+    api.set_default_actions(&[(MouseButton::Left, None, "list")])?;
+
+    let format = config.format.with_default(" $icon $failed ")?;
+
+    let system = match config.system {
+        true => Some(manager(new_system_dbus_connection().await?).await?),
+        false => None,
+    };
+    let user = match config.user {
+        true => Some(manager(new_dbus_connection().await?).await?),
+        false => None,
+    };
+
+    let mut changes = futures::stream::select_all(
+        futures::future::join_all(
+            system
+                .iter()
+                .chain(&user)
+                .map(|m| m.receive_n_failed_units_changed()),
+        )
+        .await,
+    );
+
+    loop {
+        let counts = FailedCounts {
+            system: match &system {
+                Some(m) => Some(failed_units(m).await?),
+                None => None,
+            },
+            user: match &user {
+                Some(m) => Some(failed_units(m).await?),
+                None => None,
+            },
+        };
+
+        let mut widget = Widget::new().with_format(format.clone());
+        widget.state = counts.state();
+        widget.set_values(counts.values());
+        api.set_widget(widget)?;
+
+        select! {
+            Some(_) = changes.next() => (),
+            _ = api.wait_for_update_request() => (),
+            Some(action) = actions.recv() => match action.as_ref() {
+                "list" => {
+                    if let Some(cmd) = &config.list_cmd {
+                        spawn_shell(cmd).error("Failed to run list_cmd")?;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// The numbers of failed units of the counted managers
+#[derive(Debug, Clone, Copy)]
+struct FailedCounts {
+    system: Option<u32>,
+    user: Option<u32>,
+}
+
+impl FailedCounts {
+    fn total(self) -> u32 {
+        self.system.unwrap_or(0) + self.user.unwrap_or(0)
+    }
+
+    fn state(self) -> State {
+        if self.total() > 0 {
+            State::Critical
+        } else {
+            State::Idle
+        }
+    }
+
+    fn values(self) -> Values {
+        map! {
+            "icon" => Value::icon("cogs"),
+            "failed" => Value::number(self.total()),
+            [if let Some(n) = self.system] "system" => Value::number(n),
+            [if let Some(n) = self.user] "user" => Value::number(n),
+        }
+    }
+}
+
+async fn manager(dbus_conn: zbus::Connection) -> Result<ManagerProxy<'static>> {
+    let manager = ManagerProxy::new(&dbus_conn)
+        .await
+        .error("Failed to create ManagerProxy")?;
+    // systemd only emits signals once a client subscribed. Another block may have done so on the
+    // same connection already.
+    if let Err(err) = manager.subscribe().await {
+        debug!("Subscribe failed: {err}");
+    }
+    Ok(manager)
+}
+
+async fn failed_units(manager: &ManagerProxy<'_>) -> Result<u32> {
+    manager
+        .n_failed_units()
+        .await
+        .error("Failed to get the number of failed units")
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+trait Manager {
+    fn subscribe(&self) -> zbus::Result<()>;
+
+    #[zbus(property, name = "NFailedUnits")]
+    fn n_failed_units(&self) -> zbus::Result<u32>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts() {
+        let both = FailedCounts {
+            system: Some(2),
+            user: Some(1),
+        };
+        assert_eq!(both.total(), 3);
+        assert_eq!(both.state(), State::Critical);
+
+        let none_failed = FailedCounts {
+            system: Some(0),
+            user: Some(0),
+        };
+        assert_eq!(none_failed.total(), 0);
+        assert_eq!(none_failed.state(), State::Idle);
+
+        // Only the counted managers add up and get a placeholder
+        let user_only = FailedCounts {
+            system: None,
+            user: Some(4),
+        };
+        assert_eq!(user_only.total(), 4);
+        assert_eq!(user_only.state(), State::Critical);
+        let values = user_only.values();
+        assert!(values.contains_key("failed"));
+        assert!(values.contains_key("user"));
+        assert!(!values.contains_key("system"));
+
+        let mut keys: Vec<_> = both.values().into_keys().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["failed", "icon", "system", "user"]);
+    }
+}
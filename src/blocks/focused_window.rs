@@ -1,15 +1,15 @@
 //! Currently focused window
 //!
 //! This block displays the title and/or the active marks (when used with `sway`/`i3`) of the currently
-//! focused window. Supported WMs are: `sway`, `i3` and most wlroots-based compositors. See `driver`
-//! option for more info.
+//! focused window. Supported WMs are: `sway`, `i3`, Hyprland, niri and most wlroots-based compositors. See
+//! `driver` option for more info.
 //!
 //! # Configuration
 //!
 //! Key | Values | Default
 //! ----|--------|--------
 //! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" $title.str(max_w:21) \|\"</code>
-//! `driver` | Which driver to use. Available values: `sway_ipc` - for `i3` and `sway`, `wlr_toplevel_management` - for Wayland compositors that implement [wlr-foreign-toplevel-management-unstable-v1](https://gitlab.freedesktop.org/wlroots/wlr-protocols/-/blob/master/unstable/wlr-foreign-toplevel-management-unstable-v1.xml), `auto` - try to automatically guess which driver to use. | `"auto"`
+//! `driver` | Which driver to use. Available values: `sway_ipc` - for `i3` and `sway` (Linux only), `hyprland` - for Hyprland, `niri` - for niri, `wlr_toplevel_management` - for Wayland compositors that implement [wlr-foreign-toplevel-management-unstable-v1](https://gitlab.freedesktop.org/wlroots/wlr-protocols/-/blob/master/unstable/wlr-foreign-toplevel-management-unstable-v1.xml), `auto` - use `hyprland` if `$HYPRLAND_INSTANCE_SIGNATURE` is set, `niri` if `$NIRI_SOCKET` is set, otherwise try to automatically guess which driver to use. | `"auto"`
 //!
 //! Placeholder     | Value                                                                 | Type | Unit
 //! ----------------|-----------------------------------------------------------------------|------|-----
@@ -35,9 +35,15 @@
 //! format = " $title.str(0,21) | Missing "
 //! ```
 
+mod hyprland;
+mod niri;
+#[cfg(target_os = "linux")]
 mod sway_ipc;
 mod wlr_toplevel_management;
 
+use hyprland::Hyprland;
+use niri::Niri;
+#[cfg(target_os = "linux")]
 use sway_ipc::SwayIpc;
 use wlr_toplevel_management::WlrToplevelManagement;
 
@@ -55,7 +61,10 @@ pub struct Config {
 pub enum Driver {
     #[default]
     Auto,
+    #[cfg(target_os = "linux")]
     SwayIpc,
+    Hyprland,
+    Niri,
     WlrToplevelManagement,
 }
 
@@ -63,11 +72,19 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
     let format = config.format.with_default(" $title.str(max_w:21) |")?;
 
     let mut backend: Box<dyn Backend> = match config.driver {
+        Driver::Auto if crate::hyprland::is_running() => Box::new(Hyprland::new().await?),
+        Driver::Auto if crate::niri::is_running() => Box::new(Niri::new().await?),
+        #[cfg(target_os = "linux")]
         Driver::Auto => match SwayIpc::new().await {
             Ok(swayipc) => Box::new(swayipc),
             Err(_) => Box::new(WlrToplevelManagement::new().await?),
         },
+        #[cfg(not(target_os = "linux"))]
+        Driver::Auto => Box::new(WlrToplevelManagement::new().await?),
+        #[cfg(target_os = "linux")]
         Driver::SwayIpc => Box::new(SwayIpc::new().await?),
+        Driver::Hyprland => Box::new(Hyprland::new().await?),
+        Driver::Niri => Box::new(Niri::new().await?),
         Driver::WlrToplevelManagement => Box::new(WlrToplevelManagement::new().await?),
     };
 
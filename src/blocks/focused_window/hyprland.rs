@@ -0,0 +1,65 @@
+use super::{Backend, Info};
+use crate::blocks::prelude::*;
+use crate::hyprland::{self, Events};
+
+pub(super) struct Hyprland {
+    events: Events,
+    /// The address of the focused window, as sent by `activewindowv2`
+    address: String,
+    /// The title is only read from the socket on the first call to `get_info`
+    initial: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ActiveWindow {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    address: String,
+}
+
+impl Hyprland {
+    pub(super) async fn new() -> Result<Self> {
+        let events = Events::new().await?;
+        // `{}` if no window is focused
+        let window: ActiveWindow = hyprland::request("activewindow").await?;
+        Ok(Self {
+            events,
+            address: window.address.trim_start_matches("0x").into(),
+            initial: Some(window.title),
+        })
+    }
+}
+
+#[async_trait]
+impl Backend for Hyprland {
+    async fn get_info(&mut self) -> Result<Info> {
+        if let Some(title) = self.initial.take() {
+            return Ok(Info {
+                title,
+                marks: Vec::new(),
+            });
+        }
+        loop {
+            let (event, data) = self.events.next().await?;
+            let title = match event.as_str() {
+                // `class,title`
+                "activewindow" => data.split_once(',').map_or("", |(_, title)| title),
+                "activewindowv2" => {
+                    self.address = data;
+                    continue;
+                }
+                // `address,title`
+                "windowtitlev2" => match data.split_once(',') {
+                    Some((address, title)) if address == self.address => title,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            return Ok(Info {
+                title: title.into(),
+                marks: Vec::new(),
+            });
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use super::{Backend, Info};
+use crate::blocks::prelude::*;
+use crate::niri::{EventStream, State};
+
+pub(super) struct Niri {
+    events: EventStream,
+    state: State,
+    /// The last title returned by `get_info`, `None` before the first call
+    title: Option<String>,
+}
+
+impl Niri {
+    pub(super) async fn new() -> Result<Self> {
+        Ok(Self {
+            events: EventStream::new().await?,
+            state: State::default(),
+            title: None,
+        })
+    }
+}
+
+#[async_trait]
+impl Backend for Niri {
+    async fn get_info(&mut self) -> Result<Info> {
+        loop {
+            // niri sends the current state as events first
+            self.state.apply(self.events.next().await?);
+            let title = self
+                .state
+                .focused_window()
+                .and_then(|w| w.title.clone())
+                .unwrap_or_default();
+            if self.title.as_ref() != Some(&title) {
+                self.title = Some(title.clone());
+                return Ok(Info {
+                    title,
+                    marks: Vec::new(),
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! A rotating fortune, quote or word of the day
+//!
+//! This block shows an entry taken either from the output of a command (e.g. `fortune`) or from a
+//! file with one entry per line, and moves on to the next entry every `interval` seconds or on
+//! click. In file mode, the entries are shown in order and the file is reread on every rotation,
+//! so it may be edited while the bar is running. Empty lines are skipped. In command mode, the
+//! command is run on every rotation, and line breaks in its output are replaced with spaces.
+//!
+//! Long entries are best displayed with the rotating string formatter, as in the default format.
+//!
+//! Exactly one of `command` and `file` must be set.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" $text.str(max_w:40,rot_interval:0.5) \"</code>
+//! `command` | Shell command which prints an entry | None
+//! `file` | File with one entry per line. Supports path expansions e.g. `~`. | None
+//! `interval` | Update interval in seconds | `600`
+//!
+//! Placeholder | Value                                     | Type   | Unit
+//! ------------|-------------------------------------------|--------|-----
+//! `text`      | The current entry                         | Text   | -
+//! `index`     | The number of the entry. Only in file mode. | Number | -
+//! `count`     | The number of entries. Only in file mode. | Number | -
+//!
+//! Action | Default button
+//! -------|---------------
+//! `next` | Left
+//!
+//! # Examples
+//!
+//! ```toml
+//! [[block]]
+//! block = "fortune"
+//! command = "fortune -s"
+//! interval = 300
+//! ```
+//!
+//! ```toml
+//! [[block]]
+//! block = "fortune"
+//! file = "~/words.txt"
+//! format = " $text ($index.eng(w:1)/$count.eng(w:1)) "
+//! interval = 86400
+//! ```
+
+use tokio::process::Command;
+
+use super::prelude::*;
+use crate::util::read_file;
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    pub command: Option<String>,
+    pub file: Option<ShellString>,
+    #[default(600.into())]
+    pub interval: Seconds,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+    api.set_default_actions(&[(MouseButton::Left, None, "next")])?;
+
+    let format = config
+        .format
+        .with_default(" $text.str(max_w:40,rot_interval:0.5) ")?;
+
+    let source = match (&config.command, &config.file) {
+        (Some(command), None) => Source::Command(command),
+        (None, Some(file)) => Source::File(file.expand()?.into_owned()),
+        _ => return Err(Error::new("Exactly one of 'command' or 'file' must be set")),
+    };
+
+    let mut timer = config.interval.timer();
+    let mut index = 0;
+
+    loop {
+        let mut widget = Widget::new().with_format(format.clone());
+        match &source {
+            Source::Command(command) => {
+                widget.set_values(map!("text" => Value::text(run_command(command).await?)));
+            }
+            Source::File(path) => {
+                let content = read_file(path)
+                    .await
+                    .or_error(|| format!("Failed to read '{path}'"))?;
+                let entries: Vec<&str> = content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .collect();
+                if entries.is_empty() {
+                    return Err(Error::new(format!("'{path}' has no entries")));
+                }
+                index %= entries.len();
+                widget.set_values(map! {
+                    "text" => Value::text(entries[index].into()),
+                    "index" => Value::number(index + 1),
+                    "count" => Value::number(entries.len()),
+                });
+            }
+        }
+        api.set_widget(widget)?;
+
+        select! {
+            _ = timer.tick() => index += 1,
+            _ = api.wait_for_update_request() => (),
+            Some(action) = actions.recv() => {
+                if action == "next" {
+                    timer.reset();
+                    index += 1;
+                }
+            }
+        }
+    }
+}
+
+enum Source<'a> {
+    Command(&'a str),
+    File(String),
+}
+
+/// Run the command and return its output as a single line
+async fn run_command(command: &str) -> Result<String> {
+    let output = Command::new("sh")
+        .args(["-c", command])
+        .output()
+        .await
+        .error("Failed to run command")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::new(format!("Command failed: {}", stderr.trim())));
+    }
+    Ok(single_line(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Join the lines of a (possibly multi-line) entry, collapsing all whitespace to single spaces
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace() {
+        assert_eq!(
+            single_line("  Q:\tWhat is orange?\n\n\tA:  An orange.\n"),
+            "Q: What is orange? A: An orange."
+        );
+        assert_eq!(single_line("One line"), "One line");
+        assert_eq!(single_line(" \n\t\n"), "");
+    }
+
+    #[tokio::test]
+    async fn command() {
+        assert_eq!(
+            run_command("printf 'a\\n  b\\n'").await.unwrap(),
+            "a b"
+        );
+        let err = run_command("echo 'not found' >&2; exit 1")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+}
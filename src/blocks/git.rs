@@ -0,0 +1,221 @@
+//! Branch and status of a git repository
+//!
+//! This block shows the current branch of a repository, how many commits it is ahead of and behind
+//! its upstream, and whether the working tree has uncommitted changes. The repository is watched
+//! with inotify, so commits, checkouts, fetches and staged changes are shown immediately. Changes
+//! to the working tree which are not staged are noticed on the next `interval`.
+//!
+//! The `git` command must be installed.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `path` | Path to the repository (or any directory inside it). Supports path expansions e.g. `~`. | **Required**
+//! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" $icon $branch{$dirty *\|}{ ↑$ahead\|}{ ↓$behind\|} \"</code>
+//! `interval` | Update interval in seconds, for changes which inotify does not notice | `30`
+//!
+//! Placeholder | Value                                                                  | Type   | Unit
+//! ------------|------------------------------------------------------------------------|--------|-----
+//! `icon`      | A static icon                                                          | Icon   | -
+//! `branch`    | The current branch, or the abbreviated commit if `HEAD` is detached    | Text   | -
+//! `ahead`     | The number of commits not pushed to the upstream. Absent if zero or without upstream. | Number | -
+//! `behind`    | The number of upstream commits not merged yet. Absent if zero or without upstream. | Number | -
+//! `dirty`     | Present if there are uncommitted changes or untracked files           | Flag   | -
+//! `changes`   | The number of changed and untracked files                              | Number | -
+//!
+//! The block is in the info state if the working tree is dirty, and in the warning state if the
+//! branch has diverged from its upstream.
+//!
+//! # Example
+//!
+//! ```toml
+//! [[block]]
+//! block = "git"
+//! path = "~/dotfiles"
+//! format = " $icon dotfiles {$changes changed|clean}{ ↑$ahead|} "
+//! ```
+//!
+//! # Icons Used
+//! - `git`
+
+use futures::FutureExt;
+use inotify::{Inotify, WatchMask};
+use std::path::PathBuf;
+use tokio::process::Command;
+
+use super::prelude::*;
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub path: ShellString,
+    #[serde(default)]
+    pub format: FormatConfig,
+    #[serde(default = "default_interval")]
+    pub interval: Seconds,
+}
+
+fn default_interval() -> Seconds {
+    30.into()
+}
+
+/// How long to wait for more changes after inotify reported one, since git writes several files
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let format = config
+        .format
+        .with_default(" $icon $branch{$dirty *|}{ ↑$ahead|}{ ↓$behind|} ")?;
+
+    let path = PathBuf::from(&*config.path.expand()?);
+    // `.git` may be a file pointing to the actual directory (in worktrees and submodules)
+    let git_dir = git(&path, &["rev-parse", "--absolute-git-dir"]).await?;
+
+    let notify = Inotify::init().error("Failed to start inotify")?;
+    let mask = WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::CREATE | WatchMask::DELETE;
+    // `HEAD`, `index` and `FETCH_HEAD` are in the git directory, local branches in `refs/heads`
+    notify
+        .watches()
+        .add(&git_dir, mask)
+        .error("Failed to watch the git directory")?;
+    let _ = notify
+        .watches()
+        .add(format!("{git_dir}/refs/heads"), mask);
+    let mut updates = notify
+        .into_event_stream([0; 1024])
+        .error("Failed to create event stream")?;
+
+    let mut timer = config.interval.timer();
+
+    loop {
+        let output = git(&path, &["status", "--porcelain=v2", "--branch"]).await?;
+        let status = parse_status(&output);
+
+        let mut widget = Widget::new().with_format(format.clone());
+        widget.state = if status.ahead > 0 && status.behind > 0 {
+            State::Warning
+        } else if status.changes > 0 {
+            State::Info
+        } else {
+            State::Idle
+        };
+        widget.set_values(map! {
+            "icon" => Value::icon("git"),
+            "branch" => Value::text(status.branch),
+            "changes" => Value::number(status.changes),
+            [if status.changes > 0] "dirty" => Value::flag(),
+            [if status.ahead > 0] "ahead" => Value::number(status.ahead),
+            [if status.behind > 0] "behind" => Value::number(status.behind),
+        });
+        api.set_widget(widget)?;
+
+        select! {
+            _ = timer.tick() => (),
+            _ = api.wait_for_update_request() => (),
+            Some(update) = updates.next() => {
+                update.error("Bad inotify update")?;
+                tokio::time::sleep(DEBOUNCE).await;
+                // Drop the events of the same change
+                while let Some(Some(update)) = updates.next().now_or_never() {
+                    update.error("Bad inotify update")?;
+                }
+            }
+        }
+    }
+}
+
+/// Runs a git command in `path` and returns its trimmed output
+async fn git(path: &std::path::Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        // Keep `git status` from refreshing the index, which would trigger inotify again
+        .arg("--no-optional-locks")
+        .arg("-C")
+        .arg(path)
+        .args(args)
+        .env("LC_ALL", "C")
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .error("Failed to run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::new(format!("git failed: {}", stderr.trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Status {
+    branch: String,
+    ahead: u32,
+    behind: u32,
+    changes: usize,
+}
+
+/// Parses the output of `git status --porcelain=v2 --branch`
+fn parse_status(output: &str) -> Status {
+    let mut status = Status::default();
+    let mut commit = "";
+    for line in output.lines() {
+        if let Some(header) = line.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.oid" => commit = value,
+                "branch.head" => status.branch = value.to_owned(),
+                "branch.ab" => {
+                    for count in value.split(' ') {
+                        if let Some(ahead) = count.strip_prefix('+') {
+                            status.ahead = ahead.parse().unwrap_or(0);
+                        } else if let Some(behind) = count.strip_prefix('-') {
+                            status.behind = behind.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        } else if !line.starts_with('!') {
+            status.changes += 1;
+        }
+    }
+    if status.branch == "(detached)" {
+        status.branch = commit.chars().take(7).collect();
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status() {
+        let output = "\
+# branch.oid 3f4e2a1c9b8d7e6f5a4b3c2d1e0f9a8b7c6d5e4f
+# branch.head main
+# branch.upstream origin/main
+# branch.ab +2 -0
+1 .M N... 100644 100644 100644 3f4e2a1c 3f4e2a1c src/main.rs
+? notes.txt";
+        assert_eq!(
+            parse_status(output),
+            Status {
+                branch: "main".into(),
+                ahead: 2,
+                behind: 0,
+                changes: 2,
+            }
+        );
+
+        let output = "\
+# branch.oid 3f4e2a1c9b8d7e6f5a4b3c2d1e0f9a8b7c6d5e4f
+# branch.head (detached)";
+        assert_eq!(
+            parse_status(output),
+            Status {
+                branch: "3f4e2a1".into(),
+                ..default()
+            }
+        );
+    }
+}
@@ -59,6 +59,7 @@
 //! - `github`
 
 use super::prelude::*;
+use crate::formatting::intern::Istr;
 
 #[derive(Deserialize, Debug, SmartDefault)]
 #[serde(deny_unknown_fields, default)]
@@ -106,11 +107,12 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
                 }
             }
 
-            let mut values: HashMap<_, _> = stats
+            let mut values: Values = stats
                 .into_iter()
-                .map(|(k, v)| (k.into(), Value::number(v)))
+                // The reasons come from GitHub, so don't intern those which no format uses
+                .filter_map(|(k, v)| Some((Istr::lookup(&k)?.as_str(), Value::number(v))))
                 .collect();
-            values.insert("icon".into(), Value::icon("github"));
+            values.insert("icon", Value::icon("github"));
             widget.set_values(values);
 
             api.set_widget(widget)?;
@@ -0,0 +1,88 @@
+//! A group of blocks which can be folded
+//!
+//! This block wraps several child blocks. While folded, only a summary is displayed. Clicking the
+//! summary expands the group and displays its blocks inline, right after the summary.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block while folded. See below for available placeholders. | `" $name+ "`
+//! `format_expanded` | A string to customise the output of this block while expanded. See below for available placeholders. | `" $name- "`
+//! `name` | Name of the group | `""`
+//! `expanded` | Whether the group is expanded on start | `false`
+//! `[[block.blocks]]` | The blocks in this group. Groups cannot be nested. | `[]`
+//!
+//! Placeholder | Value                          | Type   | Unit
+//! ------------|--------------------------------|--------|-----
+//! `name`      | The value of `name` option     | Text   | -
+//! `count`     | Number of blocks in this group | Number | -
+//!
+//! Action   | Default button
+//! ---------|---------------
+//! `toggle` | Left
+//!
+//! # Example
+//!
+//! ```toml
+//! [[block]]
+//! block = "group"
+//! name = "sys"
+//! format = " $name ($count) "
+//! [[block.blocks]]
+//! block = "cpu"
+//! [[block.blocks]]
+//! block = "memory"
+//! ```
+
+use super::prelude::*;
+use crate::config::BlockConfigEntry;
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    pub format_expanded: FormatConfig,
+    pub name: String,
+    pub expanded: bool,
+    pub blocks: Vec<BlockConfigEntry>,
+    /// The number of blocks, set when the blocks are moved out to be spawned by the bar
+    #[serde(skip)]
+    pub count: usize,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    api.set_default_actions(&[(MouseButton::Left, None, "toggle")])?;
+
+    let format = config.format.with_default(" $name+ ")?;
+    let format_expanded = config.format_expanded.with_default(" $name- ")?;
+
+    let mut expanded = config.expanded;
+    let mut actions = api.get_actions()?;
+
+    loop {
+        api.set_expanded(expanded)?;
+
+        let mut widget = Widget::new().with_format(if expanded {
+            format_expanded.clone()
+        } else {
+            format.clone()
+        });
+        widget.set_values(map! {
+            "name" => Value::text(config.name.clone()),
+            "count" => Value::number(config.count),
+        });
+        api.set_widget(widget)?;
+
+        loop {
+            match actions.recv().await.as_deref() {
+                Some("toggle") => {
+                    expanded = !expanded;
+                    break;
+                }
+                Some(_) => (),
+                None => return Ok(()),
+            }
+        }
+    }
+}
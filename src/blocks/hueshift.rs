@@ -4,6 +4,9 @@
 //! A left click on the block sets the color temperature to `click_temp` that is by default to `6500K`.
 //! A right click completely resets the color temperature to its default value (`6500K`).
 //!
+//! With `wl_gammarelay_rs`, the block can also control the brightness of the screens in software,
+//! which is useful for desktop monitors without a backlight that can be controlled.
+//!
 //! # Configuration
 //!
 //! Key | Values | Default
@@ -14,10 +17,12 @@
 //! `max_temp`    | Max color temperature in Kelvin. | `10000`
 //! `min_temp`    | Min color temperature in Kelvin. | `1000`
 //! `click_temp`  | Left click color temperature in Kelvin. | `6500`
+//! `brightness_step` | The step brightness is in/decreased in percent (`wl_gammarelay_rs` only). | `5`
 //!
 //! Placeholder           | Value                        | Type   | Unit
 //! ----------------------|------------------------------|--------|---------------
 //! `temperature`         | Current temperature          | Number | -
+//! `brightness`          | Current brightness. Absent unless `wl_gammarelay_rs` is used. | Number | %
 //!
 //! Action             | Default button
 //! -------------------|---------------
@@ -25,6 +30,8 @@
 //! `reset`            | Right
 //! `temperature_up`   | Wheel Up
 //! `temperature_down` | Wheel Down
+//! `brightness_up`    | -
+//! `brightness_down`  | -
 //!
 //! # Available Hue Shifters
 //!
@@ -52,8 +59,24 @@
 //! click_temp = 3500
 //! ```
 //!
+//! Scroll to change the brightness instead of the temperature with `wl-gammarelay-rs`
+//!
+//! ```toml
+//! [[block]]
+//! block = "hueshift"
+//! hue_shifter = "wl_gammarelay_rs"
+//! format = " $temperature $brightness "
+//! [[block.click]]
+//! button = "up"
+//! action = "brightness_up"
+//! [[block.click]]
+//! button = "down"
+//! action = "brightness_down"
+//! ```
+//!
 //! A hard limit is set for the `max_temp` to `10000K` and the same for the `min_temp` which is `1000K`.
 //! The `step` has a hard limit as well, defined to `500K` to avoid too brutal changes.
+//! The brightness cannot be decreased below `10%`, so that the screen does not go black.
 
 use super::prelude::*;
 use crate::subprocess::{spawn_process, spawn_shell};
@@ -79,8 +102,13 @@ pub struct Config {
     pub step: u16,
     #[default(6_500)]
     pub click_temp: u16,
+    #[default(5)]
+    pub brightness_step: u8,
 }
 
+/// Below this, a screen dimmed in software is too dark to use
+const MIN_BRIGHTNESS: f64 = 0.1;
+
 pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
     let mut actions = api.get_actions()?;
     api.set_default_actions(&[
@@ -127,21 +155,29 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         HueShifter::WlGammarelayRs => Box::new(WlGammarelayRs::new("wl-gammarelay-rs").await?),
     };
 
+    let brightness_step = f64::from(config.brightness_step) / 100.0;
+
     let mut current_temp = driver.get().await?.unwrap_or(config.current_temp);
+    let mut brightness = driver.get_brightness().await?;
 
     loop {
         let mut widget = Widget::new().with_format(format.clone());
-        widget.set_values(map!("temperature" => Value::number(current_temp)));
+        widget.set_values(map! {
+            "temperature" => Value::number(current_temp),
+            [if let Some(b) = brightness] "brightness" => Value::percents(b * 100.0),
+        });
         api.set_widget(widget)?;
 
         select! {
-            update = driver.receive_update() => {
-                current_temp = update?;
-            }
+            update = driver.receive_update() => match update? {
+                Update::Temperature(temp) => current_temp = temp,
+                Update::Brightness(b) => brightness = Some(b),
+            },
             _ = api.wait_for_update_request() => {
                 if let Some(val) = driver.get().await? {
                     current_temp = val;
                 }
+                brightness = driver.get_brightness().await?;
             }
             Some(action) = actions.recv() => match action.as_ref() {
                 "set_click_temp" => {
@@ -165,6 +201,16 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
                     current_temp = current_temp.saturating_sub(step).max(min_temp);
                     driver.update(current_temp).await?;
                 }
+                "brightness_up" | "brightness_down" => {
+                    if let Some(b) = &mut brightness {
+                        *b = if action == "brightness_up" {
+                            (*b + brightness_step).min(1.0)
+                        } else {
+                            (*b - brightness_step).max(MIN_BRIGHTNESS)
+                        };
+                        driver.set_brightness(*b).await?;
+                    }
+                }
                 _ => (),
             }
         }
@@ -182,12 +228,26 @@ pub enum HueShifter {
     WlGammarelayRs,
 }
 
+/// A change which was made outside of the block
+enum Update {
+    Temperature(u16),
+    Brightness(f64),
+}
+
 #[async_trait]
-trait HueShiftDriver {
+trait HueShiftDriver: Send {
     async fn get(&mut self) -> Result<Option<u16>>;
     async fn update(&mut self, temp: u16) -> Result<()>;
     async fn reset(&mut self) -> Result<()>;
-    async fn receive_update(&mut self) -> Result<u16>;
+    async fn receive_update(&mut self) -> Result<Update>;
+
+    /// The brightness from 0 to 1, if the driver can control it
+    async fn get_brightness(&mut self) -> Result<Option<f64>> {
+        Ok(None)
+    }
+    async fn set_brightness(&mut self, _brightness: f64) -> Result<()> {
+        Ok(())
+    }
 }
 
 struct Redshift {
@@ -214,7 +274,7 @@ impl HueShiftDriver for Redshift {
         spawn_process("redshift", &["-x"])
             .error("Failed to set new color temperature using redshift.")
     }
-    async fn receive_update(&mut self) -> Result<u16> {
+    async fn receive_update(&mut self) -> Result<Update> {
         sleep(self.interval.0).await;
         // self.get().await
         pending().await
@@ -244,7 +304,7 @@ impl HueShiftDriver for Sct {
     async fn reset(&mut self) -> Result<()> {
         spawn_process("sct", &[]).error("Failed to set new color temperature using sct.")
     }
-    async fn receive_update(&mut self) -> Result<u16> {
+    async fn receive_update(&mut self) -> Result<Update> {
         sleep(self.interval.0).await;
         // self.get().await
         pending().await
@@ -275,7 +335,7 @@ impl HueShiftDriver for Gammastep {
         spawn_process("gammastep", &["-x"])
             .error("Failed to set new color temperature using gammastep.")
     }
-    async fn receive_update(&mut self) -> Result<u16> {
+    async fn receive_update(&mut self) -> Result<Update> {
         sleep(self.interval.0).await;
         // self.get().await
         pending().await
@@ -321,7 +381,7 @@ impl HueShiftDriver for Wlsunset {
         spawn_process("pkill", &["wlsunset"])
             .error("Failed to set new color temperature using wlsunset.")
     }
-    async fn receive_update(&mut self) -> Result<u16> {
+    async fn receive_update(&mut self) -> Result<Update> {
         sleep(self.interval.0).await;
         // self.get().await
         pending().await
@@ -331,6 +391,7 @@ impl HueShiftDriver for Wlsunset {
 struct WlGammarelayRs {
     proxy: WlGammarelayRsBusProxy<'static>,
     updates: zbus::proxy::PropertyStream<'static, u16>,
+    brightness_updates: zbus::proxy::PropertyStream<'static, f64>,
 }
 
 impl WlGammarelayRs {
@@ -344,7 +405,12 @@ impl WlGammarelayRs {
             .await
             .error("Failed to create wl-gammarelay-rs DBus proxy")?;
         let updates = proxy.receive_temperature_changed().await;
-        Ok(Self { proxy, updates })
+        let brightness_updates = proxy.receive_brightness_changed().await;
+        Ok(Self {
+            proxy,
+            updates,
+            brightness_updates,
+        })
     }
 }
 
@@ -367,9 +433,33 @@ impl HueShiftDriver for WlGammarelayRs {
     async fn reset(&mut self) -> Result<()> {
         self.update(6500).await
     }
-    async fn receive_update(&mut self) -> Result<u16> {
-        let update = self.updates.next().await.error("No next update")?;
-        update.get().await.error("Failed to get temperature")
+    async fn receive_update(&mut self) -> Result<Update> {
+        select! {
+            update = self.updates.next() => {
+                let update = update.error("No next update")?;
+                let temp = update.get().await.error("Failed to get temperature")?;
+                Ok(Update::Temperature(temp))
+            }
+            update = self.brightness_updates.next() => {
+                let update = update.error("No next update")?;
+                let brightness = update.get().await.error("Failed to get brightness")?;
+                Ok(Update::Brightness(brightness))
+            }
+        }
+    }
+    async fn get_brightness(&mut self) -> Result<Option<f64>> {
+        let value = self
+            .proxy
+            .brightness()
+            .await
+            .error("Failed to get brightness")?;
+        Ok(Some(value))
+    }
+    async fn set_brightness(&mut self, brightness: f64) -> Result<()> {
+        self.proxy
+            .set_brightness(brightness)
+            .await
+            .error("Failed to set brightness")
     }
 }
 
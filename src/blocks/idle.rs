@@ -0,0 +1,523 @@
+//! Idle daemon status, screen lock status and upcoming screen lock
+//!
+//! This block shows which idle daemon (e.g. `swayidle` or `hypridle`) is running, whether the
+//! screen is locked, and whether another application inhibits idling (e.g. a video player). If the
+//! idle daemon's lock timeout is known, the block also shows the time left until the screen is
+//! locked once the user has been idle for `timeout - warning` seconds. The timeout is either set
+//! with `timeout`, or read on startup from the arguments or config file of a running `swayidle`
+//! (the shortest timeout whose command contains `lock`).
+//!
+//! How long the user has been idle is known from the `ext-idle-notify-v1` Wayland protocol. If it
+//! is not available, e.g. on X11, the `IdleHint` of the `systemd-logind` session is used instead,
+//! which requires the session to report it (e.g. with `swayidle idlehint <seconds>` or
+//! `xss-lock`).
+//!
+//! Clicking the block postpones locking: the block takes an idle inhibitor from `systemd-logind`
+//! and `org.freedesktop.ScreenSaver` (when available) until the user returns after `timeout` has
+//! passed, i.e. the next lock is skipped. If `timeout` is not set, locking is postponed until the
+//! block is clicked again. Note that the idle daemon must respect these inhibitors, which is the
+//! case for `hypridle`, but not for `swayidle`.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" {$daemon{ $remaining\|}{$postponed (postponed)\|}\|no idle daemon} \"</code>
+//! `daemons` | The names of the idle daemon processes | `["swayidle", "hypridle"]`
+//! `lockers` | The names of the screen locker processes | `["swaylock", "hyprlock", "i3lock", "gtklock", "xsecurelock"]`
+//! `timeout` | The lock timeout of the idle daemon, in seconds | Read from `swayidle`
+//! `warning` | How long before locking to show the remaining time, in seconds | `60`
+//! `interval` | How often to check for the idle daemon, in seconds | `5`
+//! `lock_command` | The command which the `lock` action runs | None
+//!
+//! Placeholder | Value                                                          | Type     | Unit
+//! ------------|----------------------------------------------------------------|----------|-----
+//! `daemon`    | The name of the running idle daemon. Absent if none is running. | Text     | -
+//! `remaining` | Time left until the screen is locked. Absent unless locking is imminent. | Duration | -
+//! `postponed` | Present if locking is postponed                                | Flag     | -
+//! `inhibited` | Present if another application inhibits idling                 | Flag     | -
+//! `locked`    | Present if a screen locker is running                          | Flag     | -
+//! `last_lock` | When a screen locker was last started. Absent if the screen was not locked since the block started. | DateTime | -
+//!
+//! The block's state is critical if no idle daemon is running, warning if locking is imminent and
+//! info if locking is postponed or inhibited.
+//!
+//! Action     | Description                                          | Default button
+//! -----------|------------------------------------------------------|---------------
+//! `postpone` | Postpone the next lock                               | Left
+//! `lock`     | Lock the screen now, using `lock_command` if it is set, otherwise through `systemd-logind` (which the idle daemon handles, e.g. `swayidle lock 'swaylock -f'`) | Right
+//!
+//! # Example
+//!
+//! ```toml
+//! [[block]]
+//! block = "idle"
+//! daemons = ["hypridle"]
+//! timeout = 300
+//! ```
+//!
+//! Show when the screen was last locked and whether a video player keeps the screen on:
+//!
+//! ```toml
+//! [[block]]
+//! block = "idle"
+//! format = " {$remaining|}{$inhibited inhibited|} {$last_lock.datetime(f:'%R')|} "
+//! ```
+
+use std::os::fd::OwnedFd;
+use std::time::{Instant, SystemTime};
+
+use chrono::Utc;
+
+use wayrs_client::global::GlobalsExt;
+use wayrs_client::protocol::WlSeat;
+use wayrs_client::{Connection, EventCtx};
+use wayrs_protocols::ext_idle_notify_v1::*;
+
+use super::prelude::*;
+use crate::subprocess::spawn_shell;
+
+make_log_macro!(warn, "idle");
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    #[default(vec!["swayidle".into(), "hypridle".into()])]
+    pub daemons: Vec<String>,
+    #[default(vec![
+        "swaylock".into(),
+        "hyprlock".into(),
+        "i3lock".into(),
+        "gtklock".into(),
+        "xsecurelock".into(),
+    ])]
+    pub lockers: Vec<String>,
+    pub timeout: Option<Seconds>,
+    #[default(60.into())]
+    pub warning: Seconds,
+    #[default(5.into())]
+    pub interval: Seconds,
+    pub lock_command: Option<String>,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+    api.set_default_actions(&[
+        (MouseButton::Left, None, "postpone"),
+        (MouseButton::Right, None, "lock"),
+    ])?;
+
+    let format = config
+        .format
+        .with_default(" {$daemon{ $remaining|}{$postponed (postponed)|}|no idle daemon} ")?;
+
+    let timeout = match config.timeout {
+        Some(timeout) => Some(timeout.0),
+        None => find_process(&["swayidle".into()]).and_then(|(pid, _)| swayidle_timeout(pid)),
+    };
+    // The user is considered idle after this long, at which point the countdown is displayed
+    let notify_after = timeout.map(|t| t.saturating_sub(config.warning.0));
+    // Without Wayland, logind is asked how long the user has been idle
+    let mut idle_monitor = None;
+    let mut session = None;
+    if let Some(after) = notify_after {
+        match IdleMonitor::new(after).await {
+            Ok(monitor) => idle_monitor = Some(monitor),
+            Err(error) => {
+                session = Some(
+                    logind_session()
+                        .await
+                        .map_err(|_| error)
+                        .error("Neither ext-idle-notify-v1 nor logind are available")?,
+                );
+            }
+        }
+    }
+    // When the user became idle (only known after `notify_after`)
+    let mut idle_since: Option<Instant> = None;
+
+    let manager = logind_manager().await.ok();
+    let mut inhibitor: Option<Inhibitor> = None;
+    let mut last_lock = None;
+    let mut was_locked = false;
+
+    loop {
+        let daemon = find_process(&config.daemons).map(|(_, name)| name);
+        let locked = find_process(&config.lockers).is_some();
+        if locked && !was_locked {
+            last_lock = Some(Utc::now());
+        }
+        was_locked = locked;
+        let inhibited = match &manager {
+            Some(manager) => manager
+                .block_inhibited()
+                .await
+                .is_ok_and(|what| what.split(':').any(|w| w == "idle")),
+            None => false,
+        };
+        // Our own inhibitor is shown as postponed instead
+        let inhibited = inhibited && inhibitor.is_none();
+
+        if let (Some(session), Some(after)) = (&session, notify_after) {
+            idle_since = logind_idle_since(session)
+                .await
+                .filter(|since| since.elapsed() >= after);
+        }
+        let remaining = match (idle_since, timeout) {
+            (Some(since), Some(timeout)) if !locked => {
+                Some(timeout.saturating_sub(since.elapsed()))
+            }
+            _ => None,
+        };
+
+        let mut widget = Widget::new().with_format(format.clone());
+        widget.state = if daemon.is_none() {
+            State::Critical
+        } else if inhibitor.is_some() || inhibited {
+            State::Info
+        } else if remaining.is_some() {
+            State::Warning
+        } else {
+            State::Idle
+        };
+        widget.set_values(map! {
+            [if let Some(d) = daemon] "daemon" => Value::text(d),
+            [if let Some(r) = remaining] "remaining" => Value::duration(r),
+            [if inhibitor.is_some()] "postponed" => Value::flag(),
+            [if inhibited] "inhibited" => Value::flag(),
+            [if locked] "locked" => Value::flag(),
+            [if let Some(t) = last_lock] "last_lock" => Value::datetime(t, None),
+        });
+        api.set_widget(widget)?;
+
+        // Tick every second during the countdown
+        let delay = if remaining.is_some() {
+            Duration::from_secs(1)
+        } else {
+            config.interval.0
+        };
+
+        select! {
+            _ = sleep(delay) => (),
+            _ = api.wait_for_update_request() => (),
+            idle = next_idle_event(&mut idle_monitor) => {
+                let idle = idle?;
+                if idle {
+                    idle_since = notify_after.map(|after| Instant::now() - after);
+                } else {
+                    // The user is back. If the lock was skipped, postponing is over.
+                    let skipped = idle_since
+                        .zip(timeout)
+                        .is_some_and(|(since, timeout)| since.elapsed() >= timeout);
+                    if skipped {
+                        inhibitor = None;
+                    }
+                    idle_since = None;
+                }
+            }
+            Some(action) = actions.recv() => match action.as_ref() {
+                "postpone" => {
+                    inhibitor = match inhibitor {
+                        Some(_) => None,
+                        None => match Inhibitor::new("idle", "Locking postponed").await {
+                            Ok(inhibitor) => Some(inhibitor),
+                            Err(err) => {
+                                warn!("{err}");
+                                None
+                            }
+                        },
+                    };
+                }
+                "lock" => {
+                    if let Err(err) = lock(config.lock_command.as_deref()).await {
+                        warn!("{err}");
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Find the first running process with one of the given names
+fn find_process(names: &[String]) -> Option<(u32, String)> {
+    let procs = std::fs::read_dir("/proc").ok()?;
+    let running: Vec<(u32, String)> = procs
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse().ok()?;
+            let comm = std::fs::read_to_string(entry.path().join("comm")).ok()?;
+            Some((pid, comm.trim_end().to_owned()))
+        })
+        .collect();
+    names
+        .iter()
+        .find_map(|name| running.iter().find(|(_, comm)| comm == name).cloned())
+}
+
+/// The lock timeout of a running `swayidle`, from its arguments or its config file
+fn swayidle_timeout(pid: u32) -> Option<Duration> {
+    let cmdline = std::fs::read_to_string(format!("/proc/{pid}/cmdline")).ok()?;
+    let args: Vec<&str> = cmdline.split('\0').skip(1).collect();
+    if let Some(timeout) = lock_timeout_from_args(&args) {
+        return Some(timeout);
+    }
+    let config_path = match args.iter().position(|&arg| arg == "-C") {
+        Some(i) => args.get(i + 1)?.into(),
+        None => dirs::config_dir()?.join("swayidle/config"),
+    };
+    lock_timeout_from_config(&std::fs::read_to_string(config_path).ok()?)
+}
+
+/// The shortest `timeout <seconds> <command>` whose command locks the screen, in the arguments
+/// of `swayidle`, where each command is a single argument
+fn lock_timeout_from_args(args: &[&str]) -> Option<Duration> {
+    args.windows(3)
+        .filter(|w| w[0] == "timeout" && w[2].contains("lock"))
+        .filter_map(|w| w[1].parse().ok())
+        .min()
+        .map(Duration::from_secs)
+}
+
+/// Like `lock_timeout_from_args`, in a `swayidle` config file with one command per line
+fn lock_timeout_from_config(config: &str) -> Option<Duration> {
+    config
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            if words.next()? != "timeout" {
+                return None;
+            }
+            let seconds = words.next()?.parse().ok()?;
+            words.any(|w| w.contains("lock")).then_some(seconds)
+        })
+        .min()
+        .map(Duration::from_secs)
+}
+
+/// When the user became idle according to logind, if they are idle
+async fn logind_idle_since(session: &LogindSessionProxy<'_>) -> Option<Instant> {
+    if !session.idle_hint().await.ok()? {
+        return None;
+    }
+    let since_usec = session.idle_since_hint().await.ok()?;
+    let now_usec = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()?
+        .as_micros();
+    let idle_for = Duration::from_micros(now_usec.saturating_sub(since_usec.into()) as u64);
+    Instant::now().checked_sub(idle_for)
+}
+
+async fn lock(command: Option<&str>) -> Result<()> {
+    match command {
+        Some(command) => spawn_shell(command).error("Failed to run lock_command"),
+        None => logind_session()
+            .await?
+            .lock()
+            .await
+            .error("Failed to lock the session"),
+    }
+}
+
+async fn logind_manager() -> Result<LogindManagerProxy<'static>> {
+    let conn = new_system_dbus_connection().await?;
+    LogindManagerProxy::builder(&conn)
+        .cache_properties(zbus::proxy::CacheProperties::No)
+        .build()
+        .await
+        .error("Failed to create logind proxy")
+}
+
+async fn logind_session() -> Result<LogindSessionProxy<'static>> {
+    let conn = new_system_dbus_connection().await?;
+    LogindSessionProxy::builder(&conn)
+        .cache_properties(zbus::proxy::CacheProperties::No)
+        .build()
+        .await
+        .error("Failed to create logind session proxy")
+}
+
+/// Idle inhibitors which are released when dropped
+pub(super) struct Inhibitor {
+    _logind: Option<OwnedFd>,
+    screensaver: Option<(ScreenSaverProxy<'static>, u32)>,
+}
+
+impl Inhibitor {
+    /// Takes a `systemd-logind` inhibitor for `what` (e.g. `"idle:sleep"`), and inhibits the
+    /// screensaver too if `what` includes `idle`
+    pub(super) async fn new(what: &str, why: &str) -> Result<Self> {
+        let logind = async {
+            let conn = new_system_dbus_connection().await?;
+            let manager = LogindManagerProxy::new(&conn)
+                .await
+                .error("Failed to create logind proxy")?;
+            let fd = manager
+                .inhibit(what, "i3status-rs", why, "block")
+                .await
+                .error("Failed to take an idle inhibitor")?;
+            Ok::<OwnedFd, Error>(fd.into())
+        }
+        .await;
+        let screensaver = async {
+            if !what.split(':').any(|w| w == "idle") {
+                return Err(Error::new("Not inhibiting idle"));
+            }
+            let conn = new_dbus_connection().await?;
+            let proxy = ScreenSaverProxy::new(&conn)
+                .await
+                .error("Failed to create ScreenSaver proxy")?;
+            let cookie = proxy
+                .inhibit("i3status-rs", why)
+                .await
+                .error("Failed to inhibit the screensaver")?;
+            Ok::<_, Error>((proxy, cookie))
+        }
+        .await;
+
+        match (logind, screensaver) {
+            (Err(e), Err(_)) => Err(e),
+            (logind, screensaver) => Ok(Self {
+                _logind: logind.ok(),
+                screensaver: screensaver.ok(),
+            }),
+        }
+    }
+}
+
+impl Drop for Inhibitor {
+    fn drop(&mut self) {
+        if let Some((proxy, cookie)) = self.screensaver.take() {
+            tokio::spawn(async move {
+                let _ = proxy.un_inhibit(cookie).await;
+            });
+        }
+    }
+}
+
+/// Receives a notification when the user has been idle for a given time and when they are back
+struct IdleMonitor {
+    conn: Connection<IdleEvents>,
+    events: IdleEvents,
+}
+
+#[derive(Default)]
+struct IdleEvents(Vec<bool>);
+
+impl IdleMonitor {
+    async fn new(after: Duration) -> Result<Self> {
+        let (mut conn, globals) = Connection::async_connect_and_collect_globals()
+            .await
+            .error("failed to connect to wayland")?;
+        let notifier: ExtIdleNotifierV1 = globals
+            .bind(&mut conn, 1)
+            .error("compositor does not support ext-idle-notify-v1")?;
+        let seat: WlSeat = globals.bind(&mut conn, 1..=9).error("no wayland seat")?;
+        let timeout = after.as_millis().try_into().unwrap_or(u32::MAX);
+        notifier.get_idle_notification_with_cb(&mut conn, timeout, seat, idle_notification_cb);
+        Ok(Self {
+            conn,
+            events: default(),
+        })
+    }
+
+    /// Returns `true` when the user becomes idle and `false` when they are back
+    async fn next(&mut self) -> Result<bool> {
+        loop {
+            if !self.events.0.is_empty() {
+                return Ok(self.events.0.remove(0));
+            }
+            self.conn.async_flush().await.error("wayland error")?;
+            self.conn.async_recv_events().await.error("wayland error")?;
+            self.conn.dispatch_events(&mut self.events);
+        }
+    }
+}
+
+async fn next_idle_event(monitor: &mut Option<IdleMonitor>) -> Result<bool> {
+    match monitor {
+        Some(monitor) => monitor.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+fn idle_notification_cb(ctx: EventCtx<IdleEvents, ExtIdleNotificationV1>) {
+    match ctx.event {
+        ext_idle_notification_v1::Event::Idled => ctx.state.0.push(true),
+        ext_idle_notification_v1::Event::Resumed => ctx.state.0.push(false),
+        _ => (),
+    }
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait LogindManager {
+    fn inhibit(
+        &self,
+        what: &str,
+        who: &str,
+        why: &str,
+        mode: &str,
+    ) -> zbus::Result<zbus::zvariant::OwnedFd>;
+
+    #[zbus(property)]
+    fn block_inhibited(&self) -> zbus::Result<String>;
+}
+
+/// The session of this process
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait LogindSession {
+    fn lock(&self) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn idle_hint(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn idle_since_hint(&self) -> zbus::Result<u64>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.ScreenSaver",
+    default_service = "org.freedesktop.ScreenSaver",
+    default_path = "/org/freedesktop/ScreenSaver"
+)]
+trait ScreenSaver {
+    fn inhibit(&self, application_name: &str, reason_for_inhibit: &str) -> zbus::Result<u32>;
+    fn un_inhibit(&self, cookie: u32) -> zbus::Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swayidle_lock_timeout() {
+        let args = [
+            "-w",
+            "timeout",
+            "600",
+            "swaymsg 'output * power off'",
+            "timeout",
+            "300",
+            "swaylock -f",
+            "before-sleep",
+            "swaylock -f",
+        ];
+        assert_eq!(lock_timeout_from_args(&args), Some(Duration::from_secs(300)));
+        assert_eq!(lock_timeout_from_args(&["-w"]), None);
+
+        let config = "timeout 900 'swaylock -f -c 000000'\n\
+                      timeout 960 'swaymsg \"output * power off\"'\n\
+                      before-sleep 'swaylock -f'\n";
+        assert_eq!(lock_timeout_from_config(config), Some(Duration::from_secs(900)));
+    }
+}
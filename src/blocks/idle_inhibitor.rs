@@ -0,0 +1,118 @@
+//! Keep the screen awake
+//!
+//! Clicking this block toggles an inhibitor which prevents the screen from being locked or blanked
+//! while idle, like the "caffeine" applets of other bars. The inhibitor is taken from
+//! `systemd-logind` and `org.freedesktop.ScreenSaver` (whichever are available), so the idle
+//! daemon must respect them, which is the case for e.g. `hypridle` and `xss-lock`, but not for
+//! `swayidle`. With `timeout`, the inhibitor is released automatically.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" $icon {$active{ $remaining\|}\|off} \"</code>
+//! `timeout` | Release the inhibitor after this many seconds | Never
+//! `what` | What to inhibit, as a colon-separated list of `systemd-logind` inhibitor types, e.g. `"idle:sleep"` to prevent suspending too | `"idle"`
+//!
+//! Placeholder | Value                                                      | Type     | Unit
+//! ------------|------------------------------------------------------------|----------|-----
+//! `icon`      | A static icon                                              | Icon     | -
+//! `active`    | Present while the inhibitor is held                        | Flag     | -
+//! `remaining` | Time until the inhibitor is released (present with `timeout`) | Duration | -
+//!
+//! The block is in the info state while the inhibitor is held.
+//!
+//! Action   | Description                         | Default button
+//! ---------|-------------------------------------|---------------
+//! `toggle` | Takes or releases the inhibitor     | Left
+//!
+//! # Example
+//!
+//! Keep the screen on for an hour at most:
+//!
+//! ```toml
+//! [[block]]
+//! block = "idle_inhibitor"
+//! timeout = 3600
+//! ```
+//!
+//! # Icons Used
+//! - `tea`
+
+use std::time::Instant;
+
+use super::idle::Inhibitor;
+use super::prelude::*;
+
+make_log_macro!(warn, "idle_inhibitor");
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    pub timeout: Option<Seconds>,
+    #[default("idle".into())]
+    pub what: String,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+    api.set_default_actions(&[(MouseButton::Left, None, "toggle")])?;
+
+    let format = config
+        .format
+        .with_default(" $icon {$active{ $remaining|}|off} ")?;
+
+    let mut inhibitor: Option<(Inhibitor, Instant)> = None;
+
+    loop {
+        let remaining = match (&inhibitor, config.timeout) {
+            (Some((_, since)), Some(timeout)) => Some(timeout.0.saturating_sub(since.elapsed())),
+            _ => None,
+        };
+        if remaining.is_some_and(|r| r.is_zero()) {
+            inhibitor = None;
+            continue;
+        }
+
+        let mut widget = Widget::new().with_format(format.clone());
+        widget.state = match inhibitor {
+            Some(_) => State::Info,
+            None => State::Idle,
+        };
+        widget.set_values(map! {
+            "icon" => Value::icon("tea"),
+            [if inhibitor.is_some()] "active" => Value::flag(),
+            [if let Some(r) = remaining] "remaining" => Value::duration(r),
+        });
+        api.set_widget(widget)?;
+
+        // Tick every second while counting down
+        let countdown = async {
+            match remaining {
+                Some(remaining) => sleep(remaining.min(Duration::from_secs(1))).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        select! {
+            _ = countdown => (),
+            _ = api.wait_for_update_request() => (),
+            Some(action) = actions.recv() => match action.as_ref() {
+                "toggle" => {
+                    inhibitor = match inhibitor {
+                        Some(_) => None,
+                        None => match Inhibitor::new(&config.what, "Kept awake by the user").await {
+                            Ok(inhibitor) => Some((inhibitor, Instant::now())),
+                            Err(err) => {
+                                warn!("{err}");
+                                None
+                            }
+                        },
+                    };
+                }
+                _ => (),
+            }
+        }
+    }
+}
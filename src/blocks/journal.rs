@@ -0,0 +1,104 @@
+//! Errors in the systemd journal
+//!
+//! This block follows the systemd journal and counts the messages of error priority or higher which
+//! were logged since i3status-rs started or since the count was last reset. The journal is read
+//! with `journalctl`, so the user has to be allowed to read it (e.g. as a member of the
+//! `systemd-journal` group) to see the messages of the system.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | `" $icon $count "`
+//! `priority` | The lowest priority to count, by name (`"emerg"`, `"alert"`, `"crit"`, `"err"`, `"warning"`, `"notice"`, `"info"`, `"debug"`) or number | `"err"`
+//! `units` | Only count the messages of these units | All units
+//! `user` | Follow the journal of the user instead of the one of the system | `false`
+//!
+//! Placeholder | Value                                                   | Type   | Unit
+//! ------------|---------------------------------------------------------|--------|-----
+//! `icon`      | A static icon                                           | Icon   | -
+//! `count`     | The number of messages since the last reset             | Number | -
+//! `message`   | The last message (absent if there was none)             | Text   | -
+//! `source`    | The unit or program of the last message (absent if unknown) | Text | -
+//!
+//! The block is in the critical state if the count is not zero.
+//!
+//! Action  | Description              | Default button
+//! --------|--------------------------|---------------
+//! `reset` | Resets the count to zero | Left
+//!
+//! # Example
+//!
+//! Show the last error of some units, and nothing if there was none:
+//!
+//! ```toml
+//! [[block]]
+//! block = "journal"
+//! units = ["nginx.service", "postgresql.service"]
+//! format = "{ $icon $count $source: $message.str(max_w:30) |}"
+//! ```
+//!
+//! # Icons Used
+//! - `notification`
+
+use crate::journal::{Entry, Filter, Journal};
+
+use super::prelude::*;
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    #[default("err".into())]
+    pub priority: String,
+    pub units: Vec<String>,
+    pub user: bool,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+    api.set_default_actions(&[(MouseButton::Left, None, "reset")])?;
+
+    let format = config.format.with_default(" $icon $count ")?;
+
+    let mut journal = Journal::follow(&Filter {
+        priority: Some(&config.priority),
+        units: &config.units,
+        user: config.user,
+    })?;
+
+    let mut count = 0;
+    let mut last: Option<Entry> = None;
+
+    loop {
+        let mut widget = Widget::new().with_format(format.clone());
+        widget.state = if count > 0 {
+            State::Critical
+        } else {
+            State::Idle
+        };
+        let source = last.as_ref().and_then(|entry| entry.source.clone());
+        widget.set_values(map! {
+            "icon" => Value::icon("notification"),
+            "count" => Value::number(count),
+            [if let Some(entry) = &last] "message" => Value::text(entry.message.clone()),
+            [if let Some(s) = source] "source" => Value::text(s),
+        });
+        api.set_widget(widget)?;
+
+        select! {
+            entry = journal.next() => {
+                count += 1;
+                last = Some(entry?);
+            }
+            _ = api.wait_for_update_request() => (),
+            Some(action) = actions.recv() => match action.as_ref() {
+                "reset" => {
+                    count = 0;
+                    last = None;
+                }
+                _ => (),
+            }
+        }
+    }
+}
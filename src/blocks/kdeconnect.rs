@@ -112,7 +112,7 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
 
                 if let Some(bat_level) = info.bat_level {
                     values.insert(
-                        "bat_icon".into(),
+                        "bat_icon",
                         Value::icon_progression(
                             if info.charging { "bat_charging" } else { "bat" },
                             bat_level as f64 / 100.0,
@@ -148,22 +148,22 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
                     let cell_network_percent =
                         (info.cellular_network_strength.clamp(0, 4) * 25) as f64;
                     values.insert(
-                        "network_icon".into(),
+                        "network_icon",
                         Value::icon_progression(
                             "net_cellular",
                             (info.cellular_network_strength + 1).clamp(0, 5) as f64 / 5.0,
                         ),
                     );
                     values.insert(
-                        "network_strength".into(),
+                        "network_strength",
                         Value::percents(cell_network_percent),
                     );
 
                     if info.cellular_network_strength <= 0 {
                         widget.state = State::Critical;
-                        values.insert("network_type".into(), Value::text("×".into()));
+                        values.insert("network_type", Value::text("×".into()));
                     } else {
-                        values.insert("network_type".into(), Value::text(cellular_network_type));
+                        values.insert("network_type", Value::text(cellular_network_type));
                     }
                 }
 
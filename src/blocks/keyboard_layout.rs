@@ -1,11 +1,13 @@
 //! Keyboard layout indicator
 //!
-//! Four drivers are available:
+//! Seven drivers are available:
 //! - `setxkbmap` which polls setxkbmap to get the current layout
 //! - `xkbswitch` which utilizes [XkbSwitch](https://github.com/grwlf/xkb-switch) to monitor and retrieve the current layout and variant
 //! - `localebus` which can read asynchronous updates from the systemd `org.freedesktop.locale1` D-Bus path
 //! - `kbddbus` which uses [kbdd](https://github.com/qnikst/kbdd) to monitor per-window layout changes via DBus
-//! - `sway` which can read asynchronous updates from the sway IPC
+//! - `sway` which can read asynchronous updates from the sway IPC (Linux only)
+//! - `hyprland` which can read asynchronous updates from the Hyprland IPC
+//! - `niri` which can read asynchronous updates from the niri IPC
 //!
 //! Which of these methods is appropriate will depend on your system setup.
 //!
@@ -13,7 +15,7 @@
 //!
 //! Key | Values | Default
 //! ----|--------|--------
-//! `driver` | One of `"setxkbmap"`, `"xkbswitch"`, "localebus"`, `"kbddbus"` or `"sway"`, depending on your system. | `"setxkbmap"`
+//! `driver` | One of `"setxkbmap"`, `"xkbswitch"`, `"localebus"`, `"kbddbus"`, `"sway"`, `"hyprland"` or `"niri"`, depending on your system. | `"hyprland"` if `$HYPRLAND_INSTANCE_SIGNATURE` is set, `"niri"` if `$NIRI_SOCKET` is set, `"setxkbmap"` otherwise
 //! `interval` | Update interval, in seconds. Only used by the `"setxkbmap"` driver. | `60`
 //! `format` | A string to customise the output of this block. See below for available placeholders. | `" $layout "`
 //! `sway_kb_identifier` | Identifier of the device you want to monitor, as found in the output of `swaymsg -t get_inputs`. | Defaults to first input found
@@ -101,42 +103,63 @@ use locale_bus::LocaleBus;
 mod kbdd_bus;
 use kbdd_bus::KbddBus;
 
+#[cfg(target_os = "linux")]
 mod sway;
+#[cfg(target_os = "linux")]
 use sway::Sway;
 
+mod hyprland;
+use hyprland::Hyprland;
+
+mod niri;
+use niri::Niri;
+
 use super::prelude::*;
 
 #[derive(Deserialize, Debug, SmartDefault)]
 #[serde(deny_unknown_fields, default)]
 pub struct Config {
     pub format: FormatConfig,
-    pub driver: KeyboardLayoutDriver,
+    pub driver: Option<KeyboardLayoutDriver>,
     #[default(60.into())]
     pub interval: Seconds,
     pub sway_kb_identifier: Option<String>,
     pub mappings: Option<HashMap<String, String>>,
 }
 
-#[derive(Deserialize, Debug, SmartDefault, Clone, Copy)]
+#[derive(Deserialize, Debug, Clone, Copy)]
 #[serde(rename_all = "lowercase")]
 pub enum KeyboardLayoutDriver {
-    #[default]
     SetXkbMap,
     XkbSwitch,
     LocaleBus,
     KbddBus,
+    #[cfg(target_os = "linux")]
     Sway,
+    Hyprland,
+    Niri,
 }
 
 pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
     let format = config.format.with_default(" $layout ")?;
 
-    let mut backend: Box<dyn Backend> = match config.driver {
+    let driver = config.driver.unwrap_or(if crate::hyprland::is_running() {
+        KeyboardLayoutDriver::Hyprland
+    } else if crate::niri::is_running() {
+        KeyboardLayoutDriver::Niri
+    } else {
+        KeyboardLayoutDriver::SetXkbMap
+    });
+
+    let mut backend: Box<dyn Backend> = match driver {
         KeyboardLayoutDriver::SetXkbMap => Box::new(SetXkbMap::new(config.interval)),
         KeyboardLayoutDriver::XkbSwitch => Box::new(XkbSwitch::new(config.interval)),
         KeyboardLayoutDriver::LocaleBus => Box::new(LocaleBus::new().await?),
         KeyboardLayoutDriver::KbddBus => Box::new(KbddBus::new().await?),
+        #[cfg(target_os = "linux")]
         KeyboardLayoutDriver::Sway => Box::new(Sway::new(config.sway_kb_identifier.clone()).await?),
+        KeyboardLayoutDriver::Hyprland => Box::new(Hyprland::new().await?),
+        KeyboardLayoutDriver::Niri => Box::new(Niri::new().await?),
     };
 
     loop {
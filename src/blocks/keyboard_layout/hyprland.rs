@@ -0,0 +1,58 @@
+use super::*;
+use crate::hyprland::{self, Events};
+
+pub(super) struct Hyprland {
+    events: Events,
+    cur_layout: String,
+}
+
+#[derive(Deserialize)]
+struct Devices {
+    keyboards: Vec<Keyboard>,
+}
+
+#[derive(Deserialize)]
+struct Keyboard {
+    active_keymap: String,
+    #[serde(default)]
+    main: bool,
+}
+
+impl Hyprland {
+    pub(super) async fn new() -> Result<Self> {
+        let events = Events::new().await?;
+        let devices: Devices = hyprland::request("devices").await?;
+        let keyboard = devices
+            .keyboards
+            .iter()
+            .find(|k| k.main)
+            .or(devices.keyboards.first())
+            .error("No keyboard found")?;
+        Ok(Self {
+            events,
+            cur_layout: keyboard.active_keymap.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl Backend for Hyprland {
+    async fn get_info(&mut self) -> Result<Info> {
+        Ok(Info::from_layout_variant_str(&self.cur_layout))
+    }
+
+    async fn wait_for_change(&mut self) -> Result<()> {
+        loop {
+            let (event, data) = self.events.next().await?;
+            // `keyboard,layout`
+            if event == "activelayout" {
+                if let Some((_, layout)) = data.split_once(',') {
+                    if layout != self.cur_layout {
+                        self.cur_layout = layout.into();
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use super::*;
+use crate::niri::{EventStream, State};
+
+pub(super) struct Niri {
+    events: EventStream,
+    state: State,
+    cur_layout: Option<String>,
+}
+
+impl Niri {
+    pub(super) async fn new() -> Result<Self> {
+        let mut niri = Self {
+            events: EventStream::new().await?,
+            state: State::default(),
+            cur_layout: None,
+        };
+        // niri sends the current layouts right after the start of the event stream
+        niri.wait_for_change().await?;
+        Ok(niri)
+    }
+}
+
+#[async_trait]
+impl Backend for Niri {
+    async fn get_info(&mut self) -> Result<Info> {
+        Ok(Info::from_layout_variant_str(
+            self.cur_layout.as_deref().unwrap_or_default(),
+        ))
+    }
+
+    async fn wait_for_change(&mut self) -> Result<()> {
+        loop {
+            self.state.apply(self.events.next().await?);
+            let layout = self.state.keyboard_layout();
+            if layout.is_some() && layout != self.cur_layout.as_deref() {
+                self.cur_layout = layout.map(Into::into);
+                return Ok(());
+            }
+        }
+    }
+}
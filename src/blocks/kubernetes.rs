@@ -0,0 +1,313 @@
+//! Current Kubernetes context and pod health
+//!
+//! This block shows the current context and namespace of the kubeconfig and, optionally, the number
+//! of pods in the namespace which are not ready. Clicking the block switches to another context.
+//!
+//! The block uses [`kubectl`](https://kubernetes.io/docs/reference/kubectl/), so it respects
+//! `$KUBECONFIG` and supports all the authentication methods `kubectl` does.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" $context{:$namespace\|} \"</code>
+//! `contexts` | The contexts to switch between on click. If empty, all contexts from the kubeconfig are used. | `[]`
+//! `check_pods` | Whether to query the API server for the pods in the namespace | `false`
+//! `namespace` | The namespace to check the pods in. Defaults to the namespace of the current context or `default`. | None
+//! `interval` | Update interval in seconds | `30`
+//!
+//! Placeholder | Value                                                        | Type   | Unit
+//! ------------|--------------------------------------------------------------|--------|-----
+//! `context`   | The current context                                          | Text   | -
+//! `cluster`   | The cluster of the current context                           | Text   | -
+//! `namespace` | The namespace of the current context. Absent if not set.     | Text   | -
+//! `pods`      | Number of pods in the namespace. Only with `check_pods`.     | Number | -
+//! `not_ready` | Number of pods which are not ready (completed pods are not counted). Only with `check_pods`. | Number | -
+//!
+//! The block's state is warning if any pod is not ready.
+//!
+//! Action         | Default button
+//! ---------------|---------------
+//! `next_context` | Left
+//! `prev_context` | Right
+//!
+//! # Example
+//!
+//! ```toml
+//! [[block]]
+//! block = "kubernetes"
+//! format = " $context{:$namespace|} $not_ready/$pods "
+//! contexts = ["staging", "production"]
+//! check_pods = true
+//! ```
+
+use super::prelude::*;
+use tokio::process::Command;
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    pub contexts: Vec<String>,
+    pub check_pods: bool,
+    pub namespace: Option<String>,
+    #[default(30.into())]
+    pub interval: Seconds,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+    api.set_default_actions(&[
+        (MouseButton::Left, None, "next_context"),
+        (MouseButton::Right, None, "prev_context"),
+    ])?;
+
+    let format = config.format.with_default(" $context{:$namespace|} ")?;
+
+    let mut timer = config.interval.timer();
+
+    loop {
+        let current = current_context().await?;
+
+        let mut widget = Widget::new().with_format(format.clone());
+        let mut values = map! {
+            "context" => Value::text(current.name.clone()),
+            "cluster" => Value::text(current.context.cluster),
+            [if let Some(ns) = &current.context.namespace] "namespace" => Value::text(ns.clone()),
+        };
+
+        if config.check_pods {
+            let namespace = config
+                .namespace
+                .as_deref()
+                .or(current.context.namespace.as_deref())
+                .unwrap_or("default");
+            let pods = pods(&current.name, namespace).await?;
+            let not_ready = pods.iter().filter(|pod| !pod.is_ready()).count();
+            if not_ready > 0 {
+                widget.state = State::Warning;
+            }
+            values.insert("pods", Value::number(pods.len()));
+            values.insert("not_ready", Value::number(not_ready));
+        }
+
+        widget.set_values(values);
+        api.set_widget(widget)?;
+
+        select! {
+            _ = timer.tick() => (),
+            _ = api.wait_for_update_request() => (),
+            Some(action) = actions.recv() => {
+                let step = match action.as_ref() {
+                    "next_context" => 1,
+                    "prev_context" => -1,
+                    _ => continue,
+                };
+                let contexts = if config.contexts.is_empty() {
+                    all_contexts().await?
+                } else {
+                    config.contexts.clone()
+                };
+                if let Some(context) = switch_context(&contexts, &current.name, step) {
+                    kubectl(&["config", "use-context", context]).await?;
+                }
+            }
+        }
+    }
+}
+
+/// Run `kubectl` and return its standard output
+async fn kubectl(args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("kubectl")
+        .args(args)
+        .output()
+        .await
+        .error("Failed to run 'kubectl'")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::new(format!("'kubectl' failed: {}", stderr.trim())));
+    }
+    Ok(output.stdout)
+}
+
+/// The context `step` positions away from `current`, or the first one if `current` is not listed
+fn switch_context<'a>(contexts: &'a [String], current: &str, step: isize) -> Option<&'a str> {
+    if contexts.is_empty() {
+        return None;
+    }
+    let i = contexts
+        .iter()
+        .position(|c| c == current)
+        .map_or(0, |i| (i as isize + step).rem_euclid(contexts.len() as isize) as usize);
+    Some(&contexts[i])
+}
+
+async fn current_context() -> Result<NamedContext> {
+    let output = kubectl(&["config", "view", "--minify", "-o", "json"]).await?;
+    parse_current_context(&output)
+}
+
+fn parse_current_context(output: &[u8]) -> Result<NamedContext> {
+    let config: KubeConfig =
+        serde_json::from_slice(output).error("'kubectl' produced wrong JSON")?;
+    config
+        .contexts
+        .into_iter()
+        .find(|c| c.name == config.current_context)
+        .error("Current context not found in kubeconfig")
+}
+
+async fn all_contexts() -> Result<Vec<String>> {
+    let output = kubectl(&["config", "get-contexts", "-o", "name"]).await?;
+    let output = String::from_utf8(output).error("'kubectl' produced non-UTF8 output")?;
+    Ok(output.lines().map(Into::into).collect())
+}
+
+async fn pods(context: &str, namespace: &str) -> Result<Vec<Pod>> {
+    let output = kubectl(&[
+        "--context",
+        context,
+        "--namespace",
+        namespace,
+        "--request-timeout",
+        "10s",
+        "get",
+        "pods",
+        "-o",
+        "json",
+    ])
+    .await?;
+    parse_pods(&output)
+}
+
+fn parse_pods(output: &[u8]) -> Result<Vec<Pod>> {
+    let list: PodList = serde_json::from_slice(output).error("'kubectl' produced wrong JSON")?;
+    Ok(list.items)
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+struct KubeConfig {
+    #[serde(default)]
+    current_context: String,
+    #[serde(default)]
+    contexts: Vec<NamedContext>,
+}
+
+#[derive(Deserialize, Debug)]
+struct NamedContext {
+    name: String,
+    context: Context,
+}
+
+#[derive(Deserialize, Debug)]
+struct Context {
+    #[serde(default)]
+    cluster: String,
+    namespace: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PodList {
+    items: Vec<Pod>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Pod {
+    #[serde(default)]
+    status: PodStatus,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct PodStatus {
+    #[serde(default)]
+    phase: String,
+    #[serde(default)]
+    conditions: Vec<PodCondition>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PodCondition {
+    #[serde(rename = "type")]
+    kind: String,
+    status: String,
+}
+
+impl Pod {
+    fn is_ready(&self) -> bool {
+        // Pods of finished jobs are never ready, but they are not a problem either
+        self.status.phase == "Succeeded"
+            || self
+                .status
+                .conditions
+                .iter()
+                .any(|c| c.kind == "Ready" && c.status == "True")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_context() {
+        let output = br#"{
+            "kind": "Config",
+            "apiVersion": "v1",
+            "current-context": "staging",
+            "contexts": [
+                {"name": "staging", "context": {"cluster": "eu-1", "user": "admin", "namespace": "web"}}
+            ],
+            "clusters": [{"name": "eu-1", "cluster": {"server": "https://10.0.0.1:6443"}}]
+        }"#;
+        let context = parse_current_context(output).unwrap();
+        assert_eq!(context.name, "staging");
+        assert_eq!(context.context.cluster, "eu-1");
+        assert_eq!(context.context.namespace.as_deref(), Some("web"));
+
+        // The namespace is optional
+        let output = br#"{
+            "current-context": "minikube",
+            "contexts": [{"name": "minikube", "context": {"cluster": "minikube"}}]
+        }"#;
+        let context = parse_current_context(output).unwrap();
+        assert_eq!(context.name, "minikube");
+        assert_eq!(context.context.namespace, None);
+
+        // Without a current context, `--minify` prints an empty config
+        let output = br#"{"kind": "Config", "apiVersion": "v1", "preferences": {}}"#;
+        assert!(parse_current_context(output).is_err());
+        assert!(parse_current_context(b"error: no context").is_err());
+    }
+
+    #[test]
+    fn switch() {
+        let contexts = ["dev".to_owned(), "staging".to_owned(), "prod".to_owned()];
+        assert_eq!(switch_context(&contexts, "dev", 1), Some("staging"));
+        assert_eq!(switch_context(&contexts, "prod", 1), Some("dev"));
+        assert_eq!(switch_context(&contexts, "dev", -1), Some("prod"));
+        assert_eq!(switch_context(&contexts, "minikube", -1), Some("dev"));
+        assert_eq!(switch_context(&[], "dev", 1), None);
+    }
+
+    #[test]
+    fn pods() {
+        let output = br#"{"items": [
+            {"status": {"phase": "Running", "conditions": [
+                {"type": "Initialized", "status": "True"},
+                {"type": "Ready", "status": "True"}
+            ]}},
+            {"status": {"phase": "Running", "conditions": [
+                {"type": "Ready", "status": "False"}
+            ]}},
+            {"status": {"phase": "Pending"}},
+            {"status": {"phase": "Succeeded", "conditions": [
+                {"type": "Ready", "status": "False"}
+            ]}}
+        ]}"#;
+        let pods = parse_pods(output).unwrap();
+        let ready: Vec<bool> = pods.iter().map(Pod::is_ready).collect();
+        assert_eq!(ready, [true, false, false, true]);
+        assert!(parse_pods(br#"{"items": []}"#).unwrap().is_empty());
+    }
+}
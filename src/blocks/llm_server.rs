@@ -0,0 +1,280 @@
+//! Status of a local LLM inference server
+//!
+//! This block shows whether an [ollama](https://ollama.com) or
+//! [llama.cpp](https://github.com/ggml-org/llama.cpp) server is running, which model it has
+//! loaded and, for ollama, how much VRAM the loaded models use. With ollama, the loaded models can
+//! be unloaded to free the memory.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block if the server is running. See below for available placeholders. | <code>\" $icon {$model{ $vram.eng(w:3,u:B,p:Mi)\|}\|idle} \"</code>
+//! `format_down` | A string to customise the output of this block if the server is not running | `" $icon down "`
+//! `server` | The kind of server: `"ollama"` or `"llama_cpp"` | `"ollama"`
+//! `url` | The URL of the server | `"http://localhost:11434"` for ollama, `"http://localhost:8080"` for llama.cpp
+//! `interval` | Update interval in seconds | `10`
+//!
+//! Placeholder | Value                                                         | Type   | Unit
+//! ------------|---------------------------------------------------------------|--------|------
+//! `icon`      | A static icon                                                 | Icon   | -
+//! `model`     | The name of the loaded model. Absent if no model is loaded.   | Text   | -
+//! `models`    | The number of loaded models                                   | Number | -
+//! `size`      | The memory used by the loaded models. Only for ollama.        | Number | Bytes
+//! `vram`      | The VRAM used by the loaded models. Only for ollama.          | Number | Bytes
+//!
+//! The block is in the info state while a model is loaded.
+//!
+//! Action   | Description                                                     | Default button
+//! ---------|-----------------------------------------------------------------|---------------
+//! `unload` | Unload all loaded models. Only for ollama. Failures are logged. | Right
+//!
+//! # Example
+//!
+//! ```toml
+//! [[block]]
+//! block = "llm_server"
+//! format = " $icon {$model $size.eng(w:3,u:B,p:Mi) ($vram.eng(w:3,u:B,p:Mi) VRAM)|idle} "
+//! ```
+//!
+//! # Icons Used
+//! - `gpu`
+
+use super::prelude::*;
+use serde::de::DeserializeOwned;
+
+make_log_macro!(warn, "llm_server");
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    pub format_down: FormatConfig,
+    pub server: Server,
+    pub url: Option<String>,
+    #[default(10.into())]
+    pub interval: Seconds,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Server {
+    #[default]
+    Ollama,
+    LlamaCpp,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+    if config.server == Server::Ollama {
+        api.set_default_actions(&[(MouseButton::Right, None, "unload")])?;
+    }
+
+    let format = config
+        .format
+        .with_default(" $icon {$model{ $vram.eng(w:3,u:B,p:Mi)|}|idle} ")?;
+    let format_down = config.format_down.with_default(" $icon down ")?;
+
+    let url = match (&config.url, config.server) {
+        (Some(url), _) => url.trim_end_matches('/'),
+        (None, Server::Ollama) => "http://localhost:11434",
+        (None, Server::LlamaCpp) => "http://localhost:8080",
+    };
+
+    let mut timer = config.interval.timer();
+
+    loop {
+        let models = match config.server {
+            Server::Ollama => ollama_models(url).await?,
+            Server::LlamaCpp => llama_cpp_models(url).await?,
+        };
+
+        let mut widget = Widget::new();
+        match &models {
+            None => {
+                widget.set_format(format_down.clone());
+                widget.set_values(map!("icon" => Value::icon("gpu")));
+            }
+            Some(models) => {
+                widget.set_format(format.clone());
+                if !models.is_empty() {
+                    widget.state = State::Info;
+                }
+                let sum = |field: fn(&Model) -> Option<u64>| {
+                    models.iter().map(field).sum::<Option<u64>>()
+                };
+                widget.set_values(map! {
+                    "icon" => Value::icon("gpu"),
+                    "models" => Value::number(models.len()),
+                    [if let Some(model) = models.first()] "model" => Value::text(model.name.clone()),
+                    [if let Some(size) = sum(|m| m.size)] "size" => Value::bytes(size as f64),
+                    [if let Some(vram) = sum(|m| m.vram)] "vram" => Value::bytes(vram as f64),
+                });
+            }
+        }
+        api.set_widget(widget)?;
+
+        loop {
+            select! {
+                _ = timer.tick() => break,
+                _ = api.wait_for_update_request() => break,
+                Some(action) = actions.recv() => match action.as_ref() {
+                    "unload" if config.server == Server::Ollama => {
+                        for model in models.iter().flatten() {
+                            if let Err(err) = ollama_unload(url, &model.name).await {
+                                warn!("failed to unload {}: {err}", model.name);
+                            }
+                        }
+                        break;
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Model {
+    name: String,
+    size: Option<u64>,
+    vram: Option<u64>,
+}
+
+/// Sends a GET request to the server. Returns `None` if the server is not running.
+async fn get<T: DeserializeOwned>(url: String) -> Result<Option<T>> {
+    let response = match REQWEST_CLIENT.get(url).send().await {
+        Ok(response) => response,
+        Err(err) if err.is_connect() => return Ok(None),
+        Err(err) => return Err(err).error("Failed to send request"),
+    };
+    response
+        .error_for_status()
+        .error("Server returned an error")?
+        .json()
+        .await
+        .error("Failed to get JSON")
+        .map(Some)
+}
+
+#[derive(Deserialize)]
+struct OllamaPs {
+    models: Vec<OllamaModel>,
+}
+
+#[derive(Deserialize)]
+struct OllamaModel {
+    name: String,
+    size: u64,
+    #[serde(default)]
+    size_vram: u64,
+}
+
+impl From<OllamaPs> for Vec<Model> {
+    fn from(ps: OllamaPs) -> Self {
+        ps.models
+            .into_iter()
+            .map(|model| Model {
+                name: model.name,
+                size: Some(model.size),
+                vram: Some(model.size_vram),
+            })
+            .collect()
+    }
+}
+
+// https://github.com/ollama/ollama/blob/main/docs/api.md#list-running-models
+async fn ollama_models(url: &str) -> Result<Option<Vec<Model>>> {
+    let ps: Option<OllamaPs> = get(format!("{url}/api/ps")).await?;
+    Ok(ps.map(Into::into))
+}
+
+/// Unloads a model by setting its `keep_alive` to zero
+async fn ollama_unload(url: &str, model: &str) -> Result<()> {
+    REQWEST_CLIENT
+        .post(format!("{url}/api/generate"))
+        .json(&serde_json::json!({ "model": model, "keep_alive": 0 }))
+        .send()
+        .await
+        .error("Failed to send request")?
+        .error_for_status()
+        .error("Failed to unload the model")?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct OpenAiModels {
+    data: Vec<OpenAiModel>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiModel {
+    id: String,
+}
+
+impl From<OpenAiModels> for Vec<Model> {
+    fn from(models: OpenAiModels) -> Self {
+        models
+            .data
+            .into_iter()
+            .map(|model| Model {
+                // llama.cpp names models after the path of their file
+                name: model
+                    .id
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default()
+                    .trim_end_matches(".gguf")
+                    .into(),
+                size: None,
+                vram: None,
+            })
+            .collect()
+    }
+}
+
+async fn llama_cpp_models(url: &str) -> Result<Option<Vec<Model>>> {
+    let models: Option<OpenAiModels> = get(format!("{url}/v1/models")).await?;
+    Ok(models.map(Into::into))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ollama_ps() {
+        let ps: OllamaPs = serde_json::from_str(
+            r#"{"models":[{"name":"llama3:latest","model":"llama3:latest","size":5137025024,
+            "digest":"365c0bd3c000a25d28ddbf732fe1c6add414de7275464c4e4d1c3b5fcb5d8ad1",
+            "details":{"format":"gguf","family":"llama"},"expires_at":"2024-06-04T14:38:31.83753-07:00",
+            "size_vram":5137025024}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Vec::<Model>::from(ps),
+            [Model {
+                name: "llama3:latest".into(),
+                size: Some(5137025024),
+                vram: Some(5137025024),
+            }]
+        );
+    }
+
+    #[test]
+    fn llama_cpp() {
+        let models: OpenAiModels = serde_json::from_str(
+            r#"{"object":"list","data":[{"id":"/models/qwen2.5-7b-instruct-q4_k_m.gguf",
+            "object":"model","created":1735000000,"owned_by":"llamacpp"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Vec::<Model>::from(models),
+            [Model {
+                name: "qwen2.5-7b-instruct-q4_k_m".into(),
+                size: None,
+                vram: None,
+            }]
+        );
+    }
+}
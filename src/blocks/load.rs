@@ -30,6 +30,7 @@
 //! - `cogs`
 
 use super::prelude::*;
+#[cfg(target_os = "linux")]
 use crate::util;
 
 #[derive(Deserialize, Debug, SmartDefault)]
@@ -49,31 +50,10 @@ pub struct Config {
 pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
     let format = config.format.with_default(" $icon $1m.eng(w:4) ")?;
 
-    // borrowed from https://docs.rs/cpuinfo/0.1.1/src/cpuinfo/count/logical.rs.html#4-6
-    let logical_cores = util::read_file("/proc/cpuinfo")
-        .await
-        .error("Your system doesn't support /proc/cpuinfo")?
-        .lines()
-        .filter(|l| l.starts_with("processor"))
-        .count();
+    let logical_cores = logical_cores().await?;
 
     loop {
-        let loadavg = util::read_file("/proc/loadavg")
-            .await
-            .error("Your system does not support reading the load average from /proc/loadavg")?;
-        let mut values = loadavg.split(' ');
-        let m1: f64 = values
-            .next()
-            .and_then(|x| x.parse().ok())
-            .error("bad /proc/loadavg file")?;
-        let m5: f64 = values
-            .next()
-            .and_then(|x| x.parse().ok())
-            .error("bad /proc/loadavg file")?;
-        let m15: f64 = values
-            .next()
-            .and_then(|x| x.parse().ok())
-            .error("bad /proc/loadavg file")?;
+        let [m1, m5, m15] = read_loadavg().await?;
 
         let mut widget = Widget::new().with_format(format.clone());
         widget.state = match m1 / logical_cores as f64 {
@@ -91,8 +71,51 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         api.set_widget(widget)?;
 
         select! {
-            _ = sleep(config.interval.0) => (),
+            _ = config.interval.sleep() => (),
             _ = api.wait_for_update_request() => (),
         }
     }
 }
+
+#[cfg(target_os = "linux")]
+async fn logical_cores() -> Result<usize> {
+    // borrowed from https://docs.rs/cpuinfo/0.1.1/src/cpuinfo/count/logical.rs.html#4-6
+    Ok(util::read_file("/proc/cpuinfo")
+        .await
+        .error("Your system doesn't support /proc/cpuinfo")?
+        .lines()
+        .filter(|l| l.starts_with("processor"))
+        .count())
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn logical_cores() -> Result<usize> {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .error("Failed to get the number of CPUs")
+}
+
+#[cfg(target_os = "linux")]
+async fn read_loadavg() -> Result<[f64; 3]> {
+    let loadavg = util::read_file("/proc/loadavg")
+        .await
+        .error("Your system does not support reading the load average from /proc/loadavg")?;
+    let mut values = loadavg.split(' ');
+    let mut next = || {
+        values
+            .next()
+            .and_then(|x| x.parse().ok())
+            .error("bad /proc/loadavg file")
+    };
+    Ok([next()?, next()?, next()?])
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn read_loadavg() -> Result<[f64; 3]> {
+    let mut loadavg = [0.0; 3];
+    // SAFETY: the buffer has room for 3 values
+    if unsafe { libc::getloadavg(loadavg.as_mut_ptr(), 3) } != 3 {
+        return Err(Error::new("Failed to get the load average"));
+    }
+    Ok(loadavg)
+}
@@ -95,7 +95,7 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         api.set_widget(widget)?;
 
         select! {
-            _ = sleep(config.interval.0) => (),
+            _ = config.interval.sleep() => (),
             _ = api.wait_for_update_request() => (),
         }
     }
@@ -71,11 +71,15 @@
 //! - `memory_swap`
 
 use std::cmp::min;
+#[cfg(not(target_os = "freebsd"))]
 use std::str::FromStr;
+#[cfg(not(target_os = "freebsd"))]
 use tokio::fs::{read_dir, File};
+#[cfg(not(target_os = "freebsd"))]
 use tokio::io::{AsyncBufReadExt, BufReader};
 
 use super::prelude::*;
+#[cfg(not(target_os = "freebsd"))]
 use crate::util::read_file;
 
 #[derive(Deserialize, Debug, SmartDefault)]
@@ -279,6 +283,7 @@ struct Memstate {
 }
 
 impl Memstate {
+    #[cfg(not(target_os = "freebsd"))]
     async fn new() -> Result<Self> {
         // Reference: https://www.kernel.org/doc/Documentation/filesystems/proc.txt
         let mut file = BufReader::new(
@@ -384,6 +389,37 @@ impl Memstate {
             mem_state.zfs_arc_min = c_min.parse().error("failed to parse zfs_arc_min size")?;
         }
 
+        Ok(mem_state)
+    }
+    /// Note that swap usage is not available on FreeBSD
+    #[cfg(target_os = "freebsd")]
+    async fn new() -> Result<Self> {
+        use crate::sysctl::read;
+
+        let page_size = read::<libc::c_uint>("vm.stats.vm.v_page_size")? as u64;
+        let pages = |name| read::<libc::c_uint>(name).map(|n| n as u64 * page_size / 1024);
+
+        let free = pages("vm.stats.vm.v_free_count")?;
+        let inactive = pages("vm.stats.vm.v_inactive_count")?;
+        let laundry = pages("vm.stats.vm.v_laundry_count").unwrap_or(0);
+
+        let mut mem_state = Memstate {
+            mem_total: read::<libc::c_ulong>("hw.physmem")? as u64 / 1024,
+            mem_free: free,
+            // Inactive pages can be reclaimed, which is what Linux considers cache
+            mem_available: free + inactive + laundry,
+            pagecache: inactive + laundry,
+            ..Default::default()
+        };
+
+        if let (Ok(size), Ok(c_min)) = (
+            read::<u64>("kstat.zfs.misc.arcstats.size"),
+            read::<u64>("kstat.zfs.misc.arcstats.c_min"),
+        ) {
+            mem_state.zfs_arc_cache = size;
+            mem_state.zfs_arc_min = c_min;
+        }
+
         Ok(mem_state)
     }
 }
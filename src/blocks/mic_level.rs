@@ -0,0 +1,246 @@
+//! The input level of a microphone
+//!
+//! This block shows a VU meter of the peak level of an audio source, e.g. to check during a call
+//! that the microphone is live. It uses the peak detection of PulseAudio, which is also provided by
+//! PipeWire through `pipewire-pulse`.
+//!
+//! Measuring the level requires a recording stream, so while this block runs the microphone is
+//! in use and the `privacy` block shows it. The stream belongs to the application `i3status-rs`,
+//! e.g. `exclude_input = ["i3status-rs"]` hides it in the `pipewire` driver of the `privacy`
+//! block.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" $icon $level.bar(w:5) \"</code>
+//! `device` | The name of the source to measure. The default source is used (and followed when it changes) if unset. | None
+//! `interval` | How often (in seconds) the meter is updated. The highest peak since the last update is shown. | `0.2`
+//! `active_level` | The level (in percent) above which the microphone is considered live and the state is Good | `5`
+//! `clip_level` | The level (in percent) at which the input is considered to clip | `99`
+//! `clip_hold` | How long (in seconds) the state stays Critical and `$clipping` is set after the input clipped | `2`
+//!
+//! Placeholder | Value                                                 | Type   | Unit
+//! ------------|-------------------------------------------------------|--------|-----
+//! `icon`      | A static icon                                         | Icon   | -
+//! `level`     | The peak level since the last update                  | Number | %
+//! `clipping`  | Present if the input clipped within `clip_hold`       | Flag   | -
+//!
+//! # Example
+//!
+//! Show "CLIP" when the microphone is too loud
+//!
+//! ```toml
+//! [[block]]
+//! block = "mic_level"
+//! format = " $icon $level.bar(w:8) {$clipping{CLIP }|}"
+//! ```
+//!
+//! # Icons Used
+//! - `microphone`
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use libpulse_binding::context::{Context, FlagSet, State as PulseState};
+use libpulse_binding::def::BufferAttr;
+use libpulse_binding::mainloop::standard::{IterateResult, Mainloop};
+use libpulse_binding::sample::{Format, Spec};
+use libpulse_binding::stream::{self, PeekResult, Stream};
+
+use super::prelude::*;
+
+/// How many peaks per second PulseAudio computes
+const PEAK_RATE: u32 = 25;
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    pub device: Option<String>,
+    #[default(Seconds(Duration::from_millis(200)))]
+    pub interval: Seconds,
+    #[default(5.0)]
+    pub active_level: f64,
+    #[default(99.0)]
+    pub clip_level: f64,
+    #[default(2.into())]
+    pub clip_hold: Seconds,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let format = config.format.with_default(" $icon $level.bar(w:5) ")?;
+
+    // The highest peak since the last update, shared with the monitoring thread
+    let peak = Arc::new(Mutex::new(0.0f32));
+    let mut monitor = tokio::task::spawn_blocking({
+        let peak = peak.clone();
+        let device = config.device.clone();
+        move || monitor(device.as_deref(), &peak)
+    });
+
+    let mut timer = config.interval.timer();
+    let mut last_clip = None;
+
+    loop {
+        let level = percent(std::mem::take(&mut *peak.lock().unwrap()));
+        let now = Instant::now();
+        if level >= config.clip_level {
+            last_clip = Some(now);
+        }
+        let clipping = last_clip.is_some_and(|t| now - t < config.clip_hold.0);
+
+        let mut widget = Widget::new().with_format(format.clone());
+        widget.state = level_state(config, level, clipping);
+        widget.set_values(map! {
+            "icon" => Value::icon("microphone"),
+            "level" => Value::percents(level.min(100.0)),
+            [if clipping] "clipping" => Value::flag(),
+        });
+        api.set_widget(widget)?;
+
+        select! {
+            _ = timer.tick() => (),
+            result = &mut monitor => {
+                result.error("The monitoring thread panicked")??;
+                return Err(Error::new("The recording stream was closed"));
+            }
+        }
+    }
+}
+
+/// The level (in percent) of a peak, where `1.0` is full scale
+fn percent(peak: f32) -> f64 {
+    f64::from(peak) * 100.0
+}
+
+fn level_state(config: &Config, level: f64, clipping: bool) -> State {
+    if clipping {
+        State::Critical
+    } else if level >= config.active_level {
+        State::Good
+    } else {
+        State::Idle
+    }
+}
+
+/// The highest absolute value of the native endian `f32` samples in `data`
+fn max_sample(data: &[u8]) -> f32 {
+    data.chunks_exact(size_of::<f32>())
+        .map(|s| f32::from_ne_bytes(s.try_into().unwrap()).abs())
+        .fold(0.0, f32::max)
+}
+
+/// Records the peaks of `device` into `peak` until the block stops
+fn monitor(device: Option<&str>, peak: &Arc<Mutex<f32>>) -> Result<()> {
+    fn iterate(mainloop: &mut Mainloop) -> Result<()> {
+        match mainloop.iterate(true) {
+            IterateResult::Quit(_) | IterateResult::Err(_) => {
+                Err(Error::new("failed to iterate pulseaudio state"))
+            }
+            IterateResult::Success(_) => Ok(()),
+        }
+    }
+
+    let mut mainloop = Mainloop::new().error("Failed to create pulseaudio mainloop")?;
+
+    let mut context = Context::new(&mainloop, env!("CARGO_PKG_NAME"))
+        .error("Failed to create new pulseaudio context")?;
+    context
+        .connect(None, FlagSet::NOFLAGS, None)
+        .error("Failed to connect to pulseaudio context")?;
+    loop {
+        iterate(&mut mainloop)?;
+        match context.get_state() {
+            PulseState::Ready => break,
+            PulseState::Failed | PulseState::Terminated => {
+                return Err(Error::new("pulseaudio context state failed/terminated"));
+            }
+            _ => (),
+        }
+    }
+
+    // With peak detection, each sample is the peak of the last 1/PEAK_RATE seconds
+    let spec = Spec {
+        format: Format::FLOAT32NE,
+        channels: 1,
+        rate: PEAK_RATE,
+    };
+    let mut stream = Stream::new(&mut context, "Microphone level", &spec, None)
+        .error("Failed to create the recording stream")?;
+    let attr = BufferAttr {
+        maxlength: u32::MAX,
+        tlength: u32::MAX,
+        prebuf: u32::MAX,
+        minreq: u32::MAX,
+        fragsize: size_of::<f32>() as u32,
+    };
+    stream
+        .connect_record(
+            device,
+            Some(&attr),
+            stream::FlagSet::PEAK_DETECT | stream::FlagSet::ADJUST_LATENCY,
+        )
+        .error("Failed to start recording")?;
+
+    loop {
+        iterate(&mut mainloop)?;
+        match stream.get_state() {
+            stream::State::Ready => (),
+            stream::State::Failed | stream::State::Terminated => {
+                return Err(Error::new(match device {
+                    Some(device) => format!("Failed to record from '{device}'"),
+                    None => "Failed to record from the default source".into(),
+                }));
+            }
+            _ => continue,
+        }
+        loop {
+            match stream.peek().error("Failed to read the recording stream")? {
+                PeekResult::Empty => break,
+                PeekResult::Hole(_) => (),
+                PeekResult::Data(data) => {
+                    let mut peak = peak.lock().unwrap();
+                    *peak = peak.max(max_sample(data));
+                }
+            }
+            stream.discard().error("Failed to read the recording stream")?;
+        }
+        // The block has stopped
+        if Arc::strong_count(peak) == 1 {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels() {
+        assert_eq!(percent(0.0), 0.0);
+        assert_eq!(percent(0.25), 25.0);
+        assert_eq!(percent(1.0), 100.0);
+
+        let config = Config::default();
+        assert_eq!(level_state(&config, 0.0, false), State::Idle);
+        assert_eq!(level_state(&config, 4.9, false), State::Idle);
+        assert_eq!(level_state(&config, 5.0, false), State::Good);
+        assert_eq!(level_state(&config, 80.0, false), State::Good);
+        // Clipping is held after the level dropped again
+        assert_eq!(level_state(&config, 0.0, true), State::Critical);
+    }
+
+    #[test]
+    fn samples() {
+        let data: Vec<u8> = [0.1f32, -0.6, 0.3]
+            .iter()
+            .flat_map(|s| s.to_ne_bytes())
+            .collect();
+        assert_eq!(max_sample(&data), 0.6);
+        assert_eq!(max_sample(&[]), 0.0);
+        // An incomplete trailing sample is ignored
+        assert_eq!(max_sample(&data[..data.len() - 1]), 0.6);
+    }
+}
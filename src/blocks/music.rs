@@ -136,7 +136,7 @@
 //! [MediaPlayer2 Interface]: https://specifications.freedesktop.org/mpris-spec/latest/Player_Interface.html
 
 use super::prelude::*;
-use crate::wrappers::DisplaySlice;
+use crate::wrappers::{DisplaySlice, SerdeRegex};
 
 use regex::Regex;
 use std::fmt;
@@ -159,8 +159,8 @@ pub struct Config {
     pub format: FormatConfig,
     pub format_alt: Option<FormatConfig>,
     pub player: PlayerName,
-    #[default(vec!["playerctld".into()])]
-    pub interface_name_exclude: Vec<String>,
+    #[default(vec![SerdeRegex(Regex::new("playerctld").unwrap())])]
+    pub interface_name_exclude: Vec<SerdeRegex>,
     #[default(" - ".into())]
     pub separator: String,
     #[default(1.into())]
@@ -215,7 +215,7 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         .as_micros() as i64);
 
     let new_btn = |icon: &str, instance: &'static str| -> Result<Value> {
-        Ok(Value::icon(icon.to_string()).with_instance(instance))
+        Ok(Value::icon(icon).with_instance(instance))
     };
 
     let values = map! {
@@ -228,12 +228,11 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         PlayerName::Single(name) => vec![name],
         PlayerName::Multiple(names) => names,
     };
-    let exclude_regex = config
+    let exclude_regex: Vec<Regex> = config
         .interface_name_exclude
         .iter()
-        .map(|r| Regex::new(r))
-        .collect::<Result<Vec<_>, _>>()
-        .error("Invalid regex")?;
+        .map(|re| re.0.clone())
+        .collect();
 
     let playerctld_proxy = zbus_playerctld::PlayerctldProxy::new(&dbus_conn)
         .await
@@ -306,10 +305,10 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         match player {
             Some(player) => {
                 let mut values = values.clone();
-                values.insert("avail".into(), Value::number(avail));
-                values.insert("cur".into(), Value::number(cur_player.unwrap() + 1));
+                values.insert("avail", Value::number(avail));
+                values.insert("cur", Value::number(cur_player.unwrap() + 1));
                 values.insert(
-                    "player".into(),
+                    "player",
                     Value::text(
                         extract_player_name(player.bus_name.as_str())
                             .unwrap()
@@ -320,9 +319,9 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
                     Some(PlaybackStatus::Playing) => (State::Info, "music_pause"),
                     _ => (State::Idle, "music_play"),
                 };
-                values.insert("play".into(), new_btn(play_icon, PLAY_PAUSE_BTN)?);
+                values.insert("play", new_btn(play_icon, PLAY_PAUSE_BTN)?);
                 if let Some(url) = &player.metadata.url {
-                    values.insert("url".into(), Value::text(url.clone()));
+                    values.insert("url", Value::text(url.clone()));
                 }
                 match (
                     &player.metadata.title,
@@ -330,32 +329,32 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
                     &player.metadata.url,
                 ) {
                     (Some(t), None, _) => {
-                        values.insert("combo".into(), Value::text(t.clone()));
-                        values.insert("title".into(), Value::text(t.clone()));
+                        values.insert("combo", Value::text(t.clone()));
+                        values.insert("title", Value::text(t.clone()));
                     }
                     (None, Some(a), _) => {
-                        values.insert("combo".into(), Value::text(a.clone()));
-                        values.insert("artist".into(), Value::text(a.clone()));
+                        values.insert("combo", Value::text(a.clone()));
+                        values.insert("artist", Value::text(a.clone()));
                     }
                     (Some(t), Some(a), _) => {
                         values.insert(
-                            "combo".into(),
+                            "combo",
                             Value::text(format!("{t}{}{a}", config.separator)),
                         );
-                        values.insert("title".into(), Value::text(t.clone()));
-                        values.insert("artist".into(), Value::text(a.clone()));
+                        values.insert("title", Value::text(t.clone()));
+                        values.insert("artist", Value::text(a.clone()));
                     }
                     (None, None, Some(url)) => {
-                        values.insert("combo".into(), Value::text(url.clone()));
+                        values.insert("combo", Value::text(url.clone()));
                     }
                     _ => (),
                 }
                 if let Some(volume) = player.volume {
                     values.insert(
-                        "volume_icon".into(),
+                        "volume_icon",
                         Value::icon_progression("volume", volume),
                     );
-                    values.insert("volume".into(), Value::percents(volume * 100.0));
+                    values.insert("volume", Value::percents(volume * 100.0));
                 }
                 let mut widget = Widget::new().with_format(format.clone());
                 widget.set_values(values);
@@ -2,6 +2,11 @@
 //!
 //! This block uses `sysfs` and `netlink` and thus does not require any external dependencies.
 //!
+//! Android does not allow apps to use `netlink`. If i3status-rs is built with the `termux` feature
+//! and runs under Termux, this block falls back to `/proc/net/dev` and gets WiFi information from
+//! `termux-wifi-connectioninfo` (requires [Termux:API](https://wiki.termux.com/wiki/Termux:API)).
+//! IPv6 addresses are not available in this mode.
+//!
 //! # Configuration
 //!
 //! Key | Values | Default
@@ -12,10 +17,21 @@
 //! `format_alt` | If set, block will switch between `format` and `format_alt` on every click | `None`
 //! `inactive_format` | Same as `format` but for when the interface is inactive | `" $icon Down "`
 //! `missing_format` | Same as `format` but for when the device is missing | `" × "`
+//! `qr_command` | Shell command which displays a QR code for the text on its standard input, used by the `share_wifi` action | `"qrencode -s 10 -o - \| feh -"`
+//! `portal_check` | Whether to periodically check for a captive portal, e.g. in hotels or trains. The state of the block is warning while a portal intercepts traffic. | `false`
+//! `portal_check_url` | The URL to probe for a captive portal. It must answer with `204 No Content`. | `"http://connectivitycheck.gstatic.com/generate_204"`
+//! `portal_check_interval` | How often to check for a captive portal, in seconds | `60`
+//! `browser_cmd` | Command to open the login page of a captive portal. The URL is passed as an argument. | `"xdg-open"`
+//! `dns_check` | Whether to periodically resolve `dns_check_name` with the system resolver. The state of the block is critical while resolution fails, and warning while it takes longer than `dns_check_warning`. | `false`
+//! `dns_check_name` | The domain name to resolve | `"example.com"`
+//! `dns_check_interval` | How often to check DNS resolution, in seconds | `60`
+//! `dns_check_warning` | The resolver latency in milliseconds above which the block is in the warning state | `1000`
 //!
 //! Action          | Description                               | Default button
 //! ----------------|-------------------------------------------|---------------
 //! `toggle_format` | Toggles between `format` and `format_alt` | Left
+//! `share_wifi`    | Shows a QR code which phones can scan to join the WiFi network of the device. Requires NetworkManager, which may ask for authorization to reveal the key. Failures are logged. | Right
+//! `open_portal`   | Opens the login page of the captive portal in the browser. Failures are logged. | Middle
 //!
 //! Placeholder       | Value                       | Type   | Unit
 //! ------------------|-----------------------------|--------|---------------
@@ -32,6 +48,8 @@
 //! `ip`              | IPv4 address of the iface   | Text   | -
 //! `ipv6`            | IPv6 address of the iface   | Text   | -
 //! `nameserver`      | Nameserver                  | Text   | -
+//! `portal`          | The login page of the captive portal. Absent if there is none or `portal_check` is disabled. | Text | -
+//! `dns_latency`     | How long it took to resolve `dns_check_name`. Absent if resolution failed or `dns_check` is disabled. | Number | Seconds
 //!
 //! # Example
 //!
@@ -51,6 +69,33 @@
 //! device = "^wlo0$"
 //! ```
 //!
+//! Show a warning when a captive portal needs a login
+//!
+//! ```toml
+//! [[block]]
+//! block = "net"
+//! format = " $icon {$ssid|Wired connection} {$portal.str(max_w:0)captive portal |}"
+//! portal_check = true
+//! ```
+//!
+//! Tell a broken resolver apart from a broken connection
+//!
+//! ```toml
+//! [[block]]
+//! block = "net"
+//! format = " $icon {$ssid|Wired connection} {$dns_latency.eng(w:3,prefix:m)|DNS down} "
+//! dns_check = true
+//! dns_check_interval = 30
+//! ```
+//!
+//! Share the WiFi network with `imv` instead of `feh`
+//!
+//! ```toml
+//! [[block]]
+//! block = "net"
+//! qr_command = "qrencode -s 10 -o - | imv -"
+//! ```
+//!
 //! # Icons Used
 //! - `net_loopback`
 //! - `net_vpn`
@@ -61,26 +106,53 @@
 
 use super::prelude::*;
 use crate::netlink::NetDevice;
-use crate::util;
+use crate::subprocess::spawn_process;
+use crate::util::{self, has_command};
+use crate::wrappers::SerdeRegex;
 use itertools::Itertools;
-use regex::Regex;
 use std::time::Instant;
 
+mod dns;
+mod portal;
+mod wifi_qr;
+
+make_log_macro!(warn, "net");
+
 #[derive(Deserialize, Debug, SmartDefault)]
 #[serde(deny_unknown_fields, default)]
 pub struct Config {
-    pub device: Option<String>,
+    pub device: Option<SerdeRegex>,
     #[default(2.into())]
     pub interval: Seconds,
     pub format: FormatConfig,
     pub format_alt: Option<FormatConfig>,
     pub inactive_format: FormatConfig,
     pub missing_format: FormatConfig,
+    #[default("qrencode -s 10 -o - | feh -".into())]
+    pub qr_command: String,
+    pub portal_check: bool,
+    #[default("http://connectivitycheck.gstatic.com/generate_204".into())]
+    pub portal_check_url: String,
+    #[default(60.into())]
+    pub portal_check_interval: Seconds,
+    #[default("xdg-open".into())]
+    pub browser_cmd: ShellString,
+    pub dns_check: bool,
+    #[default("example.com".into())]
+    pub dns_check_name: String,
+    #[default(60.into())]
+    pub dns_check_interval: Seconds,
+    #[default(1000)]
+    pub dns_check_warning: u64,
 }
 
 pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
     let mut actions = api.get_actions()?;
-    api.set_default_actions(&[(MouseButton::Left, None, "toggle_format")])?;
+    api.set_default_actions(&[
+        (MouseButton::Left, None, "toggle_format"),
+        (MouseButton::Right, None, "share_wifi"),
+        (MouseButton::Middle, None, "open_portal"),
+    ])?;
 
     let mut format = config.format.with_default(
         " $icon ^icon_net_down $speed_down.eng(prefix:K) ^icon_net_up $speed_up.eng(prefix:K) ",
@@ -94,12 +166,7 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
 
     let mut timer = config.interval.timer();
 
-    let device_re = config
-        .device
-        .as_deref()
-        .map(Regex::new)
-        .transpose()
-        .error("Failed to parse device regex")?;
+    let device_re = config.device.as_ref().map(|re| &re.0);
 
     // Stats
     let mut stats = None;
@@ -107,13 +174,46 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
     let mut tx_hist = [0f64; 8];
     let mut rx_hist = [0f64; 8];
 
+    let mut portal = None;
+    let mut next_portal_check = Instant::now();
+
+    let mut dns_latency = None;
+    let mut next_dns_check = Instant::now();
+
     loop {
-        match NetDevice::new(device_re.as_ref()).await? {
+        let device = NetDevice::new(device_re).await?;
+        let iface = device.as_ref().map(|device| device.iface.name.clone());
+
+        if !config.portal_check || !device.as_ref().is_some_and(|device| device.is_up()) {
+            portal = None;
+        } else if Instant::now() >= next_portal_check {
+            portal = portal::detect(&config.portal_check_url).await;
+            next_portal_check = Instant::now() + config.portal_check_interval.0;
+        }
+
+        if !config.dns_check || !device.as_ref().is_some_and(|device| device.is_up()) {
+            dns_latency = None;
+        } else if Instant::now() >= next_dns_check {
+            dns_latency = Some(dns::probe(&config.dns_check_name).await);
+            next_dns_check = Instant::now() + config.dns_check_interval.0;
+        }
+
+        match device {
             None => {
                 api.set_widget(Widget::new().with_format(missing_format.clone()))?;
             }
             Some(device) => {
                 let mut widget = Widget::new();
+                widget.state = match dns_latency {
+                    Some(None) => State::Critical,
+                    Some(Some(latency))
+                        if latency > Duration::from_millis(config.dns_check_warning) =>
+                    {
+                        State::Warning
+                    }
+                    _ if portal.is_some() => State::Warning,
+                    _ => State::Idle,
+                };
 
                 if device.is_up() {
                     widget.set_format(format.clone());
@@ -169,6 +269,8 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
                                                                                 .join(" "),
                                                                         ),
                     "device" => Value::text(device.iface.name),
+                    [if let Some(v) = &portal] "portal" => Value::text(v.clone()),
+                    [if let Some(Some(v)) = dns_latency] "dns_latency" => Value::seconds(v.as_secs_f64()),
                 });
 
                 api.set_widget(widget)?;
@@ -178,7 +280,11 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         loop {
             select! {
                 _ = timer.tick() => break,
-                _ = api.wait_for_update_request() => break,
+                _ = api.wait_for_update_request() => {
+                    next_portal_check = Instant::now();
+                    next_dns_check = Instant::now();
+                    break;
+                }
                 Some(action) = actions.recv() => match action.as_ref() {
                     "toggle_format" => {
                         if let Some(format_alt) = &mut format_alt {
@@ -186,6 +292,21 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
                             break;
                         }
                     }
+                    "share_wifi" => {
+                        if let Some(iface) = &iface {
+                            // E.g. a denied authorization should not stop the block
+                            if let Err(err) = wifi_qr::share(iface, &config.qr_command).await {
+                                warn!("failed to share the WiFi network: {err}");
+                            }
+                        }
+                    }
+                    "open_portal" => {
+                        if let Some(portal) = &portal {
+                            if let Err(err) = open_browser(config, portal).await {
+                                warn!("failed to open the captive portal: {err}");
+                            }
+                        }
+                    }
                     _ => ()
                 }
             }
@@ -193,6 +314,14 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
     }
 }
 
+async fn open_browser(config: &Config, url: &str) -> Result<()> {
+    let cmd = config.browser_cmd.expand()?;
+    if !has_command(&cmd).await? {
+        return Err(Error::new("Browser command not found"));
+    }
+    spawn_process(&cmd, &[url]).error("Open browser failed")
+}
+
 fn push_to_hist<T>(hist: &mut [T], elem: T) {
     hist[0] = elem;
     hist.rotate_left(1);
@@ -0,0 +1,36 @@
+//! DNS health check
+//!
+//! A name is resolved with the system resolver (like other programs do, so `/etc/hosts`,
+//! `systemd-resolved` and the like are involved). This tells a broken resolver apart from a
+//! connection which is down.
+
+use std::time::Instant;
+
+use super::*;
+
+make_log_macro!(debug, "net");
+
+/// Resolutions which take longer than this are considered failed
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Returns how long it took to resolve `name`, or `None` if it could not be resolved
+pub(super) async fn probe(name: &str) -> Option<Duration> {
+    let start = Instant::now();
+    match tokio::time::timeout(TIMEOUT, tokio::net::lookup_host((name, 0))).await {
+        Ok(Ok(mut addrs)) => {
+            let resolved = addrs.next().is_some();
+            if !resolved {
+                debug!("DNS check: no addresses for {name}");
+            }
+            resolved.then(|| start.elapsed())
+        }
+        Ok(Err(err)) => {
+            debug!("DNS check failed: {err}");
+            None
+        }
+        Err(_) => {
+            debug!("DNS check timed out");
+            None
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Captive portal detection
+//!
+//! Like NetworkManager does, a URL which answers with `204 No Content` is requested. A captive
+//! portal intercepts the request and answers with a redirect to its login page or with the login
+//! page itself.
+
+use reqwest::header::LOCATION;
+use reqwest::{redirect, StatusCode, Url};
+
+use super::*;
+
+make_log_macro!(debug, "net");
+
+/// The portal answers instead of the probe server, so redirects must not be followed
+static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .user_agent(crate::APP_USER_AGENT)
+        .redirect(redirect::Policy::none())
+        .timeout(Duration::from_secs(5))
+        .build()
+        .unwrap()
+});
+
+/// Returns the URL of the login page of the captive portal, if any
+pub(super) async fn detect(probe_url: &str) -> Option<String> {
+    match CLIENT.get(probe_url).send().await {
+        Ok(response) => portal_url(
+            probe_url,
+            response.status(),
+            response
+                .headers()
+                .get(LOCATION)
+                .and_then(|location| location.to_str().ok()),
+        ),
+        Err(err) => {
+            // Without connectivity, there is no portal to log in to
+            debug!("captive portal check failed: {err}");
+            None
+        }
+    }
+}
+
+fn portal_url(probe_url: &str, status: StatusCode, location: Option<&str>) -> Option<String> {
+    if status == StatusCode::NO_CONTENT {
+        return None;
+    }
+    let redirect = location
+        .filter(|_| status.is_redirection())
+        .and_then(|location| Url::parse(probe_url).ok()?.join(location).ok());
+    Some(redirect.map_or_else(|| probe_url.into(), String::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROBE: &str = "http://connectivitycheck.gstatic.com/generate_204";
+
+    #[test]
+    fn portal() {
+        assert_eq!(portal_url(PROBE, StatusCode::NO_CONTENT, None), None);
+        assert_eq!(
+            portal_url(
+                PROBE,
+                StatusCode::FOUND,
+                Some("https://login.example.com/?next=x")
+            ),
+            Some("https://login.example.com/?next=x".into())
+        );
+        assert_eq!(
+            portal_url(PROBE, StatusCode::SEE_OTHER, Some("/login")),
+            Some("http://connectivitycheck.gstatic.com/login".into())
+        );
+        assert_eq!(portal_url(PROBE, StatusCode::OK, None), Some(PROBE.into()));
+    }
+}
@@ -0,0 +1,204 @@
+//! Sharing the WiFi network of a device as a QR code
+//!
+//! The network name and key are read from NetworkManager. Reading the key may require
+//! authorization through polkit.
+
+use std::process::Stdio;
+
+use tokio::process::Command;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+
+use super::*;
+
+type Settings = HashMap<String, HashMap<String, OwnedValue>>;
+
+const WIRELESS: &str = "802-11-wireless";
+const WIRELESS_SECURITY: &str = "802-11-wireless-security";
+
+/// Runs `command` with the `WIFI:` string of the network `iface` is connected to on its standard
+/// input
+pub(super) async fn share(iface: &str, command: &str) -> Result<()> {
+    let text = wifi_text(iface).await?;
+    let mut child = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .error("Failed to run qr_command")?;
+    let mut stdin = child.stdin.take().error("Failed to open stdin of qr_command")?;
+    stdin
+        .write_all(text.as_bytes())
+        .await
+        .error("Failed to write to qr_command")?;
+    drop(stdin);
+    // The image viewer is open until it is closed by the user
+    tokio::spawn(async move { child.wait().await });
+    Ok(())
+}
+
+async fn wifi_text(iface: &str) -> Result<String> {
+    let conn = new_system_dbus_connection().await?;
+    let nm = NetworkManagerProxy::new(&conn)
+        .await
+        .error("Failed to create NetworkManagerProxy")?;
+    let device = nm
+        .get_device_by_ip_iface(iface)
+        .await
+        .or_error(|| format!("NetworkManager does not manage '{iface}'"))?;
+    let active = DeviceProxy::builder(&conn)
+        .path(device)
+        .error("Failed to set path")?
+        .build()
+        .await
+        .error("Failed to create DeviceProxy")?
+        .active_connection()
+        .await
+        .error("Failed to get the active connection")?;
+    if active.as_str() == "/" {
+        return Err(Error::new(format!("'{iface}' is not connected")));
+    }
+    let settings = ActiveConnectionProxy::builder(&conn)
+        .path(active)
+        .error("Failed to set path")?
+        .build()
+        .await
+        .error("Failed to create ActiveConnectionProxy")?
+        .connection()
+        .await
+        .error("Failed to get the connection settings")?;
+    let settings = SettingsConnectionProxy::builder(&conn)
+        .path(settings)
+        .error("Failed to set path")?
+        .build()
+        .await
+        .error("Failed to create SettingsConnectionProxy")?;
+
+    let config = settings
+        .get_settings()
+        .await
+        .error("Failed to get the connection settings")?;
+    let wireless = config.get(WIRELESS).error("Not a WiFi connection")?;
+    let ssid: Vec<u8> = wireless
+        .get("ssid")
+        .and_then(|ssid| ssid.try_clone().ok()?.try_into().ok())
+        .error("The connection has no SSID")?;
+    let hidden = wireless
+        .get("hidden")
+        .and_then(|hidden| bool::try_from(hidden).ok())
+        .unwrap_or(false);
+    let key_mgmt = config
+        .get(WIRELESS_SECURITY)
+        .and_then(|security| security.get("key-mgmt"))
+        .and_then(|key_mgmt| key_mgmt.downcast_ref::<&str>().ok());
+
+    let (auth, key) = match key_mgmt {
+        None | Some("owe") => ("nopass", None),
+        Some(key_mgmt) => {
+            let auth = match key_mgmt {
+                "none" => "WEP",
+                "sae" => "SAE",
+                _ => "WPA",
+            };
+            let secrets = settings
+                .get_secrets(WIRELESS_SECURITY)
+                .await
+                .error("Failed to get the WiFi key from NetworkManager")?;
+            let key = secrets
+                .get(WIRELESS_SECURITY)
+                .and_then(|secrets| secrets.get(if auth == "WEP" { "wep-key0" } else { "psk" }))
+                .and_then(|key| key.downcast_ref::<&str>().ok())
+                .map(String::from)
+                .error("NetworkManager did not return the WiFi key")?;
+            (auth, Some(key))
+        }
+    };
+
+    Ok(format_wifi(
+        &String::from_utf8_lossy(&ssid),
+        auth,
+        key.as_deref(),
+        hidden,
+    ))
+}
+
+/// Formats the network in the `WIFI:` format understood by the cameras of phones
+fn format_wifi(ssid: &str, auth: &str, key: Option<&str>, hidden: bool) -> String {
+    fn escape(s: &str) -> String {
+        let mut escaped = String::with_capacity(s.len());
+        for c in s.chars() {
+            if matches!(c, '\\' | ';' | ',' | '"' | ':') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+
+    let mut text = format!("WIFI:T:{auth};S:{};", escape(ssid));
+    if let Some(key) = key {
+        let _ = write!(text, "P:{};", escape(key));
+    }
+    if hidden {
+        text.push_str("H:true;");
+    }
+    text.push(';');
+    text
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager"
+)]
+trait NetworkManager {
+    fn get_device_by_ip_iface(&self, iface: &str) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Device",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Device {
+    #[zbus(property)]
+    fn active_connection(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Connection.Active",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait ActiveConnection {
+    #[zbus(property)]
+    fn connection(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Settings.Connection",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait SettingsConnection {
+    fn get_settings(&self) -> zbus::Result<Settings>;
+
+    fn get_secrets(&self, setting_name: &str) -> zbus::Result<Settings>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wifi_format() {
+        assert_eq!(
+            format_wifi("Guest", "WPA", Some("hunter2"), false),
+            "WIFI:T:WPA;S:Guest;P:hunter2;;"
+        );
+        assert_eq!(
+            format_wifi("Café; \"1\"", "SAE", Some("a:b,c\\"), true),
+            r#"WIFI:T:SAE;S:Café\; \"1\";P:a\:b\,c\\;H:true;;"#
+        );
+        assert_eq!(
+            format_wifi("Open", "nopass", None, false),
+            "WIFI:T:nopass;S:Open;;"
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! The remote host and the process using the most bandwidth
+//!
+//! This block uses the connection tracking of netfilter (`/proc/net/nf_conntrack`) to find the
+//! connections which transferred the most data since the last update, and shows the remote host
+//! and the local process with the highest traffic.
+//!
+//! Note that you need to enable `net_top` feature to use this block:
+//! ```sh
+//! cargo build --release --features net_top
+//! ```
+//!
+//! Conntrack only counts the traffic of connections if accounting is enabled, which can be made
+//! permanent in `/etc/sysctl.d/`:
+//! ```sh
+//! sysctl net.netfilter.nf_conntrack_acct=1
+//! ```
+//!
+//! `/proc/net/nf_conntrack` is only readable by root, so i3status-rs needs the
+//! `CAP_DAC_READ_SEARCH` capability, e.g. `setcap cap_dac_read_search+ep $(which i3status-rs)`.
+//! Processes of other users are only found with `CAP_SYS_PTRACE`. Traffic which is forwarded
+//! (e.g. on a router) or belongs to processes which are not found counts for the host only.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" $icon {$top_host $top_host_speed.eng(prefix:K)\|-} \"</code>
+//! `interval` | Update interval in seconds | `5`
+//!
+//! Placeholder      | Value                                                  | Type   | Unit
+//! -----------------|--------------------------------------------------------|--------|-----------------
+//! `icon`           | A static icon                                          | Icon   | -
+//! `top_host`       | The IP address of the remote host with the most traffic | Text   | -
+//! `top_host_speed` | The traffic (up and down) with `top_host`               | Number | Bytes per second
+//! `top_proc`       | The name of the process with the most traffic           | Text   | -
+//! `top_proc_speed` | The traffic (up and down) of `top_proc`                 | Number | Bytes per second
+//!
+//! The placeholders are absent if there was no traffic since the last update.
+//!
+//! # Example
+//!
+//! ```toml
+//! [[block]]
+//! block = "net_top"
+//! format = " $icon {$top_proc $top_proc_speed.eng(prefix:K)|idle} "
+//! interval = 2
+//! ```
+//!
+//! # Icons Used
+//! - `net_wired`
+
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::IpAddr;
+use std::time::Instant;
+
+use super::prelude::*;
+use crate::util;
+
+const CONNTRACK: &str = "/proc/net/nf_conntrack";
+const CONNTRACK_ACCT: &str = "/proc/sys/net/netfilter/nf_conntrack_acct";
+/// The sockets of the local processes and their protocol numbers
+const SOCKET_TABLES: [(&str, u8); 4] = [
+    ("/proc/net/tcp", 6),
+    ("/proc/net/tcp6", 6),
+    ("/proc/net/udp", 17),
+    ("/proc/net/udp6", 17),
+];
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    #[default(5.into())]
+    pub interval: Seconds,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let format = config
+        .format
+        .with_default(" $icon {$top_host $top_host_speed.eng(prefix:K)|-} ")?;
+
+    let acct = util::read_file(CONNTRACK_ACCT)
+        .await
+        .error("Connection tracking is not available. Is the nf_conntrack module loaded?")?;
+    if acct.trim() == "0" {
+        return Err(Error::new(
+            "Connection tracking accounting is disabled. Enable it with 'sysctl net.netfilter.nf_conntrack_acct=1'",
+        ));
+    }
+
+    let mut prev: Option<(Instant, HashMap<Connection, u64>)> = None;
+    let mut timer = config.interval.timer();
+
+    loop {
+        let now = Instant::now();
+        let connections = read_conntrack().await?;
+
+        let mut widget = Widget::new().with_format(format.clone());
+        let mut values = map! {
+            "icon" => Value::icon("net_wired"),
+        };
+
+        if let Some((prev_time, prev_connections)) = &prev {
+            let elapsed = now.duration_since(*prev_time).as_secs_f64();
+            let sockets = read_sockets().await?;
+            let mut hosts: HashMap<IpAddr, u64> = HashMap::new();
+            let mut inodes: HashMap<u64, u64> = HashMap::new();
+            for (conn, &bytes) in &connections {
+                // Connections which are new since the last update transferred all of their bytes
+                let bytes = bytes.saturating_sub(prev_connections.get(conn).copied().unwrap_or(0));
+                if bytes == 0 || conn.src.is_loopback() && conn.dst.is_loopback() {
+                    continue;
+                }
+                let (host, inode) = conn.remote(&sockets);
+                *hosts.entry(host).or_default() += bytes;
+                if let Some(inode) = inode {
+                    *inodes.entry(inode).or_default() += bytes;
+                }
+            }
+
+            if let Some((host, bytes)) = hosts.into_iter().max_by_key(|&(_, bytes)| bytes) {
+                map! { @extend values
+                    "top_host" => Value::text(host.to_string()),
+                    "top_host_speed" => Value::bytes(bytes as f64 / elapsed),
+                }
+            }
+
+            if !inodes.is_empty() {
+                let processes = tokio::task::spawn_blocking(find_processes)
+                    .await
+                    .error("Failed to find the processes")?;
+                let mut names: HashMap<&str, u64> = HashMap::new();
+                for (inode, bytes) in inodes {
+                    if let Some(name) = processes.get(&inode) {
+                        *names.entry(name).or_default() += bytes;
+                    }
+                }
+                if let Some((name, bytes)) = names.into_iter().max_by_key(|&(_, bytes)| bytes) {
+                    map! { @extend values
+                        "top_proc" => Value::text(name.into()),
+                        "top_proc_speed" => Value::bytes(bytes as f64 / elapsed),
+                    }
+                }
+            }
+        }
+        prev = Some((now, connections));
+
+        widget.set_values(values);
+        api.set_widget(widget)?;
+
+        select! {
+            _ = timer.tick() => (),
+            _ = api.wait_for_update_request() => (),
+        }
+    }
+}
+
+/// The original direction of a tracked connection
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct Connection {
+    protocol: u8,
+    src: IpAddr,
+    dst: IpAddr,
+    sport: u16,
+    dport: u16,
+}
+
+impl Connection {
+    /// Parses a line of `/proc/net/nf_conntrack` into the connection and the bytes it transferred
+    /// in both directions. Connections without ports, such as ICMP, have port 0.
+    fn parse(line: &str) -> Option<(Self, u64)> {
+        let mut fields = line.split_whitespace();
+        let protocol = fields.nth(3)?.parse().ok()?;
+        let (mut src, mut dst, mut sport, mut dport) = (None, None, None, None);
+        let mut bytes = 0;
+        for (key, value) in fields.filter_map(|f| f.split_once('=')) {
+            // The original direction comes first, followed by the reply
+            match key {
+                "src" if src.is_none() => src = value.parse().ok(),
+                "dst" if dst.is_none() => dst = value.parse().ok(),
+                "sport" if sport.is_none() => sport = value.parse().ok(),
+                "dport" if dport.is_none() => dport = value.parse().ok(),
+                "bytes" => bytes += value.parse::<u64>().ok()?,
+                _ => (),
+            }
+        }
+        Some((
+            Self {
+                protocol,
+                src: src?,
+                dst: dst?,
+                sport: sport.unwrap_or(0),
+                dport: dport.unwrap_or(0),
+            },
+            bytes,
+        ))
+    }
+
+    /// The remote host and the inode of the local socket, if the connection has one
+    fn remote(&self, sockets: &HashMap<(u8, u16), u64>) -> (IpAddr, Option<u64>) {
+        if let Some(&inode) = sockets.get(&(self.protocol, self.sport)) {
+            (self.dst, Some(inode))
+        } else if let Some(&inode) = sockets.get(&(self.protocol, self.dport)) {
+            // An incoming connection
+            (self.src, Some(inode))
+        } else {
+            (self.dst, None)
+        }
+    }
+}
+
+async fn read_conntrack() -> Result<HashMap<Connection, u64>> {
+    let conntrack = util::read_file(CONNTRACK).await.map_err(|error| {
+        if error.kind() == ErrorKind::PermissionDenied {
+            Error::new(format!(
+                "{CONNTRACK} is only readable by root. Grant i3status-rs the CAP_DAC_READ_SEARCH capability with setcap"
+            ))
+        } else {
+            Error::new(format!("Failed to read {CONNTRACK}: {error}"))
+        }
+    })?;
+    Ok(conntrack.lines().filter_map(Connection::parse).collect())
+}
+
+/// The inodes of the local sockets by protocol number and local port
+async fn read_sockets() -> Result<HashMap<(u8, u16), u64>> {
+    let mut sockets = HashMap::new();
+    for (table, protocol) in SOCKET_TABLES {
+        // IPv6 may be disabled
+        let Ok(table) = util::read_file(table).await else {
+            continue;
+        };
+        sockets.extend(
+            table
+                .lines()
+                .skip(1)
+                .filter_map(parse_socket)
+                .map(|(port, inode)| ((protocol, port), inode)),
+        );
+    }
+    Ok(sockets)
+}
+
+/// Parses a line of `/proc/net/tcp` or a similar table into the local port and the inode
+fn parse_socket(line: &str) -> Option<(u16, u64)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let (_, port) = fields.get(1)?.rsplit_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    // Sockets in TIME_WAIT have no inode
+    let inode = fields.get(9)?.parse().ok().filter(|&inode| inode != 0)?;
+    Some((port, inode))
+}
+
+/// The names of the processes by the inodes of their sockets
+fn find_processes() -> HashMap<u64, String> {
+    let mut processes = HashMap::new();
+    let Ok(proc) = std::fs::read_dir("/proc") else {
+        return processes;
+    };
+    for entry in proc.flatten() {
+        let path = entry.path();
+        // Processes of other users can't be read without CAP_SYS_PTRACE
+        let Ok(fds) = std::fs::read_dir(path.join("fd")) else {
+            continue;
+        };
+        let mut name = None;
+        for fd in fds.flatten() {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            let Some(inode) = target
+                .to_str()
+                .and_then(|t| t.strip_prefix("socket:["))
+                .and_then(|t| t.strip_suffix(']'))
+                .and_then(|t| t.parse().ok())
+            else {
+                continue;
+            };
+            let name = name.get_or_insert_with(|| {
+                std::fs::read_to_string(path.join("comm"))
+                    .map(|comm| comm.trim_end().to_string())
+                    .unwrap_or_default()
+            });
+            processes.insert(inode, name.clone());
+        }
+    }
+    processes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_conntrack() {
+        let line = "ipv4     2 tcp      6 431999 ESTABLISHED src=192.168.1.2 dst=93.184.216.34 sport=50000 dport=443 packets=10 bytes=1000 src=93.184.216.34 dst=192.168.1.2 sport=443 dport=50000 packets=12 bytes=20000 [ASSURED] mark=0 zone=0 use=2";
+        let (conn, bytes) = Connection::parse(line).unwrap();
+        assert_eq!(
+            conn,
+            Connection {
+                protocol: 6,
+                src: "192.168.1.2".parse().unwrap(),
+                dst: "93.184.216.34".parse().unwrap(),
+                sport: 50000,
+                dport: 443,
+            }
+        );
+        assert_eq!(bytes, 21000);
+
+        let sockets = map! { (6, 50000) => 1234_u64 };
+        assert_eq!(conn.remote(&sockets), (conn.dst, Some(1234)));
+        let sockets = map! { (6, 443) => 1234_u64 };
+        assert_eq!(conn.remote(&sockets), (conn.src, Some(1234)));
+
+        let icmp = "ipv6     10 icmpv6   58 29 src=fe80::1 dst=ff02::1 type=128 code=0 id=1 packets=1 bytes=64 src=ff02::1 dst=fe80::1 type=129 code=0 id=1 packets=0 bytes=0 mark=0 zone=0 use=2";
+        let (conn, bytes) = Connection::parse(icmp).unwrap();
+        assert_eq!((conn.protocol, conn.sport, bytes), (58, 0, 64));
+    }
+
+    #[test]
+    fn parse_socket_table() {
+        let line = "   1: 0201A8C0:C350 22D8B85D:01BB 01 00000000:00000000 00:00000000 00000000  1000        0 56789 1 0000000000000000 20 4 30 10 -1";
+        assert_eq!(parse_socket(line), Some((50000, 56789)));
+        let time_wait = "   2: 0201A8C0:C351 22D8B85D:01BB 06 00000000:00000000 03:00000D2B 00000000     0        0 0 3 0000000000000000";
+        assert_eq!(parse_socket(time_wait), None);
+    }
+}
@@ -0,0 +1,233 @@
+//! NixOS and home-manager generations
+//!
+//! This block shows the current generation of a Nix profile, whether the configuration has changed
+//! since it was built (i.e. a rebuild is pending), and optionally whether the upstream of the
+//! configuration's git repository has a newer `flake.lock`. Clicking the block runs the rebuild
+//! command.
+//!
+//! A rebuild is pending if a file in `config_path` is newer than the current generation. Hidden
+//! files and directories (such as `.git`) and symlinks (such as `result`) are ignored.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" $icon $generation{$pending *\|}{$update ↑\|} \"</code>
+//! `profile` | The Nix profile. Use `"~/.local/state/nix/profiles/home-manager"` for home-manager. Supports path expansions e.g. `~`. | `"/nix/var/nix/profiles/system"`
+//! `config_path` | The directory of the configuration. Supports path expansions e.g. `~`. | `"/etc/nixos"`
+//! `rebuild_cmd` | The shell command which the `rebuild` action runs | `"pkexec nixos-rebuild switch"`
+//! `upstream_check` | Whether to fetch the upstream of the git repository in `config_path` to check for a newer `flake.lock` | `false`
+//! `upstream_interval` | How often to fetch the upstream, in seconds | `3600`
+//! `interval` | Update interval in seconds | `60`
+//!
+//! Placeholder  | Value                                                              | Type   | Unit
+//! -------------|--------------------------------------------------------------------|--------|-----
+//! `icon`       | A static icon                                                      | Icon   | -
+//! `generation` | The number of the current generation                               | Number | -
+//! `pending`    | Present if the configuration is newer than the current generation  | Flag   | -
+//! `update`     | Present if the upstream has a newer `flake.lock`                   | Flag   | -
+//! `rebuilding` | Present while the rebuild command runs                             | Flag   | -
+//!
+//! The block is in the warning state while a rebuild is pending, in the info state if only an
+//! update is available, and in the critical state if the last rebuild failed.
+//!
+//! Action    | Description                | Default button
+//! ----------|----------------------------|---------------
+//! `rebuild` | Runs `rebuild_cmd`         | Left
+//!
+//! # Examples
+//!
+//! A flake-based NixOS configuration in a git repository:
+//!
+//! ```toml
+//! [[block]]
+//! block = "nix"
+//! config_path = "~/nixos-config"
+//! rebuild_cmd = "pkexec nixos-rebuild switch --flake ~/nixos-config"
+//! upstream_check = true
+//! ```
+//!
+//! home-manager:
+//!
+//! ```toml
+//! [[block]]
+//! block = "nix"
+//! profile = "~/.local/state/nix/profiles/home-manager"
+//! config_path = "~/.config/home-manager"
+//! rebuild_cmd = "home-manager switch"
+//! format = " $icon HM $generation{$pending  rebuild pending|} "
+//! ```
+//!
+//! # Icons Used
+//! - `nix`
+
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+use tokio::process::Command;
+
+use super::prelude::*;
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    #[default("/nix/var/nix/profiles/system".into())]
+    pub profile: ShellString,
+    #[default("/etc/nixos".into())]
+    pub config_path: ShellString,
+    #[default("pkexec nixos-rebuild switch".into())]
+    pub rebuild_cmd: String,
+    pub upstream_check: bool,
+    #[default(3600.into())]
+    pub upstream_interval: Seconds,
+    #[default(60.into())]
+    pub interval: Seconds,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+    api.set_default_actions(&[(MouseButton::Left, None, "rebuild")])?;
+
+    let format = config
+        .format
+        .with_default(" $icon $generation{$pending *|}{$update ↑|} ")?;
+
+    let profile = PathBuf::from(&*config.profile.expand()?);
+    let config_path = PathBuf::from(&*config.config_path.expand()?);
+
+    let mut timer = config.interval.timer();
+
+    let mut update = false;
+    let mut next_upstream_check = Instant::now();
+    let mut rebuild_failed = false;
+
+    loop {
+        let (generation, built) = current_generation(&profile)?;
+        let pending = newest_mtime(&config_path).is_some_and(|modified| modified > built);
+
+        if config.upstream_check && Instant::now() >= next_upstream_check {
+            update = upstream_has_newer_lock(&config_path).await;
+            next_upstream_check = Instant::now() + config.upstream_interval.0;
+        }
+
+        let mut widget = Widget::new().with_format(format.clone());
+        widget.state = if rebuild_failed {
+            State::Critical
+        } else if pending {
+            State::Warning
+        } else if update {
+            State::Info
+        } else {
+            State::Idle
+        };
+        widget.set_values(map! {
+            "icon" => Value::icon("nix"),
+            "generation" => Value::number(generation),
+            [if pending] "pending" => Value::flag(),
+            [if update] "update" => Value::flag(),
+        });
+        api.set_widget(widget.clone())?;
+
+        select! {
+            _ = timer.tick() => (),
+            _ = api.wait_for_update_request() => (),
+            Some(action) = actions.recv() => match action.as_ref() {
+                "rebuild" => {
+                    widget.set_value("rebuilding", Value::flag());
+                    widget.state = State::Info;
+                    api.set_widget(widget)?;
+                    let status = Command::new("sh")
+                        .args(["-c", &config.rebuild_cmd])
+                        .stdin(std::process::Stdio::null())
+                        .stdout(std::process::Stdio::null())
+                        .stderr(std::process::Stdio::null())
+                        .status()
+                        .await
+                        .error("Failed to run the rebuild command")?;
+                    rebuild_failed = !status.success();
+                    // A new generation may make a pull of the upstream necessary
+                    next_upstream_check = Instant::now();
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Returns the number of the generation `profile` points to and when it was created
+fn current_generation(profile: &Path) -> Result<(u32, SystemTime)> {
+    let link = std::fs::read_link(profile)
+        .or_error(|| format!("Failed to read the profile '{}'", profile.display()))?;
+    let link = profile.with_file_name(link);
+    let name = link.file_name().and_then(|name| name.to_str()).unwrap_or("");
+    let generation = parse_generation(name)
+        .or_error(|| format!("'{}' is not a generation", link.display()))?;
+    let built = std::fs::symlink_metadata(&link)
+        .and_then(|metadata| metadata.modified())
+        .error("Failed to get the time of the generation")?;
+    Ok((generation, built))
+}
+
+/// Parses the number of a generation link, e.g. `system-123-link`
+fn parse_generation(name: &str) -> Option<u32> {
+    let (_, number) = name.strip_suffix("-link")?.rsplit_once('-')?;
+    number.parse().ok()
+}
+
+/// The modification time of the newest file in `dir`, ignoring hidden files and symlinks
+fn newest_mtime(dir: &Path) -> Option<SystemTime> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            if entry.file_name().as_encoded_bytes().starts_with(b".") {
+                return None;
+            }
+            let file_type = entry.file_type().ok()?;
+            if file_type.is_dir() {
+                newest_mtime(&entry.path())
+            } else if file_type.is_file() {
+                entry.metadata().ok()?.modified().ok()
+            } else {
+                None
+            }
+        })
+        .max()
+}
+
+/// Fetches the upstream of the repository in `dir` and checks whether it changed `flake.lock`
+async fn upstream_has_newer_lock(dir: &Path) -> bool {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .output()
+    };
+    // Without network or upstream, there is nothing to tell
+    if !git(&["fetch", "--quiet"])
+        .await
+        .is_ok_and(|output| output.status.success())
+    {
+        return false;
+    }
+    git(&["log", "--format=%H", "HEAD..@{upstream}", "--", "flake.lock"])
+        .await
+        .is_ok_and(|output| output.status.success() && !output.stdout.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generation() {
+        assert_eq!(parse_generation("system-123-link"), Some(123));
+        assert_eq!(parse_generation("home-manager-45-link"), Some(45));
+        assert_eq!(parse_generation("system"), None);
+        assert_eq!(parse_generation("system-link"), None);
+    }
+}
@@ -2,23 +2,29 @@
 //!
 //! Left-clicking on this block will enable/disable notifications.
 //!
+//! fnott has no way to report its state, so with the `fnott` driver the number of notifications is
+//! polled with `fnottctl list`, and only pausing by this block is known. It has no notification
+//! panel either, so the `show` action does nothing.
+//!
 //! # Configuration
 //!
 //! Key | Values | Default
 //! ----|--------|--------
-//! `driver` | Which notifications daemon is running. Available drivers are: `"dunst"` and `"swaync"` | `"dunst"`
+//! `driver` | Which notifications daemon is running. Available drivers are: `"dunst"`, `"swaync"` ([SwayNotificationCenter](https://github.com/ErikReider/SwayNotificationCenter)) and `"fnott"` | `"dunst"`
 //! `format` | A string to customise the output of this block. See below for available placeholders. | `" $icon "`
+//! `interval` | How often to poll the number of notifications, in seconds. Only used by the `"fnott"` driver. | `5`
 //!
 //! Placeholder                               | Value                                      | Type   | Unit
 //! ------------------------------------------|--------------------------------------------|--------|-----
 //! `icon`                                    | Icon based on notification's state         | Icon   | -
 //! `notification_count`[^dunst_version_note] | The number of notification (omitted if 0)  | Number | -
 //! `paused`                                  | Present only if notifications are disabled | Flag   | -
+//! `dnd`                                     | Present only if "do not disturb" is on. Unlike `paused`, inhibitors of SwayNC do not count. | Flag | -
 //!
-//! Action          | Default button
-//! ----------------|---------------
-//! `toggle_paused` | Left
-//! `show`          | -
+//! Action          | Description                                                                 | Default button
+//! ----------------|-----------------------------------------------------------------------------|---------------
+//! `toggle_paused` | Enables/disables notifications                                              | Left
+//! `show`          | Toggles the notification panel of SwayNC, or shows the last notification of the history of dunst | -
 //!
 //! # Examples
 //!
@@ -56,18 +62,23 @@
 //!
 //! [^dunst_version_note]: when using `notification_count` with the `dunst` driver use dunst > 1.9.0
 
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use super::prelude::*;
+use tokio::process::Command;
 use tokio::{join, try_join};
 use zbus::proxy::PropertyStream;
 
 const ICON_ON: &str = "bell";
 const ICON_OFF: &str = "bell-slash";
 
-#[derive(Deserialize, Debug, Default)]
+#[derive(Deserialize, Debug, SmartDefault)]
 #[serde(deny_unknown_fields, default)]
 pub struct Config {
     pub driver: DriverType,
     pub format: FormatConfig,
+    #[default(5.into())]
+    pub interval: Seconds,
 }
 
 #[derive(Deserialize, Debug, SmartDefault)]
@@ -76,6 +87,7 @@ pub enum DriverType {
     #[default]
     Dunst,
     SwayNC,
+    Fnott,
 }
 
 pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
@@ -87,17 +99,22 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
     let mut driver: Box<dyn Driver> = match config.driver {
         DriverType::Dunst => Box::new(DunstDriver::new().await?),
         DriverType::SwayNC => Box::new(SwayNCDriver::new().await?),
+        DriverType::Fnott => Box::new(FnottDriver::new(config.interval)),
     };
 
     loop {
-        let (is_paused, notification_count) =
-            try_join!(driver.is_paused(), driver.notification_count())?;
+        let (is_paused, is_dnd, notification_count) = try_join!(
+            driver.is_paused(),
+            driver.is_dnd(),
+            driver.notification_count()
+        )?;
 
         let mut widget = Widget::new().with_format(format.clone());
         widget.set_values(map!(
             "icon" => Value::icon(if is_paused { ICON_OFF } else { ICON_ON }),
             [if notification_count != 0] "notification_count" => Value::number(notification_count),
             [if is_paused] "paused" => Value::flag(),
+            [if is_dnd] "dnd" => Value::flag(),
         ));
         widget.state = if notification_count == 0 {
             State::Idle
@@ -122,8 +139,11 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
 }
 
 #[async_trait]
-trait Driver {
+trait Driver: Sync {
     async fn is_paused(&self) -> Result<bool>;
+    async fn is_dnd(&self) -> Result<bool> {
+        self.is_paused().await
+    }
     async fn set_paused(&self, paused: bool) -> Result<()>;
     async fn notification_show(&self) -> Result<()>;
     async fn notification_count(&self) -> Result<u32>;
@@ -243,6 +263,10 @@ impl Driver for SwayNCDriver {
             .map(|is_dnd| is_dnd || is_inhibited.unwrap_or_default())
     }
 
+    async fn is_dnd(&self) -> Result<bool> {
+        self.proxy.get_dnd().await.error("Failed to call 'GetDnd'")
+    }
+
     async fn set_paused(&self, paused: bool) -> Result<()> {
         if paused {
             self.proxy.set_dnd(paused).await
@@ -301,3 +325,61 @@ trait SwayNCDbus {
         inhibited: bool,
     ) -> zbus::Result<()>;
 }
+
+struct FnottDriver {
+    /// fnott cannot be asked whether it is paused, so this is only what this block did
+    paused: AtomicBool,
+    timer: Timer,
+}
+
+impl FnottDriver {
+    fn new(interval: Seconds) -> Self {
+        Self {
+            paused: AtomicBool::new(false),
+            timer: interval.timer(),
+        }
+    }
+}
+
+async fn fnottctl(arg: &str) -> Result<String> {
+    let output = Command::new("fnottctl")
+        .arg(arg)
+        .output()
+        .await
+        .error("Failed to run fnottctl")?;
+    if !output.status.success() {
+        return Err(Error::new(format!(
+            "fnottctl {arg} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    String::from_utf8(output.stdout).error("fnottctl produced non-UTF8 output")
+}
+
+#[async_trait]
+impl Driver for FnottDriver {
+    async fn is_paused(&self) -> Result<bool> {
+        Ok(self.paused.load(Ordering::Relaxed))
+    }
+
+    async fn set_paused(&self, paused: bool) -> Result<()> {
+        fnottctl(if paused { "pause" } else { "unpause" }).await?;
+        self.paused.store(paused, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn notification_show(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn notification_count(&self) -> Result<u32> {
+        // One notification per line
+        let list = fnottctl("list").await?;
+        Ok(list.lines().filter(|line| !line.trim().is_empty()).count() as u32)
+    }
+
+    async fn wait_for_change(&mut self) -> Result<()> {
+        self.timer.tick().await;
+        Ok(())
+    }
+}
@@ -0,0 +1,269 @@
+//! The lighting profile of RGB devices controlled by OpenRGB
+//!
+//! This block talks to the [OpenRGB](https://openrgb.org) SDK server, which must be enabled in
+//! OpenRGB (e.g. by running `openrgb --server`). Clicking the block loads the next or the previous
+//! profile.
+//!
+//! OpenRGB does not report which profile is currently active, so `$profile` is the profile which
+//! was last loaded by this block (or `profile`, which is loaded when the block starts).
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" $icon {$profile \|}\"</code>
+//! `address` | The address of the OpenRGB SDK server | `"localhost:6742"`
+//! `profiles` | The profiles to cycle through on click. If empty, all profiles known to OpenRGB are used. | `[]`
+//! `profile` | A profile to load when the block starts | None
+//! `interval` | Update interval in seconds | `30`
+//!
+//! Placeholder | Value                                                     | Type   | Unit
+//! ------------|-----------------------------------------------------------|--------|-----
+//! `icon`      | A static icon                                             | Icon   | -
+//! `profile`   | The profile last loaded by this block. Absent if unknown. | Text   | -
+//! `count`     | The number of profiles known to OpenRGB                   | Number | -
+//!
+//! Action         | Default button
+//! ---------------|---------------
+//! `next_profile` | Left
+//! `prev_profile` | Right
+//!
+//! # Example
+//!
+//! ```toml
+//! [[block]]
+//! block = "openrgb"
+//! profiles = ["Rainbow", "Work", "Off"]
+//! profile = "Work"
+//! ```
+//!
+//! # Icons Used
+//! - `rgb`
+
+use tokio::net::TcpStream;
+
+use super::prelude::*;
+
+/// The version of the SDK protocol spoken by this block. Profiles require version 2.
+const PROTOCOL_VERSION: u32 = 4;
+const MIN_PROTOCOL_VERSION: u32 = 2;
+
+const MAGIC: &[u8; 4] = b"ORGB";
+const HEADER_LEN: usize = 16;
+
+const REQUEST_PROTOCOL_VERSION: u32 = 40;
+const SET_CLIENT_NAME: u32 = 50;
+const REQUEST_PROFILE_LIST: u32 = 150;
+const REQUEST_LOAD_PROFILE: u32 = 152;
+
+/// Servers which are too old to know the protocol version request do not reply to it
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    #[default("localhost:6742".into())]
+    pub address: String,
+    pub profiles: Vec<String>,
+    pub profile: Option<String>,
+    #[default(30.into())]
+    pub interval: Seconds,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+    api.set_default_actions(&[
+        (MouseButton::Left, None, "next_profile"),
+        (MouseButton::Right, None, "prev_profile"),
+    ])?;
+
+    let format = config.format.with_default(" $icon {$profile |}")?;
+
+    let mut timer = config.interval.timer();
+
+    let mut current = None;
+    if let Some(profile) = &config.profile {
+        Connection::connect(&config.address)
+            .await?
+            .load_profile(profile)
+            .await?;
+        current = Some(profile.clone());
+    }
+
+    loop {
+        let all_profiles = Connection::connect(&config.address)
+            .await?
+            .profiles()
+            .await?;
+        // The profile may have been deleted in the meantime
+        if current.as_ref().is_some_and(|p| !all_profiles.contains(p)) {
+            current = None;
+        }
+
+        let mut widget = Widget::new().with_format(format.clone());
+        widget.set_values(map! {
+            "icon" => Value::icon("rgb"),
+            [if let Some(profile) = &current] "profile" => Value::text(profile.clone()),
+            "count" => Value::number(all_profiles.len()),
+        });
+        api.set_widget(widget)?;
+
+        select! {
+            _ = timer.tick() => (),
+            _ = api.wait_for_update_request() => (),
+            Some(action) = actions.recv() => {
+                let step = match action.as_ref() {
+                    "next_profile" => 1,
+                    "prev_profile" => -1,
+                    _ => continue,
+                };
+                let profiles = if config.profiles.is_empty() {
+                    &all_profiles
+                } else {
+                    &config.profiles
+                };
+                if profiles.is_empty() {
+                    continue;
+                }
+                let i = current
+                    .as_ref()
+                    .and_then(|current| profiles.iter().position(|p| p == current))
+                    .map_or(0, |i| (i as isize + step).rem_euclid(profiles.len() as isize) as usize);
+                Connection::connect(&config.address)
+                    .await?
+                    .load_profile(&profiles[i])
+                    .await?;
+                current = Some(profiles[i].clone());
+            }
+        }
+    }
+}
+
+/// A connection to the OpenRGB SDK server. See
+/// <https://gitlab.com/CalcProgrammer1/OpenRGB/-/blob/master/Documentation/OpenRGBSDK.md>.
+struct Connection(TcpStream);
+
+impl Connection {
+    async fn connect(address: &str) -> Result<Self> {
+        let stream = TcpStream::connect(address)
+            .await
+            .or_error(|| format!("Failed to connect to the OpenRGB SDK server at '{address}'"))?;
+        let mut conn = Self(stream);
+
+        conn.send(SET_CLIENT_NAME, b"i3status-rs\0").await?;
+        conn.send(REQUEST_PROTOCOL_VERSION, &PROTOCOL_VERSION.to_le_bytes())
+            .await?;
+        let reply = conn.recv(REQUEST_PROTOCOL_VERSION).await?;
+        let version = u32::from_le_bytes(
+            reply
+                .get(..4)
+                .and_then(|v| v.try_into().ok())
+                .error("Invalid protocol version reply")?,
+        );
+        if version < MIN_PROTOCOL_VERSION {
+            return Err(Error::new(format!(
+                "OpenRGB SDK protocol version {version} does not support profiles"
+            )));
+        }
+
+        Ok(conn)
+    }
+
+    async fn send(&mut self, packet_id: u32, data: &[u8]) -> Result<()> {
+        let mut packet = Vec::with_capacity(HEADER_LEN + data.len());
+        packet.extend_from_slice(MAGIC);
+        packet.extend_from_slice(&0u32.to_le_bytes()); // device index
+        packet.extend_from_slice(&packet_id.to_le_bytes());
+        packet.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        packet.extend_from_slice(data);
+        self.0
+            .write_all(&packet)
+            .await
+            .error("Failed to write to the OpenRGB SDK server")
+    }
+
+    /// Reads packets until one with `packet_id` arrives and returns its data
+    async fn recv(&mut self, packet_id: u32) -> Result<Vec<u8>> {
+        loop {
+            let (id, data) = tokio::time::timeout(REPLY_TIMEOUT, self.recv_any())
+                .await
+                .error("The OpenRGB SDK server did not reply")??;
+            if id == packet_id {
+                return Ok(data);
+            }
+        }
+    }
+
+    async fn recv_any(&mut self) -> Result<(u32, Vec<u8>)> {
+        let mut header = [0; HEADER_LEN];
+        self.0
+            .read_exact(&mut header)
+            .await
+            .error("Failed to read from the OpenRGB SDK server")?;
+        if &header[..4] != MAGIC {
+            return Err(Error::new("Invalid reply from the OpenRGB SDK server"));
+        }
+        let packet_id = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        let len = u32::from_le_bytes(header[12..16].try_into().unwrap());
+        let mut data = vec![0; len as usize];
+        self.0
+            .read_exact(&mut data)
+            .await
+            .error("Failed to read from the OpenRGB SDK server")?;
+        Ok((packet_id, data))
+    }
+
+    async fn profiles(&mut self) -> Result<Vec<String>> {
+        self.send(REQUEST_PROFILE_LIST, &[]).await?;
+        let reply = self.recv(REQUEST_PROFILE_LIST).await?;
+        parse_profile_list(&reply).error("Invalid profile list")
+    }
+
+    async fn load_profile(&mut self, name: &str) -> Result<()> {
+        let mut data = name.as_bytes().to_vec();
+        data.push(0);
+        self.send(REQUEST_LOAD_PROFILE, &data).await
+    }
+}
+
+/// The profile list consists of its size (`u32`), the number of profiles (`u16`) and, for each
+/// profile, the length (`u16`) of its NUL terminated name followed by the name.
+fn parse_profile_list(data: &[u8]) -> Option<Vec<String>> {
+    fn take<'a>(data: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+        let (head, tail) = data.split_at_checked(n)?;
+        *data = tail;
+        Some(head)
+    }
+    fn take_u16(data: &mut &[u8]) -> Option<u16> {
+        Some(u16::from_le_bytes(take(data, 2)?.try_into().ok()?))
+    }
+
+    let mut data = data;
+    take(&mut data, 4)?;
+    let count = take_u16(&mut data)?;
+    (0..count)
+        .map(|_| {
+            let len = take_u16(&mut data)?;
+            let name = take(&mut data, len.into())?;
+            let name = name.strip_suffix(b"\0").unwrap_or(name);
+            Some(String::from_utf8_lossy(name).into_owned())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_list() {
+        let data = b"\x16\0\0\0\x02\0\x08\0Rainbow\0\x04\0Off\0";
+        assert_eq!(
+            parse_profile_list(data),
+            Some(vec!["Rainbow".into(), "Off".into()])
+        );
+        assert_eq!(parse_profile_list(b"\x06\0\0\0\0\0"), Some(vec![]));
+        assert_eq!(parse_profile_list(b"\x16\0\0\0\x02\0\x08\0Rain"), None);
+    }
+}
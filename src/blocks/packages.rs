@@ -179,6 +179,7 @@ use pacman::{Aur, Pacman};
 pub mod dnf;
 use dnf::Dnf;
 
+use crate::wrappers::SerdeRegex;
 use regex::Regex;
 
 use super::prelude::*;
@@ -192,9 +193,9 @@ pub struct Config {
     pub format: FormatConfig,
     pub format_singular: FormatConfig,
     pub format_up_to_date: FormatConfig,
-    pub warning_updates_regex: Option<String>,
-    pub critical_updates_regex: Option<String>,
-    pub ignore_updates_regex: Option<String>,
+    pub warning_updates_regex: Option<SerdeRegex>,
+    pub critical_updates_regex: Option<SerdeRegex>,
+    pub ignore_updates_regex: Option<SerdeRegex>,
     pub ignore_phased_updates: bool,
     pub aur_command: Option<String>,
 }
@@ -246,24 +247,9 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         config.package_manager.push(PackageManager::Dnf);
     }
 
-    let warning_updates_regex = config
-        .warning_updates_regex
-        .as_deref()
-        .map(Regex::new)
-        .transpose()
-        .error("invalid warning updates regex")?;
-    let critical_updates_regex = config
-        .critical_updates_regex
-        .as_deref()
-        .map(Regex::new)
-        .transpose()
-        .error("invalid critical updates regex")?;
-    let ignore_updates_regex = config
-        .ignore_updates_regex
-        .as_deref()
-        .map(Regex::new)
-        .transpose()
-        .error("invalid ignore updates regex")?;
+    let warning_updates_regex = config.warning_updates_regex.as_ref().map(|re| &re.0);
+    let critical_updates_regex = config.critical_updates_regex.as_ref().map(|re| &re.0);
+    let ignore_updates_regex = config.ignore_updates_regex.as_ref().map(|re| &re.0);
 
     let mut package_manager_vec: Vec<Box<dyn Backend>> = Vec::new();
 
@@ -279,7 +265,7 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
     }
 
     loop {
-        let mut package_manager_map: HashMap<Cow<'static, str>, Value> = HashMap::new();
+        let mut package_manager_map = Values::new();
 
         let mut critical = false;
         let mut warning = false;
@@ -288,27 +274,23 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         // Iterate over the all package manager listed in Config
         for package_manager in &package_manager_vec {
             let mut updates = package_manager.get_updates_list().await?;
-            if let Some(regex) = ignore_updates_regex.clone() {
+            if let Some(regex) = ignore_updates_regex {
                 updates.retain(|u| !regex.is_match(u));
             }
 
             let updates_count = updates.len();
 
-            package_manager_map.insert(package_manager.name(), Value::number(updates_count));
+            package_manager_map.insert(intern(&package_manager.name()), Value::number(updates_count));
             total_count += updates_count;
 
-            warning |= warning_updates_regex
-                .as_ref()
-                .is_some_and(|regex| has_matching_update(&updates, regex));
-            critical |= critical_updates_regex
-                .as_ref()
-                .is_some_and(|regex| has_matching_update(&updates, regex));
+            warning |= warning_updates_regex.is_some_and(|regex| has_matching_update(&updates, regex));
+            critical |= critical_updates_regex.is_some_and(|regex| has_matching_update(&updates, regex));
         }
 
         let mut widget = Widget::new();
 
-        package_manager_map.insert("icon".into(), Value::icon("update"));
-        package_manager_map.insert("total".into(), Value::number(total_count));
+        package_manager_map.insert("icon", Value::icon("update"));
+        package_manager_map.insert("total", Value::number(total_count));
 
         widget.set_format(match total_count {
             0 => format_up_to_date.clone(),
@@ -332,7 +314,7 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         api.set_widget(widget)?;
 
         select! {
-            _ = sleep(config.interval.0) => (),
+            _ = config.interval.sleep() => (),
             _ = api.wait_for_update_request() => (),
         }
     }
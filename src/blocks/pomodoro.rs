@@ -88,7 +88,7 @@ impl Block<'_> {
             "icon" => Value::icon("pomodoro"),
         );
         if !text.is_empty() {
-            values.insert("message".into(), Value::text(text));
+            values.insert("message", Value::text(text));
         }
         self.widget.set_values(values);
         self.api.set_widget(self.widget.clone())
@@ -0,0 +1,226 @@
+//! Power profile of power-profiles-daemon
+//!
+//! This block shows the active profile of
+//! [power-profiles-daemon](https://gitlab.freedesktop.org/upower/power-profiles-daemon) (or
+//! another implementation of its D-Bus interface, such as `tuned-ppd`) and switches between the
+//! available profiles, usually `power-saver`, `balanced` and `performance`.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | `" $icon $profile "`
+//!
+//! Placeholder | Value                                                                       | Type | Unit
+//! ------------|-----------------------------------------------------------------------------|------|-----
+//! `icon`      | An icon for the active profile                                              | Icon | -
+//! `profile`   | The name of the active profile                                              | Text | -
+//! `degraded`  | Why the `performance` profile is degraded (e.g. `lap-detected` or `high-operating-temperature`). Absent if it is not. | Text | -
+//!
+//! The block is in the warning state if the performance profile is degraded.
+//!
+//! Action        | Description                                              | Default button
+//! --------------|----------------------------------------------------------|---------------
+//! `next`        | Switch to the next profile, towards `performance`, wrapping around | Left, Wheel Up
+//! `prev`        | Switch to the previous profile, towards `power-saver`    | Wheel Down
+//! `set_<profile>` | Switch to the given profile, e.g. `set_power-saver`    | -
+//!
+//! # Example
+//!
+//! Show an icon and, if the performance profile is degraded, why, and switch to `power-saver` with
+//! a right click:
+//!
+//! ```toml
+//! [[block]]
+//! block = "power_profile"
+//! format = " $icon {$degraded |}"
+//! [[block.click]]
+//! button = "right"
+//! action = "set_power-saver"
+//! ```
+//!
+//! # Icons Used
+//! - `power_profile_power_saver`
+//! - `power_profile_balanced`
+//! - `power_profile_performance`
+//! - `unknown` (for other profiles)
+
+use zbus::zvariant::OwnedValue;
+
+use super::prelude::*;
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+    api.set_default_actions(&[
+        (MouseButton::Left, None, "next"),
+        (MouseButton::WheelUp, None, "next"),
+        (MouseButton::WheelDown, None, "prev"),
+    ])?;
+
+    let format = config.format.with_default(" $icon $profile ")?;
+
+    let dbus_conn = new_system_dbus_connection().await?;
+    let proxy = PowerProfilesProxy::new(&dbus_conn)
+        .await
+        .error("Failed to create PowerProfilesProxy")?;
+    let mut profile_changes = proxy.receive_active_profile_changed().await;
+    let mut degraded_changes = proxy.receive_performance_degraded_changed().await;
+
+    loop {
+        let profile = proxy
+            .active_profile()
+            .await
+            .error("Failed to get the active profile")?;
+        let degraded = proxy
+            .performance_degraded()
+            .await
+            .error("Failed to get PerformanceDegraded")?;
+
+        let mut widget = Widget::new().with_format(format.clone());
+        widget.state = degraded_state(&degraded);
+        widget.set_values(map! {
+            "icon" => Value::icon(profile_icon(&profile)),
+            "profile" => Value::text(profile.clone()),
+            [if !degraded.is_empty()] "degraded" => Value::text(degraded),
+        });
+        api.set_widget(widget)?;
+
+        select! {
+            _ = profile_changes.next() => (),
+            _ = degraded_changes.next() => (),
+            _ = api.wait_for_update_request() => (),
+            Some(action) = actions.recv() => {
+                let target = match action.as_ref() {
+                    "next" | "prev" => {
+                        let profiles = profile_names(
+                            proxy.profiles().await.error("Failed to get the profiles")?,
+                        );
+                        match cycle(&profiles, &profile, action == "next") {
+                            Some(target) => target.to_owned(),
+                            None => continue,
+                        }
+                    }
+                    action => match action.strip_prefix("set_") {
+                        Some(target) => target.to_owned(),
+                        None => continue,
+                    },
+                };
+                proxy
+                    .set_active_profile(&target)
+                    .await
+                    .or_error(|| format!("Failed to switch to the profile '{target}'"))?;
+            }
+        }
+    }
+}
+
+fn profile_icon(profile: &str) -> &'static str {
+    match profile {
+        "power-saver" => "power_profile_power_saver",
+        "balanced" => "power_profile_balanced",
+        "performance" => "power_profile_performance",
+        _ => "unknown",
+    }
+}
+
+/// The state for the `PerformanceDegraded` reason, which is empty unless the performance profile
+/// is degraded
+fn degraded_state(degraded: &str) -> State {
+    if degraded.is_empty() {
+        State::Idle
+    } else {
+        State::Warning
+    }
+}
+
+/// The profile after (or before) `current`, wrapping around. `None` if `current` is not listed.
+fn cycle<'a>(profiles: &'a [String], current: &str, next: bool) -> Option<&'a str> {
+    let len = profiles.len();
+    let current = profiles.iter().position(|p| p == current)?;
+    let i = if next { current + 1 } else { current + len - 1 };
+    Some(&profiles[i % len])
+}
+
+/// The names of the profiles, from the least to the most power hungry
+fn profile_names(profiles: Vec<HashMap<String, OwnedValue>>) -> Vec<String> {
+    profiles
+        .iter()
+        .filter_map(|profile| profile.get("Profile")?.downcast_ref::<&str>().ok())
+        .map(Into::into)
+        .collect()
+}
+
+#[zbus::proxy(
+    interface = "net.hadess.PowerProfiles",
+    default_service = "net.hadess.PowerProfiles",
+    default_path = "/net/hadess/PowerProfiles"
+)]
+trait PowerProfiles {
+    #[zbus(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn set_active_profile(&self, profile: &str) -> zbus::Result<()>;
+    #[zbus(property)]
+    fn performance_degraded(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn profiles(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icons_and_state() {
+        assert_eq!(profile_icon("power-saver"), "power_profile_power_saver");
+        assert_eq!(profile_icon("balanced"), "power_profile_balanced");
+        assert_eq!(profile_icon("performance"), "power_profile_performance");
+        assert_eq!(profile_icon("turbo"), "unknown");
+
+        assert_eq!(degraded_state(""), State::Idle);
+        assert_eq!(degraded_state("lap-detected"), State::Warning);
+        assert_eq!(degraded_state("high-operating-temperature"), State::Warning);
+    }
+
+    #[test]
+    fn cycle_order() {
+        let profile = |name: &str| {
+            HashMap::from([
+                (
+                    "Profile".to_owned(),
+                    OwnedValue::try_from(zbus::zvariant::Value::from(name)).unwrap(),
+                ),
+                (
+                    "Driver".to_owned(),
+                    OwnedValue::try_from(zbus::zvariant::Value::from("platform_profile")).unwrap(),
+                ),
+            ])
+        };
+        let profiles = profile_names(vec![
+            profile("power-saver"),
+            profile("balanced"),
+            profile("performance"),
+            HashMap::new(),
+        ]);
+        assert_eq!(profiles, ["power-saver", "balanced", "performance"]);
+
+        assert_eq!(cycle(&profiles, "power-saver", true), Some("balanced"));
+        assert_eq!(cycle(&profiles, "balanced", true), Some("performance"));
+        assert_eq!(cycle(&profiles, "performance", true), Some("power-saver"));
+        assert_eq!(cycle(&profiles, "power-saver", false), Some("performance"));
+        assert_eq!(cycle(&profiles, "performance", false), Some("balanced"));
+        assert_eq!(cycle(&profiles, "turbo", true), None);
+        assert_eq!(cycle(&[], "balanced", false), None);
+
+        // Without the performance profile, e.g. if the hardware does not support it
+        let profiles = ["power-saver".to_owned(), "balanced".to_owned()];
+        assert_eq!(cycle(&profiles, "balanced", true), Some("power-saver"));
+        assert_eq!(cycle(&profiles, "balanced", false), Some("power-saver"));
+    }
+}
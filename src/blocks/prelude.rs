@@ -2,10 +2,10 @@ pub use super::{BlockAction, CommonApi};
 
 pub use crate::click::MouseButton;
 pub use crate::errors::*;
-pub use crate::formatting::{config::Config as FormatConfig, value::Value, Values};
+pub use crate::formatting::{config::Config as FormatConfig, intern::intern, value::Value, Values};
 pub use crate::util::{default, new_dbus_connection, new_system_dbus_connection};
 pub use crate::widget::{State, Widget};
-pub use crate::wrappers::{Seconds, ShellString};
+pub use crate::wrappers::{Seconds, ShellString, Timer};
 pub(crate) use crate::REQWEST_CLIENT;
 pub(crate) use crate::REQWEST_CLIENT_IPV4;
 
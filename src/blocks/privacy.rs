@@ -1,10 +1,15 @@
 //! Privacy Monitor
 //!
+//! Like the privacy indicators of GNOME, this block shows an icon for each kind of media which is
+//! being captured: the microphone, the webcam, the screen (e.g. screen sharing through the desktop
+//! portal) and audio monitors. The `pipewire` driver finds the capture streams by the media class of
+//! the linked nodes, the `v4l` driver finds the processes which opened a webcam.
+//!
 //! # Configuration
 //!
 //! Key        | Values | Default|
 //! -----------|--------|--------|
-//! `driver` | The configuration of a driver (see below). | **Required**
+//! `driver` | The configuration of a driver (see below). | `pipewire` (if the pipewire feature is enabled) and `v4l`
 //! `format`   | Format string. | <code>\"{ $icon_audio \|}{ $icon_audio_sink \|}{ $icon_video \|}{ $icon_webcam \|}{ $icon_unknown \|}\"</code> |
 //! `format_alt`   | Format string. | <code>\"{ $icon_audio $info_audio \|}{ $icon_audio_sink $info_audio_sink \|}{ $icon_video $info_video \|}{ $icon_webcam $info_webcam \|}{ $icon_unknown $info_unknown \|}\"</code> |
 //!
@@ -85,9 +90,18 @@ pub struct Config {
     pub format: FormatConfig,
     #[serde(default)]
     pub format_alt: FormatConfig,
+    #[serde(default = "default_drivers")]
     pub driver: Vec<PrivacyDriver>,
 }
 
+fn default_drivers() -> Vec<PrivacyDriver> {
+    vec![
+        #[cfg(feature = "pipewire")]
+        PrivacyDriver::Pipewire(default()),
+        PrivacyDriver::V4l(default()),
+    ]
+}
+
 #[cfg(feature = "pipewire")]
 #[derive(Deserialize, Debug)]
 #[serde(tag = "name", rename_all = "snake_case")]
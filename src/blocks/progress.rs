@@ -0,0 +1,234 @@
+//! The progress of long running tasks
+//!
+//! This block reads the progress of tasks such as copies or downloads from log files or from the
+//! output of commands, and shows the task which will take the longest to finish. The block is
+//! hidden when no task is running.
+//!
+//! The last line of each file (or command output) is parsed. It must contain a percentage, either
+//! followed by a `%` sign or on its own, and may contain the remaining time in the `h:mm:ss` or
+//! `mm:ss` format after the percentage. This matches the output of `pv` (also with `-n`) and of
+//! `rsync --info=progress2`, for example:
+//!
+//! ```text
+//! 1.20GiB 0:00:10 [ 120MiB/s] [=========>           ] 45% ETA 0:00:12
+//! 1,234,567,890  45%  120.00MB/s    0:00:12 (xfr#3, to-chk=2/10)
+//! 45
+//! ```
+//!
+//! A task is done once it reaches 100% or its file has not been modified for `timeout` seconds.
+//! Files which do not exist are ignored.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" $icon $name.str(max_w:10) $percentage.bar(w:5) {$eta \|}\"</code>
+//! `files` | Files to read the progress from. Supports path expansions e.g. `~` and glob patterns. | `[]`
+//! `commands` | Shell commands which print the progress | `[]`
+//! `interval` | Update interval in seconds | `2`
+//! `timeout` | Time in seconds after which a file which is not modified anymore is ignored | `60`
+//!
+//! Placeholder  | Value                                                        | Type     | Unit
+//! -------------|--------------------------------------------------------------|----------|---------
+//! `icon`       | A static icon                                                | Icon     | -
+//! `name`       | The name of the file (without the directory) or the command | Text     | -
+//! `percentage` | The progress of the task                                     | Number   | %
+//! `eta`        | The remaining time. Absent if unknown.                       | Duration | -
+//! `count`      | The number of running tasks                                  | Number   | -
+//!
+//! # Example
+//!
+//! Show the progress of copies started with `pv big.iso 2> ~/.cache/progress/iso.log > /mnt/big.iso`:
+//!
+//! ```toml
+//! [[block]]
+//! block = "progress"
+//! files = ["~/.cache/progress/*.log"]
+//! format = " $icon $percentage.eng(w:2) {$eta.dur(hms:true) |}{($count) |}"
+//! ```
+//!
+//! # Icons Used
+//! - `tasks`
+
+use std::time::SystemTime;
+
+use tokio::process::Command;
+
+use super::prelude::*;
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    pub files: Vec<ShellString>,
+    pub commands: Vec<String>,
+    #[default(2.into())]
+    pub interval: Seconds,
+    #[default(60)]
+    pub timeout: u64,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let format = config
+        .format
+        .with_default(" $icon $name.str(max_w:10) $percentage.bar(w:5) {$eta |}")?;
+    let timeout = Duration::from_secs(config.timeout);
+
+    let mut timer = config.interval.timer();
+
+    loop {
+        let mut tasks = Vec::new();
+        for pattern in &config.files {
+            let pattern = pattern.expand()?;
+            let paths = glob::glob(&pattern).or_error(|| format!("Invalid pattern '{pattern}'"))?;
+            for path in paths.flatten() {
+                if let Some(task) = read_file_task(&path, timeout).await {
+                    tasks.push(task);
+                }
+            }
+        }
+        for command in &config.commands {
+            let output = Command::new("sh")
+                .args(["-c", command])
+                .output()
+                .await
+                .error("Failed to run command")?;
+            let output = String::from_utf8_lossy(&output.stdout);
+            if let Some(progress) = parse_progress(&output) {
+                tasks.push(Task {
+                    name: command.clone(),
+                    progress,
+                });
+            }
+        }
+        tasks.retain(|task| task.progress.percentage < 100.0);
+
+        let count = tasks.len();
+        match tasks.into_iter().max_by(|a, b| a.progress.cmp_remaining(&b.progress)) {
+            Some(task) => {
+                let mut widget = Widget::new().with_format(format.clone());
+                widget.set_values(map! {
+                    "icon" => Value::icon("tasks"),
+                    "name" => Value::text(task.name),
+                    "percentage" => Value::percents(task.progress.percentage),
+                    [if let Some(eta) = task.progress.eta] "eta" => Value::duration(eta),
+                    "count" => Value::number(count),
+                });
+                api.set_widget(widget)?;
+            }
+            None => api.hide()?,
+        }
+
+        select! {
+            _ = timer.tick() => (),
+            _ = api.wait_for_update_request() => (),
+        }
+    }
+}
+
+struct Task {
+    name: String,
+    progress: Progress,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Progress {
+    percentage: f64,
+    eta: Option<Duration>,
+}
+
+impl Progress {
+    /// Tasks with a longer ETA take longer to finish. Without an ETA, compare the percentages.
+    fn cmp_remaining(&self, other: &Self) -> std::cmp::Ordering {
+        match (self.eta, other.eta) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => std::cmp::Ordering::Greater,
+            (None, Some(_)) => std::cmp::Ordering::Less,
+            (None, None) => other.percentage.total_cmp(&self.percentage),
+        }
+    }
+}
+
+async fn read_file_task(path: &std::path::Path, timeout: Duration) -> Option<Task> {
+    let modified = tokio::fs::metadata(path).await.ok()?.modified().ok()?;
+    if SystemTime::now().duration_since(modified).unwrap_or_default() > timeout {
+        return None;
+    }
+    // Progress files are usually small, but may be long logs
+    let content = tokio::fs::read(path).await.ok()?;
+    let tail = &content[content.len().saturating_sub(4096)..];
+    let progress = parse_progress(&String::from_utf8_lossy(tail))?;
+    Some(Task {
+        name: path.file_name()?.to_string_lossy().into_owned(),
+        progress,
+    })
+}
+
+/// Parses the last non-empty line. Progress bars are redrawn using `\r`, so that counts as a line
+/// break too.
+fn parse_progress(output: &str) -> Option<Progress> {
+    let line = output
+        .rsplit(['\n', '\r'])
+        .map(str::trim)
+        .find(|line| !line.is_empty())?;
+
+    // `pv -n` prints just the percentage
+    if let Ok(percentage) = line.parse() {
+        return Some(Progress {
+            percentage,
+            eta: None,
+        });
+    }
+
+    let (before, after) = line.split_once('%')?;
+    let percentage = before
+        .rsplit(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .next()?
+        .parse()
+        .ok()?;
+    let eta = after.split_whitespace().find_map(parse_time);
+    Some(Progress { percentage, eta })
+}
+
+/// Parses `h:mm:ss` or `mm:ss`
+fn parse_time(s: &str) -> Option<Duration> {
+    let mut secs = 0;
+    let mut parts = 0;
+    for part in s.split(':') {
+        secs = secs * 60 + part.parse::<u64>().ok()?;
+        parts += 1;
+    }
+    (2..=3)
+        .contains(&parts)
+        .then(|| Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress() {
+        let progress = |percentage, eta: Option<u64>| {
+            Some(Progress {
+                percentage,
+                eta: eta.map(Duration::from_secs),
+            })
+        };
+        assert_eq!(
+            parse_progress(
+                "0.50GiB 0:00:05 [ 100MiB/s] [===>      ] 20% ETA 0:00:40\r\
+                 1.20GiB 0:00:10 [ 120MiB/s] [=========>           ] 45% ETA 0:00:12\r"
+            ),
+            progress(45.0, Some(12))
+        );
+        assert_eq!(
+            parse_progress("  1,234,567,890  45%  120.00MB/s    1:02:03 (xfr#3, to-chk=2/10)\n"),
+            progress(45.0, Some(3723))
+        );
+        assert_eq!(parse_progress("12\n45\n\n"), progress(45.0, None));
+        assert_eq!(parse_progress("done, 99.5% 01:30"), progress(99.5, Some(90)));
+        assert_eq!(parse_progress("no progress here"), None);
+        assert_eq!(parse_progress(""), None);
+    }
+}
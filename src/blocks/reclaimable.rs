@@ -0,0 +1,317 @@
+//! Disk space reclaimable from container engines and flatpak
+//!
+//! This block shows how much disk space can be freed by removing unused container images,
+//! containers and build cache (as reported by `docker system df` or `podman system df`) and
+//! unused flatpak runtimes. A flatpak runtime is unused if no installed app uses it or the runtime
+//! it extends. Runtimes pinned with `flatpak pin` are counted, although they are not uninstalled.
+//!
+//! Clicking the block asks for confirmation, and clicking it again within five seconds runs the
+//! prune commands of all sources:
+//!
+//! Source    | Prune command
+//! ----------|--------------
+//! `docker`  | `docker system prune --all --force`
+//! `podman`  | `podman system prune --all --force`
+//! `flatpak` | `flatpak uninstall --unused --noninteractive`
+//!
+//! Note that volumes are neither counted nor pruned, since they may hold data.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | `" $icon $reclaimable.eng(w:3,u:B,p:M) "`
+//! `format_confirm` | The format shown while waiting for the confirmation to prune | `" $icon Prune $reclaimable.eng(w:3,u:B,p:M)? "`
+//! `sources` | Where to look for reclaimable space: a list of `"docker"`, `"podman"` and `"flatpak"` | `["docker"]`
+//! `warning` | The reclaimable space in GB above which the block is in the warning state | `10.0`
+//! `interval` | Update interval in seconds | `600`
+//!
+//! Placeholder       | Value                                                  | Type   | Unit
+//! ------------------|--------------------------------------------------------|--------|------
+//! `icon`            | A static icon                                          | Icon   | -
+//! `reclaimable`     | The total reclaimable space                            | Number | Bytes
+//! `docker`          | The space reclaimable from docker. Absent if `docker` is not in `sources`. | Number | Bytes
+//! `podman`          | The space reclaimable from podman. Absent if `podman` is not in `sources`. | Number | Bytes
+//! `flatpak`         | The size of the unused flatpak runtimes. Absent if `flatpak` is not in `sources`. | Number | Bytes
+//! `unused_runtimes` | The number of unused flatpak runtimes. Absent if `flatpak` is not in `sources`. | Number | -
+//!
+//! Action  | Description                                          | Default button
+//! --------|------------------------------------------------------|---------------
+//! `prune` | Ask for confirmation, or prune if already asked      | Left
+//!
+//! # Example
+//!
+//! ```toml
+//! [[block]]
+//! block = "reclaimable"
+//! sources = ["podman", "flatpak"]
+//! format = " $icon $reclaimable.eng(w:3,u:B,p:M) ($unused_runtimes runtimes) "
+//! warning = 5.0
+//! ```
+//!
+//! # Icons Used
+//! - `disk_drive`
+
+use super::prelude::*;
+use itertools::Itertools;
+use std::process::Stdio;
+use tokio::process::Command;
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    pub format_confirm: FormatConfig,
+    #[default(vec![Source::Docker])]
+    pub sources: Vec<Source>,
+    #[default(10.0)]
+    pub warning: f64,
+    #[default(600.into())]
+    pub interval: Seconds,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Docker,
+    Podman,
+    Flatpak,
+}
+
+/// How long the block waits for the prune to be confirmed
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+    api.set_default_actions(&[(MouseButton::Left, None, "prune")])?;
+
+    let format = config
+        .format
+        .with_default(" $icon $reclaimable.eng(w:3,u:B,p:M) ")?;
+    let format_confirm = config
+        .format_confirm
+        .with_default(" $icon Prune $reclaimable.eng(w:3,u:B,p:M)? ")?;
+
+    let mut timer = config.interval.timer();
+
+    loop {
+        let mut total = 0.0;
+        let mut values = map!("icon" => Value::icon("disk_drive"));
+        for source in &config.sources {
+            match source {
+                Source::Docker | Source::Podman => {
+                    let engine = if *source == Source::Docker {
+                        "docker"
+                    } else {
+                        "podman"
+                    };
+                    let bytes = container_reclaimable(engine).await?;
+                    total += bytes;
+                    values.insert(engine, Value::bytes(bytes));
+                }
+                Source::Flatpak => {
+                    let (count, bytes) = flatpak_unused().await?;
+                    total += bytes;
+                    values.insert("flatpak", Value::bytes(bytes));
+                    values.insert("unused_runtimes", Value::number(count));
+                }
+            }
+        }
+        values.insert("reclaimable", Value::bytes(total));
+
+        let mut widget = Widget::new().with_format(format.clone());
+        if total > config.warning * 1e9 {
+            widget.state = State::Warning;
+        }
+        widget.set_values(values);
+        api.set_widget(widget.clone())?;
+
+        loop {
+            select! {
+                _ = timer.tick() => break,
+                _ = api.wait_for_update_request() => break,
+                Some(action) = actions.recv() => match action.as_ref() {
+                    "prune" => {
+                        widget.set_format(format_confirm.clone());
+                        api.set_widget(widget.clone())?;
+                        let confirmed = tokio::time::timeout(CONFIRM_TIMEOUT, actions.recv())
+                            .await
+                            .is_ok_and(|action| action.as_deref() == Some("prune"));
+                        if confirmed {
+                            for source in &config.sources {
+                                prune(*source).await?;
+                            }
+                            break;
+                        }
+                        widget.set_format(format.clone());
+                        api.set_widget(widget.clone())?;
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+/// Runs a command and returns its standard output
+async fn output(command: &mut Command) -> Result<String> {
+    let program = command.as_std().get_program().to_string_lossy().into_owned();
+    let output = command
+        .env("LC_ALL", "C")
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .or_error(|| format!("Failed to run '{program}'"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::new(format!("'{program}' failed: {}", stderr.trim())));
+    }
+    String::from_utf8(output.stdout).or_error(|| format!("'{program}' produced non-UTF8 output"))
+}
+
+async fn container_reclaimable(engine: &str) -> Result<f64> {
+    let output = output(Command::new(engine).args(["system", "df", "--format", "{{json .}}"]))
+        .await?;
+    parse_system_df(&output).or_error(|| format!("Failed to parse the output of '{engine}'"))
+}
+
+/// Sums up the `Reclaimable` column of `docker system df`, e.g. `"1.2GB (40%)"`, except for the
+/// volumes, which are not pruned
+fn parse_system_df(output: &str) -> Option<f64> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Row {
+        #[serde(rename = "Type")]
+        kind: String,
+        reclaimable: String,
+    }
+
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let row: Row = serde_json::from_str(line).ok()?;
+            if row.kind == "Local Volumes" {
+                return Some(0.0);
+            }
+            let size = row.reclaimable.split(" (").next()?;
+            parse_size(size)
+        })
+        .sum()
+}
+
+/// Returns the number and the total size of the unused flatpak runtimes
+async fn flatpak_unused() -> Result<(usize, f64)> {
+    let runtimes = output(Command::new("flatpak").args([
+        "list",
+        "--runtime",
+        "--columns=ref,size",
+    ]))
+    .await?;
+    let apps = output(Command::new("flatpak").args(["list", "--app", "--columns=runtime"])).await?;
+    let unused = parse_unused(&runtimes, &apps);
+    Ok((unused.len(), unused.iter().sum()))
+}
+
+/// Returns the sizes of the runtimes in the output of `flatpak list --runtime`, e.g.
+/// `org.gnome.Platform/x86_64/46\t1.1 GB`, which are not used by any app in the output of
+/// `flatpak list --app --columns=runtime`, e.g. `org.gnome.Platform/x86_64/46`. Extensions such as
+/// `org.gnome.Platform.Locale` are used if the runtime they extend is used with the same branch,
+/// or with any branch if that runtime is not installed with the branch of the extension.
+fn parse_unused(runtimes: &str, apps: &str) -> Vec<f64> {
+    let runtimes: Vec<((&str, &str, &str), &str)> = runtimes
+        .lines()
+        .filter_map(|line| {
+            let (runtime, size) = line.split_once('\t')?;
+            Some((runtime.trim().split('/').next_tuple()?, size))
+        })
+        .collect();
+    let used: Vec<(&str, &str, &str)> = apps
+        .lines()
+        .filter_map(|runtime| runtime.trim().split('/').next_tuple())
+        .collect();
+    let is_used = |(id, arch, branch): (&str, &str, &str)| {
+        used.iter().any(|&(used_id, used_arch, used_branch)| {
+            let extends = id
+                .strip_prefix(used_id)
+                .is_some_and(|suffix| suffix.starts_with('.'));
+            used_arch == arch
+                && (id == used_id || extends)
+                && (branch == used_branch
+                    || extends && !runtimes.iter().any(|(r, _)| *r == (used_id, arch, branch)))
+        })
+    };
+    runtimes
+        .iter()
+        .filter(|(runtime, _)| !is_used(*runtime))
+        .filter_map(|(_, size)| parse_size(size))
+        .collect()
+}
+
+/// Parses a size in decimal units such as `1.2GB`, `1.2 GB` or `512 bytes`
+fn parse_size(size: &str) -> Option<f64> {
+    let size: String = size.chars().filter(|c| !c.is_whitespace()).collect();
+    let split = size
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    let multiplier = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" | "byte" | "bytes" => 1e0,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "tb" => 1e12,
+        "pb" => 1e15,
+        _ => return None,
+    };
+    Some(number.parse::<f64>().ok()? * multiplier)
+}
+
+async fn prune(source: Source) -> Result<()> {
+    let (program, args): (_, &[_]) = match source {
+        Source::Docker => ("docker", &["system", "prune", "--all", "--force"]),
+        Source::Podman => ("podman", &["system", "prune", "--all", "--force"]),
+        Source::Flatpak => ("flatpak", &["uninstall", "--unused", "--noninteractive"]),
+    };
+    output(Command::new(program).args(args)).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size() {
+        assert_eq!(parse_size("1.2GB"), Some(1.2e9));
+        assert_eq!(parse_size("1.2\u{a0}GB"), Some(1.2e9));
+        assert_eq!(parse_size("512 bytes"), Some(512.0));
+        assert_eq!(parse_size("0B"), Some(0.0));
+        assert_eq!(parse_size("3 parsecs"), None);
+    }
+
+    #[test]
+    fn system_df() {
+        let output = r#"{"Active":"2","Reclaimable":"1.5GB (40%)","Size":"3.7GB","TotalCount":"5","Type":"Images"}
+{"Active":"1","Reclaimable":"0B (0%)","Size":"12kB","TotalCount":"1","Type":"Containers"}
+{"Active":"0","Reclaimable":"250MB (100%)","Size":"250MB","TotalCount":"2","Type":"Local Volumes"}
+{"Active":"0","Reclaimable":"2kB","Size":"2kB","TotalCount":"3","Type":"Build Cache"}
+"#;
+        assert_eq!(parse_system_df(output), Some(1.5e9 + 2e3));
+    }
+
+    #[test]
+    fn unused_runtimes() {
+        let runtimes = "org.gnome.Platform/x86_64/46\t1.1\u{a0}GB
+org.gnome.Platform.Locale/x86_64/46\t20.0\u{a0}kB
+org.gnome.Platform/x86_64/45\t1.0\u{a0}GB
+org.gnome.Platform.Locale/x86_64/45\t18.0\u{a0}kB
+org.freedesktop.Platform.openh264/x86_64/2.2.0\t1.0\u{a0}MB
+org.freedesktop.Platform/x86_64/23.08\t500.0\u{a0}MB
+";
+        let apps = "org.gnome.Platform/x86_64/46
+org.freedesktop.Platform/x86_64/23.08
+";
+        assert_eq!(parse_unused(runtimes, apps), [1.0e9, 18.0e3]);
+    }
+}
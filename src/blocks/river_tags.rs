@@ -0,0 +1,340 @@
+//! Tags of the river compositor
+//!
+//! This block shows the tags of an output in river in the style of dwm: the occupied tags, with
+//! the focused and urgent tags marked. By default the block follows the focused output; set
+//! `output` to always show the same output, e.g. with one bar per output. The information is
+//! received through river's `river-status-unstable-v1` Wayland protocol.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | `" $tags "`
+//! `output` | The name of the output to show, e.g. `"eDP-1"` | The focused output
+//! `tag_names` | The names of the tags, starting with the first one. Only the tags in this list are shown. | `["1", "2", "3", "4", "5", "6", "7", "8", "9"]`
+//! `tag_format` | How occupied tags are shown in `$tags`. `{name}` is replaced with the name of the tag. | `"{name}"`
+//! `focused_tag_format` | How focused tags are shown in `$tags` | `"[{name}]"`
+//! `urgent_tag_format` | How tags with an urgent view are shown in `$tags` | `"{name}!"`
+//! `show_empty_tags` | Whether to show tags without views in `$tags` | `false`
+//!
+//! Placeholder   | Value                                                   | Type   | Unit
+//! --------------|---------------------------------------------------------|--------|-----
+//! `tags`        | The focused, occupied and urgent tags                   | Text   | -
+//! `focused`     | The names of the focused tags                           | Text   | -
+//! `views`       | The number of views on the focused tags                 | Number | -
+//! `total_views` | The number of views on the output                       | Number | -
+//! `output`      | The name of the output                                  | Text   | -
+//! `layout`      | The name of the layout of the output. Absent if no layout generator is running. | Text | -
+//! `mode`        | The mode of the seat, e.g. `normal` or `locked`         | Text   | -
+//!
+//! The block is in the warning state if a view on any tag is urgent.
+//!
+//! # Example
+//!
+//! ```toml
+//! [[block]]
+//! block = "river_tags"
+//! format = " $tags {$layout |}({$views}) "
+//! show_empty_tags = true
+//! ```
+
+mod protocol;
+
+use protocol::*;
+use wayrs_client::global::{Global, GlobalExt, GlobalsExt};
+use wayrs_client::object::{ObjectId, Proxy};
+use wayrs_client::protocol::{wl_output, wl_registry, WlOutput, WlSeat};
+use wayrs_client::{Connection, EventCtx};
+
+use super::prelude::*;
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    pub output: Option<String>,
+    #[default((1..=9).map(|i| i.to_string()).collect())]
+    pub tag_names: Vec<String>,
+    #[default("{name}".into())]
+    pub tag_format: String,
+    #[default("[{name}]".into())]
+    pub focused_tag_format: String,
+    #[default("{name}!".into())]
+    pub urgent_tag_format: String,
+    pub show_empty_tags: bool,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let format = config.format.with_default(" $tags ")?;
+
+    let mut river = RiverStatus::new().await?;
+
+    loop {
+        river.wait_for_update().await?;
+
+        let state = &river.state;
+        let output = match &config.output {
+            Some(name) => state
+                .outputs
+                .values()
+                .find(|output| output.name.as_ref() == Some(name)),
+            None => state.outputs.iter().find_map(|(wl_output, output)| {
+                (state.focused_output == Some(wl_output.id())).then_some(output)
+            }),
+        };
+        let Some(output) = output else {
+            api.hide()?;
+            continue;
+        };
+
+        let (tags, focused) = format_tags(config, output);
+        let views = output
+            .view_tags
+            .iter()
+            .filter(|tags| *tags & output.focused_tags != 0)
+            .count();
+
+        let mut widget = Widget::new().with_format(format.clone());
+        if output.urgent_tags != 0 {
+            widget.state = State::Warning;
+        }
+        widget.set_values(map! {
+            "tags" => Value::text(tags),
+            "focused" => Value::text(focused),
+            "views" => Value::number(views),
+            "total_views" => Value::number(output.view_tags.len()),
+            [if let Some(name) = &output.name] "output" => Value::text(name.clone()),
+            [if let Some(layout) = &output.layout] "layout" => Value::text(layout.clone()),
+            [if let Some(mode) = &state.mode] "mode" => Value::text(mode.clone()),
+        });
+        api.set_widget(widget)?;
+    }
+}
+
+/// Returns the dwm-style list of tags and the names of the focused tags
+fn format_tags(config: &Config, output: &Output) -> (String, String) {
+    let occupied = output.view_tags.iter().fold(0, |acc, tags| acc | tags);
+    let mut tags = Vec::new();
+    let mut focused = Vec::new();
+    for (i, name) in config.tag_names.iter().enumerate().take(32) {
+        let tag = 1 << i;
+        let tag_format = if output.focused_tags & tag != 0 {
+            focused.push(name.as_str());
+            &config.focused_tag_format
+        } else if output.urgent_tags & tag != 0 {
+            &config.urgent_tag_format
+        } else if occupied & tag != 0 || config.show_empty_tags {
+            &config.tag_format
+        } else {
+            continue;
+        };
+        tags.push(tag_format.replace("{name}", name));
+    }
+    (tags.join(" "), focused.join(" "))
+}
+
+struct RiverStatus {
+    conn: Connection<RiverState>,
+    state: RiverState,
+}
+
+struct RiverState {
+    manager: ZriverStatusManagerV1,
+    outputs: HashMap<WlOutput, Output>,
+    focused_output: Option<ObjectId>,
+    mode: Option<String>,
+    updated: bool,
+}
+
+struct Output {
+    /// The name of the `wl_output` global
+    global: u32,
+    status: ZriverOutputStatusV1,
+    name: Option<String>,
+    focused_tags: u32,
+    view_tags: Vec<u32>,
+    urgent_tags: u32,
+    layout: Option<String>,
+}
+
+impl RiverStatus {
+    async fn new() -> Result<Self> {
+        let (mut conn, globals) = Connection::async_connect_and_collect_globals()
+            .await
+            .error("failed to connect to wayland")?;
+        let manager: ZriverStatusManagerV1 = globals
+            .bind(&mut conn, 1..=4)
+            .error("compositor does not support river-status-unstable-v1")?;
+        let seat: WlSeat = globals.bind(&mut conn, 1..=9).error("no wayland seat")?;
+
+        let mut state = RiverState {
+            manager,
+            outputs: HashMap::new(),
+            focused_output: None,
+            mode: None,
+            updated: false,
+        };
+        for global in globals.iter().filter(|global| global.is::<WlOutput>()) {
+            bind_output(&mut conn, &mut state, global);
+        }
+        // River only reports the focused output if the client has already bound it
+        manager.get_river_seat_status_with_cb(&mut conn, seat, seat_status_cb);
+        conn.add_registry_cb(registry_cb);
+
+        Ok(Self { conn, state })
+    }
+
+    async fn wait_for_update(&mut self) -> Result<()> {
+        while !self.state.updated {
+            self.conn.async_flush().await.error("wayland error")?;
+            self.conn.async_recv_events().await.error("wayland error")?;
+            self.conn.dispatch_events(&mut self.state);
+        }
+        self.state.updated = false;
+        Ok(())
+    }
+}
+
+fn bind_output(conn: &mut Connection<RiverState>, state: &mut RiverState, global: &Global) {
+    let Ok(output) = global.bind_with_cb(conn, 1..=4, output_cb) else {
+        return;
+    };
+    let status = state
+        .manager
+        .get_river_output_status_with_cb(conn, output, move |ctx| {
+            output_status_cb(ctx, output);
+        });
+    state.outputs.insert(
+        output,
+        Output {
+            global: global.name,
+            status,
+            name: None,
+            focused_tags: 0,
+            view_tags: Vec::new(),
+            urgent_tags: 0,
+            layout: None,
+        },
+    );
+}
+
+fn registry_cb(
+    conn: &mut Connection<RiverState>,
+    state: &mut RiverState,
+    event: &wl_registry::Event,
+) {
+    match event {
+        wl_registry::Event::Global(global) if global.is::<WlOutput>() => {
+            bind_output(conn, state, global);
+        }
+        wl_registry::Event::GlobalRemove(name) => {
+            let Some(output) = state
+                .outputs
+                .iter()
+                .find(|(_, output)| output.global == *name)
+                .map(|(output, _)| *output)
+            else {
+                return;
+            };
+            if let Some(removed) = state.outputs.remove(&output) {
+                removed.status.destroy(conn);
+            }
+            if output.version() >= 3 {
+                output.release(conn);
+            }
+            if state.focused_output == Some(output.id()) {
+                state.focused_output = None;
+            }
+            state.updated = true;
+        }
+        _ => (),
+    }
+}
+
+fn output_cb(ctx: EventCtx<RiverState, WlOutput>) {
+    if let wl_output::Event::Name(name) = ctx.event {
+        if let Some(output) = ctx.state.outputs.get_mut(&ctx.proxy) {
+            output.name = Some(String::from_utf8_lossy(name.as_bytes()).into());
+            ctx.state.updated = true;
+        }
+    }
+}
+
+fn output_status_cb(ctx: EventCtx<RiverState, ZriverOutputStatusV1>, output: WlOutput) {
+    use zriver_output_status_v1::Event;
+
+    let Some(output) = ctx.state.outputs.get_mut(&output) else {
+        return;
+    };
+    match ctx.event {
+        Event::FocusedTags(tags) => output.focused_tags = tags,
+        Event::ViewTags(tags) => {
+            output.view_tags = tags
+                .chunks_exact(4)
+                .map(|b| u32::from_ne_bytes(b.try_into().unwrap()))
+                .collect();
+        }
+        Event::UrgentTags(tags) => output.urgent_tags = tags,
+        Event::LayoutName(name) => {
+            output.layout = Some(String::from_utf8_lossy(name.as_bytes()).into());
+        }
+        Event::LayoutNameClear => output.layout = None,
+    }
+    ctx.state.updated = true;
+}
+
+fn seat_status_cb(ctx: EventCtx<RiverState, ZriverSeatStatusV1>) {
+    use zriver_seat_status_v1::Event;
+
+    match ctx.event {
+        Event::FocusedOutput(output) => ctx.state.focused_output = Some(output),
+        Event::UnfocusedOutput(output) => {
+            if ctx.state.focused_output == Some(output) {
+                ctx.state.focused_output = None;
+            }
+        }
+        Event::Mode(name) => {
+            ctx.state.mode = Some(String::from_utf8_lossy(name.as_bytes()).into());
+        }
+        Event::FocusedView(_) => return,
+    }
+    ctx.state.updated = true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags() {
+        let config = Config::default();
+        let output = |focused_tags, view_tags: &[u32], urgent_tags| Output {
+            global: 0,
+            status: ZriverOutputStatusV1::new(ObjectId(1.try_into().unwrap()), 1),
+            name: None,
+            focused_tags,
+            view_tags: view_tags.to_vec(),
+            urgent_tags,
+            layout: None,
+        };
+        assert_eq!(
+            format_tags(&config, &output(0b10, &[0b1, 0b1001, 0b10], 0b100000)),
+            ("1 [2] 4 6!".into(), "2".into())
+        );
+        assert_eq!(
+            format_tags(&config, &output(0b11, &[], 0)),
+            ("[1] [2]".into(), "1 2".into())
+        );
+
+        let config = Config {
+            tag_names: vec!["web".into(), "dev".into(), "chat".into()],
+            tag_format: "<{name}>".into(),
+            show_empty_tags: true,
+            ..default()
+        };
+        assert_eq!(
+            format_tags(&config, &output(0b1, &[0b1000], 0)),
+            ("[web] <dev> <chat>".into(), "web".into())
+        );
+    }
+}
@@ -0,0 +1,5 @@
+//! Bindings for river's `river-status-unstable-v1` protocol
+
+use wayrs_client::protocol::*;
+
+wayrs_client::generate!("src/blocks/river_tags/river-status-unstable-v1.xml");
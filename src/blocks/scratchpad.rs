@@ -1,5 +1,8 @@
 //! Scratchpad indicator
 //!
+//! This block shows the number of windows in the scratchpad of sway or i3. Clicking it shows the
+//! scratchpad windows one after another, like `scratchpad show` does.
+//!
 //! # Configuration
 //!
 //! Key | Values | Default
@@ -11,6 +14,10 @@
 //! `icon`      | A static icon                              | Icon   | -
 //! `count`     | Number of windows in scratchpad            | Number | -
 //!
+//! Action | Description                                        | Default button
+//! -------|----------------------------------------------------|---------------
+//! `show` | Show the next scratchpad window, or hide the shown one | Left
+//!
 //! # Example
 //!
 //! ```toml
@@ -37,6 +44,9 @@ fn count_scratchpad_windows(node: &Node) -> usize {
 }
 
 pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+    api.set_default_actions(&[(MouseButton::Left, None, "show")])?;
+
     let format = config
         .format
         .with_default(" $icon $count.eng(range:1..) |")?;
@@ -71,15 +81,24 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         api.set_widget(widget)?;
 
         loop {
-            let event = events
-                .next()
-                .await
-                .error("swayipc channel closed")?
-                .error("bad event")?;
-
-            match event {
-                SwayEvent::Window(e) if e.change == WindowChange::Move => break,
-                _ => continue,
+            select! {
+                event = events.next() => {
+                    let event = event
+                        .error("swayipc channel closed")?
+                        .error("bad event")?;
+                    if let SwayEvent::Window(e) = event {
+                        if e.change == WindowChange::Move {
+                            break;
+                        }
+                    }
+                }
+                Some(action) = actions.recv() => match action.as_ref() {
+                    "show" => {
+                        // Fails if the scratchpad is empty
+                        let _ = connection_for_tree.run_command("scratchpad show").await;
+                    }
+                    _ => (),
+                }
             }
         }
     }
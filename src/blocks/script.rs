@@ -0,0 +1,408 @@
+//! A block driven by an embedded Rhai script
+//!
+//! This block runs a [Rhai](https://rhai.rs/book/) script on every update. Compared to the
+//! `custom` block, which depends on the output of a shell pipeline, the script has direct access to
+//! the block's placeholders and state, can keep data between updates and can react to clicks.
+//!
+//! Note that you need to enable `rhai` feature to use this block:
+//! ```sh
+//! cargo build --release --features rhai
+//! ```
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | `" $text "`
+//! `script` | The script's source code | None
+//! `path` | Path to a file with the script. Supports path expansions e.g. `~`. Either `script` or `path` must be set. | None
+//! `interval` | Update interval in seconds (or "once" to update only once) | `10`
+//! `max_operations` | Maximum number of operations a single run of the script may perform. `0` means unlimited. | `1000000`
+//!
+//! Placeholder | Value                                   | Type                     | Unit
+//! ------------|-----------------------------------------|--------------------------|-----
+//! Any         | Placeholders set by the script's `set`  | Text, Number or Flag     | -
+//!
+//! Action   | Default button
+//! ---------|---------------
+//! `left`   | Left
+//! `middle` | Middle
+//! `right`  | Right
+//! `up`     | Wheel Up
+//! `down`   | Wheel Down
+//!
+//! # Scripting API
+//!
+//! The script is run from top to bottom on every update. The following variables are available:
+//!
+//! Variable | Description
+//! ---------|------------
+//! `state`  | An object map which is preserved between runs
+//! `action` | The name of the action which triggered this run or `()` for regular updates
+//!
+//! The following functions are available:
+//!
+//! Function | Description
+//! ---------|------------
+//! `set(name, value)` | Set a placeholder. Strings are displayed as text, integers and floats as numbers and `true` as a flag.
+//! `unset(name)` | Remove a placeholder
+//! `set_state(state)` | Set the block's state: `"idle"`, `"info"`, `"good"`, `"warning"` or `"critical"`
+//! `set_interval(seconds)` | Change the delay until the next update
+//! `hide()` | Hide the block until the next run
+//! `run(command)` | Run a shell command and return its standard output
+//! `http_get(url)` | Perform a HTTP GET request and return the response body
+//!
+//! # Example
+//!
+//! Count clicks and show the number of running processes:
+//!
+//! ```toml
+//! [[block]]
+//! block = "script"
+//! format = " $procs procs, $clicks clicks "
+//! script = '''
+//! if action == "left" {
+//!     state.clicks = (state.clicks ?? 0) + 1;
+//! }
+//! set("clicks", state.clicks ?? 0);
+//! let procs = run("ps -e --no-headers | wc -l");
+//! procs.trim();
+//! set("procs", parse_int(procs));
+//! if parse_int(procs) > 500 { set_state("warning"); } else { set_state("idle"); }
+//! '''
+//! ```
+
+use super::prelude::*;
+use crate::formatting::intern::Istr;
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use std::sync::{Arc, Mutex};
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    pub script: Option<String>,
+    pub path: Option<ShellString>,
+    #[default(10.into())]
+    pub interval: Seconds,
+    #[default(1_000_000)]
+    pub max_operations: u64,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    api.set_default_actions(&[
+        (MouseButton::Left, None, "left"),
+        (MouseButton::Middle, None, "middle"),
+        (MouseButton::Right, None, "right"),
+        (MouseButton::WheelUp, None, "up"),
+        (MouseButton::WheelDown, None, "down"),
+    ])?;
+
+    let format = config.format.with_default(" $text ")?;
+
+    let source = match (&config.script, &config.path) {
+        (Some(script), None) => script.clone(),
+        (None, Some(path)) => {
+            let path = path.expand()?;
+            tokio::fs::read_to_string(&*path)
+                .await
+                .or_error(|| format!("Failed to read '{path}'"))?
+        }
+        _ => return Err(Error::new("either 'script' or 'path' must be set")),
+    };
+
+    let output = Arc::new(Mutex::new(Output::default()));
+    let engine = Arc::new(new_engine(config.max_operations, &output));
+    let ast = Arc::new(engine.compile(source).error("Failed to compile the script")?);
+
+    let mut scope = new_scope();
+    let mut actions = api.get_actions()?;
+    let mut action = None;
+
+    loop {
+        scope = eval(engine.clone(), ast.clone(), scope, action.take()).await?;
+
+        let delay = {
+            let mut output = output.lock().unwrap();
+            if output.hidden {
+                output.hidden = false;
+                api.hide()?;
+            } else {
+                let mut widget = Widget::new()
+                    .with_format(format.clone())
+                    .with_state(output.state);
+                widget.set_values(output.values.clone());
+                api.set_widget(widget)?;
+            }
+            output.interval.take().unwrap_or(config.interval.0)
+        };
+
+        select! {
+            _ = sleep(delay) => (),
+            _ = api.wait_for_update_request() => (),
+            Some(a) = actions.recv() => action = Some(a),
+        }
+    }
+}
+
+/// The variables of the script before the first run
+fn new_scope() -> Scope<'static> {
+    let mut scope = Scope::new();
+    scope.push("state", Map::new());
+    scope.push("action", Dynamic::UNIT);
+    scope
+}
+
+/// Run the script on a blocking thread, because the host functions may block.
+async fn eval(
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+    mut scope: Scope<'static>,
+    action: Option<BlockAction>,
+) -> Result<Scope<'static>> {
+    tokio::task::spawn_blocking(move || {
+        let action = action.map_or(Dynamic::UNIT, |a| a.into_owned().into());
+        scope.set_value("action", action);
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .error("Script error")?;
+        Ok(scope)
+    })
+    .await
+    .error("Failed to join the script thread")?
+}
+
+#[derive(Debug, Default)]
+struct Output {
+    values: Values,
+    state: State,
+    interval: Option<Duration>,
+    hidden: bool,
+}
+
+type FnResult<T> = Result<T, Box<EvalAltResult>>;
+
+fn new_engine(max_operations: u64, output: &Arc<Mutex<Output>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(max_operations);
+
+    let o = output.clone();
+    engine.register_fn("set", move |name: &str, value: Dynamic| -> FnResult<()> {
+        let value = if let Some(s) = value.clone().try_cast::<String>() {
+            Value::text(s)
+        } else if let Ok(i) = value.as_int() {
+            Value::number(i)
+        } else if let Ok(f) = value.as_float() {
+            Value::number(f)
+        } else if value.as_bool() == Ok(true) {
+            Value::flag()
+        } else if value.as_bool() == Ok(false) || value.is_unit() {
+            o.lock().unwrap().values.remove(name);
+            return Ok(());
+        } else {
+            return Err(format!("'{}' cannot be used as a placeholder", value.type_name()).into());
+        };
+        // A placeholder which no format uses cannot be displayed, and interning its name would
+        // leak it
+        if let Some(name) = Istr::lookup(name) {
+            o.lock().unwrap().values.insert(name.as_str(), value);
+        }
+        Ok(())
+    });
+
+    let o = output.clone();
+    engine.register_fn("unset", move |name: &str| {
+        o.lock().unwrap().values.remove(name);
+    });
+
+    let o = output.clone();
+    engine.register_fn("set_state", move |state: &str| -> FnResult<()> {
+        o.lock().unwrap().state = match state {
+            "idle" => State::Idle,
+            "info" => State::Info,
+            "good" => State::Good,
+            "warning" => State::Warning,
+            "critical" => State::Critical,
+            other => return Err(format!("unknown state '{other}'").into()),
+        };
+        Ok(())
+    });
+
+    let o = output.clone();
+    engine.register_fn("set_interval", move |secs: rhai::INT| {
+        o.lock().unwrap().interval = Some(Duration::from_secs(secs.max(0) as u64));
+    });
+    let o = output.clone();
+    engine.register_fn("set_interval", move |secs: rhai::FLOAT| -> FnResult<()> {
+        let interval = Duration::try_from_secs_f64(secs.max(0.0))
+            .map_err(|_| format!("invalid interval {secs}"))?;
+        o.lock().unwrap().interval = Some(interval);
+        Ok(())
+    });
+
+    let o = output.clone();
+    engine.register_fn("hide", move || {
+        o.lock().unwrap().hidden = true;
+    });
+
+    engine.register_fn("run", |cmd: &str| -> FnResult<String> {
+        let output = std::process::Command::new("sh")
+            .args(["-c", cmd])
+            .stdin(std::process::Stdio::null())
+            .output()
+            .map_err(|e| format!("failed to run '{cmd}': {e}"))?;
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    });
+
+    // The script runs on a blocking thread, so the request is sent by a task on the runtime and
+    // the thread waits for its result
+    let handle = tokio::runtime::Handle::current();
+    engine.register_fn("http_get", move |url: &str| -> FnResult<String> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let request = REQWEST_CLIENT.get(url);
+        handle.spawn(async move {
+            let _ = tx.send(async { request.send().await?.text().await }.await);
+        });
+        rx.blocking_recv()
+            .map_err(|_| format!("request to '{url}' was cancelled"))?
+            .map_err(|e| format!("request to '{url}' failed: {e}").into())
+    });
+
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatting::value::ValueInner;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    struct Script {
+        engine: Arc<Engine>,
+        ast: Arc<AST>,
+        output: Arc<Mutex<Output>>,
+        scope: Option<Scope<'static>>,
+    }
+
+    impl Script {
+        fn new(source: &str) -> Self {
+            let output = Arc::new(Mutex::new(Output::default()));
+            let engine = Arc::new(new_engine(10_000, &output));
+            let ast = Arc::new(engine.compile(source).unwrap());
+            Self {
+                engine,
+                ast,
+                output,
+                scope: Some(new_scope()),
+            }
+        }
+
+        async fn run(&mut self, action: Option<&'static str>) -> Result<()> {
+            let scope = self.scope.take().unwrap_or_else(new_scope);
+            let action = action.map(Into::into);
+            self.scope = Some(eval(self.engine.clone(), self.ast.clone(), scope, action).await?);
+            Ok(())
+        }
+
+        fn value(&self, name: &str) -> Option<ValueInner> {
+            let output = self.output.lock().unwrap();
+            output.values.get(name).map(|value| value.inner.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn placeholders() {
+        for name in ["text", "count", "ratio", "flag"] {
+            let _ = intern(name);
+        }
+        let mut script = Script::new(
+            r#"
+            set("text", "hello");
+            set("count", 42);
+            set("ratio", 0.5);
+            set("flag", true);
+            set("not_in_format", "ignored");
+            set_state("warning");
+            "#,
+        );
+        script.run(None).await.unwrap();
+        assert!(matches!(script.value("text"), Some(ValueInner::Text(t)) if &*t == "hello"));
+        assert!(matches!(script.value("count"), Some(ValueInner::Number { val, .. }) if val == 42.0));
+        assert!(matches!(script.value("ratio"), Some(ValueInner::Number { val, .. }) if val == 0.5));
+        assert!(matches!(script.value("flag"), Some(ValueInner::Flag)));
+        assert!(script.value("not_in_format").is_none());
+        assert_eq!(script.output.lock().unwrap().state, State::Warning);
+
+        let mut script = Script::new(
+            r#"
+            set("text", "hello");
+            set("flag", true);
+            unset("text");
+            set("flag", false);
+            "#,
+        );
+        script.run(None).await.unwrap();
+        assert!(script.value("text").is_none());
+        assert!(script.value("flag").is_none());
+    }
+
+    #[tokio::test]
+    async fn state_and_actions() {
+        let _ = intern("clicks");
+        let mut script = Script::new(
+            r#"
+            if action == "left" {
+                state.clicks = (state.clicks ?? 0) + 1;
+            }
+            set("clicks", state.clicks ?? 0);
+            "#,
+        );
+        script.run(None).await.unwrap();
+        script.run(Some("left")).await.unwrap();
+        script.run(Some("right")).await.unwrap();
+        script.run(Some("left")).await.unwrap();
+        assert!(matches!(script.value("clicks"), Some(ValueInner::Number { val, .. }) if val == 2.0));
+    }
+
+    #[tokio::test]
+    async fn interval_and_hide() {
+        let mut script = Script::new(r#"set_interval(2.5); hide();"#);
+        script.run(None).await.unwrap();
+        let output = script.output.lock().unwrap();
+        assert_eq!(output.interval, Some(Duration::from_millis(2500)));
+        assert!(output.hidden);
+    }
+
+    #[tokio::test]
+    async fn errors() {
+        assert!(Script::new(r#"set_state("purple");"#).run(None).await.is_err());
+        assert!(Script::new(r#"set("text", [1, 2]);"#).run(None).await.is_err());
+        assert!(Script::new("loop {}").run(None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn host_functions() {
+        let mut script = Script::new(r#"let out = run("echo hi"); out.trim(); state.out = out;"#);
+        script.run(None).await.unwrap();
+        let scope = script.scope.as_ref().unwrap();
+        let state = scope.get_value::<Map>("state").unwrap();
+        assert_eq!(state["out"].clone().into_string().unwrap(), "hi");
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello")
+                .await
+                .unwrap();
+        });
+        let mut script = Script::new(&format!(r#"state.body = http_get("http://{addr}/");"#));
+        script.run(None).await.unwrap();
+        let scope = script.scope.as_ref().unwrap();
+        let state = scope.get_value::<Map>("state").unwrap();
+        assert_eq!(state["body"].clone().into_string().unwrap(), "hello");
+    }
+}
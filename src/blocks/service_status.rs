@@ -110,35 +110,7 @@ struct SystemdDriver {
 impl SystemdDriver {
     async fn new(service: String) -> Result<Self> {
         let dbus_conn = new_system_dbus_connection().await?;
-
-        if !service.is_ascii() {
-            return Err(Error::new(format!(
-                "service name \"{service}\" must only contain ASCII characters"
-            )));
-        }
-        let encoded_service = format!("{service}.service")
-            // For each byte...
-            .bytes()
-            .map(|b| {
-                if b.is_ascii_alphanumeric() {
-                    // Just use the character as a string
-                    char::from(b).to_string()
-                } else {
-                    // Otherwise use the hex representation of the byte preceded by an underscore
-                    format!("_{b:02x}")
-                }
-            })
-            .collect::<String>();
-
-        let path = format!("/org/freedesktop/systemd1/unit/{encoded_service}");
-
-        let proxy = UnitProxy::builder(&dbus_conn)
-            .path(path)
-            .error("Could not set path")?
-            .build()
-            .await
-            .error("Failed to create UnitProxy")?;
-
+        let proxy = unit_proxy(&dbus_conn, &service).await?;
         Ok(Self {
             active_state_changed: proxy.receive_active_state_changed().await,
             proxy,
@@ -162,11 +134,45 @@ impl Driver for SystemdDriver {
     }
 }
 
+/// Create a proxy for `<service>.service` systemd unit
+pub(super) async fn unit_proxy(
+    dbus_conn: &zbus::Connection,
+    service: &str,
+) -> Result<UnitProxy<'static>> {
+    if !service.is_ascii() {
+        return Err(Error::new(format!(
+            "service name \"{service}\" must only contain ASCII characters"
+        )));
+    }
+    let encoded_service = format!("{service}.service")
+        // For each byte...
+        .bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() {
+                // Just use the character as a string
+                char::from(b).to_string()
+            } else {
+                // Otherwise use the hex representation of the byte preceded by an underscore
+                format!("_{b:02x}")
+            }
+        })
+        .collect::<String>();
+
+    let path = format!("/org/freedesktop/systemd1/unit/{encoded_service}");
+
+    UnitProxy::builder(dbus_conn)
+        .path(path)
+        .error("Could not set path")?
+        .build()
+        .await
+        .error("Failed to create UnitProxy")
+}
+
 #[zbus::proxy(
     interface = "org.freedesktop.systemd1.Unit",
     default_service = "org.freedesktop.systemd1"
 )]
-trait Unit {
+pub(super) trait Unit {
     #[zbus(property)]
     fn active_state(&self) -> zbus::Result<String>;
 }
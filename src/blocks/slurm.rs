@@ -0,0 +1,292 @@
+//! Slurm job queue status
+//!
+//! This block runs `squeue` to show how many of the user's jobs are running and pending, and how
+//! much time is left until the soonest-ending running job reaches its time limit. If `host` is
+//! set, `squeue` is run on that host over `ssh` (which must not ask for a password), so that the
+//! block can be used on a machine that is not part of the cluster.
+//!
+//! Slurm only keeps finished jobs in the queue for a short time (`MinJobAge`, five minutes by
+//! default), so the block remembers the jobs which it saw failing. While there are any, the block
+//! is in the warning state until the failures are acknowledged by clicking the block.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" $icon $running/$pending{ $time_remaining.dur(hms:true, min_unit:m)\|} \"</code>
+//! `interval` | Update interval in seconds | `60`
+//! `host` | The host to run `squeue` on over `ssh` | None
+//! `clusters` | A comma separated list of clusters to query (passed to `squeue --clusters`) | None
+//!
+//! Placeholder      | Value                                                              | Type     | Unit
+//! -----------------|--------------------------------------------------------------------|----------|-----
+//! `icon`           | A static icon                                                      | Icon     | -
+//! `running`        | The number of running jobs                                         | Number   | -
+//! `pending`        | The number of pending jobs                                         | Number   | -
+//! `failed`         | The number of failed jobs which have not been acknowledged         | Number   | -
+//! `time_remaining` | Time left until the soonest-ending running job hits its time limit. Absent if no running job has a time limit. | Duration | -
+//! `job`            | The name of the soonest-ending running job. Absent if no running job has a time limit. | Text | -
+//!
+//! Jobs that ended in `FAILED`, `TIMEOUT`, `OUT_OF_MEMORY`, `NODE_FAIL`, `BOOT_FAIL` or `DEADLINE`
+//! count as failed.
+//!
+//! Action         | Description                         | Default button
+//! ---------------|-------------------------------------|---------------
+//! `clear_failed` | Acknowledge the failed jobs         | Left
+//!
+//! # Example
+//!
+//! ```toml
+//! [[block]]
+//! block = "slurm"
+//! host = "login.cluster.example.org"
+//! format = " $icon $running R $pending PD{ $failed failed|} "
+//! ```
+//!
+//! # Icons Used
+//! - `tasks`
+
+use super::prelude::*;
+use std::collections::HashSet;
+use tokio::process::Command;
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    #[default(60.into())]
+    pub interval: Seconds,
+    pub host: Option<String>,
+    pub clusters: Option<String>,
+}
+
+const FAILED_STATES: &[&str] = &[
+    "FAILED",
+    "TIMEOUT",
+    "OUT_OF_MEMORY",
+    "NODE_FAIL",
+    "BOOT_FAIL",
+    "DEADLINE",
+];
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+    api.set_default_actions(&[(MouseButton::Left, None, "clear_failed")])?;
+
+    let format = config
+        .format
+        .with_default(" $icon $running/$pending{ $time_remaining.dur(hms:true, min_unit:m)|} ")?;
+
+    let mut failed = HashSet::new();
+    let mut acknowledged = HashSet::new();
+    let mut timer = config.interval.timer();
+
+    loop {
+        let jobs = squeue(config).await?;
+
+        for job in &jobs {
+            if FAILED_STATES.contains(&job.state.as_str()) && !acknowledged.contains(&job.id) {
+                failed.insert(job.id.clone());
+            }
+        }
+        // Forget acknowledged jobs once Slurm has purged them from the queue
+        acknowledged.retain(|id| jobs.iter().any(|job| &job.id == id));
+
+        let running = jobs.iter().filter(|job| job.state == "RUNNING").count();
+        let pending = jobs.iter().filter(|job| job.state == "PENDING").count();
+        let soonest = jobs
+            .iter()
+            .filter(|job| job.state == "RUNNING")
+            .filter_map(|job| Some((job.time_left?, job)))
+            .min_by_key(|(time_left, _)| *time_left);
+
+        let mut widget = Widget::new().with_format(format.clone());
+        widget.state = if failed.is_empty() {
+            State::Idle
+        } else {
+            State::Warning
+        };
+        widget.set_values(map! {
+            "icon" => Value::icon("tasks"),
+            "running" => Value::number(running),
+            "pending" => Value::number(pending),
+            "failed" => Value::number(failed.len()),
+            [if let Some((time_left, _)) = soonest] "time_remaining" => Value::duration(time_left),
+            [if let Some((_, job)) = soonest] "job" => Value::text(job.name.clone()),
+        });
+        api.set_widget(widget)?;
+
+        loop {
+            select! {
+                _ = timer.tick() => break,
+                _ = api.wait_for_update_request() => break,
+                Some(action) = actions.recv() => match action.as_ref() {
+                    "clear_failed" => {
+                        acknowledged.extend(failed.drain());
+                        break;
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Job {
+    id: String,
+    state: String,
+    time_left: Option<Duration>,
+    name: String,
+}
+
+async fn squeue(config: &Config) -> Result<Vec<Job>> {
+    let mut command = squeue_command(config);
+    let output = command
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .error("Failed to run squeue")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::new(format!("squeue failed: {}", stderr.trim())));
+    }
+
+    let stdout = std::str::from_utf8(&output.stdout).error("squeue output is not valid UTF-8")?;
+    Ok(parse_squeue(stdout))
+}
+
+fn squeue_command(config: &Config) -> Command {
+    let mut args = vec![
+        "squeue".to_owned(),
+        "--me".to_owned(),
+        "--states=all".to_owned(),
+        "--noheader".to_owned(),
+        "--format=%i|%T|%L|%j".to_owned(),
+    ];
+    if let Some(clusters) = &config.clusters {
+        args.push(format!("--clusters={clusters}"));
+    }
+
+    match &config.host {
+        Some(host) => {
+            let mut command = Command::new("ssh");
+            command.args(["-o", "BatchMode=yes", host.as_str(), &remote_command(&args)]);
+            command
+        }
+        None => {
+            let mut command = Command::new(&args[0]);
+            command.args(&args[1..]);
+            command
+        }
+    }
+}
+
+/// Joins the arguments into a command line for the shell which `ssh` runs on the remote host,
+/// quoting each of them (the format contains `|`, which the shell would take for a pipe)
+fn remote_command(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| format!("'{}'", arg.replace('\'', r"'\''")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_squeue(output: &str) -> Vec<Job> {
+    output
+        .lines()
+        .filter_map(|line| {
+            // With `--clusters`, each cluster's jobs are preceded by a `CLUSTER: name` line
+            let mut fields = line.trim().splitn(4, '|');
+            Some(Job {
+                id: fields.next()?.to_owned(),
+                state: fields.next()?.to_owned(),
+                time_left: parse_time(fields.next()?),
+                name: fields.next()?.to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Parse a Slurm time such as `59:59`, `1:00:00` or `2-12:00:00`. `UNLIMITED`, `NOT_SET` and
+/// `INVALID` are `None`.
+fn parse_time(time: &str) -> Option<Duration> {
+    let (days, rest) = match time.split_once('-') {
+        Some((days, rest)) => (days.parse::<u64>().ok()?, rest),
+        None => (0, time),
+    };
+    let parts = rest
+        .split(':')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let (hours, minutes, seconds) = match (parts.as_slice(), days > 0) {
+        ([h], true) => (*h, 0, 0),
+        ([h, m], true) => (*h, *m, 0),
+        ([m], false) => (0, *m, 0),
+        ([m, s], false) => (0, *m, *s),
+        ([h, m, s], _) => (*h, *m, *s),
+        _ => return None,
+    };
+    Some(Duration::from_secs(
+        ((days * 24 + hours) * 60 + minutes) * 60 + seconds,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time() {
+        assert_eq!(parse_time("0:42"), Some(Duration::from_secs(42)));
+        assert_eq!(parse_time("59:59"), Some(Duration::from_secs(3599)));
+        assert_eq!(parse_time("1:00:00"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_time("2-12:00:00"), Some(Duration::from_secs(216000)));
+        assert_eq!(parse_time("1-01"), Some(Duration::from_secs(90000)));
+        assert_eq!(parse_time("UNLIMITED"), None);
+        assert_eq!(parse_time("NOT_SET"), None);
+    }
+
+    #[test]
+    fn ssh_command() {
+        let config = Config {
+            host: Some("login".into()),
+            clusters: Some("hpc,gpu's".into()),
+            ..Default::default()
+        };
+        let command = squeue_command(&config);
+        let args: Vec<_> = command.as_std().get_args().collect();
+        assert_eq!(
+            args,
+            [
+                "-o",
+                "BatchMode=yes",
+                "login",
+                r"'squeue' '--me' '--states=all' '--noheader' '--format=%i|%T|%L|%j' '--clusters=hpc,gpu'\''s'"
+            ]
+        );
+    }
+
+    #[test]
+    fn squeue_output() {
+        let output = "CLUSTER: hpc\n\
+                      1234|RUNNING|1:02:03|train|model\n\
+                      1235|PENDING|2-00:00:00|eval\n";
+        assert_eq!(
+            parse_squeue(output),
+            [
+                Job {
+                    id: "1234".into(),
+                    state: "RUNNING".into(),
+                    time_left: Some(Duration::from_secs(3723)),
+                    name: "train|model".into(),
+                },
+                Job {
+                    id: "1235".into(),
+                    state: "PENDING".into(),
+                    time_left: Some(Duration::from_secs(172800)),
+                    name: "eval".into(),
+                },
+            ]
+        );
+    }
+}
@@ -129,6 +129,34 @@ enum Mappings<'a> {
     Regex(Vec<(Regex, &'a str)>),
 }
 
+impl<'a> Mappings<'a> {
+    fn new(config: &'a Config) -> Result<Option<Self>> {
+        let Some(m) = &config.mappings else {
+            return Ok(None);
+        };
+        if !config.mappings_use_regex {
+            return Ok(Some(Self::Exact(m)));
+        }
+        m.iter()
+            .map(|(key, val)| {
+                Ok((
+                    Regex::new(key)
+                        .or_error(|| format!("Failed to parse `{key}` in mappings as regex"))?,
+                    val.as_str(),
+                ))
+            })
+            .collect::<Result<_>>()
+            .map(|m| Some(Self::Regex(m)))
+    }
+}
+
+impl Config {
+    /// Checks the `mappings`, which are only regexes if `mappings_use_regex` is set
+    pub(crate) fn check(&self) -> Result<()> {
+        Mappings::new(self).map(drop)
+    }
+}
+
 pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
     let mut actions = api.get_actions()?;
     api.set_default_actions(&[
@@ -213,26 +241,7 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         )?),
     };
 
-    let mappings = match &config.mappings {
-        Some(m) => {
-            if config.mappings_use_regex {
-                Some(Mappings::Regex(
-                    m.iter()
-                        .map(|(key, val)| {
-                            Ok((
-                                Regex::new(key)
-                                    .error("Failed to parse `{key}` in mappings as regex")?,
-                                val.as_str(),
-                            ))
-                        })
-                        .collect::<Result<_>>()?,
-                ))
-            } else {
-                Some(Mappings::Exact(m))
-            }
-        }
-        None => None,
-    };
+    let mappings = Mappings::new(config)?;
 
     loop {
         device.get_info().await?;
@@ -90,22 +90,22 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         );
 
         if is_timer_active {
-            values.insert("time".into(), Value::duration(remaining_time));
+            values.insert("time", Value::duration(remaining_time));
             let mut seconds = remaining_time.as_secs();
 
             if format.contains_key("hours") {
                 let hours = seconds / 3_600;
-                values.insert("hours".into(), Value::text(format!("{hours:02}")));
+                values.insert("hours", Value::text(format!("{hours:02}")));
                 seconds %= 3_600;
             }
 
             if format.contains_key("minutes") {
                 let minutes = seconds / 60;
-                values.insert("minutes".into(), Value::text(format!("{minutes:02}")));
+                values.insert("minutes", Value::text(format!("{minutes:02}")));
                 seconds %= 60;
             }
 
-            values.insert("seconds".into(), Value::text(format!("{seconds:02}")));
+            values.insert("seconds", Value::text(format!("{seconds:02}")));
         }
 
         widget.set_values(values);
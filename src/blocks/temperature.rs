@@ -1,6 +1,14 @@
 //! The system temperature
 //!
-//! This block displays the system temperature, based on `libsensors` library.
+//! This block displays the system temperature, based on `libsensors` library. If i3status-rs is
+//! built without the `lm_sensors` feature, the temperatures are read directly from
+//! `/sys/class/hwmon` instead. In this case `chip` is matched against the name of the
+//! hwmon device (e.g. `coretemp`), not the full chip name (e.g. `coretemp-isa-0000`).
+//!
+//! On FreeBSD, the temperatures of the CPU cores (`dev.cpu.N.temperature`, which requires the
+//! `coretemp` or `amdtemp` kernel module) and of the ACPI thermal zones
+//! (`hw.acpi.thermal.tzN.temperature`) are read with `sysctl`. In this case `chip` is matched
+//! against the sysctl node (e.g. `dev.cpu.*` or `hw.acpi.thermal.tz0`) and `inputs` is ignored.
 //!
 //! This block has two modes: "collapsed", which uses only color as an indicator, and "expanded",
 //! which shows the content of a `format` string. The average, minimum, and maximum temperatures
@@ -56,9 +64,6 @@
 //! - `thermometer`
 
 use super::prelude::*;
-use sensors::FeatureType::SENSORS_FEATURE_TEMP;
-use sensors::Sensors;
-use sensors::SubfeatureType::SENSORS_SUBFEATURE_TEMP_INPUT;
 
 const DEFAULT_GOOD: f64 = 20.0;
 const DEFAULT_IDLE: f64 = 45.0;
@@ -127,46 +132,20 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
     loop {
         let chip = config.chip.clone();
         let inputs = config.inputs.clone();
-        let config_scale = config.scale;
-        let temp = tokio::task::spawn_blocking(move || {
-            let mut vals = Vec::new();
-            let sensors = Sensors::new();
-            let chips = match &chip {
-                Some(chip) => sensors
-                    .detected_chips(chip)
-                    .error("Failed to create chip iterator")?,
-                None => sensors.into_iter(),
-            };
-            for chip in chips {
-                for feat in chip {
-                    if *feat.feature_type() != SENSORS_FEATURE_TEMP {
-                        continue;
-                    }
-                    if let Some(inputs) = &inputs {
-                        let label = feat.get_label().error("Failed to get input label")?;
-                        if !inputs.contains(&label) {
-                            continue;
-                        }
+        let temp: Vec<f64> =
+            tokio::task::spawn_blocking(move || read_temperatures(chip.as_deref(), inputs.as_deref()))
+                .await
+                .error("Failed to join tokio task")??
+                .into_iter()
+                .filter(|value| {
+                    let in_range = (-100.0..=150.0).contains(value);
+                    if !in_range {
+                        eprintln!("Temperature ({value}) outside of range ([-100, 150])");
                     }
-                    for subfeat in feat {
-                        if *subfeat.subfeature_type() == SENSORS_SUBFEATURE_TEMP_INPUT {
-                            if let Ok(value) = subfeat.get_value() {
-                                if (-100.0..=150.0).contains(&value) {
-                                    vals.push(config_scale.from_celsius(value));
-                                } else {
-                                    eprintln!(
-                                        "Temperature ({value}) outside of range ([-100, 150])"
-                                    );
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            Ok(vals)
-        })
-        .await
-        .error("Failed to join tokio task")??;
+                    in_range
+                })
+                .map(|value| config.scale.from_celsius(value))
+                .collect();
 
         let min_temp = temp
             .iter()
@@ -200,7 +179,7 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         api.set_widget(widget)?;
 
         select! {
-            _ = sleep(config.interval.0) => (),
+            _ = config.interval.sleep() => (),
             _ = api.wait_for_update_request() => (),
             Some(action) = actions.recv() => match action.as_ref() {
                 "toggle_format" => {
@@ -213,3 +192,120 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
         }
     }
 }
+
+/// Returns the temperatures (in Celsius) of all matching inputs using `libsensors`
+#[cfg(all(feature = "lm_sensors", target_os = "linux"))]
+fn read_temperatures(chip: Option<&str>, inputs: Option<&[String]>) -> Result<Vec<f64>> {
+    use sensors::FeatureType::SENSORS_FEATURE_TEMP;
+    use sensors::Sensors;
+    use sensors::SubfeatureType::SENSORS_SUBFEATURE_TEMP_INPUT;
+
+    let mut vals = Vec::new();
+    let sensors = Sensors::new();
+    let chips = match chip {
+        Some(chip) => sensors
+            .detected_chips(chip)
+            .error("Failed to create chip iterator")?,
+        None => sensors.into_iter(),
+    };
+    for chip in chips {
+        for feat in chip {
+            if *feat.feature_type() != SENSORS_FEATURE_TEMP {
+                continue;
+            }
+            if let Some(inputs) = inputs {
+                let label = feat.get_label().error("Failed to get input label")?;
+                if !inputs.contains(&label) {
+                    continue;
+                }
+            }
+            for subfeat in feat {
+                if *subfeat.subfeature_type() == SENSORS_SUBFEATURE_TEMP_INPUT {
+                    if let Ok(value) = subfeat.get_value() {
+                        vals.push(value);
+                    }
+                }
+            }
+        }
+    }
+    Ok(vals)
+}
+
+/// Returns the temperatures (in Celsius) of all matching inputs by reading `/sys/class/hwmon`
+#[cfg(not(any(all(feature = "lm_sensors", target_os = "linux"), target_os = "freebsd")))]
+fn read_temperatures(chip: Option<&str>, inputs: Option<&[String]>) -> Result<Vec<f64>> {
+    use std::fs::{read_dir, read_to_string};
+
+    let chip = chip.map(chip_regex).transpose()?;
+
+    let mut vals = Vec::new();
+    for device in read_dir("/sys/class/hwmon").error("Failed to read /sys/class/hwmon")? {
+        let path = device.error("Failed to read /sys/class/hwmon")?.path();
+        if let Some(chip) = &chip {
+            let name = read_to_string(path.join("name")).unwrap_or_default();
+            if !chip.is_match(name.trim()) {
+                continue;
+            }
+        }
+        let Ok(entries) = read_dir(&path) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some(input) = file_name
+                .to_str()
+                .and_then(|f| f.strip_suffix("_input"))
+                .filter(|i| i.starts_with("temp"))
+            else {
+                continue;
+            };
+            if let Some(inputs) = inputs {
+                // libsensors falls back to the input name if there is no label
+                let label = read_to_string(path.join(format!("{input}_label")))
+                    .map(|l| l.trim().to_owned())
+                    .unwrap_or_else(|_| input.to_owned());
+                if !inputs.contains(&label) {
+                    continue;
+                }
+            }
+            if let Some(value) = read_to_string(entry.path())
+                .ok()
+                .and_then(|v| v.trim().parse::<f64>().ok())
+            {
+                // The values are in millidegrees Celsius
+                vals.push(value / 1000.0);
+            }
+        }
+    }
+    Ok(vals)
+}
+
+/// Returns the temperatures (in Celsius) of the CPU cores and ACPI thermal zones using `sysctl`
+#[cfg(target_os = "freebsd")]
+fn read_temperatures(chip: Option<&str>, _inputs: Option<&[String]>) -> Result<Vec<f64>> {
+    let chip = chip.map(chip_regex).transpose()?;
+
+    let mut vals = Vec::new();
+    for prefix in ["dev.cpu.", "hw.acpi.thermal.tz"] {
+        for i in 0.. {
+            let node = format!("{prefix}{i}");
+            let Ok(value) = crate::sysctl::read::<libc::c_int>(&format!("{node}.temperature"))
+            else {
+                break;
+            };
+            if chip.as_ref().is_some_and(|chip| !chip.is_match(&node)) {
+                continue;
+            }
+            // The values are in tenths of a kelvin
+            vals.push(value as f64 / 10.0 - 273.15);
+        }
+    }
+    Ok(vals)
+}
+
+/// Turns a chip name with `*` wildcards into a regex
+#[cfg(not(all(feature = "lm_sensors", target_os = "linux")))]
+fn chip_regex(chip: &str) -> Result<regex::Regex> {
+    let pattern = regex::escape(chip).replace(r"\*", ".*");
+    regex::Regex::new(&format!("^{pattern}$")).error("Invalid chip name")
+}
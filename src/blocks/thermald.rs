@@ -0,0 +1,235 @@
+//! Thermal management services and package power limits
+//!
+//! This block shows which of the thermal and power management services commonly used on Intel
+//! laptops (`thermald`, `throttled`, `intel-undervolt`) are active, along with the current package
+//! power limits. It is useful to quickly tell why a laptop is running slower than expected.
+//!
+//! The power limits are read from the `intel-rapl` powercap interface in sysfs. If it is not
+//! available, the block falls back to reading `MSR_PKG_POWER_LIMIT` from `/dev/cpu/0/msr`, which
+//! requires the `msr` kernel module and read access to the device (usually root only). If neither is
+//! readable, the `pl1` and `pl2` placeholders are absent.
+//!
+//! Only `systemd` services are supported.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | <code>\" $icon {$services\|none} {$pl1.eng(w:2)/$pl2.eng(w:2) \|}\"</code>
+//! `services` | The names of the services to check | `["thermald", "throttled", "intel-undervolt"]`
+//! `interval` | Update interval in seconds | `10`
+//!
+//! Placeholder | Value                                             | Type   | Unit
+//! ------------|---------------------------------------------------|--------|-----
+//! `icon`      | A static icon                                     | Icon   | -
+//! `services`  | Comma-separated list of active services. Absent if no service is active. | Text | -
+//! `count`     | Number of active services                         | Number | -
+//! `pl1`       | Long term package power limit (PL1)               | Number | Watts
+//! `pl2`       | Short term package power limit (PL2)              | Number | Watts
+//!
+//! # Example
+//!
+//! ```toml
+//! [[block]]
+//! block = "thermald"
+//! format = " $icon $count {PL1 $pl1.eng(w:2)|} "
+//! services = ["thermald"]
+//! interval = 30
+//! ```
+//!
+//! # Used Icons
+//! - `thermometer`
+
+use std::os::unix::fs::FileExt;
+
+use super::prelude::*;
+use super::service_status::{unit_proxy, UnitProxy};
+use crate::util::read_file;
+
+const POWERCAP_PATH: &str = "/sys/class/powercap/intel-rapl:0";
+const MSR_PATH: &str = "/dev/cpu/0/msr";
+const MSR_RAPL_POWER_UNIT: u64 = 0x606;
+const MSR_PKG_POWER_LIMIT: u64 = 0x610;
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub format: FormatConfig,
+    #[default(vec!["thermald".into(), "throttled".into(), "intel-undervolt".into()])]
+    pub services: Vec<String>,
+    #[default(10.into())]
+    pub interval: Seconds,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let format = config
+        .format
+        .with_default(" $icon {$services|none} {$pl1.eng(w:2)/$pl2.eng(w:2) |}")?;
+
+    let dbus_conn = new_system_dbus_connection().await?;
+    let mut units: Vec<(&str, UnitProxy)> = Vec::with_capacity(config.services.len());
+    for service in &config.services {
+        units.push((service, unit_proxy(&dbus_conn, service).await?));
+    }
+
+    let mut timer = config.interval.timer();
+
+    loop {
+        let mut states = Vec::with_capacity(units.len());
+        for (service, proxy) in &units {
+            let state = proxy
+                .active_state()
+                .await
+                .or_error(|| format!("Could not get the state of '{service}'"))?;
+            states.push((*service, state));
+        }
+        let active = active_services(&states);
+
+        let mut values = map! {
+            "icon" => Value::icon("thermometer"),
+            "count" => Value::number(active.len()),
+        };
+        if !active.is_empty() {
+            values.insert("services", Value::text(active.join(", ")));
+        }
+        let limits = match read_powercap_limits().await {
+            Some(limits) => Some(limits),
+            None => read_msr_limits(),
+        };
+        if let Some((pl1, pl2)) = limits {
+            values.insert("pl1", Value::watts(pl1));
+            values.insert("pl2", Value::watts(pl2));
+        }
+
+        let mut widget = Widget::new().with_format(format.clone());
+        widget.set_values(values);
+        api.set_widget(widget)?;
+
+        select! {
+            _ = timer.tick() => (),
+            _ = api.wait_for_update_request() => (),
+        }
+    }
+}
+
+/// The services whose unit is active, in the configured order
+fn active_services<'a>(states: &[(&'a str, String)]) -> Vec<&'a str> {
+    // A unit which is not installed is reported as "inactive"
+    states
+        .iter()
+        .filter(|(_, state)| state == "active")
+        .map(|(service, _)| *service)
+        .collect()
+}
+
+/// Read PL1 and PL2 (in watts) from the powercap sysfs interface
+async fn read_powercap_limits() -> Option<(f64, f64)> {
+    let mut constraints = Vec::with_capacity(2);
+    for i in 0..2 {
+        let name = read_file(format!("{POWERCAP_PATH}/constraint_{i}_name"))
+            .await
+            .ok()?;
+        let limit = read_file(format!("{POWERCAP_PATH}/constraint_{i}_power_limit_uw"))
+            .await
+            .ok()?;
+        constraints.push((name, limit));
+    }
+    parse_powercap_limits(&constraints)
+}
+
+/// Find PL1 and PL2 (in watts) among the names and limits (in microwatts) of powercap constraints
+fn parse_powercap_limits(constraints: &[(String, String)]) -> Option<(f64, f64)> {
+    let mut pl1 = None;
+    let mut pl2 = None;
+    for (name, limit) in constraints {
+        let limit: f64 = limit.parse().ok()?;
+        match name.as_str() {
+            "long_term" => pl1 = Some(limit / 1e6),
+            "short_term" => pl2 = Some(limit / 1e6),
+            _ => (),
+        }
+    }
+    Some((pl1?, pl2?))
+}
+
+/// Read PL1 and PL2 (in watts) from the model specific registers of the first CPU
+fn read_msr_limits() -> Option<(f64, f64)> {
+    let msr = std::fs::File::open(MSR_PATH).ok()?;
+    let read_msr = |offset| {
+        let mut buf = [0; 8];
+        msr.read_exact_at(&mut buf, offset).ok()?;
+        Some(u64::from_le_bytes(buf))
+    };
+    Some(decode_msr_limits(
+        read_msr(MSR_RAPL_POWER_UNIT)?,
+        read_msr(MSR_PKG_POWER_LIMIT)?,
+    ))
+}
+
+/// Decode PL1 and PL2 (in watts) from `MSR_RAPL_POWER_UNIT` and `MSR_PKG_POWER_LIMIT`
+fn decode_msr_limits(power_unit: u64, limits: u64) -> (f64, f64) {
+    // Power is expressed in units of 1/2^n watts, where n is stored in bits 0..4
+    let unit = 0.5f64.powi((power_unit & 0xf) as i32);
+    let pl1 = (limits & 0x7fff) as f64 * unit;
+    let pl2 = ((limits >> 32) & 0x7fff) as f64 * unit;
+    (pl1, pl2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn services() {
+        let states = [
+            ("thermald", "active".to_owned()),
+            ("throttled", "inactive".to_owned()),
+            ("intel-undervolt", "active".to_owned()),
+            ("tlp", "failed".to_owned()),
+        ];
+        assert_eq!(active_services(&states), ["thermald", "intel-undervolt"]);
+        assert!(active_services(&[("thermald", "activating".to_owned())]).is_empty());
+    }
+
+    #[test]
+    fn powercap_limits() {
+        let constraint = |name: &str, limit: &str| (name.to_owned(), limit.to_owned());
+        assert_eq!(
+            parse_powercap_limits(&[
+                constraint("long_term", "15000000"),
+                constraint("short_term", "25000000"),
+            ]),
+            Some((15.0, 25.0))
+        );
+        // The order of the constraints does not matter
+        assert_eq!(
+            parse_powercap_limits(&[
+                constraint("short_term", "64000000"),
+                constraint("long_term", "28500000"),
+            ]),
+            Some((28.5, 64.0))
+        );
+        assert_eq!(
+            parse_powercap_limits(&[
+                constraint("long_term", "15000000"),
+                constraint("peak_power", "120000000"),
+            ]),
+            None
+        );
+        assert_eq!(
+            parse_powercap_limits(&[
+                constraint("long_term", "15 W"),
+                constraint("short_term", "25000000"),
+            ]),
+            None
+        );
+    }
+
+    #[test]
+    fn msr_limits() {
+        // Units of 1/8 W, PL1 of 120 units (15 W) and PL2 of 200 units (25 W), with the enable and
+        // clamp bits set
+        let limits = (0b11 << 47) | (200 << 32) | (0b11 << 15) | 120;
+        assert_eq!(decode_msr_limits(0x000a_0e03, limits), (15.0, 25.0));
+    }
+}
@@ -0,0 +1,235 @@
+//! Quick toggles for systemd user services
+//!
+//! This block shows a row of toggles for a list of systemd user services (`systemctl --user`).
+//! Clicking a service starts it if it is stopped and stops it otherwise. Each service is colored
+//! according to its state.
+//!
+//! Each service is available as a placeholder named after the service, with characters other than
+//! letters, digits, `_` and `-` replaced by `_` (e.g. `$syncthing` or `$foo_bar` for `foo@bar`). By
+//! default, all services are displayed in order.
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `services` | The names of the user services | **Required**
+//! `format` | A string to customise the output of this block. See above for available placeholders. | All services separated by spaces
+//!
+//! Service state | State
+//! --------------|------
+//! active        | Good
+//! activating, deactivating, reloading | Info
+//! failed        | Critical
+//! inactive      | Idle
+//!
+//! Action            | Default button
+//! ------------------|---------------
+//! `toggle_<service>` | Left on the service
+//!
+//! # Example
+//!
+//! Show the first letter of each service:
+//!
+//! ```toml
+//! [[block]]
+//! block = "user_services"
+//! services = ["syncthing", "mpd", "redshift"]
+//! format = " $syncthing.str(max_w:1) $mpd.str(max_w:1) $redshift.str(max_w:1) "
+//! ```
+
+use std::sync::Mutex;
+
+use zbus::zvariant::OwnedObjectPath;
+
+use super::prelude::*;
+use super::service_status::{unit_proxy, UnitProxy};
+
+type DefaultActions = &'static [(MouseButton, Option<&'static str>, &'static str)];
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub services: Vec<String>,
+    #[serde(default)]
+    pub format: FormatConfig,
+}
+
+struct Service {
+    name: String,
+    /// The name of the placeholder and the widget instance
+    key: &'static str,
+    action: &'static str,
+    proxy: UnitProxy<'static>,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+
+    let dbus_conn = new_dbus_connection().await?;
+    let manager = ManagerProxy::new(&dbus_conn)
+        .await
+        .error("Failed to create ManagerProxy")?;
+
+    let mut services = Vec::with_capacity(config.services.len());
+    for name in &config.services {
+        let key = intern(&placeholder_name(name));
+        services.push(Service {
+            name: name.clone(),
+            key,
+            action: intern(&format!("toggle_{key}")),
+            proxy: unit_proxy(&dbus_conn, name).await?,
+        });
+    }
+    api.set_default_actions(default_actions(&services))?;
+
+    let format = config
+        .format
+        .with_default(&default_format(services.iter().map(|s| s.key)))?;
+
+    let mut changes = futures::stream::select_all(
+        futures::future::join_all(
+            services
+                .iter()
+                .map(|s| s.proxy.receive_active_state_changed()),
+        )
+        .await,
+    );
+
+    loop {
+        let mut values = Values::new();
+        for service in &services {
+            let state = service
+                .proxy
+                .active_state()
+                .await
+                .or_error(|| format!("Could not get the state of '{}'", service.name))?;
+            values.insert(
+                service.key,
+                Value::text(service.name.clone())
+                    .with_instance(service.key)
+                    .with_state(service_state(&state)),
+            );
+        }
+
+        let mut widget = Widget::new().with_format(format.clone());
+        widget.set_values(values);
+        api.set_widget(widget)?;
+
+        select! {
+            _ = changes.next() => (),
+            _ = api.wait_for_update_request() => (),
+            Some(action) = actions.recv() => {
+                let Some(service) = services.iter().find(|s| s.action == action) else {
+                    continue;
+                };
+                let unit = format!("{}.service", service.name);
+                let running = service
+                    .proxy
+                    .active_state()
+                    .await
+                    .is_ok_and(|state| is_running(&state));
+                if running {
+                    manager.stop_unit(&unit, "replace").await
+                } else {
+                    manager.start_unit(&unit, "replace").await
+                }
+                .or_error(|| format!("Failed to toggle '{unit}'"))?;
+            }
+        }
+    }
+}
+
+/// The state of a widget for the `ActiveState` of a unit
+fn service_state(active_state: &str) -> State {
+    match active_state {
+        "active" => State::Good,
+        "activating" | "deactivating" | "reloading" => State::Info,
+        "failed" => State::Critical,
+        _ => State::Idle,
+    }
+}
+
+/// Whether toggling a unit in this `ActiveState` should stop it rather than start it
+fn is_running(active_state: &str) -> bool {
+    active_state == "active" || active_state == "activating"
+}
+
+/// The default format, which shows all services separated by spaces
+fn default_format<'a>(keys: impl Iterator<Item = &'a str>) -> String {
+    keys.fold(String::from(" "), |mut f, key| {
+        let _ = write!(f, "${key} ");
+        f
+    })
+}
+
+/// Make a valid placeholder name from a service name
+fn placeholder_name(service: &str) -> String {
+    service
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Default actions must be `'static`, so they are leaked, but only once for each list of services
+fn default_actions(services: &[Service]) -> DefaultActions {
+    static CACHE: LazyLock<Mutex<HashMap<Vec<&'static str>, DefaultActions>>> =
+        LazyLock::new(Default::default);
+    let keys: Vec<_> = services.iter().map(|s| s.key).collect();
+    CACHE.lock().unwrap().entry(keys).or_insert_with(|| {
+        services
+            .iter()
+            .map(|s| (MouseButton::Left, Some(s.key), s.action))
+            .collect::<Vec<_>>()
+            .leak()
+    })
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+trait Manager {
+    fn start_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+    fn stop_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn states() {
+        assert_eq!(service_state("active"), State::Good);
+        assert_eq!(service_state("activating"), State::Info);
+        assert_eq!(service_state("deactivating"), State::Info);
+        assert_eq!(service_state("reloading"), State::Info);
+        assert_eq!(service_state("failed"), State::Critical);
+        assert_eq!(service_state("inactive"), State::Idle);
+        assert_eq!(service_state("maintenance"), State::Idle);
+
+        assert!(is_running("active"));
+        assert!(is_running("activating"));
+        assert!(!is_running("deactivating"));
+        assert!(!is_running("failed"));
+        assert!(!is_running("inactive"));
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(placeholder_name("syncthing"), "syncthing");
+        assert_eq!(placeholder_name("pipewire-pulse"), "pipewire-pulse");
+        assert_eq!(placeholder_name("foo@bar.baz"), "foo_bar_baz");
+        assert_eq!(
+            default_format(["syncthing", "pipewire-pulse"].into_iter()),
+            " $syncthing $pipewire-pulse "
+        );
+        assert_eq!(default_format(std::iter::empty()), " ");
+    }
+}
@@ -0,0 +1,433 @@
+//! A block implemented by a WebAssembly module
+//!
+//! This block loads a `.wasm` module and lets it drive the block through a small host API. This
+//! allows third-party blocks to be distributed as sandboxed artifacts which do not require
+//! recompiling i3status-rs. The module is reloaded automatically whenever the file changes.
+//!
+//! Note that you need to enable `wasm` feature to use this block:
+//! ```sh
+//! cargo build --release --features wasm
+//! ```
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `format` | A string to customise the output of this block. See below for available placeholders. | `" $text "`
+//! `path` | Path to the `.wasm` module. Supports path expansions e.g. `~`. | **Required**
+//! `interval` | How often to call the module's `update` function (in seconds, or "once") | `10`
+//! `options` | A table of strings which the module can read with `get_option` | `{}`
+//! `fuel` | Maximum number of instructions a single call into the module may execute | `10000000`
+//!
+//! Placeholder | Value                                                      | Type | Unit
+//! ------------|------------------------------------------------------------|------|-----
+//! `text`      | The text set by `set_text`                                 | Text | -
+//! Any other   | Placeholders set by `set_value`                            | Text | -
+//!
+//! Action   | Default button
+//! ---------|---------------
+//! `left`   | Left
+//! `middle` | Middle
+//! `right`  | Right
+//! `up`     | Wheel Up
+//! `down`   | Wheel Down
+//!
+//! # Host API
+//!
+//! The module must export its linear memory as `memory` and a function `update()` which is called
+//! on start and on every update. To receive clicks, it may also export `alloc(len: i32) -> i32`,
+//! which returns a buffer of the requested size, and `click(ptr: i32, len: i32)`, which receives
+//! the name of the triggered action. Strings are passed as UTF-8 pointer/length pairs into the
+//! module's memory.
+//!
+//! The following functions are provided in the `i3status` import module:
+//!
+//! Function | Description
+//! ---------|------------
+//! `set_text(ptr: i32, len: i32)` | Set the `text` placeholder
+//! `set_value(key_ptr: i32, key_len: i32, val_ptr: i32, val_len: i32)` | Set an arbitrary placeholder
+//! `unset_value(key_ptr: i32, key_len: i32)` | Remove a placeholder
+//! `set_state(state: i32)` | Set the state: `0` - idle, `1` - info, `2` - good, `3` - warning, `4` - critical
+//! `get_option(key_ptr: i32, key_len: i32, buf_ptr: i32, buf_len: i32) -> i32` | Copy the value of an option into the buffer and return its length. Returns `-1` if the option is not set. Nothing is copied if the buffer is too small.
+//!
+//! # Example
+//!
+//! ```toml
+//! [[block]]
+//! block = "wasm"
+//! path = "~/.config/i3status-rust/plugins/hello.wasm"
+//! format = " $text ($greeting) "
+//! interval = 5
+//! [block.options]
+//! name = "world"
+//! ```
+
+use super::prelude::*;
+use crate::formatting::intern::Istr;
+use crate::formatting::Format;
+use inotify::{Inotify, WatchMask};
+use std::path::Path;
+use wasmi::{Caller, Engine, Extern, Func, Linker, Module, Store, TypedFunc};
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub format: FormatConfig,
+    pub path: ShellString,
+    #[serde(default = "default_interval")]
+    pub interval: Seconds,
+    #[serde(default)]
+    pub options: HashMap<String, String>,
+    #[serde(default = "default_fuel")]
+    pub fuel: u64,
+}
+
+fn default_interval() -> Seconds {
+    10.into()
+}
+
+fn default_fuel() -> u64 {
+    10_000_000
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    api.set_default_actions(&[
+        (MouseButton::Left, None, "left"),
+        (MouseButton::Middle, None, "middle"),
+        (MouseButton::Right, None, "right"),
+        (MouseButton::WheelUp, None, "up"),
+        (MouseButton::WheelDown, None, "down"),
+    ])?;
+
+    let format = config.format.with_default(" $text ")?;
+    let path = config.path.expand()?;
+
+    // Editors and compilers often replace the file instead of writing to it, so watch the
+    // directory rather than the inode of the module
+    let file = Path::new(&*path);
+    let dir = match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file_name = file.file_name().error("path is not a file")?.to_owned();
+    let notify = Inotify::init().error("Failed to start inotify")?;
+    notify
+        .watches()
+        .add(dir, WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO)
+        .error("Failed to watch the directory of the module")?;
+    let mut file_updates = notify
+        .into_event_stream([0; 1024])
+        .error("Failed to create event stream")?;
+
+    let mut timer = config.interval.timer();
+    let mut actions = api.get_actions()?;
+
+    let mut plugin = Plugin::load(&path, config).await?;
+    plugin.update()?;
+
+    loop {
+        api.set_widget(plugin.widget(format.clone()))?;
+
+        select! {
+            _ = timer.tick() => plugin.update()?,
+            _ = api.wait_for_update_request() => plugin.update()?,
+            Some(update) = file_updates.next() => {
+                let update = update.error("Bad inotify update")?;
+                if update.name.is_some_and(|name| name == file_name) {
+                    plugin = Plugin::load(&path, config).await?;
+                    plugin.update()?;
+                }
+            }
+            Some(action) = actions.recv() => plugin.click(&action)?,
+        }
+    }
+}
+
+struct HostState {
+    values: Values,
+    state: State,
+    options: HashMap<String, String>,
+}
+
+struct Plugin {
+    store: Store<HostState>,
+    update: TypedFunc<(), ()>,
+    click: Option<TypedFunc<(i32, i32), ()>>,
+    alloc: Option<TypedFunc<i32, i32>>,
+    memory: wasmi::Memory,
+    fuel: u64,
+}
+
+impl Plugin {
+    async fn load(path: &str, config: &Config) -> Result<Self> {
+        let bytes = tokio::fs::read(path)
+            .await
+            .or_error(|| format!("Failed to read '{path}'"))?;
+        Self::new(&bytes, config)
+    }
+
+    fn new(bytes: &[u8], config: &Config) -> Result<Self> {
+        let mut engine_config = wasmi::Config::default();
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config);
+        let module = Module::new(&engine, bytes).error("Failed to compile the module")?;
+
+        let mut store = Store::new(
+            &engine,
+            HostState {
+                values: Values::new(),
+                state: State::Idle,
+                options: config.options.clone(),
+            },
+        );
+        store
+            .set_fuel(config.fuel)
+            .map_err(|e| Error::new(format!("Failed to set fuel: {e}")))?;
+
+        let mut linker = <Linker<HostState>>::new(&engine);
+        link_host_api(&mut linker).error("Failed to link host functions")?;
+
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .error("Failed to instantiate the module")?;
+
+        let memory = instance
+            .get_memory(&store, "memory")
+            .error("The module does not export 'memory'")?;
+        let update = instance
+            .get_typed_func(&store, "update")
+            .error("The module does not export 'update()'")?;
+        let click = instance
+            .get_func(&store, "click")
+            .map(|f: Func| f.typed(&store))
+            .transpose()
+            .error("'click' export has wrong signature")?;
+        let alloc = instance
+            .get_func(&store, "alloc")
+            .map(|f: Func| f.typed(&store))
+            .transpose()
+            .error("'alloc' export has wrong signature")?;
+
+        Ok(Self {
+            store,
+            update,
+            click,
+            alloc,
+            memory,
+            fuel: config.fuel,
+        })
+    }
+
+    fn refuel(&mut self) -> Result<()> {
+        self.store
+            .set_fuel(self.fuel)
+            .map_err(|e| Error::new(format!("Failed to set fuel: {e}")))
+    }
+
+    fn update(&mut self) -> Result<()> {
+        self.refuel()?;
+        self.update
+            .call(&mut self.store, ())
+            .error("'update' trapped")
+    }
+
+    fn click(&mut self, action: &str) -> Result<()> {
+        let (Some(click), Some(alloc)) = (self.click, self.alloc) else {
+            return Ok(());
+        };
+        self.refuel()?;
+        let len = action.len() as i32;
+        let ptr = alloc
+            .call(&mut self.store, len)
+            .error("'alloc' trapped")?;
+        self.memory
+            .write(&mut self.store, ptr as usize, action.as_bytes())
+            .map_err(|_| Error::new("'alloc' returned an invalid pointer"))?;
+        click
+            .call(&mut self.store, (ptr, len))
+            .error("'click' trapped")
+    }
+
+    fn widget(&self, format: Format) -> Widget {
+        let host = self.store.data();
+        let mut widget = Widget::new().with_format(format).with_state(host.state);
+        widget.set_values(host.values.clone());
+        widget
+    }
+}
+
+fn read_str(caller: &Caller<'_, HostState>, ptr: i32, len: i32) -> Result<String, wasmi::Error> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| wasmi::Error::new("missing 'memory' export"))?;
+    let bytes = memory
+        .data(caller)
+        .get(ptr as usize..)
+        .and_then(|data| data.get(..len as usize))
+        .ok_or_else(|| wasmi::Error::new("string is out of bounds"))?;
+    String::from_utf8(bytes.to_vec()).map_err(|_| wasmi::Error::new("string is not valid UTF-8"))
+}
+
+fn link_host_api(linker: &mut Linker<HostState>) -> Result<(), wasmi::errors::LinkerError> {
+    linker.func_wrap(
+        "i3status",
+        "set_text",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> Result<(), wasmi::Error> {
+            let text = read_str(&caller, ptr, len)?;
+            caller.data_mut().values.insert("text", Value::text(text));
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "i3status",
+        "set_value",
+        |mut caller: Caller<'_, HostState>,
+         key_ptr: i32,
+         key_len: i32,
+         val_ptr: i32,
+         val_len: i32|
+         -> Result<(), wasmi::Error> {
+            let key = read_str(&caller, key_ptr, key_len)?;
+            let val = read_str(&caller, val_ptr, val_len)?;
+            // A placeholder which no format uses cannot be displayed, and interning its name
+            // would leak it
+            if let Some(key) = Istr::lookup(&key) {
+                caller.data_mut().values.insert(key.as_str(), Value::text(val));
+            }
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "i3status",
+        "unset_value",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> Result<(), wasmi::Error> {
+            let key = read_str(&caller, ptr, len)?;
+            caller.data_mut().values.remove(key.as_str());
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "i3status",
+        "set_state",
+        |mut caller: Caller<'_, HostState>, state: i32| {
+            caller.data_mut().state = match state {
+                1 => State::Info,
+                2 => State::Good,
+                3 => State::Warning,
+                4 => State::Critical,
+                _ => State::Idle,
+            };
+        },
+    )?;
+    linker.func_wrap(
+        "i3status",
+        "get_option",
+        |mut caller: Caller<'_, HostState>,
+         key_ptr: i32,
+         key_len: i32,
+         buf_ptr: i32,
+         buf_len: i32|
+         -> Result<i32, wasmi::Error> {
+            let key = read_str(&caller, key_ptr, key_len)?;
+            let Some(value) = caller.data().options.get(&key).cloned() else {
+                return Ok(-1);
+            };
+            if value.len() <= buf_len as usize {
+                let memory = caller
+                    .get_export("memory")
+                    .and_then(Extern::into_memory)
+                    .ok_or_else(|| wasmi::Error::new("missing 'memory' export"))?;
+                memory
+                    .write(&mut caller, buf_ptr as usize, value.as_bytes())
+                    .map_err(|_| wasmi::Error::new("buffer is out of bounds"))?;
+            }
+            Ok(value.len() as i32)
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULE: &str = r#"
+        (module
+            (import "i3status" "set_text" (func $set_text (param i32 i32)))
+            (import "i3status" "set_value" (func $set_value (param i32 i32 i32 i32)))
+            (import "i3status" "set_state" (func $set_state (param i32)))
+            (import "i3status" "get_option" (func $get_option (param i32 i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "name")
+            (data (i32.const 8) "greeting")
+            (data (i32.const 16) "hello")
+            (func (export "update")
+                (call $set_text
+                    (i32.const 32)
+                    (call $get_option (i32.const 0) (i32.const 4) (i32.const 32) (i32.const 32)))
+                (call $set_value (i32.const 8) (i32.const 8) (i32.const 16) (i32.const 5))
+                (call $set_state (i32.const 2)))
+            (func (export "alloc") (param i32) (result i32)
+                (i32.const 64))
+            (func (export "click") (param $ptr i32) (param $len i32)
+                (call $set_text (local.get $ptr) (local.get $len))
+                (call $set_state (i32.const 4))))
+    "#;
+
+    fn config(fuel: u64) -> Config {
+        Config {
+            format: default(),
+            path: ShellString::new("plugin.wasm"),
+            interval: default_interval(),
+            options: HashMap::from([("name".into(), "world".into())]),
+            fuel,
+        }
+    }
+
+    fn text(plugin: &Plugin) -> Option<String> {
+        match &plugin.store.data().values.get("text")?.inner {
+            crate::formatting::value::ValueInner::Text(text) => Some(text.to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn host_api() {
+        let _ = intern("greeting");
+        let bytes = wat::parse_str(MODULE).unwrap();
+        let mut plugin = Plugin::new(&bytes, &config(default_fuel())).unwrap();
+        assert_eq!(text(&plugin), None);
+        assert_eq!(plugin.store.data().state, State::Idle);
+
+        plugin.update().unwrap();
+        assert_eq!(text(&plugin).as_deref(), Some("world"));
+        assert!(plugin.store.data().values.contains_key("greeting"));
+        assert_eq!(plugin.store.data().state, State::Good);
+
+        plugin.click("left").unwrap();
+        assert_eq!(text(&plugin).as_deref(), Some("left"));
+        assert_eq!(plugin.store.data().state, State::Critical);
+    }
+
+    #[test]
+    fn exports() {
+        let bytes = wat::parse_str(r#"(module (memory (export "memory") 1))"#).unwrap();
+        assert!(Plugin::new(&bytes, &config(default_fuel())).is_err());
+        let bytes = wat::parse_str(r#"(module (func (export "update")))"#).unwrap();
+        assert!(Plugin::new(&bytes, &config(default_fuel())).is_err());
+        assert!(Plugin::new(b"not wasm", &config(default_fuel())).is_err());
+    }
+
+    #[test]
+    fn fuel() {
+        let bytes = wat::parse_str(
+            r#"(module (memory (export "memory") 1) (func (export "update") (loop (br 0))))"#,
+        )
+        .unwrap();
+        let mut plugin = Plugin::new(&bytes, &config(1000)).unwrap();
+        assert!(plugin.update().is_err());
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! This block displays local weather and temperature information. In order to use this block, you
 //! will need access to a supported weather API service. At the time of writing, OpenWeatherMap,
-//! met.no, and the US National Weather Service are supported.
+//! met.no, Open-Meteo, and the US National Weather Service are supported.
 //!
 //! Configuring this block requires configuring a weather service, which may require API keys and
 //! other parameters.
@@ -19,6 +19,10 @@
 //! `interval` | Update interval, in seconds. | `600`
 //! `autolocate` | Gets your location using the ipapi.co IP location service (no API key required). If the API call fails then the block will fallback to service specific location config. | `false`
 //! `autolocate_interval` | Update interval for `autolocate` in seconds or "once" | `interval`
+//! `autolocate_service` | Where `autolocate` gets the location from: `"ipapi"`, or `"geoclue"` to locate the computer with [GeoClue](https://gitlab.freedesktop.org/geoclue/geoclue) without an online service. GeoClue has to allow i3status-rs to access the location, e.g. with an entry `[i3status-rs]` with `allowed=true`, `system=false` and `users=` in `/etc/geoclue/geoclue.conf`. | `"ipapi"`
+//! `rain_threshold` | The precipitation (in mm/h) from which the nowcast considers it raining | `0.1`
+//! `rain_warning` | How long (in seconds) before the rain starts the state of the block becomes Warning | `900`
+//! `hints` | Rules for the `$hint` placeholder, see [Hints](#hints) | An umbrella and a sunscreen rule
 //!
 //! # OpenWeatherMap Options
 //!
@@ -44,6 +48,9 @@
 //!
 //! Forecasts are only fetched if forecast_hours > 0 and the format has keys related to forecast.
 //!
+//! The `rain_in` nowcast uses the minutely forecast of the [One Call API 3.0](https://openweathermap.org/api/one-call-3),
+//! which requires a separate subscription.
+//!
 //! # met.no Options
 //!
 //! Key | Values | Required | Default
@@ -56,6 +63,20 @@
 //!
 //! Met.no does not support location name, but if autolocate is enabled then autolocate's city value is used.
 //!
+//! # Open-Meteo Options
+//!
+//! [Open-Meteo](https://open-meteo.com/) requires no API key and provides a nowcast in 15 minute
+//! steps.
+//!
+//! Key | Values | Required | Default
+//! ----|--------|----------|--------
+//! `name` | `openmeteo`. | Yes | None
+//! `coordinates` | GPS latitude longitude coordinates as a tuple, example: `["39.2362","9.3317"]` | Required if `autolocate = false` | None
+//! `forecast_hours` | How many hours should be forecast | No | 12
+//! `units` | Either `"metric"` or `"imperial"`. | No | `"metric"`
+//!
+//! Open-Meteo does not support location name, but if autolocate is enabled then autolocate's city value is used.
+//!
 //! # US National Weather Service Options
 //!
 //! Key | Values | Required | Default
@@ -84,6 +105,8 @@
 //! `direction{,_{favg,fmin,fmax,ffin}}`         | Wind direction, e.g. "NE"                                                     | Text     | -
 //! `sunrise`                                    | Time of sunrise                                                               | DateTime | -
 //! `sunset`                                     | Time of sunset                                                                | DateTime | -
+//! `hint`                                       | The hints of all rules which match the weather, separated by spaces. Absent if none matches. | Text | -
+//! `rain_in`                                    | Time until it starts raining (zero while it rains), if rain is expected within the nowcast (the next hour with OpenWeatherMap, two hours with Open-Meteo) | Duration | -
 //!
 //! You can use the suffixes noted above to get the following:
 //!
@@ -95,6 +118,37 @@
 //! `_fmax`   | Maximum forecast value
 //! `_ffin`   | Final forecast value
 //!
+//! The `rain_in` nowcast is only fetched if the format contains it, and is only available from
+//! OpenWeatherMap and Open-Meteo. The block is updated every minute while rain is expected.
+//!
+//! # Hints
+//!
+//! The `$hint` placeholder suggests what to take along, based on the current weather and the
+//! forecast of the next `forecast_hours`. Each rule in `hints` has a `hint` text and conditions,
+//! all of which must be met for the rule to match:
+//!
+//! Key | Condition
+//! ----|----------
+//! `rain_probability_above` | The highest probability of precipitation (in %) is above this value
+//! `uv_index_above` | The highest UV index is above this value
+//! `temp_above` | The highest apparent temperature is above this value
+//! `temp_below` | The lowest apparent temperature is below this value
+//!
+//! Temperatures are in the units of the service. The probability of precipitation is provided by
+//! Open-Meteo, OpenWeatherMap and the US National Weather Service, and the UV index only by
+//! Open-Meteo. Rules with conditions on values the service does not provide never match.
+//!
+//! By default, there are two rules:
+//!
+//! ```toml
+//! [[block.hints]]
+//! hint = "umbrella"
+//! rain_probability_above = 40
+//! [[block.hints]]
+//! hint = "sunscreen"
+//! uv_index_above = 5
+//! ```
+//!
 //! Action          | Description                               | Default button
 //! ----------------|-------------------------------------------|---------------
 //! `toggle_format` | Toggles between `format` and `format_alt` | Left
@@ -116,6 +170,34 @@
 //! forecast_hours = 9
 //! ```
 //!
+//! Warn about rain in the next two hours, e.g. "rain in 12m":
+//!
+//! ```toml
+//! [[block]]
+//! block = "weather"
+//! format = " $icon $temp {rain in $rain_in.duration(min_unit:m) |}"
+//! [block.service]
+//! name = "openmeteo"
+//! coordinates = ["52.52", "13.41"]
+//! ```
+//!
+//! Suggest a coat and an umbrella:
+//!
+//! ```toml
+//! [[block]]
+//! block = "weather"
+//! format = " $icon $temp{ ($hint)|} "
+//! [block.service]
+//! name = "openmeteo"
+//! coordinates = ["52.52", "13.41"]
+//! [[block.hints]]
+//! hint = "coat"
+//! temp_below = 8
+//! [[block.hints]]
+//! hint = "umbrella"
+//! rain_probability_above = 50
+//! ```
+//!
 //! Show sunrise and sunset times in null island
 //!
 //! ```toml
@@ -154,6 +236,7 @@ use super::prelude::*;
 
 pub mod met_no;
 pub mod nws;
+pub mod open_meteo;
 pub mod open_weather_map;
 
 const IP_API_URL: &str = "https://ipapi.co/json";
@@ -172,18 +255,95 @@ pub struct Config {
     #[serde(default)]
     pub autolocate: bool,
     pub autolocate_interval: Option<Seconds>,
+    #[serde(default)]
+    pub autolocate_service: AutolocateService,
+    #[serde(default = "default_rain_threshold")]
+    pub rain_threshold: f64,
+    #[serde(default = "default_rain_warning")]
+    pub rain_warning: Seconds,
+    #[serde(default = "default_hints")]
+    pub hints: Vec<HintRule>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct HintRule {
+    pub hint: String,
+    pub rain_probability_above: Option<f64>,
+    pub uv_index_above: Option<f64>,
+    pub temp_above: Option<f64>,
+    pub temp_below: Option<f64>,
+}
+
+impl HintRule {
+    fn matches(&self, weather: &HintWeather) -> bool {
+        let above = |threshold: Option<f64>, value: Option<f64>| {
+            threshold.is_none_or(|threshold| value.is_some_and(|value| value > threshold))
+        };
+        above(self.rain_probability_above, weather.rain_probability)
+            && above(self.uv_index_above, weather.uv_index)
+            && above(self.temp_above, Some(weather.temp_max))
+            && self
+                .temp_below
+                .is_none_or(|threshold| weather.temp_min < threshold)
+    }
+}
+
+/// The weather the hint rules are evaluated on
+struct HintWeather {
+    temp_min: f64,
+    temp_max: f64,
+    rain_probability: Option<f64>,
+    uv_index: Option<f64>,
 }
 
 fn default_interval() -> Seconds {
     Seconds::new(600)
 }
 
+fn default_rain_threshold() -> f64 {
+    0.1
+}
+
+fn default_rain_warning() -> Seconds {
+    Seconds::new(900)
+}
+
+fn default_hints() -> Vec<HintRule> {
+    let rule = |hint: &str| HintRule {
+        hint: hint.into(),
+        rain_probability_above: None,
+        uv_index_above: None,
+        temp_above: None,
+        temp_below: None,
+    };
+    vec![
+        HintRule {
+            rain_probability_above: Some(40.0),
+            ..rule("umbrella")
+        },
+        HintRule {
+            uv_index_above: Some(5.0),
+            ..rule("sunscreen")
+        },
+    ]
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AutolocateService {
+    #[default]
+    Ipapi,
+    Geoclue,
+}
+
 #[async_trait]
 trait WeatherProvider {
     async fn get_weather(
         &self,
         autolocated_location: Option<&Coordinates>,
         need_forecast: bool,
+        need_nowcast: bool,
     ) -> Result<WeatherResult>;
 }
 
@@ -192,6 +352,7 @@ trait WeatherProvider {
 pub enum WeatherService {
     OpenWeatherMap(open_weather_map::Config),
     MetNo(met_no::Config),
+    OpenMeteo(open_meteo::Config),
     Nws(nws::Config),
 }
 
@@ -265,19 +426,44 @@ struct ForecastAggregateSegment {
     wind: Option<f64>,
     wind_kmh: Option<f64>,
     wind_direction: Option<f64>,
+    /// In percent
+    rain_probability: Option<f64>,
+    uv_index: Option<f64>,
 }
 
 struct WeatherResult {
     location: String,
     current_weather: WeatherMoment,
     forecast: Option<Forecast>,
+    nowcast: Option<Nowcast>,
     sunrise: DateTime<Utc>,
     sunset: DateTime<Utc>,
 }
 
 impl WeatherResult {
-    fn into_values(self) -> Values {
+    fn hint_weather(&self) -> HintWeather {
+        let current = self.current_weather.apparent;
+        let forecast = self.forecast.as_ref();
+        HintWeather {
+            temp_min: forecast.map_or(current, |f| f.min.apparent.min(current)),
+            temp_max: forecast.map_or(current, |f| f.max.apparent.max(current)),
+            rain_probability: forecast.and_then(|f| f.rain_probability),
+            uv_index: forecast.and_then(|f| f.uv_index),
+        }
+    }
+
+    /// The values of all placeholders but `rain_in`, which changes until the next update
+    fn into_values(self, hints: &[HintRule]) -> Values {
+        let weather = self.hint_weather();
+        let hint = hints
+            .iter()
+            .filter(|rule| rule.matches(&weather))
+            .map(|rule| rule.hint.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+
         let mut values = map! {
+            [if !hint.is_empty()] "hint" => Value::text(hint),
             "location" => Value::text(self.location),
             //current_weather
             "icon" => Value::icon(self.current_weather.icon.to_icon_str()),
@@ -326,11 +512,32 @@ impl WeatherResult {
     }
 }
 
+/// The precipitation in the near future
+struct Nowcast {
+    /// The start of each step and the precipitation during it, in mm/h
+    precipitation: Vec<(DateTime<Utc>, f64)>,
+    step: chrono::TimeDelta,
+}
+
+impl Nowcast {
+    /// How long until the precipitation reaches `threshold`, if it does within the nowcast
+    fn rain_in(&self, now: DateTime<Utc>, threshold: f64) -> Option<Duration> {
+        self.precipitation
+            .iter()
+            .find(|&&(start, precipitation)| precipitation >= threshold && start + self.step > now)
+            .map(|&(start, _)| (start - now).to_std().unwrap_or_default())
+    }
+}
+
 struct Forecast {
     avg: ForecastAggregate,
     min: ForecastAggregate,
     max: ForecastAggregate,
     fin: WeatherMoment,
+    /// The highest probability of precipitation, in percent
+    rain_probability: Option<f64>,
+    /// The highest UV index
+    uv_index: Option<f64>,
 }
 
 impl Forecast {
@@ -434,7 +641,17 @@ impl Forecast {
             wind_kmh: wind_kmh_avg,
             wind_direction: wind_direction_avg,
         };
-        Self { avg, min, max, fin }
+        let highest = |value: fn(&ForecastAggregateSegment) -> Option<f64>| {
+            data.iter().filter_map(value).reduce(f64::max)
+        };
+        Self {
+            avg,
+            min,
+            max,
+            fin,
+            rain_probability: highest(|s| s.rain_probability),
+            uv_index: highest(|s| s.uv_index),
+        }
     }
 }
 
@@ -450,6 +667,9 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
 
     let provider: Box<dyn WeatherProvider + Send + Sync> = match &config.service {
         WeatherService::MetNo(service_config) => Box::new(met_no::Service::new(service_config)?),
+        WeatherService::OpenMeteo(service_config) => {
+            Box::new(open_meteo::Service::new(service_config))
+        }
         WeatherService::OpenWeatherMap(service_config) => {
             Box::new(open_weather_map::Service::new(config.autolocate, service_config).await?)
         }
@@ -460,28 +680,60 @@ pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
 
     let autolocate_interval = config.autolocate_interval.unwrap_or(config.interval);
     let need_forecast = need_forecast(&format, format_alt.as_ref());
+    let need_nowcast = format.contains_key("rain_in")
+        || format_alt.as_ref().is_some_and(|f| f.contains_key("rain_in"));
 
     let mut timer = config.interval.timer();
+    // Keeps `rain_in` up to date between updates
+    let mut nowcast_timer = Seconds::<false>::new(60).timer();
 
     loop {
         let location = if config.autolocate {
-            let fetch = || find_ip_location(autolocate_interval.0);
-            Some(fetch.retry(ExponentialBuilder::default()).await?)
+            Some(match config.autolocate_service {
+                AutolocateService::Ipapi => {
+                    let fetch = || find_ip_location(autolocate_interval.0);
+                    fetch.retry(ExponentialBuilder::default()).await?
+                }
+                AutolocateService::Geoclue => {
+                    let location = api.get_location().await?;
+                    Coordinates {
+                        latitude: location.latitude,
+                        longitude: location.longitude,
+                        city: if location.description.is_empty() {
+                            "Unknown".into()
+                        } else {
+                            location.description
+                        },
+                    }
+                }
+            })
         } else {
             None
         };
 
-        let fetch = || provider.get_weather(location.as_ref(), need_forecast);
-        let data = fetch.retry(ExponentialBuilder::default()).await?;
-        let data_values = data.into_values();
+        let fetch = || provider.get_weather(location.as_ref(), need_forecast, need_nowcast);
+        let mut data = fetch.retry(ExponentialBuilder::default()).await?;
+        let nowcast = data.nowcast.take();
+        let data_values = data.into_values(&config.hints);
 
         loop {
             let mut widget = Widget::new().with_format(format.clone());
-            widget.set_values(data_values.clone());
+            let mut values = data_values.clone();
+            let rain_in = nowcast
+                .as_ref()
+                .and_then(|nowcast| nowcast.rain_in(Utc::now(), config.rain_threshold));
+            if let Some(rain_in) = rain_in {
+                values.insert("rain_in", Value::duration(rain_in));
+                if rain_in <= config.rain_warning.0 {
+                    widget.state = State::Warning;
+                }
+            }
+            widget.set_values(values);
             api.set_widget(widget)?;
 
             select! {
                 _ = timer.tick() => break,
+                _ = nowcast_timer.tick(), if rain_in.is_some() => (),
                 _ = api.wait_for_update_request() => break,
                 Some(action) = actions.recv() => match action.as_ref() {
                         "toggle_format" => {
@@ -516,11 +768,12 @@ fn need_forecast(format: &Format, format_alt: Option<&Format>) -> bool {
             || format.contains_key("icon_ffin")
             || format.contains_key("weather_ffin")
             || format.contains_key("weather_verbose_ffin")
+            || format.contains_key("hint")
     }
     has_forecast_key(format) || format_alt.is_some_and(has_forecast_key)
 }
 
-fn calculate_sunrise_sunset(
+pub(super) fn calculate_sunrise_sunset(
     lat: f64,
     lon: f64,
     altitude: Option<f64>,
@@ -649,6 +902,67 @@ fn australian_apparent_temp(temp: f64, humidity: f64, wind_speed: f64) -> f64 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn nowcast_rain_in() {
+        let start = DateTime::<Utc>::from_timestamp(0, 0).unwrap();
+        let minutes = chrono::TimeDelta::minutes;
+        let nowcast = Nowcast {
+            precipitation: vec![
+                (start, 0.0),
+                (start + minutes(15), 0.05),
+                (start + minutes(30), 0.4),
+                (start + minutes(45), 0.0),
+            ],
+            step: minutes(15),
+        };
+        let rain_in = |now: chrono::TimeDelta| nowcast.rain_in(start + now, 0.1);
+        assert_eq!(rain_in(minutes(0)), Some(Duration::from_secs(30 * 60)));
+        assert_eq!(rain_in(minutes(20)), Some(Duration::from_secs(10 * 60)));
+        // It is raining
+        assert_eq!(rain_in(minutes(40)), Some(Duration::ZERO));
+        assert_eq!(rain_in(minutes(45)), None);
+        assert_eq!(nowcast.rain_in(start, 1.0), None);
+    }
+
+    #[test]
+    fn hint_rules() {
+        let weather = HintWeather {
+            temp_min: 4.0,
+            temp_max: 12.0,
+            rain_probability: Some(60.0),
+            uv_index: None,
+        };
+        let rule = |hint: &str| HintRule {
+            hint: hint.into(),
+            rain_probability_above: None,
+            uv_index_above: None,
+            temp_above: None,
+            temp_below: None,
+        };
+        assert!(HintRule {
+            temp_below: Some(8.0),
+            ..rule("coat")
+        }
+        .matches(&weather));
+        assert!(!HintRule {
+            temp_above: Some(25.0),
+            ..rule("shorts")
+        }
+        .matches(&weather));
+        assert!(HintRule {
+            rain_probability_above: Some(40.0),
+            temp_above: Some(10.0),
+            ..rule("umbrella")
+        }
+        .matches(&weather));
+        // The UV index is not known
+        assert!(!HintRule {
+            uv_index_above: Some(5.0),
+            ..rule("sunscreen")
+        }
+        .matches(&weather));
+    }
+
     #[test]
     fn test_new_forecast_average_wind_speed() {
         let mut degrees = 0.0;
@@ -662,6 +976,8 @@ mod tests {
                         wind: Some(1.0),
                         wind_kmh: Some(3.6),
                         wind_direction: Some(degrees),
+                        rain_probability: None,
+                        uv_index: None,
                     },
                     ForecastAggregateSegment {
                         temp: None,
@@ -670,6 +986,8 @@ mod tests {
                         wind: Some(2.0),
                         wind_kmh: Some(7.2),
                         wind_direction: Some(degrees),
+                        rain_probability: None,
+                        uv_index: None,
                     },
                 ],
                 WeatherMoment::default(),
@@ -697,6 +1015,8 @@ mod tests {
                         wind: Some(1.0),
                         wind_kmh: Some(3.6),
                         wind_direction: Some(low),
+                        rain_probability: None,
+                        uv_index: None,
                     },
                     ForecastAggregateSegment {
                         temp: None,
@@ -705,6 +1025,8 @@ mod tests {
                         wind: Some(1.0),
                         wind_kmh: Some(3.6),
                         wind_direction: Some(high),
+                        rain_probability: None,
+                        uv_index: None,
                     },
                 ],
                 WeatherMoment::default(),
@@ -732,6 +1054,8 @@ mod tests {
                         wind: Some(1.0),
                         wind_kmh: Some(3.6),
                         wind_direction: Some(low),
+                        rain_probability: None,
+                        uv_index: None,
                     },
                     ForecastAggregateSegment {
                         temp: None,
@@ -740,6 +1064,8 @@ mod tests {
                         wind: Some(2.0),
                         wind_kmh: Some(7.2),
                         wind_direction: Some(high),
+                        rain_probability: None,
+                        uv_index: None,
                     },
                 ],
                 WeatherMoment::default(),
@@ -134,6 +134,8 @@ impl ForecastTimeStep {
             wind: instant.wind_speed,
             wind_kmh: instant.wind_speed.map(|t| t * 3.6),
             wind_direction: instant.wind_from_direction,
+            rain_probability: None,
+            uv_index: None,
         }
     }
 }
@@ -180,6 +182,7 @@ impl WeatherProvider for Service<'_> {
         &self,
         location: Option<&Coordinates>,
         need_forecast: bool,
+        _need_nowcast: bool,
     ) -> Result<WeatherResult> {
         let (lat, lon) = location
             .as_ref()
@@ -226,6 +229,7 @@ impl WeatherProvider for Service<'_> {
                 location: location_name,
                 current_weather,
                 forecast: None,
+                nowcast: None,
                 sunrise,
                 sunset,
             });
@@ -253,6 +257,7 @@ impl WeatherProvider for Service<'_> {
             location: location_name,
             current_weather,
             forecast,
+            nowcast: None,
             sunset,
             sunrise,
         })
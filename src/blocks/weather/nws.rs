@@ -128,10 +128,16 @@ struct ApiValue {
     unit_code: String,
 }
 
+#[derive(Deserialize, Debug)]
+struct ApiOptionalValue {
+    value: Option<f64>,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 struct ApiForecast {
     is_daytime: bool,
+    probability_of_precipitation: Option<ApiOptionalValue>,
     temperature: ApiValue,
     relative_humidity: ApiValue,
     wind_speed: ApiValue,
@@ -235,6 +241,11 @@ impl ApiForecast {
             wind: Some(self.wind_speed()),
             wind_kmh: Some(self.wind_kmh()),
             wind_direction: self.wind_direction(),
+            rain_probability: self
+                .probability_of_precipitation
+                .as_ref()
+                .and_then(|p| p.value),
+            uv_index: None,
         }
     }
 }
@@ -245,6 +256,7 @@ impl WeatherProvider for Service<'_> {
         &self,
         autolocated: Option<&Coordinates>,
         need_forecast: bool,
+        _need_nowcast: bool,
     ) -> Result<WeatherResult> {
         let location = if let Some(coords) = autolocated {
             Self::get_location_query(coords.latitude, coords.longitude, self.config.units).await?
@@ -275,6 +287,7 @@ impl WeatherProvider for Service<'_> {
                 location: location.name,
                 current_weather,
                 forecast: None,
+                nowcast: None,
                 sunrise,
                 sunset,
             });
@@ -294,6 +307,7 @@ impl WeatherProvider for Service<'_> {
             location: location.name,
             current_weather,
             forecast,
+            nowcast: None,
             sunrise,
             sunset,
         })
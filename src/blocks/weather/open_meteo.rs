@@ -0,0 +1,282 @@
+//! Support for using the Open-Meteo API.
+//!
+//! The API is documented [here](https://open-meteo.com/en/docs). Weather conditions are reported
+//! as [WMO weather codes](https://open-meteo.com/en/docs#weather_variable_documentation).
+
+use super::*;
+
+const FORECAST_URL: &str = "https://api.open-meteo.com/v1/forecast";
+
+const MPH_TO_KPH: f64 = 1.609344;
+
+/// The variables of both the current weather and the hourly forecast
+const VARIABLES: &str = "temperature_2m,relative_humidity_2m,apparent_temperature,is_day,weather_code,wind_speed_10m,wind_direction_10m";
+
+/// Hourly variables in addition to `VARIABLES`, which are only used for hints
+const HOURLY_VARIABLES: &str = "precipitation_probability,uv_index";
+
+/// The nowcast covers the next two hours
+const NOWCAST_STEPS: usize = 8;
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(tag = "name", rename_all = "lowercase", deny_unknown_fields, default)]
+pub struct Config {
+    coordinates: Option<(String, String)>,
+    #[default(12)]
+    forecast_hours: usize,
+    #[serde(default)]
+    units: UnitSystem,
+}
+
+pub(super) struct Service<'a> {
+    config: &'a Config,
+}
+
+impl<'a> Service<'a> {
+    pub(super) fn new(config: &'a Config) -> Service<'a> {
+        Self { config }
+    }
+
+    fn wind_kmh(&self, wind: f64) -> f64 {
+        match self.config.units {
+            UnitSystem::Metric => wind * 3.6,
+            UnitSystem::Imperial => wind * MPH_TO_KPH,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiResponse {
+    current: ApiCurrent,
+    hourly: Option<ApiHourly>,
+    minutely_15: Option<ApiMinutely15>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiCurrent {
+    temperature_2m: f64,
+    relative_humidity_2m: f64,
+    apparent_temperature: f64,
+    is_day: u8,
+    weather_code: u8,
+    wind_speed_10m: f64,
+    wind_direction_10m: Option<f64>,
+}
+
+impl ApiCurrent {
+    fn to_moment(&self, service: &Service) -> WeatherMoment {
+        let (weather, weather_verbose, icon) = describe(self.weather_code, self.is_day == 0);
+        WeatherMoment {
+            icon,
+            weather: weather.into(),
+            weather_verbose: weather_verbose.into(),
+            temp: self.temperature_2m,
+            apparent: self.apparent_temperature,
+            humidity: self.relative_humidity_2m,
+            wind: self.wind_speed_10m,
+            wind_kmh: service.wind_kmh(self.wind_speed_10m),
+            wind_direction: self.wind_direction_10m,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiHourly {
+    temperature_2m: Vec<Option<f64>>,
+    relative_humidity_2m: Vec<Option<f64>>,
+    apparent_temperature: Vec<Option<f64>>,
+    is_day: Vec<Option<u8>>,
+    weather_code: Vec<Option<u8>>,
+    wind_speed_10m: Vec<Option<f64>>,
+    wind_direction_10m: Vec<Option<f64>>,
+    precipitation_probability: Vec<Option<f64>>,
+    uv_index: Vec<Option<f64>>,
+}
+
+impl ApiHourly {
+    fn len(&self) -> usize {
+        self.temperature_2m.len()
+    }
+
+    fn to_moment(&self, i: usize, service: &Service) -> WeatherMoment {
+        let is_night = self.is_day[i] == Some(0);
+        let (weather, weather_verbose, icon) = match self.weather_code[i] {
+            Some(code) => describe(code, is_night),
+            None => ("Unknown", "unknown", WeatherIcon::Default),
+        };
+        let wind = self.wind_speed_10m[i].unwrap_or_default();
+        WeatherMoment {
+            icon,
+            weather: weather.into(),
+            weather_verbose: weather_verbose.into(),
+            temp: self.temperature_2m[i].unwrap_or_default(),
+            apparent: self.apparent_temperature[i].unwrap_or_default(),
+            humidity: self.relative_humidity_2m[i].unwrap_or_default(),
+            wind,
+            wind_kmh: service.wind_kmh(wind),
+            wind_direction: self.wind_direction_10m[i],
+        }
+    }
+
+    fn to_aggregate(&self, i: usize, service: &Service) -> ForecastAggregateSegment {
+        ForecastAggregateSegment {
+            temp: self.temperature_2m[i],
+            apparent: self.apparent_temperature[i],
+            humidity: self.relative_humidity_2m[i],
+            wind: self.wind_speed_10m[i],
+            wind_kmh: self.wind_speed_10m[i].map(|w| service.wind_kmh(w)),
+            wind_direction: self.wind_direction_10m[i],
+            rain_probability: self.precipitation_probability[i],
+            uv_index: self.uv_index[i],
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiMinutely15 {
+    time: Vec<i64>,
+    /// The sum (in mm) of the preceding 15 minutes
+    precipitation: Vec<Option<f64>>,
+}
+
+#[async_trait]
+impl WeatherProvider for Service<'_> {
+    async fn get_weather(
+        &self,
+        location: Option<&Coordinates>,
+        need_forecast: bool,
+        need_nowcast: bool,
+    ) -> Result<WeatherResult> {
+        let (lat, lon) = location
+            .as_ref()
+            .map(|loc| (loc.latitude.to_string(), loc.longitude.to_string()))
+            .or_else(|| self.config.coordinates.clone())
+            .error("No location given")?;
+
+        let (sunrise, sunset) = calculate_sunrise_sunset(
+            lat.parse().error("Unable to convert string to f64")?,
+            lon.parse().error("Unable to convert string to f64")?,
+            None,
+        )?;
+
+        let forecast_hours = self.config.forecast_hours;
+        let need_forecast = need_forecast && forecast_hours > 0;
+
+        let querystr: HashMap<&str, String> = map! {
+            "latitude" => lat,
+            "longitude" => lon,
+            "current" => VARIABLES,
+            "timeformat" => "unixtime",
+            [if self.config.units == UnitSystem::Imperial] "temperature_unit" => "fahrenheit",
+            "wind_speed_unit" => match self.config.units {
+                UnitSystem::Metric => "ms",
+                UnitSystem::Imperial => "mph",
+            },
+            [if need_forecast] "hourly" => format!("{VARIABLES},{HOURLY_VARIABLES}"),
+            [if need_forecast] "forecast_hours" => forecast_hours.to_string(),
+            [if need_nowcast] "minutely_15" => "precipitation",
+            [if need_nowcast] "forecast_minutely_15" => NOWCAST_STEPS.to_string(),
+        };
+
+        let data: ApiResponse = REQWEST_CLIENT
+            .get(FORECAST_URL)
+            .query(&querystr)
+            .send()
+            .await
+            .error("Forecast request failed")?
+            .json()
+            .await
+            .error("Forecast request failed")?;
+
+        let current_weather = data.current.to_moment(self);
+
+        let forecast = match data.hourly {
+            Some(hourly) if need_forecast => {
+                if hourly.len() < forecast_hours {
+                    return Err(Error::new(format!(
+                        "Unable to fetch the specified number of forecast_hours specified {}, only {} hours available",
+                        forecast_hours,
+                        hourly.len()
+                    )));
+                }
+                let data_agg: Vec<ForecastAggregateSegment> = (0..forecast_hours)
+                    .map(|i| hourly.to_aggregate(i, self))
+                    .collect();
+                let fin = hourly.to_moment(forecast_hours - 1, self);
+                Some(Forecast::new(&data_agg, fin))
+            }
+            _ => None,
+        };
+
+        let nowcast = match data.minutely_15 {
+            Some(minutely) if need_nowcast => {
+                let step = chrono::TimeDelta::minutes(15);
+                let precipitation = minutely
+                    .time
+                    .iter()
+                    .zip(&minutely.precipitation)
+                    .map(|(&time, &precipitation)| {
+                        DateTime::<Utc>::from_timestamp(time, 0)
+                            // Convert to the rate during the step before `time`
+                            .map(|end| (end - step, precipitation.unwrap_or_default() * 4.0))
+                            .error("Unable to convert timestamp to DateTime")
+                    })
+                    .collect::<Result<_>>()?;
+                Some(Nowcast {
+                    precipitation,
+                    step,
+                })
+            }
+            _ => None,
+        };
+
+        Ok(WeatherResult {
+            location: location.map_or("Unknown".to_string(), |c| c.city.clone()),
+            current_weather,
+            forecast,
+            nowcast,
+            sunrise,
+            sunset,
+        })
+    }
+}
+
+/// The brief and verbose descriptions and the icon of a WMO weather code
+fn describe(code: u8, is_night: bool) -> (&'static str, &'static str, WeatherIcon) {
+    let clear = WeatherIcon::Clear { is_night };
+    let clouds = WeatherIcon::Clouds { is_night };
+    let fog = WeatherIcon::Fog { is_night };
+    let rain = WeatherIcon::Rain { is_night };
+    let thunder = WeatherIcon::Thunder { is_night };
+    match code {
+        0 => ("Clear", "clear sky", clear),
+        1 => ("Clear", "mainly clear", clear),
+        2 => ("Clouds", "partly cloudy", clouds),
+        3 => ("Clouds", "overcast", clouds),
+        45 => ("Fog", "fog", fog),
+        48 => ("Fog", "depositing rime fog", fog),
+        51 => ("Drizzle", "light drizzle", rain),
+        53 => ("Drizzle", "moderate drizzle", rain),
+        55 => ("Drizzle", "dense drizzle", rain),
+        56 => ("Drizzle", "light freezing drizzle", rain),
+        57 => ("Drizzle", "dense freezing drizzle", rain),
+        61 => ("Rain", "slight rain", rain),
+        63 => ("Rain", "moderate rain", rain),
+        65 => ("Rain", "heavy rain", rain),
+        66 => ("Rain", "light freezing rain", rain),
+        67 => ("Rain", "heavy freezing rain", rain),
+        71 => ("Snow", "slight snow fall", WeatherIcon::Snow),
+        73 => ("Snow", "moderate snow fall", WeatherIcon::Snow),
+        75 => ("Snow", "heavy snow fall", WeatherIcon::Snow),
+        77 => ("Snow", "snow grains", WeatherIcon::Snow),
+        80 => ("Rain", "slight rain showers", rain),
+        81 => ("Rain", "moderate rain showers", rain),
+        82 => ("Rain", "violent rain showers", rain),
+        85 => ("Snow", "slight snow showers", WeatherIcon::Snow),
+        86 => ("Snow", "heavy snow showers", WeatherIcon::Snow),
+        95 => ("Thunderstorm", "thunderstorm", thunder),
+        96 => ("Thunderstorm", "thunderstorm with slight hail", thunder),
+        99 => ("Thunderstorm", "thunderstorm with heavy hail", thunder),
+        _ => ("Unknown", "unknown", WeatherIcon::Default),
+    }
+}
@@ -5,6 +5,7 @@ use serde::{de, Deserializer};
 pub(super) const GEO_URL: &str = "https://api.openweathermap.org/geo/1.0";
 pub(super) const CURRENT_URL: &str = "https://api.openweathermap.org/data/2.5/weather";
 pub(super) const FORECAST_URL: &str = "https://api.openweathermap.org/data/2.5/forecast";
+pub(super) const ONE_CALL_URL: &str = "https://api.openweathermap.org/data/3.0/onecall";
 pub(super) const API_KEY_ENV: &str = "OPENWEATHERMAP_API_KEY";
 pub(super) const CITY_ID_ENV: &str = "OPENWEATHERMAP_CITY_ID";
 pub(super) const PLACE_ENV: &str = "OPENWEATHERMAP_PLACE";
@@ -156,6 +157,9 @@ struct ApiInstantResponse {
     main: ApiMain,
     wind: ApiWind,
     dt: i64,
+    /// The probability of precipitation from 0 to 1, only in forecasts
+    #[serde(default)]
+    pop: Option<f64>,
 }
 
 impl ApiInstantResponse {
@@ -191,6 +195,8 @@ impl ApiInstantResponse {
             wind: Some(self.wind.speed),
             wind_kmh: Some(self.wind_kmh(units)),
             wind_direction: self.wind.deg,
+            rain_probability: self.pop.map(|pop| pop * 100.0),
+            uv_index: None,
         }
     }
 }
@@ -201,6 +207,7 @@ struct ApiCurrentResponse {
     instant: ApiInstantResponse,
     sys: ApiSys,
     name: String,
+    coord: CityCoord,
 }
 
 impl ApiCurrentResponse {
@@ -234,6 +241,19 @@ struct ApiWeather {
     description: String,
 }
 
+#[derive(Deserialize, Debug)]
+struct ApiOneCallResponse {
+    #[serde(default)]
+    minutely: Vec<ApiMinutely>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiMinutely {
+    dt: i64,
+    /// In mm/h
+    precipitation: f64,
+}
+
 #[derive(Deserialize, Debug)]
 struct CityCoord {
     lat: f64,
@@ -246,6 +266,7 @@ impl WeatherProvider for Service<'_> {
         &self,
         autolocated: Option<&Coordinates>,
         need_forecast: bool,
+        need_nowcast: bool,
     ) -> Result<WeatherResult> {
         let location_query = autolocated
             .as_ref()
@@ -281,56 +302,89 @@ impl WeatherProvider for Service<'_> {
         let sunset = DateTime::<Utc>::from_timestamp(current_data.sys.sunset, 0)
             .error("Unable to convert timestamp to DateTime")?;
 
-        if !need_forecast || self.forecast_hours == 0 {
-            return Ok(WeatherResult {
-                location: current_data.name,
-                current_weather,
-                forecast: None,
-                sunrise,
-                sunset,
-            });
-        }
-
-        // Refer to https://openweathermap.org/forecast5
-        let forecast_url = format!(
-            "{FORECAST_URL}?{location_query}&appid={api_key}&units={units}&lang={lang}&cnt={cnt}",
-            api_key = self.api_key,
-            units = match self.units {
-                UnitSystem::Metric => "metric",
-                UnitSystem::Imperial => "imperial",
-            },
-            lang = self.lang,
-            cnt = self.forecast_hours / 3,
-        );
-
-        let forecast_data: ApiForecastResponse = REQWEST_CLIENT
-            .get(forecast_url)
-            .send()
-            .await
-            .error("Forecast weather request failed")?
-            .json()
-            .await
-            .error("Forecast weather request failed")?;
-
-        let data_agg: Vec<ForecastAggregateSegment> = forecast_data
-            .list
-            .iter()
-            .take(self.forecast_hours)
-            .map(|f| f.to_aggregate(self.units))
-            .collect();
-
-        let fin = forecast_data
-            .list
-            .last()
-            .error("no weather available")?
-            .to_moment(self.units, &current_data);
+        let forecast = if need_forecast && self.forecast_hours > 0 {
+            // Refer to https://openweathermap.org/forecast5
+            let forecast_url = format!(
+                "{FORECAST_URL}?{location_query}&appid={api_key}&units={units}&lang={lang}&cnt={cnt}",
+                api_key = self.api_key,
+                units = match self.units {
+                    UnitSystem::Metric => "metric",
+                    UnitSystem::Imperial => "imperial",
+                },
+                lang = self.lang,
+                cnt = self.forecast_hours / 3,
+            );
+
+            let forecast_data: ApiForecastResponse = REQWEST_CLIENT
+                .get(forecast_url)
+                .send()
+                .await
+                .error("Forecast weather request failed")?
+                .json()
+                .await
+                .error("Forecast weather request failed")?;
+
+            let data_agg: Vec<ForecastAggregateSegment> = forecast_data
+                .list
+                .iter()
+                .take(self.forecast_hours)
+                .map(|f| f.to_aggregate(self.units))
+                .collect();
+
+            let fin = forecast_data
+                .list
+                .last()
+                .error("no weather available")?
+                .to_moment(self.units, &current_data);
+
+            Some(Forecast::new(&data_agg, fin))
+        } else {
+            None
+        };
 
-        let forecast = Some(Forecast::new(&data_agg, fin));
+        let nowcast = if need_nowcast {
+            // Refer to https://openweathermap.org/api/one-call-3
+            let one_call_url = format!(
+                "{ONE_CALL_URL}?lat={lat}&lon={lon}&appid={api_key}&exclude=current,hourly,daily,alerts",
+                lat = current_data.coord.lat,
+                lon = current_data.coord.lon,
+                api_key = self.api_key,
+            );
+
+            let one_call_data: ApiOneCallResponse = REQWEST_CLIENT
+                .get(one_call_url)
+                .send()
+                .await
+                .error("Nowcast request failed")?
+                .error_for_status()
+                .error("Nowcast request failed (One Call API 3.0 requires a subscription)")?
+                .json()
+                .await
+                .error("Nowcast request failed")?;
+
+            let precipitation = one_call_data
+                .minutely
+                .iter()
+                .map(|m| {
+                    DateTime::<Utc>::from_timestamp(m.dt, 0)
+                        .map(|start| (start, m.precipitation))
+                        .error("Unable to convert timestamp to DateTime")
+                })
+                .collect::<Result<_>>()?;
+
+            Some(Nowcast {
+                precipitation,
+                step: chrono::TimeDelta::minutes(1),
+            })
+        } else {
+            None
+        };
 
         Ok(WeatherResult {
             location: current_data.name,
             current_weather,
             forecast,
+            nowcast,
             sunrise,
             sunset,
         })
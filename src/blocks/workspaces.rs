@@ -0,0 +1,368 @@
+//! Workspaces of sway, i3 or niri
+//!
+//! This block lists the workspaces, like the workspace buttons of `swaybar`/`i3bar`, but styled
+//! by the theme of the bar. Clicking a workspace switches to it, and scrolling over the block
+//! switches to the previous or next workspace in the list.
+//!
+//! The workspaces are shown in the order sway/i3 reports them, or sorted by output and index with
+//! niri. Unnamed niri workspaces are shown as their index. Each workspace is colored according
+//! to its state:
+//!
+//! Workspace state                            | State
+//! -------------------------------------------|---------
+//! focused                                    | Info
+//! urgent                                     | Critical
+//! visible on an output, but not focused      | Good
+//! other                                      | Idle
+//!
+//! # Configuration
+//!
+//! Key | Values | Default
+//! ----|--------|--------
+//! `driver` | Which driver to use. Available values: `sway_ipc` - for `i3` and `sway` (Linux only), `niri` - for niri, `auto` - use `niri` if `$NIRI_SOCKET` is set, otherwise `sway_ipc`. | `"auto"`
+//! `output` | Only show the workspaces of this output (e.g. `"eDP-1"`) | All outputs
+//! `icons` | A map from workspace names to the text shown instead of the name, e.g. an icon | `{}`
+//!
+//! Action            | Description                                         | Default button
+//! ------------------|-----------------------------------------------------|---------------
+//! `focus_<n>`       | Switch to the `n`th workspace of the block, from 1  | Left on the workspace
+//! `switch_<name>`   | Switch to the workspace with the given name         | -
+//! `prev`            | Switch to the previous workspace                    | Wheel Up
+//! `next`            | Switch to the next workspace                        | Wheel Down
+//!
+//! The name of unnamed niri workspaces is their index, which is only unique on one output, so
+//! `switch_<name>` picks the first workspace with this name in the block, e.g. the one on `output`.
+//!
+//! # Example
+//!
+//! Only show the workspaces of the laptop screen, with icons for some of them:
+//!
+//! ```toml
+//! [[block]]
+//! block = "workspaces"
+//! output = "eDP-1"
+//! [block.icons]
+//! "1" = ""
+//! "2" = ""
+//! mail = ""
+//! ```
+
+mod niri;
+#[cfg(target_os = "linux")]
+mod sway_ipc;
+
+use std::sync::Mutex;
+
+use niri::Niri;
+#[cfg(target_os = "linux")]
+use sway_ipc::SwayIpc;
+
+use super::prelude::*;
+
+type DefaultActions = &'static [(MouseButton, Option<&'static str>, &'static str)];
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    pub driver: Driver,
+    pub output: Option<String>,
+    pub icons: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Debug, SmartDefault)]
+#[serde(rename_all = "snake_case")]
+pub enum Driver {
+    #[default]
+    Auto,
+    #[cfg(target_os = "linux")]
+    SwayIpc,
+    Niri,
+}
+
+pub async fn run(config: &Config, api: &CommonApi) -> Result<()> {
+    let mut actions = api.get_actions()?;
+
+    let mut backend: Box<dyn Backend> = match config.driver {
+        #[cfg(target_os = "linux")]
+        Driver::Auto if !crate::niri::is_running() => Box::new(SwayIpc::new().await?),
+        #[cfg(target_os = "linux")]
+        Driver::SwayIpc => Box::new(SwayIpc::new().await?),
+        Driver::Auto | Driver::Niri => Box::new(Niri::new().await?),
+    };
+
+    let mut len = None;
+
+    loop {
+        let workspaces: Vec<Workspace> = backend
+            .get_workspaces()
+            .await?
+            .into_iter()
+            .filter(|ws| config.output.is_none() || config.output == ws.output)
+            .collect();
+
+        if len != Some(workspaces.len()) {
+            len = Some(workspaces.len());
+            api.set_default_actions(default_actions(workspaces.len()))?;
+        }
+
+        let (format, values) = render(&workspaces, &config.icons);
+        let mut widget = Widget::new().with_format(FormatConfig::default().with_default(&format)?);
+        widget.set_values(values);
+        api.set_widget(widget)?;
+
+        loop {
+            let action = select! {
+                update = backend.wait_for_change() => {
+                    update?;
+                    break;
+                }
+                _ = api.wait_for_update_request() => break,
+                Some(action) = actions.recv() => action,
+            };
+            if let Some(target) = action_target(&workspaces, &action) {
+                backend.switch_to(target).await?;
+            }
+        }
+    }
+}
+
+#[async_trait]
+trait Backend: Send {
+    async fn get_workspaces(&mut self) -> Result<Vec<Workspace>>;
+    async fn wait_for_change(&mut self) -> Result<()>;
+    /// Switches to the workspace with the given key
+    async fn switch_to(&mut self, key: &str) -> Result<()>;
+}
+
+struct Workspace {
+    /// Identifies the workspace when switching to it
+    key: String,
+    name: String,
+    output: Option<String>,
+    focused: bool,
+    visible: bool,
+    urgent: bool,
+}
+
+impl Workspace {
+    fn state(&self) -> State {
+        if self.urgent {
+            State::Critical
+        } else if self.focused {
+            State::Info
+        } else if self.visible {
+            State::Good
+        } else {
+            State::Idle
+        }
+    }
+}
+
+/// The format and values of the workspaces. Each workspace is a placeholder and an instance named
+/// after its position.
+fn render(workspaces: &[Workspace], icons: &HashMap<String, String>) -> (String, Values) {
+    let mut format = String::from(" ");
+    let mut values = Values::new();
+    for (i, ws) in workspaces.iter().enumerate() {
+        let key = intern(&format!("ws{i}"));
+        let _ = write!(format, "${key} ");
+        let text = icons.get(&ws.name).unwrap_or(&ws.name);
+        values.insert(
+            key,
+            Value::text(text.clone())
+                .with_instance(key)
+                .with_state(ws.state()),
+        );
+    }
+    (format, values)
+}
+
+/// The key of the workspace to switch to for an action
+fn action_target<'a>(workspaces: &'a [Workspace], action: &'a str) -> Option<&'a str> {
+    match action {
+        "prev" | "next" => {
+            let current = workspaces
+                .iter()
+                .position(|ws| ws.focused)
+                .or_else(|| workspaces.iter().position(|ws| ws.visible))?;
+            let len = workspaces.len();
+            let i = if action == "prev" {
+                current + len - 1
+            } else {
+                current + 1
+            };
+            Some(&workspaces[i % len].key)
+        }
+        action => {
+            if let Some(n) = action.strip_prefix("focus_") {
+                let ws = workspaces.get(n.parse::<usize>().ok()?.checked_sub(1)?)?;
+                Some(&ws.key)
+            } else if let Some(name) = action.strip_prefix("switch_") {
+                // sway creates workspaces which do not exist yet
+                Some(
+                    workspaces
+                        .iter()
+                        .find(|ws| ws.name == name)
+                        .map_or(name, |ws| ws.key.as_str()),
+                )
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Default actions must be `'static`. The actions of `n` workspaces are the first `2 + 3 * n` of
+/// the actions of more workspaces, so they are only leaked when the number of workspaces exceeds
+/// the largest one so far.
+fn default_actions(len: usize) -> DefaultActions {
+    static ACTIONS: Mutex<DefaultActions> = Mutex::new(&[]);
+    let mut actions = ACTIONS.lock().unwrap();
+    let needed = 2 + 3 * len;
+    if actions.len() < needed {
+        let mut new_actions = vec![
+            (MouseButton::WheelUp, None, "prev"),
+            (MouseButton::WheelDown, None, "next"),
+        ];
+        for i in 0..len {
+            let instance = intern(&format!("ws{i}"));
+            new_actions.push((
+                MouseButton::Left,
+                Some(instance),
+                intern(&format!("focus_{}", i + 1)),
+            ));
+            new_actions.push((MouseButton::WheelUp, Some(instance), "prev"));
+            new_actions.push((MouseButton::WheelDown, Some(instance), "next"));
+        }
+        *actions = new_actions.leak();
+    }
+    &actions[..needed]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatting::value::ValueInner;
+
+    fn ws(name: &str, output: &str) -> Workspace {
+        Workspace {
+            key: name.into(),
+            name: name.into(),
+            output: Some(output.into()),
+            focused: false,
+            visible: false,
+            urgent: false,
+        }
+    }
+
+    #[test]
+    fn sorting() {
+        let mut state = crate::niri::State::default();
+        state.apply(
+            serde_json::from_str(
+                r#"{"WorkspacesChanged":{"workspaces":[
+                    {"id":5,"idx":2,"name":null,"output":"HDMI-A-1","is_active":false,"is_focused":false},
+                    {"id":3,"idx":2,"name":"web","output":"DP-1","is_active":false,"is_focused":false},
+                    {"id":4,"idx":1,"name":null,"output":"HDMI-A-1","is_active":true,"is_focused":false},
+                    {"id":1,"idx":1,"name":null,"output":"DP-1","is_active":true,"is_focused":true}
+                ]}}"#,
+            )
+            .unwrap(),
+        );
+        let workspaces = niri::workspaces(&state);
+        // Sorted by output and then by index, unnamed workspaces are named after their index
+        let names: Vec<_> = workspaces
+            .iter()
+            .map(|ws| (ws.output.as_deref().unwrap(), ws.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("DP-1", "1"),
+                ("DP-1", "web"),
+                ("HDMI-A-1", "1"),
+                ("HDMI-A-1", "2")
+            ]
+        );
+        // Unnamed workspaces are switched to by their id
+        let keys: Vec<_> = workspaces.iter().map(|ws| ws.key.as_str()).collect();
+        assert_eq!(keys, ["1", "web", "4", "5"]);
+    }
+
+    #[test]
+    fn formatting() {
+        let workspaces = [
+            Workspace {
+                focused: true,
+                visible: true,
+                ..ws("1", "DP-1")
+            },
+            ws("web", "DP-1"),
+            Workspace {
+                visible: true,
+                ..ws("3", "HDMI-A-1")
+            },
+            Workspace {
+                urgent: true,
+                ..ws("4", "HDMI-A-1")
+            },
+        ];
+        let icons = HashMap::from([("web".to_owned(), "W".to_owned())]);
+        let (format, values) = render(&workspaces, &icons);
+        assert_eq!(format, " $ws0 $ws1 $ws2 $ws3 ");
+
+        let widget = |key: &str| {
+            let value = &values[key];
+            let ValueInner::Text(text) = &value.inner else {
+                panic!("{key} is not text");
+            };
+            let instance = value.metadata.instance.map(|i| i.as_str());
+            (text.to_string(), instance, value.metadata.state)
+        };
+        assert_eq!(
+            widget("ws0"),
+            ("1".into(), Some("ws0"), Some(State::Info))
+        );
+        assert_eq!(widget("ws1"), ("W".into(), Some("ws1"), Some(State::Idle)));
+        assert_eq!(
+            widget("ws2"),
+            ("3".into(), Some("ws2"), Some(State::Good))
+        );
+        assert_eq!(
+            widget("ws3"),
+            ("4".into(), Some("ws3"), Some(State::Critical))
+        );
+
+        let (format, values) = render(&[], &icons);
+        assert_eq!(format, " ");
+        assert!(values.is_empty());
+    }
+
+    #[test]
+    fn actions() {
+        let mut workspaces = [
+            ws("1", "DP-1"),
+            Workspace {
+                visible: true,
+                ..ws("2", "DP-1")
+            },
+            ws("3", "DP-1"),
+        ];
+        // Without a focused workspace, the visible one is the current one
+        assert_eq!(action_target(&workspaces, "next"), Some("3"));
+        assert_eq!(action_target(&workspaces, "prev"), Some("1"));
+        workspaces[2].focused = true;
+        assert_eq!(action_target(&workspaces, "next"), Some("1"));
+        assert_eq!(action_target(&workspaces, "prev"), Some("2"));
+
+        assert_eq!(action_target(&workspaces, "focus_1"), Some("1"));
+        assert_eq!(action_target(&workspaces, "focus_3"), Some("3"));
+        assert_eq!(action_target(&workspaces, "focus_0"), None);
+        assert_eq!(action_target(&workspaces, "focus_4"), None);
+        assert_eq!(action_target(&workspaces, "switch_2"), Some("2"));
+        assert_eq!(action_target(&workspaces, "switch_mail"), Some("mail"));
+        assert_eq!(action_target(&workspaces, "toggle"), None);
+
+        assert_eq!(action_target(&[ws("1", "DP-1")], "next"), None);
+    }
+}
@@ -0,0 +1,77 @@
+use serde_json::json;
+
+use super::{Backend, Workspace};
+use crate::blocks::prelude::*;
+use crate::niri::{self, EventStream, Event, State};
+
+pub(super) struct Niri {
+    events: EventStream,
+    state: State,
+}
+
+impl Niri {
+    pub(super) async fn new() -> Result<Self> {
+        let mut niri = Self {
+            events: EventStream::new().await?,
+            state: State::default(),
+        };
+        // niri sends the current workspaces right after the start of the event stream
+        niri.wait_for_change().await?;
+        Ok(niri)
+    }
+}
+
+/// Named workspaces are unique, unnamed ones are referred to by their id, which is only used to
+/// switch to them
+fn key(ws: &niri::Workspace) -> String {
+    ws.name.clone().unwrap_or_else(|| ws.id.to_string())
+}
+
+/// The workspaces of all outputs, sorted by output and then by their index on the output
+pub(super) fn workspaces(state: &State) -> Vec<Workspace> {
+    state
+        .workspaces
+        .iter()
+        .map(|ws| Workspace {
+            key: key(ws),
+            name: ws.name.clone().unwrap_or_else(|| ws.idx.to_string()),
+            output: ws.output.clone(),
+            focused: ws.is_focused,
+            visible: ws.is_active,
+            urgent: ws.is_urgent,
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Backend for Niri {
+    async fn get_workspaces(&mut self) -> Result<Vec<Workspace>> {
+        Ok(workspaces(&self.state))
+    }
+
+    async fn wait_for_change(&mut self) -> Result<()> {
+        loop {
+            let event = self.events.next().await?;
+            let workspace_event = matches!(
+                event,
+                Event::WorkspacesChanged { .. }
+                    | Event::WorkspaceActivated { .. }
+                    | Event::WorkspaceUrgencyChanged { .. }
+            );
+            self.state.apply(event);
+            if workspace_event {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn switch_to(&mut self, key: &str) -> Result<()> {
+        let ws = self
+            .state
+            .workspaces
+            .iter()
+            .find(|ws| self::key(ws) == key)
+            .or_error(|| format!("Unknown workspace '{key}'"))?;
+        niri::action(json!({ "FocusWorkspace": { "reference": { "Id": ws.id } } })).await
+    }
+}
@@ -0,0 +1,72 @@
+use super::{Backend, Workspace};
+use crate::blocks::prelude::*;
+use swayipc_async::{Connection, Event, EventStream, EventType};
+
+pub(super) struct SwayIpc {
+    connection: Connection,
+    events: EventStream,
+}
+
+impl SwayIpc {
+    pub(super) async fn new() -> Result<Self> {
+        Ok(Self {
+            connection: Connection::new()
+                .await
+                .error("Failed to open swayipc connection")?,
+            events: Connection::new()
+                .await
+                .error("Failed to open swayipc connection")?
+                .subscribe(&[EventType::Workspace, EventType::Output])
+                .await
+                .error("Failed to subscribe to workspace events")?,
+        })
+    }
+}
+
+#[async_trait]
+impl Backend for SwayIpc {
+    async fn get_workspaces(&mut self) -> Result<Vec<Workspace>> {
+        Ok(self
+            .connection
+            .get_workspaces()
+            .await
+            .error("Failed to get workspaces")?
+            .into_iter()
+            .map(|ws| Workspace {
+                key: ws.name.clone(),
+                name: ws.name,
+                output: Some(ws.output),
+                focused: ws.focused,
+                visible: ws.visible,
+                urgent: ws.urgent,
+            })
+            .collect())
+    }
+
+    async fn wait_for_change(&mut self) -> Result<()> {
+        loop {
+            let event = self
+                .events
+                .next()
+                .await
+                .error("swayipc channel closed")?
+                .error("bad event")?;
+            if matches!(event, Event::Workspace(_) | Event::Output(_)) {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn switch_to(&mut self, key: &str) -> Result<()> {
+        let escaped = key.replace('\\', "\\\\").replace('"', "\\\"");
+        let outcomes = self
+            .connection
+            .run_command(format!("workspace \"{escaped}\""))
+            .await
+            .error("Failed to run swayipc command")?;
+        for outcome in outcomes {
+            outcome.or_error(|| format!("Failed to switch to workspace '{key}'"))?;
+        }
+        Ok(())
+    }
+}
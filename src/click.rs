@@ -1,4 +1,6 @@
 use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 use serde::de::{self, Deserializer, Visitor};
 use serde::Deserialize;
@@ -27,41 +29,165 @@ pub enum MouseButton {
     DoubleLeft,
 }
 
+/// A modifier key which is held during a click. `Mod1` is usually Alt and `Mod4` the Super (or
+/// Windows) key. i3bar also reports `Lock` (Caps Lock) and `Mod2` (usually Num Lock), which are
+/// ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Modifier {
+    Shift,
+    Control,
+    Mod1,
+    Mod3,
+    Mod4,
+    Mod5,
+}
+
+impl FromStr for Modifier {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        use Modifier::*;
+        Ok(match name.to_ascii_lowercase().as_str() {
+            "shift" => Shift,
+            "control" | "ctrl" => Control,
+            "mod1" | "alt" => Mod1,
+            "mod3" => Mod3,
+            "mod4" | "super" => Mod4,
+            "mod5" => Mod5,
+            _ => return Err(format!("unknown modifier '{name}'")),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Modifier {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl MouseButton {
+    pub fn is_wheel(self) -> bool {
+        use MouseButton::*;
+        matches!(self, WheelUp | WheelDown | WheelLeft | WheelRight)
+    }
+}
+
+/// Wheel events which are closer together than this count as fast scrolling
+const FAST_SCROLL: Duration = Duration::from_millis(300);
+/// The maximum number of times a wheel event is handled due to acceleration
+const MAX_SCROLL_STEPS: f64 = 10.0;
+
+/// Debounces and accelerates the wheel events of a block
+#[derive(Debug)]
+pub struct ScrollHandler {
+    /// The minimum interval between two handled wheel events
+    interval: Duration,
+    /// The factor by which the number of steps grows with each fast wheel event
+    acceleration: f64,
+    last: Option<(MouseButton, Instant)>,
+    streak: i32,
+}
+
+impl ScrollHandler {
+    pub fn new(interval: Duration, acceleration: f64) -> Self {
+        Self {
+            interval,
+            acceleration: acceleration.max(1.0),
+            last: None,
+            streak: 0,
+        }
+    }
+
+    /// Returns how many times an event should be handled. Wheel events which follow the previous
+    /// one too quickly are dropped (zero steps), and fast scrolling in one direction is handled
+    /// more than once.
+    pub fn steps(&mut self, button: MouseButton, now: Instant) -> usize {
+        if !button.is_wheel() {
+            return 1;
+        }
+        if let Some((last_button, last)) = self.last {
+            let elapsed = now.saturating_duration_since(last);
+            if elapsed < self.interval {
+                return 0;
+            }
+            if last_button == button && elapsed < FAST_SCROLL {
+                self.streak = self.streak.saturating_add(1);
+            } else {
+                self.streak = 0;
+            }
+        }
+        self.last = Some((button, now));
+        self.acceleration
+            .powi(self.streak)
+            .min(MAX_SCROLL_STEPS)
+            .round() as usize
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PostActions {
     pub action: Option<String>,
     pub update: bool,
+    /// A command whose output is shown as `$cmd_output` for the given duration
+    pub cmd_output: Option<(String, Duration)>,
 }
 
 #[derive(Deserialize, Debug, Clone, Default)]
 pub struct ClickHandler(Vec<ClickConfigEntry>);
 
 impl ClickHandler {
+    /// The names of the block actions which are triggered by the entries
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().filter_map(|e| e.action.as_deref())
+    }
+
     pub async fn handle(&self, event: &I3BarEvent) -> Result<Option<PostActions>> {
-        let Some(entry) = self
+        let mut entries = self
             .0
             .iter()
             .filter(|e| e.button == event.button)
-            .find(|e| match &e.widget {
+            .filter(|e| match &e.widget {
                 None => event.instance.is_none(),
                 Some(re) => re.0.is_match(event.instance.as_deref().unwrap_or("block")),
+            });
+        // Entries without modifiers also handle clicks with modifiers that have no entry
+        let Some(entry) = entries
+            .clone()
+            .find(|e| {
+                e.modifiers.len() == event.modifiers.len()
+                    && e.modifiers.iter().all(|m| event.modifiers.contains(m))
             })
+            .or_else(|| entries.find(|e| e.modifiers.is_empty()))
         else {
             return Ok(None);
         };
 
+        let mut cmd_output = None;
         if let Some(cmd) = &entry.cmd {
-            if entry.sync {
-                spawn_shell_sync(cmd).await
+            if let Some(secs) = entry.output_duration {
+                // The output is captured in the background, so it does not block the bar
+                cmd_output = Some((cmd.clone(), Duration::from_secs(secs)));
             } else {
-                spawn_shell(cmd)
+                if entry.sync {
+                    spawn_shell_sync(cmd).await
+                } else {
+                    spawn_shell(cmd)
+                }
+                .or_error(|| {
+                    format!("'{:?}' button handler: Failed to run '{cmd}", event.button)
+                })?;
             }
-            .or_error(|| format!("'{:?}' button handler: Failed to run '{cmd}", event.button))?;
         }
 
         Ok(Some(PostActions {
             action: entry.action.clone(),
             update: entry.update,
+            cmd_output,
         }))
     }
 }
@@ -74,6 +200,9 @@ pub struct ClickConfigEntry {
     /// To which part of the block this entry applies
     #[serde(default)]
     widget: Option<SerdeRegex>,
+    /// Which modifier keys must be held
+    #[serde(default)]
+    modifiers: Vec<Modifier>,
     /// Which command to run
     #[serde(default)]
     cmd: Option<String>,
@@ -86,6 +215,9 @@ pub struct ClickConfigEntry {
     /// Whether to update the block on click (default is `false`)
     #[serde(default)]
     update: bool,
+    /// For how many seconds to show the output of `cmd` as `$cmd_output`
+    #[serde(default)]
+    output_duration: Option<u64>,
 }
 
 impl<'de> Deserialize<'de> for MouseButton {
@@ -158,3 +290,29 @@ impl<'de> Deserialize<'de> for MouseButton {
         deserializer.deserialize_any(MouseButtonVisitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scroll_steps() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        let mut scroll = ScrollHandler::new(Duration::ZERO, 1.0);
+        assert_eq!(scroll.steps(MouseButton::WheelUp, at(0)), 1);
+        assert_eq!(scroll.steps(MouseButton::WheelUp, at(0)), 1);
+
+        let mut scroll = ScrollHandler::new(Duration::from_millis(100), 2.0);
+        assert_eq!(scroll.steps(MouseButton::WheelUp, at(0)), 1);
+        assert_eq!(scroll.steps(MouseButton::WheelUp, at(50)), 0);
+        assert_eq!(scroll.steps(MouseButton::Left, at(60)), 1);
+        assert_eq!(scroll.steps(MouseButton::WheelUp, at(100)), 2);
+        assert_eq!(scroll.steps(MouseButton::WheelUp, at(200)), 4);
+        assert_eq!(scroll.steps(MouseButton::WheelUp, at(300)), 8);
+        assert_eq!(scroll.steps(MouseButton::WheelUp, at(400)), 10);
+        assert_eq!(scroll.steps(MouseButton::WheelDown, at(500)), 1);
+        assert_eq!(scroll.steps(MouseButton::WheelDown, at(1000)), 1);
+    }
+}
@@ -1,6 +1,8 @@
+use chrono::Locale;
 use serde::{Deserialize, Deserializer};
 use smart_default::SmartDefault;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::blocks::BlockConfig;
@@ -8,7 +10,21 @@ use crate::click::ClickHandler;
 use crate::errors::*;
 use crate::formatting::config::Config as FormatConfig;
 use crate::icons::{Icon, Icons};
+use crate::secrets;
 use crate::themes::{Theme, ThemeOverrides, ThemeUserConfig};
+use crate::util;
+use crate::widget::State;
+use crate::wrappers::ShellString;
+
+/// The configuration file which is used if none is given on the command line
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// A minimal example configuration, which is also used if the default configuration file does
+/// not exist
+pub const MINIMAL_CONFIG: &str = include_str!("../examples/minimal.toml");
+
+/// An example configuration which shows off most of the features
+pub const FULL_CONFIG: &str = include_str!("../examples/full.toml");
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
@@ -23,11 +39,165 @@ pub struct Config {
     #[serde(default)]
     pub double_click_delay: u64,
 
+    /// Whether blocks show fake values instead of running (set by `--demo`)
+    #[serde(skip)]
+    pub demo: bool,
+
+    /// The minimum interval (ms) between two wheel events handled by a block
+    #[serde(default)]
+    pub scroll_interval: u64,
+
+    /// The factor by which the steps of fast scrolling grow
+    #[serde(default = "default_scroll_acceleration")]
+    pub scroll_acceleration: f64,
+
     #[serde(default = "default_error_format")]
     pub error_format: FormatConfig,
     #[serde(default = "default_error_fullscreen")]
     pub error_fullscreen_format: FormatConfig,
 
+    /// The maximum width of the bar (in characters). If the bar is wider, blocks switch to their
+    /// narrower `formats` and then the blocks with the lowest priority are collapsed.
+    #[serde(default)]
+    pub max_width: Option<usize>,
+
+    #[serde(default)]
+    #[serde(rename = "block")]
+    pub blocks: Vec<BlockConfigEntry>,
+
+    /// Additional bars driven by this process
+    #[serde(default)]
+    #[serde(rename = "bar")]
+    pub bars: Vec<BarConfig>,
+}
+
+impl Config {
+    /// Load the configuration from `path`, together with all the files it includes, apply block
+    /// templates and resolve secrets
+    pub async fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .or_error(|| format!("Failed to read file: {}", path.display()))?;
+        Self::load_str(&contents, path).await
+    }
+
+    /// Like [`Config::load`], but for contents which are already read (e.g. from stdin). `path`
+    /// is used in error messages and relative includes are resolved against its parent.
+    pub async fn load_str(contents: &str, path: &Path) -> Result<Self> {
+        let table: toml::Table = util::deserialize_toml_str(contents, path)?;
+        if !table.contains_key("include")
+            && !table.contains_key("templates")
+            && !secrets::has_secrets(&table)
+        {
+            // Deserialize directly from the contents to get line numbers in error messages
+            return util::deserialize_toml_str(contents, path);
+        }
+        let mut table = resolve_includes(path, table, &mut Vec::new())?;
+        apply_templates(&mut table)?;
+        secrets::resolve(&mut table).await?;
+        toml::Value::Table(table)
+            .try_into()
+            .error("Failed to deserialize the configuration")
+    }
+
+    /// Validate the parts of the configuration which are not checked during deserialization,
+    /// without starting any blocks
+    pub fn check(&self) -> Result<()> {
+        self.check_blocks(&self.blocks)
+            .error("Invalid block in the main bar")?;
+        for (i, bar) in self.bars.iter().enumerate() {
+            if bar.socket.is_some() == bar.fifo.is_some() {
+                return Err(Error::new(format!(
+                    "Bar #{}: exactly one of 'socket' or 'fifo' must be set",
+                    i + 1
+                )));
+            }
+            self.check_blocks(&bar.blocks)
+                .or_error(|| format!("Invalid block in bar #{}", i + 1))?;
+        }
+        Ok(())
+    }
+
+    fn check_blocks(&self, blocks: &[BlockConfigEntry]) -> Result<()> {
+        for (i, block) in blocks.iter().enumerate() {
+            let check = || -> Result<()> {
+                if let BlockConfig::Err(_, err) = &block.config {
+                    return Err(err.clone());
+                }
+                if let Some(theme_overrides) = &block.common.theme_overrides {
+                    (*self.shared.theme)
+                        .clone()
+                        .apply_overrides(theme_overrides.clone())?;
+                }
+                if let BlockConfig::group(group) = &block.config {
+                    if let Some(child) = group
+                        .blocks
+                        .iter()
+                        .find(|b| matches!(b.config, BlockConfig::group(_)))
+                    {
+                        return Err(Error::new(format!(
+                            "Groups cannot be nested (found '{}')",
+                            child.config.name()
+                        )));
+                    }
+                    self.check_blocks(&group.blocks)?;
+                }
+                if let BlockConfig::sound(sound) = &block.config {
+                    sound.check()?;
+                }
+                if let Some(actions) = crate::blocks::actions(block.config.name()) {
+                    // Actions like `set_<profile>` stand for all actions with that prefix
+                    let known = |action: &str| {
+                        [crate::TOGGLE_VISIBILITY, crate::PAUSE_UPDATES].contains(&action)
+                            || actions.iter().any(|name| match name.split_once('<') {
+                                Some((prefix, _)) => action.starts_with(prefix),
+                                None => *name == action,
+                            })
+                    };
+                    if let Some(action) = block.common.click.actions().find(|a| !known(a)) {
+                        return Err(Error::new(format!(
+                            "Unknown action '{action}' (the actions of this block are {})",
+                            actions.join(", ")
+                        )));
+                    }
+                }
+                Ok(())
+            };
+            check().or_error(|| format!("Block #{} ('{}')", i + 1, block.config.name()))?;
+        }
+        Ok(())
+    }
+
+    /// Returns a copy of the global options, without any blocks or bars
+    pub fn without_blocks(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            invert_scrolling: self.invert_scrolling,
+            double_click_delay: self.double_click_delay,
+            demo: self.demo,
+            scroll_interval: self.scroll_interval,
+            scroll_acceleration: self.scroll_acceleration,
+            error_format: self.error_format.clone(),
+            error_fullscreen_format: self.error_fullscreen_format.clone(),
+            max_width: self.max_width,
+            blocks: Vec::new(),
+            bars: Vec::new(),
+        }
+    }
+}
+
+/// An additional bar, which shares the process (and all backend connections) with the main bar
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct BarConfig {
+    /// Path to a UNIX socket to listen on
+    #[serde(default)]
+    pub socket: Option<ShellString>,
+    /// Path to a named pipe to write to
+    #[serde(default)]
+    pub fifo: Option<ShellString>,
+    /// Overrides the global `max_width`
+    #[serde(default)]
+    pub max_width: Option<usize>,
     #[serde(default)]
     #[serde(rename = "block")]
     pub blocks: Vec<BlockConfigEntry>,
@@ -42,6 +212,10 @@ pub struct SharedConfig {
     pub icons: Arc<Icons>,
     #[serde(default = "default_icons_format")]
     pub icons_format: Arc<String>,
+    /// The locale used to format numbers and dates, e.g. `de_DE`
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_locale")]
+    pub locale: Option<Locale>,
 }
 
 impl Default for SharedConfig {
@@ -50,10 +224,15 @@ impl Default for SharedConfig {
             theme: Default::default(),
             icons: Default::default(),
             icons_format: default_icons_format(),
+            locale: None,
         }
     }
 }
 
+fn default_scroll_acceleration() -> f64 {
+    1.0
+}
+
 fn default_error_format() -> FormatConfig {
     " {$short_error_message|X} ".parse().unwrap()
 }
@@ -87,6 +266,18 @@ pub struct BlockConfigEntry {
     pub common: CommonBlockConfig,
     #[serde(flatten)]
     pub config: BlockConfig,
+    /// Must come after `config`, since flattened structs take the keys they know from the ones
+    /// left to the following fields
+    #[serde(flatten)]
+    pub demo: DemoConfig,
+}
+
+/// The options of a block which are used in demo mode
+#[derive(Deserialize, Debug, Default)]
+pub struct DemoConfig {
+    /// The `format` of the block, if it has one
+    #[serde(default)]
+    pub format: Option<toml::Value>,
 }
 
 #[derive(Deserialize, Debug, SmartDefault)]
@@ -94,19 +285,227 @@ pub struct BlockConfigEntry {
 pub struct CommonBlockConfig {
     pub click: ClickHandler,
     pub signal: Option<i32>,
+    pub toggle_signal: Option<i32>,
+    pub hidden: bool,
     pub icons_format: Option<String>,
     pub theme_overrides: Option<ThemeOverrides>,
     pub icons_overrides: Option<HashMap<String, Icon>>,
     pub merge_with_next: bool,
+    pub priority: i32,
+    pub urgent: Urgent,
+    pub scroll_interval: Option<u64>,
+    pub scroll_acceleration: Option<f64>,
 
     #[default(5)]
     pub error_interval: u64,
+    pub restart_policy: RestartPolicy,
+    pub max_restarts: Option<u32>,
+    #[default(1.0)]
+    pub error_backoff: f64,
+    pub watchdog_secs: Option<u64>,
+    pub watchdog_restart: bool,
     pub error_format: FormatConfig,
     pub error_fullscreen_format: FormatConfig,
 
     pub if_command: Option<String>,
 }
 
+/// Moves the common `formats` and `short_format` options of a block into its `format`, so that
+/// they only affect the main format of the block and not e.g. its `format_alt`.
+pub fn apply_format_options(table: &mut toml::Table) -> Result<()> {
+    let formats = table.remove("formats");
+    let short_format = table.remove("short_format");
+    if formats.is_none() && short_format.is_none() {
+        return Ok(());
+    }
+
+    let mut format = match table.remove("format") {
+        None => toml::Table::new(),
+        Some(toml::Value::String(full)) => toml::Table::from_iter([("full".into(), full.into())]),
+        Some(toml::Value::Table(format)) => format,
+        Some(_) => return Err(Error::new("`format` must be a string or a table")),
+    };
+    if let Some(formats) = formats {
+        let toml::Value::Array(mut formats) = formats else {
+            return Err(Error::new("`formats` must be a list of strings"));
+        };
+        if !formats.is_empty() {
+            format.insert("full".into(), formats.remove(0));
+            format.insert("narrower".into(), formats.into());
+        }
+    }
+    if let Some(short_format) = short_format {
+        format.insert("short".into(), short_format);
+    }
+    if !format.is_empty() {
+        table.insert("format".into(), format.into());
+    }
+    Ok(())
+}
+
+/// The states in which a block is marked as urgent, either `true` for the critical state or a list
+/// of states
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Urgent {
+    Critical(bool),
+    States(Vec<State>),
+}
+
+impl Default for Urgent {
+    fn default() -> Self {
+        Self::Critical(false)
+    }
+}
+
+impl Urgent {
+    pub fn contains(&self, state: State) -> bool {
+        match self {
+            Self::Critical(critical) => *critical && state == State::Critical,
+            Self::States(states) => states.contains(&state),
+        }
+    }
+}
+
+/// Whether a block is restarted automatically after an error
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    Never,
+    #[default]
+    OnFailure,
+}
+
+/// Replace the `include` key of a config file with the contents of the included files.
+///
+/// The included files are merged in order, followed by the including file itself. Arrays of blocks
+/// and bars are concatenated, other tables (such as `theme` and `icons`) are merged recursively and
+/// other values are overridden by the later files.
+fn resolve_includes(
+    path: &Path,
+    mut table: toml::Table,
+    stack: &mut Vec<PathBuf>,
+) -> Result<toml::Table> {
+    let Some(include) = table.remove("include") else {
+        return Ok(table);
+    };
+    let patterns: Vec<String> = include
+        .try_into()
+        .or_error(|| format!("'include' in {} must be a list of paths", path.display()))?;
+
+    // A configuration read from stdin has no canonical path, but it cannot be included either
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if stack.contains(&canonical) {
+        return Err(Error::new(format!(
+            "'{}' includes itself",
+            canonical.display()
+        )));
+    }
+    stack.push(canonical);
+
+    // Relative paths are relative to the including file
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut merged = toml::Table::new();
+    for pattern in patterns {
+        let pattern = dir.join(&*ShellString::new(pattern).expand()?);
+        let pattern = pattern.to_str().error("Include path is not valid UTF-8")?;
+        let mut found = false;
+        for file in
+            glob::glob(pattern).or_error(|| format!("Invalid include pattern '{pattern}'"))?
+        {
+            let file = file.error("Failed to read included path")?;
+            let included = util::deserialize_toml_file(&file)?;
+            merge_tables(&mut merged, resolve_includes(&file, included, stack)?);
+            found = true;
+        }
+        // A pattern with wildcards may match nothing (e.g. an empty directory), a plain path must exist
+        if !found && glob::Pattern::escape(pattern) == pattern {
+            return Err(Error::new(format!("Included file '{pattern}' not found")));
+        }
+    }
+
+    stack.pop();
+    merge_tables(&mut merged, table);
+    Ok(merged)
+}
+
+/// Replace the `template` key of each block with the contents of the `[templates.<name>]` table.
+///
+/// Options set in the block override the ones from the template, except for click handlers, which
+/// are concatenated (the block's handlers take precedence).
+fn apply_templates(config: &mut toml::Table) -> Result<()> {
+    let templates = match config.remove("templates") {
+        Some(toml::Value::Table(templates)) => templates,
+        Some(_) => return Err(Error::new("'templates' must be a table")),
+        None => toml::Table::new(),
+    };
+
+    fn apply(templates: &toml::Table, table: &mut toml::Table, key: &str) -> Result<()> {
+        let Some(toml::Value::Array(blocks)) = table.get_mut(key) else {
+            return Ok(());
+        };
+        for block in blocks {
+            let toml::Value::Table(block) = block else {
+                continue;
+            };
+            // Blocks may contain other blocks (e.g. `group`)
+            apply(templates, block, "blocks")?;
+            let Some(name) = block.remove("template") else {
+                continue;
+            };
+            let name = name.as_str().error("'template' must be a string")?;
+            let Some(toml::Value::Table(template)) = templates.get(name) else {
+                return Err(Error::new(format!("Template '{name}' not found")));
+            };
+            let mut merged = template.clone();
+            let template_click = merged.remove("click");
+            merge_tables(&mut merged, std::mem::take(block));
+            if let Some(toml::Value::Array(template_click)) = template_click {
+                match merged.entry("click") {
+                    toml::map::Entry::Occupied(mut e) => {
+                        if let toml::Value::Array(click) = e.get_mut() {
+                            click.extend(template_click);
+                        }
+                    }
+                    toml::map::Entry::Vacant(e) => {
+                        e.insert(toml::Value::Array(template_click));
+                    }
+                }
+            }
+            *block = merged;
+        }
+        Ok(())
+    }
+
+    apply(&templates, config, "block")?;
+    if let Some(toml::Value::Array(bars)) = config.get_mut("bar") {
+        for bar in bars {
+            if let toml::Value::Table(bar) = bar {
+                apply(&templates, bar, "block")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn merge_tables(base: &mut toml::Table, other: toml::Table) {
+    for (key, value) in other {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(other)) => {
+                merge_tables(base, other);
+            }
+            (Some(toml::Value::Array(base)), toml::Value::Array(other))
+                if key == "block" || key == "bar" =>
+            {
+                base.extend(other);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
 fn deserialize_theme_config<'de, D>(deserializer: D) -> Result<Arc<Theme>, D::Error>
 where
     D: Deserializer<'de>,
@@ -115,3 +514,156 @@ where
     let theme = Theme::try_from(theme_config).serde_error()?;
     Ok(Arc::new(theme))
 }
+
+fn deserialize_locale<'de, D>(deserializer: D) -> Result<Option<Locale>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let locale = String::deserialize(deserializer)?;
+    Locale::try_from(locale.as_str())
+        .map(Some)
+        .map_err(|_| format!("Invalid locale '{locale}'"))
+        .serde_error()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge() {
+        let mut base: toml::Table = toml::from_str(
+            r##"
+            icons_format = "{icon}"
+            [theme]
+            theme = "plain"
+            [theme.overrides]
+            idle_bg = "#000000"
+            [[block]]
+            block = "cpu"
+            "##,
+        )
+        .unwrap();
+        let other: toml::Table = toml::from_str(
+            r##"
+            icons_format = " {icon} "
+            [theme.overrides]
+            idle_fg = "#ffffff"
+            [[block]]
+            block = "time"
+            "##,
+        )
+        .unwrap();
+        merge_tables(&mut base, other);
+        let expected: toml::Table = toml::from_str(
+            r##"
+            icons_format = " {icon} "
+            [theme]
+            theme = "plain"
+            [theme.overrides]
+            idle_bg = "#000000"
+            idle_fg = "#ffffff"
+            [[block]]
+            block = "cpu"
+            [[block]]
+            block = "time"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(base, expected);
+    }
+
+    #[test]
+    fn templates() {
+        let mut config: toml::Table = toml::from_str(
+            r#"
+            [templates.cmd]
+            block = "custom"
+            interval = 10
+            [[templates.cmd.click]]
+            button = "left"
+            cmd = "foo"
+            [[block]]
+            template = "cmd"
+            command = "echo a"
+            [[block]]
+            template = "cmd"
+            command = "echo b"
+            interval = 5
+            [[block.click]]
+            button = "right"
+            cmd = "bar"
+            "#,
+        )
+        .unwrap();
+        apply_templates(&mut config).unwrap();
+        let expected: toml::Table = toml::from_str(
+            r#"
+            [[block]]
+            block = "custom"
+            interval = 10
+            command = "echo a"
+            [[block.click]]
+            button = "left"
+            cmd = "foo"
+            [[block]]
+            block = "custom"
+            interval = 5
+            command = "echo b"
+            [[block.click]]
+            button = "right"
+            cmd = "bar"
+            [[block.click]]
+            button = "left"
+            cmd = "foo"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn format_options() {
+        let mut block: toml::Table = toml::from_str(
+            r#"
+            block = "disk_space"
+            format = " $icon $available "
+            format_alt = " $icon $used / $total "
+            formats = [" $icon $available free ", " $icon "]
+            short_format = " $available "
+            "#,
+        )
+        .unwrap();
+        apply_format_options(&mut block).unwrap();
+        let expected: toml::Table = toml::from_str(
+            r#"
+            block = "disk_space"
+            format_alt = " $icon $used / $total "
+            [format]
+            full = " $icon $available free "
+            narrower = [" $icon "]
+            short = " $available "
+            "#,
+        )
+        .unwrap();
+        assert_eq!(block, expected);
+
+        let config: BlockConfig = toml::from_str(
+            r#"
+            block = "disk_space"
+            format = " $icon $available "
+            format_alt = " $icon $used / $total "
+            formats = [" $icon $available free ", " $icon "]
+            "#,
+        )
+        .unwrap();
+        let BlockConfig::disk_space(config) = config else {
+            panic!("disk_space config expected");
+        };
+        assert!(config.format.full.is_some());
+        assert_eq!(config.format.narrower.len(), 1);
+        assert!(config
+            .format_alt
+            .is_some_and(|format| format.narrower.is_empty()));
+    }
+}
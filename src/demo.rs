@@ -0,0 +1,225 @@
+//! Fake data for `--demo`
+//!
+//! In demo mode, blocks do not run at all. Instead, each block shows its `format` (or the default
+//! one) filled with plausible values, which are derived from the placeholder table of the block's
+//! documentation. This makes it possible to take screenshots and develop themes without the
+//! hardware, services or network the blocks would need.
+
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::blocks::{self, CommonApi};
+use crate::errors::*;
+use crate::formatting::config::Config as FormatConfig;
+use crate::formatting::intern::intern;
+use crate::formatting::value::Value;
+use crate::formatting::Values;
+use crate::widget::Widget;
+
+/// Shows fake values for `block` until the bar exits. `format` is the `format` option of the block,
+/// if any.
+pub(crate) async fn run(
+    block: &'static str,
+    format: Option<&toml::Value>,
+    api: &CommonApi,
+) -> Result<()> {
+    let doc = blocks::doc(block).unwrap_or_default();
+    let format: FormatConfig = match format {
+        Some(format) => format.clone().try_into().error("Invalid format")?,
+        None => FormatConfig::default(),
+    };
+    let default_format = default_format(doc).unwrap_or_else(|| format!(" {block} "));
+    let format = format.with_default(&default_format)?;
+
+    let icons = icons(doc);
+    let mut values = Values::new();
+    // Some blocks do not document their `$icon`
+    if let Some(icon) = icons.first() {
+        values.insert("icon", Value::icon(icon));
+    }
+    for (name, ty, unit) in placeholders(doc) {
+        if let Some(value) = fake_value(name, &ty, &unit, &icons) {
+            values.insert(name, value);
+        }
+    }
+
+    let mut widget = Widget::new().with_format(format);
+    widget.set_values(values);
+    loop {
+        api.set_widget(widget.clone())?;
+        api.wait_for_update_request().await;
+    }
+}
+
+/// Splits a row of a markdown table into its trimmed cells. Escaped pipes (`\|`) are kept.
+fn cells(row: &str) -> Vec<String> {
+    row.replace("\\|", "\0")
+        .split('|')
+        .map(|cell| cell.trim().replace('\0', "|"))
+        .collect()
+}
+
+/// The default of the `format` option, e.g. `" $icon $utilization "`. Blocks without `format` use
+/// their first format option, such as `format_connected`.
+fn default_format(doc: &str) -> Option<String> {
+    let key = |line: &&str| cells(line)[0].clone();
+    let row = doc
+        .lines()
+        .find(|line| key(line) == "`format`")
+        .or_else(|| doc.lines().find(|line| key(line).starts_with("`format_")))?;
+    // Some defaults contain unescaped pipes, so take everything after the description
+    let default = row.splitn(3, " | ").nth(2)?.trim();
+    let default = default
+        .replace("\\|", "|")
+        .replace("<code>", "")
+        .replace("</code>", "")
+        .replace("\\\"", "\"");
+    let default = default.trim_matches('`');
+    match default.strip_prefix('"').and_then(|d| d.strip_suffix('"')) {
+        Some(format) => Some(format.to_owned()),
+        None => default.contains('$').then(|| default.to_owned()),
+    }
+}
+
+/// The names, types and units of the placeholders of a block. Tables without a type column are
+/// assumed to contain numbers.
+fn placeholders(doc: &str) -> Vec<(&'static str, String, String)> {
+    let mut placeholders = Vec::new();
+    // The indices of the type and unit columns of the current table
+    let mut columns = None;
+    for line in doc.lines() {
+        let cells = cells(line);
+        // Some placeholder tables start with a `Key` column
+        if cells[0] == "Placeholder" || cells.iter().any(|cell| cell == "Type") {
+            let column = |name| cells.iter().position(|cell| cell == name);
+            columns = Some((column("Type"), column("Unit")));
+            continue;
+        }
+        if cells.len() < 2 {
+            columns = None;
+        }
+        let Some((ty, unit)) = columns else {
+            continue;
+        };
+        let name = cells[0].trim_matches('`');
+        // Skip numbered placeholders such as `utilization<N>`
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            continue;
+        }
+        let cell = |i: Option<usize>| i.and_then(|i| cells.get(i)).cloned();
+        let ty = match cell(ty) {
+            Some(ty) => ty,
+            None if name == "icon" => "Icon".into(),
+            None => "Number".into(),
+        };
+        placeholders.push((intern(name), ty, cell(unit).unwrap_or_default()));
+    }
+    placeholders
+}
+
+/// The icons listed under "Icons Used"
+fn icons(doc: &str) -> Vec<&str> {
+    doc.lines()
+        .skip_while(|line| {
+            let heading = line.trim_start_matches('#').trim();
+            heading != "Icons Used" && heading != "Used Icons"
+        })
+        .filter_map(|line| line.strip_prefix("- `")?.split('`').next())
+        .collect()
+}
+
+/// A plausible value for a placeholder
+fn fake_value(name: &str, ty: &str, unit: &str, icons: &[&str]) -> Option<Value> {
+    let unit = unit.to_ascii_lowercase();
+    Some(match ty.split_whitespace().next().unwrap_or_default() {
+        "Icon" => {
+            let icon = icons
+                .iter()
+                .find(|icon| name != "icon" && icon.contains(name))
+                .or(icons.first())?;
+            Value::icon(icon)
+        }
+        "Flag" => Value::flag(),
+        "Duration" => Value::duration(Duration::from_secs(5400)),
+        "Datetime" => Value::datetime(Utc::now(), None),
+        "Number" | "Bytes" if unit.contains("bits") => Value::bits(48e6),
+        "Number" | "Bytes" if unit.contains("bytes") && unit.contains("second") => {
+            Value::bytes(1.2e6)
+        }
+        "Number" | "Bytes" if unit.contains("bytes") || ty == "Bytes" => Value::bytes(3.2e9),
+        "Number" if unit.contains('%') || unit.contains("percent") => Value::percents(42),
+        "Number" if unit.contains("hz") => Value::hertz(2.4e9),
+        "Number" if unit.contains("degree") => Value::degrees(47),
+        "Number" if unit.contains("second") => Value::seconds(90),
+        "Number" if unit.contains("watt") => Value::watts(15),
+        "Number" => Value::number(3),
+        _ => Value::text(name.replace('_', " ")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "\
+Key | Values | Default
+----|--------|--------
+`format` | A string to customise the output of this block. | <code>\\\" $icon {$title\\|none} \\\"</code>
+`interval` | Update interval in seconds | `5`
+
+Placeholder      | Value                       | Type   | Unit
+-----------------|-----------------------------|--------|-----
+`icon`           | A static icon               | Icon   | -
+`utilization`    | Average CPU utilization     | Number | %
+`utilization<N>` | Utilization of Nth CPU      | Number | %
+`title`          | The title                   | Text   | -
+
+# Icons Used
+- `cpu` (as a progression)
+- `cpu_boost_on`
+";
+
+    #[test]
+    fn parse_doc() {
+        assert_eq!(
+            default_format(DOC).as_deref(),
+            Some(" $icon {$title|none} ")
+        );
+        assert_eq!(
+            placeholders(DOC),
+            [
+                ("icon", "Icon".into(), "-".into()),
+                ("utilization", "Number".into(), "%".into()),
+                ("title", "Text".into(), "-".into()),
+            ]
+        );
+        assert_eq!(icons(DOC), ["cpu", "cpu_boost_on"]);
+    }
+
+    #[test]
+    fn values() {
+        let icons = ["cpu", "cpu_boost_on"];
+        let fake = |name, ty, unit, icons: &[&str]| {
+            fake_value(name, ty, unit, icons).map(|value| format!("{:?}", value.inner))
+        };
+        let expected = |value: Value| Some(format!("{:?}", value.inner));
+        assert_eq!(
+            fake("icon", "Icon", "-", &icons),
+            expected(Value::icon("cpu"))
+        );
+        assert_eq!(
+            fake("boost", "Icon", "-", &icons),
+            expected(Value::icon("cpu_boost_on"))
+        );
+        assert_eq!(fake("icon", "Icon", "-", &[]), None);
+        assert_eq!(
+            fake("speed_down", "Number", "Bytes per second", &icons),
+            expected(Value::bytes(1.2e6))
+        );
+        assert_eq!(
+            fake("window_title", "Text", "-", &icons),
+            expected(Value::text("window title".into()))
+        );
+    }
+}
@@ -37,6 +37,9 @@
 //! `width` or `w`         | Text will be exactly this length by padding or truncating as needed | N/A
 //! `rot_interval`         | if text is longer than `max_width` it will be rotated every `rot_interval` seconds, if set | None
 //! `rot_separator`        | if text is longer than `max_width` it will be rotated with this seporator | <code>\"\|\"</code>
+//! `rot_style`            | how text is rotated: `scroll` wraps around, `bounce` scrolls to the end and back, `reset` scrolls to the end and jumps back to the start | `scroll`
+//! `rot_pause`            | how long (in seconds) rotated text stays at its start, and for `bounce` and `reset` at its end | `0`
+//! `align`                | where to put the text when it is padded: `left`, `right` or `center`      | `left`
 //!
 //! Note: width just changes the values of both min_width and max_width to be the same. Use width
 //! if you want the values to be the same, or the other two otherwise. Don't mix width with
@@ -47,16 +50,23 @@
 //! Argument        | Description                                                                                      |Default value
 //! ----------------|--------------------------------------------------------------------------------------------------|-------------
 //! `width` or `w`  | the resulting text will be at least `width` characters long                                      | `2`
-//! `unit` or `u`   | some values have a [unit](unit::Unit), and it is possible to convert them by setting this option | N/A
+//! `unit` or `u`   | some values have a [unit](unit::Unit), and it is possible to convert them by setting this option. Append `/s` to display a rate, e.g. `unit:'B/s'`. | N/A
 //! `hide_unit`     | hide the unit symbol                                                                             | `false`
 //! `unit_space`    | have a whitespace before unit symbol                                                             | `false`
 //! `prefix` or `p` | specify this argument if you want to set the minimal [SI prefix](prefix::Prefix)                 | N/A
 //! `hide_prefix`   | hide the prefix symbol                                                                           | `false`
 //! `prefix_space`  | have a whitespace before prefix symbol                                                           | `false`
 //! `force_prefix`  | force the prefix value instead of setting a "minimal prefix"                                     | `false`
+//! `force_binary`  | use binary (IEC) prefixes such as `Mi`, even if `prefix` is decimal                              | `false`
+//! `force_decimal` | use decimal (SI) prefixes such as `M`, even if `prefix` is binary                                | `false`
 //! `pad_with`      | the character that is used to pad the number to be `width` long                                  | ` ` (a space)
 //! `range`         | a range of allowed values, in the format `<start>..<end>`, inclusive. Both start and end are optional. Can be used to, for example, hide the block when the value is not in a given range. | `..`
 //! `show`          | show this value. Can be used with `range` for conditional formatting                             | `true`
+//! `text_width`    | pad the whole text, including the prefix and the unit, to this many characters. Unlike `width`, which only counts the number, this keeps the column of the following text fixed. The text is never truncated. | N/A
+//! `align`         | where to put the text when it is padded to `text_width`: `left`, `right` or `center`             | `right`
+//!
+//! If the global `locale` option is set, the number is formatted with its decimal separator and
+//! digit grouping, e.g. `1.234,5` for `de_DE`.
 //!
 //! ## `bar` - Display numbers as progress bars
 //!
@@ -65,6 +75,20 @@
 //! `width` or `w`         | the width of the bar (in characters)                                            | `5` (`1` for `vertical`)
 //! `max_value`            | which value is treated as "full". For example, for battery level `100` is full. | `100`
 //! `vertical` or `v`      | whether to render the bar vertically or not                                     | `false`
+//! `style`                | `block` for a bar of block elements or `line` for a thin line. `line` does not support `vertical`. | `block`
+//!
+//! ## `sparkline` - Display the recent history of a number
+//!
+//! The formatter records one value every `interval` seconds and displays the last `n` values as a
+//! graph, for example `$utilization.sparkline(n:20, max:100)`.
+//!
+//! Argument   | Description                                                                          |Default value
+//! -----------|--------------------------------------------------------------------------------------|-------------
+//! `n`        | the number of values to display                                                      | `10`
+//! `interval` | how often to record a value, in seconds                                              | `5`
+//! `min`      | which value is displayed as the lowest bar                                           | `0`
+//! `max`      | which value is displayed as the highest bar                                          | the largest displayed value
+//! `braille`  | whether to use braille patterns, which fit two values into a character, instead of bars | `false`
 //!
 //! ## `tally` - Display numbers as tally marks
 //!
@@ -81,7 +105,7 @@
 //! Argument               | Description                                                                                               |Default value
 //! -----------------------|-----------------------------------------------------------------------------------------------------------|-------------
 //! `format` or `f`        | [chrono docs](https://docs.rs/chrono/0.3.0/chrono/format/strftime/index.html#specifiers) for all options. | `'%a %d/%m %R'`
-//! `locale` or `l`        | Locale to apply when formatting the time                                                                  | System locale
+//! `locale` or `l`        | Locale to apply when formatting the time                                                                  | The global `locale`
 //!
 //!
 //! ## `duration`/`dur` - Format durations
@@ -107,6 +131,55 @@
 //! s    | seconds
 //! ms   | milliseconds
 //!
+//! # Filters
+//!
+//! Filters transform text before it is formatted. Any number of them can be chained, but they
+//! must come before the formatter, e.g. `$title.replace(from:' - Mozilla Firefox').lower().str(max_w:20)`.
+//!
+//! Filter    | Description
+//! ----------|------------
+//! `upper`   | converts the text to uppercase
+//! `lower`   | converts the text to lowercase
+//! `trim`    | removes leading and trailing whitespace
+//! `replace` | replaces every occurrence of `from` with `to` (by default, an empty string). With `regex:true`, `from` is a [regular expression](https://docs.rs/regex/latest/regex/#syntax) and `to` can refer to its groups with `$1`, `$2` and so on.
+//!
+//! # Colors
+//!
+//! A number can be colored depending on its value, independently of the state of the block, by
+//! adding `.color(<threshold>:<color>, ...)` after its formatter. The color of the highest
+//! threshold which the value reaches is used, and values below all thresholds are not colored. A
+//! color is either the name of a state (`idle`, `info`, `good`, `warning` or `critical`), which
+//! refers to its foreground color in the theme, or a color in quotes, e.g. `'#ff0000'`. For
+//! example, `$utilization.eng(w:2).color(50:warning, 80:critical)` or
+//! `$temperature.color(0:'#88c0d0', 60:'#ebcb8b', 85:'#bf616a')`.
+//!
+//! # Styles
+//!
+//! Any placeholder can be emphasized by adding `.style(...)` after its formatter, which wraps the
+//! value in pango markup. For example, `$title.str(max_w:20).style(weight:bold, italic:true)` or
+//! `$percentage.style(size:small, underline:single)`. `.style()` and `.color()` can be combined.
+//!
+//! Argument        | Description
+//! ----------------|------------
+//! `weight`        | `thin`, `ultralight`, `light`, `semilight`, `book`, `normal`, `medium`, `semibold`, `bold`, `ultrabold`, `heavy`, `ultraheavy` or a number between 100 and 1000
+//! `italic`        | `true` or `false`
+//! `underline`     | `none`, `single`, `double`, `low` or `error`
+//! `strikethrough` | `true` or `false`
+//! `size`          | `xx-small`, `x-small`, `small`, `medium`, `large`, `x-large`, `xx-large`, `smaller`, `larger`, a size in points such as `'10pt'`, a percentage such as `'80%'` or a number in 1024ths of a point
+//!
+//! # Arithmetic
+//!
+//! Numeric placeholders can be combined with `+`, `-`, `*`, `/` and parentheses inside of `$(...)`.
+//! The result is a new number, which can be formatted like any other one. For example,
+//! `$($mem_used / $mem_total * 100).eng(w:2)%` shows the used memory in percents and
+//! `$($speed_down * 8)` multiplies a speed by eight. The unit of a placeholder is kept when it is
+//! multiplied or divided by a plain number, and dropped when it is divided by another placeholder.
+//! Outside of `$(...)`, operators are displayed as text, so `$used/$total` still shows e.g.
+//! `3/4`.
+//!
+//! If a placeholder in the expression is missing or not a number, or the result is not finite
+//! (e.g. after a division by zero), the next format separated by `|` is tried, as described below.
+//!
 //! # Handling missing placeholders and incorrect types
 //!
 //! Some blocks allow missing placeholders, for example [bluetooth](crate::blocks::bluetooth)'s
@@ -129,8 +202,11 @@
 //! $a{a is set}|$b$c{b and c are set}|${b|c}{b or c is set}|neither flag is set
 //! ```
 
+pub mod cache;
 pub mod config;
+pub mod expr;
 pub mod formatter;
+pub mod intern;
 pub mod parse;
 pub mod prefix;
 pub mod scheduling;
@@ -138,15 +214,19 @@ pub mod template;
 pub mod unit;
 pub mod value;
 
-use std::borrow::Cow;
 use std::collections::HashMap;
 
 use crate::config::SharedConfig;
 use crate::errors::*;
+use crate::widget::State;
+use cache::RenderCache;
+use intern::Istr;
+use scheduling::Interval;
 use template::FormatTemplate;
 use value::Value;
 
-pub type Values = HashMap<Cow<'static, str>, Value>;
+/// Placeholder values. Keys are either string literals or [interned](intern::intern) strings.
+pub type Values = HashMap<&'static str, Value>;
 
 #[derive(Debug, thiserror::Error)]
 pub enum FormatError {
@@ -156,6 +236,8 @@ pub enum FormatError {
     IncompatibleFormatter { ty: &'static str, fmt: &'static str },
     #[error("Number {0} is out of range")]
     NumberOutOfRange(f64),
+    #[error("{0} cannot be used in arithmetic")]
+    NotANumber(&'static str),
     #[error(transparent)]
     Other(#[from] Error),
 }
@@ -164,7 +246,9 @@ pub enum FormatError {
 pub struct Format {
     full: FormatTemplate,
     short: FormatTemplate,
-    intervals: Vec<u64>,
+    /// The narrower alternatives of `full` to which the block degrades if the bar is too wide
+    narrower: Vec<FormatTemplate>,
+    intervals: Vec<Interval>,
 }
 
 impl Format {
@@ -172,22 +256,39 @@ impl Format {
         self.full.contains_key(key) || self.short.contains_key(key)
     }
 
-    pub fn intervals(&self) -> Vec<u64> {
+    pub fn intervals(&self) -> Vec<Interval> {
         self.intervals.clone()
     }
 
+    /// Replaces the template of the full text
+    pub fn set_full(&mut self, full: FormatTemplate) {
+        self.full = full;
+        self.init_intervals();
+    }
+
+    pub fn narrower(&self) -> &[FormatTemplate] {
+        &self.narrower
+    }
+
+    fn init_intervals(&mut self) {
+        self.intervals.clear();
+        self.full.init_intervals(&mut self.intervals);
+        self.short.init_intervals(&mut self.intervals);
+    }
+
     pub fn render(
         &self,
         values: &Values,
         config: &SharedConfig,
+        cache: &RenderCache,
     ) -> Result<(Vec<Fragment>, Vec<Fragment>)> {
         let full = self
             .full
-            .render(values, config)
+            .render(values, config, cache)
             .error("Failed to render full text")?;
         let short = self
             .short
-            .render(values, config)
+            .render(values, config, cache)
             .error("Failed to render short text")?;
         Ok((full, short))
     }
@@ -221,9 +322,11 @@ impl Fragment {
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Metadata {
-    pub instance: Option<&'static str>,
+    pub instance: Option<Istr>,
     pub underline: bool,
     pub italic: bool,
+    /// Overrides the widget's state (and thus colors) for this part of the widget
+    pub state: Option<State>,
 }
 
 impl Metadata {
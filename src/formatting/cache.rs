@@ -0,0 +1,80 @@
+//! Memoization of rendered placeholders and icons
+//!
+//! Widgets with time-dependent formatters (e.g. rotating text) are re-rendered periodically even if
+//! their values did not change. To avoid escaping the same strings and looking up the same icons
+//! over and over again, the results are cached per token. The cache must be cleared whenever the
+//! values or the format change.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use super::template::Token;
+
+#[derive(Default)]
+pub struct RenderCache(Mutex<HashMap<usize, String>>);
+
+impl RenderCache {
+    pub fn clear(&mut self) {
+        self.0.get_mut().unwrap().clear();
+    }
+
+    /// Returns the cached string for this token or computes and caches a new one
+    pub fn get_or_insert<E>(
+        &self,
+        token: &Token,
+        f: impl FnOnce() -> Result<String, E>,
+    ) -> Result<String, E> {
+        // Tokens are immutable and live as long as the format, so their address is a stable key
+        let key = token as *const Token as usize;
+        if let Some(cached) = self.0.lock().unwrap().get(&key) {
+            return Ok(cached.clone());
+        }
+        let rendered = f()?;
+        self.0.lock().unwrap().insert(key, rendered.clone());
+        Ok(rendered)
+    }
+}
+
+/// Cloned widgets may get different values, so the cache is not shared
+impl Clone for RenderCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for RenderCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RenderCache").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caches_per_token() {
+        let cache = RenderCache::default();
+        let a = Token::Text("a".into());
+        let b = Token::Text("b".into());
+        let ok = |s: &str| Ok::<_, ()>(s.to_owned());
+
+        assert_eq!(cache.get_or_insert(&a, || ok("first")), Ok("first".into()));
+        assert_eq!(cache.get_or_insert(&a, || ok("second")), Ok("first".into()));
+        assert_eq!(cache.get_or_insert(&b, || ok("other")), Ok("other".into()));
+        assert_eq!(cache.get_or_insert(&b, || Err(())), Ok("other".into()));
+    }
+
+    #[test]
+    fn clear() {
+        let mut cache = RenderCache::default();
+        let a = Token::Text("a".into());
+        let _ = cache.get_or_insert(&a, || Ok::<_, ()>("first".into()));
+        cache.clear();
+        assert_eq!(
+            cache.get_or_insert(&a, || Ok::<_, ()>("second".into())),
+            Ok("second".into())
+        );
+    }
+}
@@ -9,6 +9,8 @@ use std::str::FromStr;
 pub struct Config {
     pub full: Option<FormatTemplate>,
     pub short: Option<FormatTemplate>,
+    /// The narrower alternatives of `full`, from the common `formats` option of blocks
+    pub narrower: Vec<FormatTemplate>,
 }
 
 impl Config {
@@ -34,6 +36,7 @@ impl Config {
         Ok(Format {
             full,
             short,
+            narrower: self.narrower.clone(),
             intervals,
         })
     }
@@ -57,11 +60,16 @@ impl Config {
         Format {
             full,
             short,
+            narrower: self.narrower.clone(),
             intervals,
         }
     }
 
     pub fn with_default_format(&self, default_format: &Format) -> Format {
+        let narrower = match &self.full {
+            Some(_) => self.narrower.clone(),
+            None => default_format.narrower.clone(),
+        };
         let full = self
             .full
             .clone()
@@ -78,6 +86,7 @@ impl Config {
         Format {
             full,
             short,
+            narrower,
             intervals,
         }
     }
@@ -90,6 +99,7 @@ impl FromStr for Config {
         Ok(Self {
             full: Some(s.parse()?),
             short: None,
+            narrower: Vec::new(),
         })
     }
 }
@@ -104,6 +114,7 @@ impl<'de> Deserialize<'de> for Config {
         enum Field {
             Full,
             Short,
+            Narrower,
         }
 
         struct FormatTemplateVisitor;
@@ -140,6 +151,7 @@ impl<'de> Deserialize<'de> for Config {
             {
                 let mut full: Option<FormatTemplate> = None;
                 let mut short: Option<FormatTemplate> = None;
+                let mut narrower = Vec::new();
                 while let Some(key) = map.next_key()? {
                     match key {
                         Field::Full => {
@@ -154,9 +166,21 @@ impl<'de> Deserialize<'de> for Config {
                             }
                             short = Some(map.next_value::<String>()?.parse().serde_error()?);
                         }
+                        Field::Narrower => {
+                            narrower = map
+                                .next_value::<Vec<String>>()?
+                                .iter()
+                                .map(|format| format.parse())
+                                .collect::<Result<_>>()
+                                .serde_error()?;
+                        }
                     }
                 }
-                Ok(Config { full, short })
+                Ok(Config {
+                    full,
+                    short,
+                    narrower,
+                })
             }
         }
 
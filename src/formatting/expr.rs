@@ -0,0 +1,161 @@
+//! Arithmetic on placeholders
+//!
+//! Expressions such as `$($mem_used / $mem_total * 100)` are evaluated on every render and produce
+//! a new number, which is then displayed like any other numeric placeholder.
+
+use super::unit::Unit;
+use super::value::{Value, ValueInner};
+use super::{FormatError, Values};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Placeholder(String),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Op {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '+' => Some(Self::Add),
+            '-' => Some(Self::Sub),
+            '*' => Some(Self::Mul),
+            '/' => Some(Self::Div),
+            _ => None,
+        }
+    }
+}
+
+impl Expr {
+    pub fn contains_key(&self, key: &str) -> bool {
+        match self {
+            Self::Number(_) => false,
+            Self::Placeholder(name) => name == key,
+            Self::Neg(expr) => expr.contains_key(key),
+            Self::Binary(_, lhs, rhs) => lhs.contains_key(key) || rhs.contains_key(key),
+        }
+    }
+
+    /// Calls `f` with the name of every placeholder in the expression
+    pub fn for_each_placeholder(&self, f: &mut impl FnMut(&str)) {
+        match self {
+            Self::Number(_) => (),
+            Self::Placeholder(name) => f(name),
+            Self::Neg(expr) => expr.for_each_placeholder(f),
+            Self::Binary(_, lhs, rhs) => {
+                lhs.for_each_placeholder(f);
+                rhs.for_each_placeholder(f);
+            }
+        }
+    }
+
+    /// Evaluates the expression into a number
+    pub fn eval(&self, values: &Values) -> Result<Value, FormatError> {
+        let (val, unit) = self.eval_inner(values)?;
+        if !val.is_finite() {
+            return Err(FormatError::NumberOutOfRange(val));
+        }
+        Ok(Value::number_unit(val, unit))
+    }
+
+    fn eval_inner(&self, values: &Values) -> Result<(f64, Unit), FormatError> {
+        match self {
+            Self::Number(val) => Ok((*val, Unit::None)),
+            Self::Placeholder(name) => {
+                let value = values
+                    .get(name.as_str())
+                    .ok_or_else(|| FormatError::PlaceholderNotFound(name.clone()))?;
+                match value.inner {
+                    ValueInner::Number { val, unit } => Ok((val, unit)),
+                    ref other => Err(FormatError::NotANumber(other.type_name())),
+                }
+            }
+            Self::Neg(expr) => {
+                let (val, unit) = expr.eval_inner(values)?;
+                Ok((-val, unit))
+            }
+            Self::Binary(op, lhs, rhs) => {
+                let (lhs, lhs_unit) = lhs.eval_inner(values)?;
+                let (rhs, rhs_unit) = rhs.eval_inner(values)?;
+                // Keep the unit as long as the other operand is a plain number, so that e.g.
+                // `$speed * 8` is still a speed. Otherwise the unit would be meaningless.
+                let unit = match (lhs_unit, rhs_unit) {
+                    (unit, Unit::None) => unit,
+                    (Unit::None, unit) if matches!(op, Op::Add | Op::Sub | Op::Mul) => unit,
+                    (lhs_unit, rhs_unit)
+                        if lhs_unit == rhs_unit && matches!(op, Op::Add | Op::Sub) =>
+                    {
+                        lhs_unit
+                    }
+                    _ => Unit::None,
+                };
+                let val = match op {
+                    Op::Add => lhs + rhs,
+                    Op::Sub => lhs - rhs,
+                    Op::Mul => lhs * rhs,
+                    Op::Div => lhs / rhs,
+                };
+                Ok((val, unit))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatting::parse::{parse_full, Token};
+
+    fn eval(expr: &str, values: &Values) -> Result<(f64, Unit), FormatError> {
+        let format = format!("$({expr})");
+        let mut template = parse_full(&format).unwrap();
+        let Some(Token::Expression(expression)) = template.0[0].0.pop() else {
+            panic!("not an expression: {expr}");
+        };
+        match expression.expr.eval(values)?.inner {
+            ValueInner::Number { val, unit } => Ok((val, unit)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        let values = Values::from([
+            ("used", Value::bytes(3.0)),
+            ("total", Value::bytes(4.0)),
+            ("name", Value::text("x".into())),
+        ]);
+        assert_eq!(
+            eval("$used / $total * 100", &values).unwrap(),
+            (75.0, Unit::None)
+        );
+        assert_eq!(eval("$used*8", &values).unwrap(), (24.0, Unit::Bytes));
+        assert_eq!(
+            eval("2 * ($total - $used)", &values).unwrap(),
+            (2.0, Unit::Bytes)
+        );
+        assert_eq!(eval("-$used + 1", &values).unwrap(), (-2.0, Unit::Bytes));
+        assert_eq!(eval("1 - 2 - 3", &values).unwrap(), (-4.0, Unit::None));
+        assert!(matches!(
+            eval("$missing + 1", &values),
+            Err(FormatError::PlaceholderNotFound(_))
+        ));
+        assert!(matches!(
+            eval("$name + 1", &values),
+            Err(FormatError::NotANumber("Text"))
+        ));
+        assert!(matches!(
+            eval("$used / 0", &values),
+            Err(FormatError::NumberOutOfRange(_))
+        ));
+    }
+}
@@ -1,6 +1,6 @@
 use unicode_segmentation::UnicodeSegmentation;
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{borrow::Cow, fmt::Debug};
 
 use super::parse::Arg;
@@ -25,6 +25,8 @@ macro_rules! new_fmt {
 
 mod bar;
 pub use bar::BarFormatter;
+mod color;
+pub use color::ColorThresholds;
 mod tally;
 pub use tally::TallyFormatter;
 mod datetime;
@@ -33,10 +35,16 @@ mod duration;
 pub use duration::{DurationFormatter, DEFAULT_DURATION_FORMATTER};
 mod eng;
 pub use eng::{EngFormatter, DEFAULT_NUMBER_FORMATTER};
+mod filter;
+pub use filter::Filter;
 mod flag;
 pub use flag::{FlagFormatter, DEFAULT_FLAG_FORMATTER};
 mod pango;
 pub use pango::PangoStrFormatter;
+mod sparkline;
+pub use sparkline::SparklineFormatter;
+mod style;
+pub use style::Style;
 mod str;
 pub use str::{StrFormatter, DEFAULT_STRING_FORMATTER};
 
@@ -44,12 +52,46 @@ type PadWith = Cow<'static, str>;
 
 const DEFAULT_NUMBER_PAD_WITH: PadWith = Cow::Borrowed(" ");
 
+/// Where text is placed when it is padded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    Right,
+    Center,
+}
+
+impl Alignment {
+    fn from_arg(arg: &Arg) -> Result<Self> {
+        match arg.val {
+            "left" | "l" => Ok(Self::Left),
+            "right" | "r" => Ok(Self::Right),
+            "center" | "c" => Ok(Self::Center),
+            _ => Err(Error::new("align must be left, right or center")),
+        }
+    }
+
+    /// Returns how much padding goes before and after text which is `padding` characters
+    /// narrower than its column
+    fn split(self, padding: usize) -> (usize, usize) {
+        match self {
+            Self::Left => (0, padding),
+            Self::Right => (padding, 0),
+            Self::Center => (padding / 2, padding - padding / 2),
+        }
+    }
+}
+
 pub trait Formatter: Debug + Send + Sync {
     fn format(&self, val: &Value, config: &SharedConfig) -> Result<String, FormatError>;
 
     fn interval(&self) -> Option<Duration> {
         None
     }
+
+    /// The instant the periods of `interval` are counted from, if the output depends on it
+    fn epoch(&self) -> Option<Instant> {
+        None
+    }
 }
 
 pub fn new_formatter(name: &str, args: &[Arg]) -> Result<Box<dyn Formatter>> {
@@ -59,6 +101,7 @@ pub fn new_formatter(name: &str, args: &[Arg]) -> Result<Box<dyn Formatter>> {
         "dur" | "duration" => Ok(Box::new(DurationFormatter::from_args(args)?)),
         "eng" => Ok(Box::new(EngFormatter::from_args(args)?)),
         "pango-str" => Ok(Box::new(PangoStrFormatter::from_args(args)?)),
+        "sparkline" => Ok(Box::new(SparklineFormatter::from_args(args)?)),
         "str" => Ok(Box::new(StrFormatter::from_args(args)?)),
         "tally" => Ok(Box::new(TallyFormatter::from_args(args)?)),
         _ => Err(Error::new(format!("Unknown formatter: '{name}'"))),
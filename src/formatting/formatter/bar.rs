@@ -10,6 +10,15 @@ pub struct BarFormatter {
     width: usize,
     max_value: f64,
     vertical: bool,
+    style: BarStyle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BarStyle {
+    /// Block elements with a resolution of 1/8 of a character
+    Block,
+    /// A thin line which is thicker where filled
+    Line,
 }
 
 impl BarFormatter {
@@ -17,6 +26,7 @@ impl BarFormatter {
         let mut vertical = DEFAULT_BAR_VERTICAL;
         let mut width = None;
         let mut max_value = DEFAULT_BAR_MAX_VAL;
+        let mut style = BarStyle::Block;
         for arg in args {
             match arg.key {
                 "width" | "w" => {
@@ -28,11 +38,25 @@ impl BarFormatter {
                 "vertical" | "v" => {
                     vertical = arg.val.parse().error("Vertical value must be a bool")?;
                 }
+                "style" => {
+                    style = match arg.val {
+                        "block" => BarStyle::Block,
+                        "line" => BarStyle::Line,
+                        other => {
+                            return Err(Error::new(format!(
+                                "Style must be 'block' or 'line', got '{other}'"
+                            )));
+                        }
+                    };
+                }
                 other => {
                     return Err(Error::new(format!("Unknown argument for 'bar': '{other}'")));
                 }
             }
         }
+        if vertical && style == BarStyle::Line {
+            return Err(Error::new("Vertical bars do not support the 'line' style"));
+        }
         Ok(Self {
             width: width.unwrap_or(match vertical {
                 false => DEFAULT_BAR_WIDTH_HORIZONTAL,
@@ -40,6 +64,7 @@ impl BarFormatter {
             }),
             max_value,
             vertical,
+            style,
         })
     }
 }
@@ -49,7 +74,10 @@ const HORIZONTAL_BAR_CHARS: [char; 9] = [
     '\u{2588}',
 ];
 
-const VERTICAL_BAR_CHARS: [char; 9] = [
+/// Empty, half filled and filled
+const LINE_BAR_CHARS: [char; 3] = ['\u{2500}', '\u{2578}', '\u{2501}'];
+
+pub(super) const VERTICAL_BAR_CHARS: [char; 9] = [
     ' ', '\u{2581}', '\u{2582}', '\u{2583}', '\u{2584}', '\u{2585}', '\u{2586}', '\u{2587}',
     '\u{2588}',
 ];
@@ -62,6 +90,11 @@ impl Formatter for BarFormatter {
                 if self.vertical {
                     let vert_char = VERTICAL_BAR_CHARS[(val * 8.) as usize];
                     Ok((0..self.width).map(|_| vert_char).collect())
+                } else if self.style == BarStyle::Line {
+                    let halves_to_fill = (val * self.width as f64 * 2.).round() as usize;
+                    Ok((0..self.width)
+                        .map(|i| LINE_BAR_CHARS[halves_to_fill.saturating_sub(i * 2).min(2)])
+                        .collect())
                 } else {
                     let chars_to_fill = val * self.width as f64;
                     Ok((0..self.width)
@@ -79,3 +112,31 @@ impl Formatter for BarFormatter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatting::unit::Unit;
+
+    #[test]
+    fn bar_styles() {
+        let config = SharedConfig::default();
+        let val = Value::Number {
+            val: 60.,
+            unit: Unit::Percents,
+        };
+
+        let fmt = BarFormatter::from_args(&[Arg { key: "w", val: "4" }]).unwrap();
+        assert_eq!(fmt.format(&val, &config).unwrap(), "██\u{258d} ");
+
+        let fmt = BarFormatter::from_args(&[
+            Arg { key: "w", val: "4" },
+            Arg {
+                key: "style",
+                val: "line",
+            },
+        ])
+        .unwrap();
+        assert_eq!(fmt.format(&val, &config).unwrap(), "━━╸─");
+    }
+}
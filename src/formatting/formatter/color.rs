@@ -0,0 +1,107 @@
+use super::*;
+use crate::themes::color::Color;
+use crate::widget::State;
+
+/// Colors a formatted number depending on its value, e.g. in
+/// `$utilization.eng(w:2).color(50:warning, 80:critical)`
+#[derive(Debug)]
+pub struct ColorThresholds(Vec<(f64, ThresholdColor)>);
+
+#[derive(Debug, Clone, Copy)]
+enum ThresholdColor {
+    /// The foreground color of a state in the current theme
+    State(State),
+    Color(Color),
+}
+
+impl ColorThresholds {
+    pub fn from_args(args: &[Arg]) -> Result<Self> {
+        let mut thresholds = args
+            .iter()
+            .map(|arg| {
+                let threshold: f64 = arg
+                    .key
+                    .parse()
+                    .or_error(|| format!("Threshold '{}' must be a number", arg.key))?;
+                let color = match arg.val.to_ascii_lowercase().as_str() {
+                    "idle" => ThresholdColor::State(State::Idle),
+                    "info" => ThresholdColor::State(State::Info),
+                    "good" => ThresholdColor::State(State::Good),
+                    "warning" => ThresholdColor::State(State::Warning),
+                    "critical" => ThresholdColor::State(State::Critical),
+                    _ => ThresholdColor::Color(arg.val.parse()?),
+                };
+                Ok((threshold, color))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if thresholds.is_empty() {
+            return Err(Error::new("'color' requires at least one threshold"));
+        }
+        thresholds.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Self(thresholds))
+    }
+
+    /// Wraps `text` in a pango span with the color of the highest threshold `val` reaches. The
+    /// text is left as is if `val` is below all thresholds.
+    pub fn apply(
+        &self,
+        val: &Value,
+        text: String,
+        config: &SharedConfig,
+    ) -> Result<String, FormatError> {
+        let Value::Number { val, .. } = val else {
+            return Err(FormatError::IncompatibleFormatter {
+                ty: val.type_name(),
+                fmt: "color",
+            });
+        };
+        let Some(&(_, color)) = self.0.iter().rev().find(|(threshold, _)| val >= threshold) else {
+            return Ok(text);
+        };
+        let color = match color {
+            ThresholdColor::State(state) => config.theme.get_colors(state).1,
+            ThresholdColor::Color(color) => color,
+        };
+        Ok(match color.to_hex() {
+            Some(hex) => format!("<span foreground=\"{hex}\">{text}</span>"),
+            None => text,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatting::unit::Unit;
+
+    #[test]
+    fn thresholds() {
+        let config = SharedConfig::default();
+        let color = ColorThresholds::from_args(&[
+            Arg {
+                key: "80",
+                val: "#ff0000",
+            },
+            Arg {
+                key: "50.5",
+                val: "#ffff00",
+            },
+        ])
+        .unwrap();
+        let apply = |val: f64| {
+            color
+                .apply(
+                    &Value::Number {
+                        val,
+                        unit: Unit::None,
+                    },
+                    "x".into(),
+                    &config,
+                )
+                .unwrap()
+        };
+        assert_eq!(apply(10.0), "x");
+        assert_eq!(apply(50.5), "<span foreground=\"#FFFF00FF\">x</span>");
+        assert_eq!(apply(99.0), "<span foreground=\"#FF0000FF\">x</span>");
+    }
+}
@@ -17,6 +17,7 @@ pub static DEFAULT_DATETIME_FORMATTER: LazyLock<DatetimeFormatter> =
 #[derive(Debug)]
 pub enum DatetimeFormatter {
     Chrono {
+        format: String,
         items: Vec<Item<'static>>,
         locale: Option<Locale>,
     },
@@ -50,6 +51,7 @@ impl DatetimeFormatter {
     }
 
     fn new(format: Option<&str>, locale: Option<&str>) -> Result<Self> {
+        let format_str = format.unwrap_or(DEFAULT_DATETIME_FORMAT);
         let (items, locale) = match locale {
             Some(locale) => {
                 #[cfg(feature = "icu_calendar")]
@@ -71,24 +73,18 @@ impl DatetimeFormatter {
                 #[cfg(not(feature = "icu_calendar"))]
                 let locale = locale.try_into().ok().error("invalid locale")?;
                 (
-                    StrftimeItems::new_with_locale(
-                        format.unwrap_or(DEFAULT_DATETIME_FORMAT),
-                        locale,
-                    ),
+                    StrftimeItems::new_with_locale(format_str, locale),
                     Some(locale),
                 )
             }
-            None => (
-                StrftimeItems::new(format.unwrap_or(DEFAULT_DATETIME_FORMAT)),
-                None,
-            ),
+            None => (StrftimeItems::new(format_str), None),
         };
 
         Ok(Self::Chrono {
-            items: items.parse_to_owned().error(format!(
-                "Invalid format: \"{}\"",
-                format.unwrap_or(DEFAULT_DATETIME_FORMAT)
-            ))?,
+            format: format_str.to_owned(),
+            items: items
+                .parse_to_owned()
+                .error(format!("Invalid format: \"{format_str}\""))?,
             locale,
         })
     }
@@ -134,10 +130,10 @@ fn borrow_item<'a>(item: &'a Item) -> Item<'a> {
 }
 
 impl Formatter for DatetimeFormatter {
-    fn format(&self, val: &Value, _config: &SharedConfig) -> Result<String, FormatError> {
-        #[allow(clippy::unnecessary_wraps)]
+    fn format(&self, val: &Value, config: &SharedConfig) -> Result<String, FormatError> {
         fn for_generic_datetime<T>(
             this: &DatetimeFormatter,
+            config: &SharedConfig,
             datetime: DateTime<T>,
         ) -> Result<String, FormatError>
         where
@@ -145,7 +141,23 @@ impl Formatter for DatetimeFormatter {
             T::Offset: Display,
         {
             Ok(match this {
-                DatetimeFormatter::Chrono { items, locale } => {
+                DatetimeFormatter::Chrono {
+                    format,
+                    items,
+                    locale,
+                } => {
+                    // Fall back to the global locale. The items are parsed again, because
+                    // specifiers such as `%x` expand differently depending on the locale.
+                    let localized_items;
+                    let (items, locale) = match (*locale, config.locale) {
+                        (None, Some(locale)) => {
+                            localized_items = StrftimeItems::new_with_locale(format, locale)
+                                .parse_to_owned()
+                                .error(format!("Invalid format: \"{format}\""))?;
+                            (&localized_items, Some(locale))
+                        }
+                        (locale, _) => (items, locale),
+                    };
                     let new_items = items.iter().map(|item| match item {
                         Item::Fixed(Fixed::TimezoneName) => match T::timezone_name(&datetime) {
                             Ok(name) => name,
@@ -156,7 +168,7 @@ impl Formatter for DatetimeFormatter {
                         },
                         item => borrow_item(item),
                     });
-                    match *locale {
+                    match locale {
                         Some(locale) => datetime
                             .format_localized_with_items(new_items, locale)
                             .to_string(),
@@ -186,8 +198,8 @@ impl Formatter for DatetimeFormatter {
         }
         match val {
             Value::Datetime(datetime, timezone) => match timezone {
-                Some(tz) => for_generic_datetime(self, datetime.with_timezone(tz)),
-                None => for_generic_datetime(self, datetime.with_timezone(&Local)),
+                Some(tz) => for_generic_datetime(self, config, datetime.with_timezone(tz)),
+                None => for_generic_datetime(self, config, datetime.with_timezone(&Local)),
             },
             other => Err(FormatError::IncompatibleFormatter {
                 ty: other.type_name(),
@@ -1,6 +1,9 @@
 use crate::formatting::prefix::Prefix;
 use crate::formatting::unit::Unit;
 
+use chrono::Locale;
+use pure_rust_locales::locale_match;
+
 use std::borrow::Cow;
 use std::ops::RangeInclusive;
 
@@ -12,13 +15,17 @@ pub const DEFAULT_NUMBER_FORMATTER: EngFormatter = EngFormatter {
     show: true,
     width: DEFAULT_NUMBER_WIDTH,
     unit: None,
+    unit_per_second: false,
     unit_has_space: false,
     unit_hidden: false,
     prefix: None,
     prefix_has_space: false,
     prefix_hidden: false,
     prefix_forced: false,
+    binary: None,
     pad_with: DEFAULT_NUMBER_PAD_WITH,
+    text_width: None,
+    align: Alignment::Right,
     range: f64::NEG_INFINITY..=f64::INFINITY,
 };
 
@@ -27,19 +34,26 @@ pub struct EngFormatter {
     show: bool,
     width: usize,
     unit: Option<Unit>,
+    unit_per_second: bool,
     unit_has_space: bool,
     unit_hidden: bool,
     prefix: Option<Prefix>,
     prefix_has_space: bool,
     prefix_hidden: bool,
     prefix_forced: bool,
+    /// Whether to use binary (`true`) or decimal (`false`) prefixes regardless of `prefix`
+    binary: Option<bool>,
     pad_with: PadWith,
+    /// The width of the whole text, including the prefix and the unit
+    text_width: Option<usize>,
+    align: Alignment,
     range: RangeInclusive<f64>,
 }
 
 impl EngFormatter {
     pub(super) fn from_args(args: &[Arg]) -> Result<Self> {
         let mut result = DEFAULT_NUMBER_FORMATTER;
+        let mut align = None;
 
         for arg in args {
             match arg.key {
@@ -47,7 +61,12 @@ impl EngFormatter {
                     result.width = arg.val.parse().error("Width must be a positive integer")?;
                 }
                 "unit" | "u" => {
-                    result.unit = Some(arg.val.parse()?);
+                    let (unit, per_second) = match arg.val.strip_suffix("/s") {
+                        Some(unit) => (unit, true),
+                        None => (arg.val, false),
+                    };
+                    result.unit = Some(unit.parse()?);
+                    result.unit_per_second = per_second;
                 }
                 "hide_unit" => {
                     result.unit_hidden = arg
@@ -87,6 +106,22 @@ impl EngFormatter {
                         .ok()
                         .error("force_prefix must be true or false")?;
                 }
+                "force_binary" | "force_decimal" => {
+                    let force: bool = arg
+                        .val
+                        .parse()
+                        .ok()
+                        .or_error(|| format!("{} must be true or false", arg.key))?;
+                    if force {
+                        let binary = arg.key == "force_binary";
+                        if result.binary == Some(!binary) {
+                            return Err(Error::new(
+                                "force_binary and force_decimal cannot be used together",
+                            ));
+                        }
+                        result.binary = Some(binary);
+                    }
+                }
                 "pad_with" => {
                     if arg.val.graphemes(true).count() < 2 {
                         result.pad_with = Cow::Owned(arg.val.into());
@@ -96,6 +131,16 @@ impl EngFormatter {
                         ));
                     }
                 }
+                "text_width" => {
+                    result.text_width = Some(
+                        arg.val
+                            .parse()
+                            .error("text_width must be a positive integer")?,
+                    );
+                }
+                "align" => {
+                    align = Some(Alignment::from_arg(arg)?);
+                }
                 "range" => {
                     let (start, end) = arg.val.split_once("..").error("invalid range")?;
                     if !start.is_empty() {
@@ -116,12 +161,19 @@ impl EngFormatter {
             }
         }
 
+        if let Some(align) = align {
+            if result.text_width.is_none() {
+                return Err(Error::new("align requires text_width"));
+            }
+            result.align = align;
+        }
+
         Ok(result)
     }
 }
 
 impl Formatter for EngFormatter {
-    fn format(&self, val: &Value, _config: &SharedConfig) -> Result<String, FormatError> {
+    fn format(&self, val: &Value, config: &SharedConfig) -> Result<String, FormatError> {
         match val {
             Value::Number { mut val, mut unit } => {
                 if !self.range.contains(&val) {
@@ -142,14 +194,22 @@ impl Formatter for EngFormatter {
                     unit = new_unit;
                 }
 
-                let (min_prefix, max_prefix) = match (self.prefix, self.prefix_forced) {
+                let (mut min_prefix, mut max_prefix) = match (self.prefix, self.prefix_forced) {
                     (Some(prefix), true) => (prefix, prefix),
                     (Some(prefix), false) => (prefix, Prefix::max_available()),
                     (None, _) => (Prefix::min_available(), Prefix::max_available()),
                 };
+                let binary = self.binary.unwrap_or(min_prefix.is_binary());
+                if binary {
+                    min_prefix = min_prefix.to_binary();
+                    max_prefix = max_prefix.to_binary();
+                } else {
+                    min_prefix = min_prefix.to_decimal();
+                    max_prefix = max_prefix.to_decimal();
+                }
 
                 let prefix = unit
-                    .clamp_prefix(if min_prefix.is_binary() {
+                    .clamp_prefix(if binary {
                         Prefix::eng_binary(val)
                     } else {
                         Prefix::eng(val)
@@ -173,6 +233,9 @@ impl Formatter for EngFormatter {
                     1 => format!("{}{sign}{}", self.pad_with, val.round() as i64),
                     rest => format!("{sign}{val:.*}", rest as usize - 1),
                 };
+                if let Some(locale) = config.locale {
+                    retval = localize(&retval, locale);
+                }
 
                 let display_prefix =
                     !self.prefix_hidden && prefix != Prefix::One && prefix != Prefix::OneButBinary;
@@ -189,6 +252,15 @@ impl Formatter for EngFormatter {
                         retval.push(' ');
                     }
                     retval.push_str(&unit.to_string());
+                    if self.unit_per_second {
+                        retval.push_str("/s");
+                    }
+                }
+
+                if let Some(text_width) = self.text_width {
+                    let width = retval.graphemes(true).count();
+                    let (before, after) = self.align.split(text_width.saturating_sub(width));
+                    retval = format!("{}{retval}{}", " ".repeat(before), " ".repeat(after));
                 }
 
                 Ok(retval)
@@ -201,6 +273,44 @@ impl Formatter for EngFormatter {
     }
 }
 
+/// Uses the decimal point of `locale` and groups the digits of the integer part with its
+/// thousands separator
+fn localize(number: &str, locale: Locale) -> String {
+    let decimal_point = locale_match!(locale => LC_NUMERIC::DECIMAL_POINT);
+    let thousands_sep = locale_match!(locale => LC_NUMERIC::THOUSANDS_SEP);
+    // The sizes of the groups from right to left. The last size repeats, and a size which is not
+    // positive ends the grouping.
+    let grouping = locale_match!(locale => LC_NUMERIC::GROUPING);
+
+    let start = number
+        .find(|c: char| c.is_ascii_digit())
+        .unwrap_or(number.len());
+    let end = number[start..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(number.len(), |i| start + i);
+    let mut int = &number[start..end];
+
+    let mut groups = Vec::new();
+    if !thousands_sep.is_empty() {
+        let last = grouping.last().copied().unwrap_or(-1);
+        for size in grouping.iter().copied().chain(std::iter::repeat(last)) {
+            if size <= 0 || int.len() <= size as usize {
+                break;
+            }
+            let (head, tail) = int.split_at(int.len() - size as usize);
+            groups.push(tail);
+            int = head;
+        }
+    }
+    groups.push(int);
+    groups.reverse();
+
+    let mut retval = number[..start].to_owned();
+    retval.push_str(&groups.join(thousands_sep));
+    retval.push_str(&number[end..].replacen('.', decimal_point, 1));
+    retval
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -314,4 +424,113 @@ mod tests {
         let result = fmt.format(&val, &config).unwrap();
         assert_eq!(result, "15GiB");
     }
+
+    #[test]
+    fn eng_locale() {
+        let format = |locale: &str, val: f64, width: &str| {
+            let config = SharedConfig {
+                locale: Some(locale.try_into().unwrap()),
+                ..Default::default()
+            };
+            new_formatter(
+                "eng",
+                &[
+                    Arg {
+                        key: "w",
+                        val: width,
+                    },
+                    Arg { key: "p", val: "1" },
+                ],
+            )
+            .unwrap()
+            .format(
+                &Value::Number {
+                    val,
+                    unit: Unit::None,
+                },
+                &config,
+            )
+            .unwrap()
+        };
+        assert_eq!(format("de_DE", 1.5, "3"), "1,5");
+        assert_eq!(format("de_DE", 1234567.5, "9"), "1.234.567,5");
+        assert_eq!(format("en_US", -1234.0, "3"), "-1,234");
+        assert_eq!(format("en_IN", 12345678.0, "3"), "1,23,45,678");
+        assert_eq!(format("en_US", 123.0, "5"), "123.0");
+        assert_eq!(format("POSIX", 12345.0, "3"), "12345");
+    }
+
+    #[test]
+    fn eng_binary_and_decimal() {
+        let config = SharedConfig::default();
+        // 1.5 MiB/s
+        let val = Value::Number {
+            val: 1.5 * 1024. * 1024.,
+            unit: Unit::Bytes,
+        };
+
+        let fmt = new_fmt!(eng, w: 3, p: K, force_binary: true).unwrap();
+        assert_eq!(fmt.format(&val, &config).unwrap(), "1.5MiB");
+
+        let fmt = new_fmt!(eng, w: 3, p: Ki, force_decimal: true).unwrap();
+        assert_eq!(fmt.format(&val, &config).unwrap(), "1.6MB");
+
+        let fmt = new_fmt!(eng, w: 3, force_binary: true).unwrap();
+        assert_eq!(fmt.format(&val, &config).unwrap(), "1.5MiB");
+
+        let fmt = new_fmt!(eng, w: 3, force_binary: true, force_decimal: true);
+        assert!(fmt.is_err());
+    }
+
+    #[test]
+    fn eng_unit_per_second() {
+        let config = SharedConfig::default();
+        let val = Value::Number {
+            val: 2_000_000.,
+            unit: Unit::Bytes,
+        };
+
+        let fmt = new_formatter(
+            "eng",
+            &[
+                Arg {
+                    key: "unit",
+                    val: "b/s",
+                },
+                Arg {
+                    key: "prefix_space",
+                    val: "true",
+                },
+            ],
+        )
+        .unwrap();
+        assert_eq!(fmt.format(&val, &config).unwrap(), "16 Mb/s");
+    }
+
+    #[test]
+    fn eng_text_width() {
+        let config = SharedConfig::default();
+        let format = |fmt: &dyn Formatter, val: f64| {
+            fmt.format(
+                &Value::Number {
+                    val,
+                    unit: Unit::Bytes,
+                },
+                &config,
+            )
+            .unwrap()
+        };
+
+        let fmt = new_fmt!(eng, w: 3, text_width: 6).unwrap();
+        assert_eq!(format(&*fmt, 999.0), "  999B");
+        assert_eq!(format(&*fmt, 1_500.0), " 1.5KB");
+
+        let fmt = new_fmt!(eng, w: 3, text_width: 6, align: left).unwrap();
+        assert_eq!(format(&*fmt, 999.0), "999B  ");
+
+        let fmt = new_fmt!(eng, w: 3, text_width: 7, align: center).unwrap();
+        assert_eq!(format(&*fmt, 999.0), " 999B  ");
+
+        assert!(new_fmt!(eng, align: left).is_err());
+    }
 }
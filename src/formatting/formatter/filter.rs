@@ -0,0 +1,145 @@
+use regex::Regex;
+
+use super::*;
+
+/// A transformation of text which is applied before the text is formatted, e.g. in
+/// `$title.lower().str(max_w:20)`
+#[derive(Debug)]
+pub enum Filter {
+    Upper,
+    Lower,
+    Trim,
+    Replace { from: String, to: String },
+    ReplaceRegex { from: Regex, to: String },
+}
+
+impl Filter {
+    /// Returns `None` if `name` is not a filter
+    pub fn new(name: &str, args: &[Arg]) -> Option<Result<Self>> {
+        let no_args = |filter| match args.first() {
+            Some(arg) => Err(Error::new(format!(
+                "Unknown argument for '{name}': '{}'",
+                arg.key
+            ))),
+            None => Ok(filter),
+        };
+        Some(match name {
+            "upper" => no_args(Self::Upper),
+            "lower" => no_args(Self::Lower),
+            "trim" => no_args(Self::Trim),
+            "replace" => Self::replace_from_args(args),
+            _ => return None,
+        })
+    }
+
+    fn replace_from_args(args: &[Arg]) -> Result<Self> {
+        let mut from = None;
+        let mut to = String::new();
+        let mut regex = false;
+        for arg in args {
+            match arg.key {
+                "from" => {
+                    from = Some(arg.val.to_owned());
+                }
+                "to" => {
+                    to = arg.val.to_owned();
+                }
+                "regex" => {
+                    regex = arg.val.parse().error("Regex value must be a bool")?;
+                }
+                other => {
+                    return Err(Error::new(format!(
+                        "Unknown argument for 'replace': '{other}'"
+                    )));
+                }
+            }
+        }
+        let from = from.error("'replace' requires the 'from' argument")?;
+        if regex {
+            let from = Regex::new(&from).error("Invalid regex in 'replace'")?;
+            Ok(Self::ReplaceRegex { from, to })
+        } else {
+            Ok(Self::Replace { from, to })
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Upper => "upper",
+            Self::Lower => "lower",
+            Self::Trim => "trim",
+            Self::Replace { .. } | Self::ReplaceRegex { .. } => "replace",
+        }
+    }
+
+    pub fn apply(&self, val: &Value) -> Result<Value, FormatError> {
+        let Value::Text(text) = val else {
+            return Err(FormatError::IncompatibleFormatter {
+                ty: val.type_name(),
+                fmt: self.name(),
+            });
+        };
+        let text = match self {
+            Self::Upper => text.to_uppercase(),
+            Self::Lower => text.to_lowercase(),
+            Self::Trim => text.trim().to_owned(),
+            Self::Replace { from, to } => text.replace(from.as_str(), to),
+            Self::ReplaceRegex { from, to } => from.replace_all(text, to.as_str()).into_owned(),
+        };
+        Ok(Value::Text(text.into_boxed_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(name: &str, args: &[Arg], text: &str) -> String {
+        let filter = Filter::new(name, args).unwrap().unwrap();
+        match filter.apply(&Value::Text(text.into())).unwrap() {
+            Value::Text(text) => text.into(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(apply("upper", &[], "Foo"), "FOO");
+        assert_eq!(apply("lower", &[], "Foo"), "foo");
+        assert_eq!(apply("trim", &[], "  foo "), "foo");
+        assert_eq!(
+            apply(
+                "replace",
+                &[Arg {
+                    key: "from",
+                    val: " - Mozilla Firefox"
+                }],
+                "Page - Mozilla Firefox"
+            ),
+            "Page"
+        );
+        assert_eq!(
+            apply(
+                "replace",
+                &[
+                    Arg {
+                        key: "from",
+                        val: r"^(\w+) (\w+)$"
+                    },
+                    Arg {
+                        key: "to",
+                        val: "$2 $1"
+                    },
+                    Arg {
+                        key: "regex",
+                        val: "true"
+                    },
+                ],
+                "foo bar"
+            ),
+            "bar foo"
+        );
+        assert!(Filter::new("str", &[]).is_none());
+        assert!(Filter::new("replace", &[]).unwrap().is_err());
+    }
+}
@@ -18,8 +18,10 @@ impl PangoStrFormatter {
 impl Formatter for PangoStrFormatter {
     fn format(&self, val: &Value, config: &SharedConfig) -> Result<String, FormatError> {
         match val {
-            Value::Text(x) => Ok(x.clone()), // No escaping
-            Value::Icon(icon, value) => config.get_icon(icon, *value).map_err(Into::into),
+            Value::Text(x) => Ok(x.to_string()), // No escaping
+            Value::Icon(icon, value) => config
+                .get_icon(icon.as_str(), value.map(Into::into))
+                .map_err(Into::into),
             other => Err(FormatError::IncompatibleFormatter {
                 ty: other.type_name(),
                 fmt: "pango-str",
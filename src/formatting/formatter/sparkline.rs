@@ -0,0 +1,215 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use super::bar::VERTICAL_BAR_CHARS;
+use super::*;
+
+const DEFAULT_SPARKLINE_N: usize = 10;
+const DEFAULT_SPARKLINE_INTERVAL: f64 = 5.0;
+const DEFAULT_SPARKLINE_MIN: f64 = 0.0;
+
+const BRAILLE_BLANK: u32 = 0x2800;
+/// Braille dots of the left and the right column, from the bottom up
+const BRAILLE_LEFT: [u32; 4] = [0x40, 0x04, 0x02, 0x01];
+const BRAILLE_RIGHT: [u32; 4] = [0x80, 0x20, 0x10, 0x08];
+
+#[derive(Debug)]
+pub struct SparklineFormatter {
+    n: usize,
+    interval: Duration,
+    min: f64,
+    max: Option<f64>,
+    braille: bool,
+    history: Mutex<History>,
+}
+
+/// The last values, one per `interval`
+#[derive(Debug)]
+struct History {
+    start: Instant,
+    last_slot: Option<u64>,
+    samples: VecDeque<f64>,
+}
+
+impl SparklineFormatter {
+    pub(super) fn from_args(args: &[Arg]) -> Result<Self> {
+        let mut n = DEFAULT_SPARKLINE_N;
+        let mut interval = DEFAULT_SPARKLINE_INTERVAL;
+        let mut min = DEFAULT_SPARKLINE_MIN;
+        let mut max = None;
+        let mut braille = false;
+        for arg in args {
+            match arg.key {
+                "n" => {
+                    n = arg.val.parse().error("n must be a positive integer")?;
+                }
+                "interval" => {
+                    interval = arg
+                        .val
+                        .parse()
+                        .error("Interval must be a positive number")?;
+                }
+                "min" => {
+                    min = arg.val.parse().error("Min must be a number")?;
+                }
+                "max" => {
+                    max = Some(arg.val.parse().error("Max must be a number")?);
+                }
+                "braille" => {
+                    braille = arg.val.parse().error("Braille value must be a bool")?;
+                }
+                other => {
+                    return Err(Error::new(format!(
+                        "Unknown argument for 'sparkline': '{other}'"
+                    )));
+                }
+            }
+        }
+        if n == 0 {
+            return Err(Error::new("n must be a positive integer"));
+        }
+        if interval < 0.1 {
+            return Err(Error::new("Interval must be greater than 0.1"));
+        }
+        Ok(Self {
+            n,
+            interval: Duration::from_secs_f64(interval),
+            min,
+            max,
+            braille,
+            history: Mutex::new(History {
+                start: Instant::now(),
+                last_slot: None,
+                samples: VecDeque::with_capacity(n),
+            }),
+        })
+    }
+
+    /// Records a value. Values within the same interval replace each other.
+    fn push(&self, val: f64, now: Instant) {
+        let mut history = self.history.lock().unwrap();
+        // Renders are scheduled every `interval`, so round to the nearest slot to tolerate jitter
+        let slot = ((now - history.start).as_secs_f64() / self.interval.as_secs_f64()).round();
+        let slot = slot as u64;
+        if history.last_slot == Some(slot) {
+            if let Some(latest) = history.samples.back_mut() {
+                *latest = val;
+            }
+            return;
+        }
+        history.last_slot = Some(slot);
+        if history.samples.len() == self.n {
+            history.samples.pop_front();
+        }
+        history.samples.push_back(val);
+    }
+
+    fn render(&self) -> String {
+        let history = self.history.lock().unwrap();
+        let samples = &history.samples;
+        let max = self
+            .max
+            .unwrap_or_else(|| samples.iter().copied().fold(self.min, f64::max));
+        let level = |val: f64, levels: usize| {
+            let range = max - self.min;
+            if range <= 0.0 {
+                return 0;
+            }
+            (((val - self.min) / range).clamp(0., 1.) * levels as f64).round() as usize
+        };
+
+        // Pad on the left until the history is full
+        let padding = self.n - samples.len();
+        if self.braille {
+            let heights: Vec<Option<usize>> = std::iter::repeat_n(None, padding)
+                .chain(samples.iter().map(|&val| Some(level(val, 4))))
+                .collect();
+            heights
+                .chunks(2)
+                .map(|pair| {
+                    let column = |dots: &[u32; 4], height: Option<usize>| {
+                        dots[..height.unwrap_or(0)].iter().sum::<u32>()
+                    };
+                    let left = column(&BRAILLE_LEFT, pair[0]);
+                    let right = pair.get(1).map_or(0, |&h| column(&BRAILLE_RIGHT, h));
+                    char::from_u32(BRAILLE_BLANK + left + right).unwrap()
+                })
+                .collect()
+        } else {
+            std::iter::repeat_n(' ', padding)
+                .chain(
+                    samples
+                        .iter()
+                        .map(|&val| VERTICAL_BAR_CHARS[1 + level(val, 7)]),
+                )
+                .collect()
+        }
+    }
+}
+
+impl Formatter for SparklineFormatter {
+    fn format(&self, val: &Value, _config: &SharedConfig) -> Result<String, FormatError> {
+        match val {
+            Value::Number { val, .. } => {
+                self.push(*val, Instant::now());
+                Ok(self.render())
+            }
+            other => Err(FormatError::IncompatibleFormatter {
+                ty: other.type_name(),
+                fmt: "sparkline",
+            }),
+        }
+    }
+
+    fn interval(&self) -> Option<Duration> {
+        // Re-render periodically, so that values are recorded even if they do not change
+        Some(self.interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(fmt: &SparklineFormatter, values: &[f64]) {
+        let start = fmt.history.lock().unwrap().start;
+        for (i, &val) in values.iter().enumerate() {
+            fmt.push(val, start + fmt.interval * i as u32);
+        }
+    }
+
+    #[test]
+    fn sparkline_bars() {
+        let fmt = SparklineFormatter::from_args(&[
+            Arg { key: "n", val: "5" },
+            Arg {
+                key: "max",
+                val: "7",
+            },
+        ])
+        .unwrap();
+        history(&fmt, &[0.0, 7.0, 3.0, 100.0]);
+        assert_eq!(fmt.render(), " ▁█▄█");
+
+        // Values within the same interval replace each other
+        let start = fmt.history.lock().unwrap().start;
+        fmt.push(0.0, start + fmt.interval * 3);
+        assert_eq!(fmt.render(), " ▁█▄▁");
+    }
+
+    #[test]
+    fn sparkline_braille() {
+        let fmt = SparklineFormatter::from_args(&[
+            Arg { key: "n", val: "4" },
+            Arg {
+                key: "braille",
+                val: "true",
+            },
+        ])
+        .unwrap();
+        // Scaled to the largest value
+        history(&fmt, &[2.0, 4.0, 8.0]);
+        assert_eq!(fmt.render(), "⢀⣼");
+    }
+}
@@ -1,4 +1,5 @@
-use std::iter::repeat;
+use std::iter::repeat_n;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::Instant;
 
 use crate::escape::CollectEscaped;
@@ -16,8 +17,57 @@ pub const DEFAULT_STRING_FORMATTER: StrFormatter = StrFormatter {
     rot_interval_ms: None,
     init_time: None,
     rot_separator: None,
+    rot_style: RotStyle::Scroll,
+    rot_pause_ms: 0,
+    align: Alignment::Left,
 };
 
+/// The number of rotating str formatters created so far
+static ROTATING: AtomicU32 = AtomicU32::new(0);
+
+/// Returns the instant the rotation ticks of a new formatter are counted from. Each formatter
+/// gets its own phase, so that texts with the same `rot_interval` do not all move at once and
+/// the bar is not redrawn for all of them at the same time.
+fn rot_epoch(rot_interval_ms: u64) -> Instant {
+    // Successive multiples of the golden ratio are spread evenly over [0, 1)
+    let n = ROTATING.fetch_add(1, Ordering::Relaxed);
+    let fraction = (f64::from(n) * 0.618_033_988_749_895).fract();
+    let now = Instant::now();
+    let phase = Duration::from_millis((rot_interval_ms as f64 * fraction) as u64);
+    now.checked_sub(phase).unwrap_or(now)
+}
+
+/// How text which is longer than `max_width` is rotated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RotStyle {
+    /// Scroll to the left and wrap around, separated by `rot_separator`
+    Scroll,
+    /// Scroll to the end and back to the start
+    Bounce,
+    /// Scroll to the end and jump back to the start
+    Reset,
+}
+
+impl RotStyle {
+    /// Returns the offset of the first visible character at `tick`. `span` is the number of
+    /// positions the text is scrolled by and the text stays at its ends for `pause` ticks.
+    fn offset(self, tick: usize, span: usize, pause: usize) -> usize {
+        let scrolled = |t: usize| t.saturating_sub(pause).min(span);
+        match self {
+            // Wrapping around does not stop at the end
+            Self::Scroll => scrolled(tick % (span + pause)),
+            Self::Bounce => {
+                let half = span + pause;
+                match tick % (2 * half) {
+                    t if t < half => scrolled(t),
+                    t => span - scrolled(t - half),
+                }
+            }
+            Self::Reset => scrolled(tick % (span + 2 * pause + 1)),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct StrFormatter {
     min_width: usize,
@@ -25,6 +75,9 @@ pub struct StrFormatter {
     rot_interval_ms: Option<u64>,
     init_time: Option<Instant>,
     rot_separator: Option<String>,
+    rot_style: RotStyle,
+    rot_pause_ms: u64,
+    align: Alignment,
 }
 
 impl StrFormatter {
@@ -33,6 +86,9 @@ impl StrFormatter {
         let mut max_width = DEFAULT_STR_MAX_WIDTH;
         let mut rot_interval = DEFAULT_STR_ROT_INTERVAL;
         let mut rot_separator = DEFAULT_STR_ROT_SEP;
+        let mut rot_style = RotStyle::Scroll;
+        let mut rot_pause = 0.0;
+        let mut align = Alignment::Left;
         for arg in args {
             match arg.key {
                 "min_width" | "min_w" => {
@@ -55,6 +111,25 @@ impl StrFormatter {
                 "rot_separator" => {
                     rot_separator = Some(arg.val.to_string());
                 }
+                "rot_style" => {
+                    rot_style = match arg.val {
+                        "scroll" => RotStyle::Scroll,
+                        "bounce" => RotStyle::Bounce,
+                        "reset" => RotStyle::Reset,
+                        _ => return Err(Error::new("rot_style must be scroll, bounce or reset")),
+                    };
+                }
+                "rot_pause" => {
+                    rot_pause = arg
+                        .val
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|pause| *pause >= 0.0)
+                        .error("Pause must be a positive number")?;
+                }
+                "align" => {
+                    align = Alignment::from_arg(arg)?;
+                }
                 other => {
                     return Err(Error::new(format!("Unknown argument for 'str': '{other}'")));
                 }
@@ -70,12 +145,16 @@ impl StrFormatter {
                 return Err(Error::new("Interval must be greater than 0.1"));
             }
         }
+        let rot_interval_ms = rot_interval.map(|x| (x * 1e3) as u64);
         Ok(StrFormatter {
             min_width,
             max_width,
-            rot_interval_ms: rot_interval.map(|x| (x * 1e3) as u64),
-            init_time: Some(Instant::now()),
+            rot_interval_ms,
+            init_time: Some(rot_interval_ms.map_or_else(Instant::now, rot_epoch)),
             rot_separator,
+            rot_style,
+            rot_pause_ms: (rot_pause * 1e3) as u64,
+            align,
         })
     }
 }
@@ -88,33 +167,48 @@ impl Formatter for StrFormatter {
                 let width = text.len();
                 Ok(match (self.rot_interval_ms, self.init_time) {
                     (Some(rot_interval_ms), Some(init_time)) if width > self.max_width => {
-                        let rot_separator: Vec<&str> = self
-                            .rot_separator
-                            .as_deref()
-                            .unwrap_or("|")
-                            .graphemes(true)
-                            .collect();
-                        let width = width + rot_separator.len(); // Now we include `rot_separator` at the end
-                        let step = (init_time.elapsed().as_millis() as u64 / rot_interval_ms)
-                            as usize
-                            % width;
-                        let w1 = self.max_width.min(width - step);
-                        text.iter()
-                            .chain(rot_separator.iter())
-                            .skip(step)
-                            .take(w1)
+                        let tick =
+                            (init_time.elapsed().as_millis() as u64 / rot_interval_ms) as usize;
+                        let pause = self.rot_pause_ms.div_ceil(rot_interval_ms) as usize;
+                        if self.rot_style == RotStyle::Scroll {
+                            let rot_separator: Vec<&str> = self
+                                .rot_separator
+                                .as_deref()
+                                .unwrap_or("|")
+                                .graphemes(true)
+                                .collect();
+                            let width = width + rot_separator.len(); // Now we include `rot_separator` at the end
+                            let step = self.rot_style.offset(tick, width, pause);
+                            let w1 = self.max_width.min(width - step);
+                            text.iter()
+                                .chain(rot_separator.iter())
+                                .skip(step)
+                                .take(w1)
+                                .chain(text.iter())
+                                .take(self.max_width)
+                                .collect_pango_escaped()
+                        } else {
+                            let step = self.rot_style.offset(tick, width - self.max_width, pause);
+                            text.iter()
+                                .skip(step)
+                                .take(self.max_width)
+                                .collect_pango_escaped()
+                        }
+                    }
+                    _ => {
+                        let (before, after) =
+                            self.align.split(self.min_width.saturating_sub(width));
+                        repeat_n(&" ", before)
                             .chain(text.iter())
+                            .chain(repeat_n(&" ", after))
                             .take(self.max_width)
                             .collect_pango_escaped()
                     }
-                    _ => text
-                        .iter()
-                        .chain(repeat(&" ").take(self.min_width.saturating_sub(width)))
-                        .take(self.max_width)
-                        .collect_pango_escaped(),
                 })
             }
-            Value::Icon(icon, value) => config.get_icon(icon, *value).map_err(Into::into),
+            Value::Icon(icon, value) => config
+                .get_icon(icon.as_str(), value.map(Into::into))
+                .map_err(Into::into),
             other => Err(FormatError::IncompatibleFormatter {
                 ty: other.type_name(),
                 fmt: "str",
@@ -125,4 +219,67 @@ impl Formatter for StrFormatter {
     fn interval(&self) -> Option<Duration> {
         self.rot_interval_ms.map(Duration::from_millis)
     }
+
+    fn epoch(&self) -> Option<Instant> {
+        self.init_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn str_align() {
+        let config = SharedConfig::default();
+        let format = |fmt: Box<dyn Formatter>| fmt.format(&Value::Text("ab".into()), &config);
+
+        assert_eq!(format(new_fmt!(str, w: 5).unwrap()).unwrap(), "ab   ");
+        assert_eq!(
+            format(new_fmt!(str, w: 5, align: right).unwrap()).unwrap(),
+            "   ab"
+        );
+        assert_eq!(
+            format(new_fmt!(str, w: 5, align: center).unwrap()).unwrap(),
+            " ab  "
+        );
+        assert_eq!(
+            format(new_fmt!(str, w: 1, align: right).unwrap()).unwrap(),
+            "a"
+        );
+        assert!(new_fmt!(str, align: top).is_err());
+    }
+
+    #[test]
+    fn rotation_offsets() {
+        let offsets = |style: RotStyle, pause| {
+            (0..12)
+                .map(|tick| style.offset(tick, 3, pause))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            offsets(RotStyle::Scroll, 0),
+            [0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2]
+        );
+        assert_eq!(
+            offsets(RotStyle::Scroll, 1),
+            [0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2]
+        );
+        assert_eq!(
+            offsets(RotStyle::Bounce, 0),
+            [0, 1, 2, 3, 2, 1, 0, 1, 2, 3, 2, 1]
+        );
+        assert_eq!(
+            offsets(RotStyle::Bounce, 1),
+            [0, 0, 1, 2, 3, 3, 2, 1, 0, 0, 1, 2]
+        );
+        assert_eq!(
+            offsets(RotStyle::Reset, 0),
+            [0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2, 3]
+        );
+        assert_eq!(
+            offsets(RotStyle::Reset, 1),
+            [0, 0, 1, 2, 3, 3, 0, 0, 1, 2, 3, 3]
+        );
+    }
 }
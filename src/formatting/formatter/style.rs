@@ -0,0 +1,129 @@
+use std::fmt::Write as _;
+
+use super::*;
+
+const WEIGHTS: &[&str] = &[
+    "thin",
+    "ultralight",
+    "light",
+    "semilight",
+    "book",
+    "normal",
+    "medium",
+    "semibold",
+    "bold",
+    "ultrabold",
+    "heavy",
+    "ultraheavy",
+];
+const UNDERLINES: &[&str] = &["none", "single", "double", "low", "error"];
+const SIZES: &[&str] = &[
+    "xx-small", "x-small", "small", "medium", "large", "x-large", "xx-large", "smaller", "larger",
+];
+
+/// Wraps a formatted value in pango markup, e.g. in
+/// `$title.str(max_w:20).style(weight:bold, italic:true)`
+#[derive(Debug)]
+pub struct Style {
+    /// The attributes of the span, each preceded by a space
+    attributes: String,
+}
+
+impl Style {
+    pub fn from_args(args: &[Arg]) -> Result<Self> {
+        let mut attributes = String::new();
+        for arg in args {
+            let val = arg.val.to_ascii_lowercase();
+            let (name, val) = match arg.key {
+                "weight" => {
+                    if !WEIGHTS.contains(&val.as_str())
+                        && !val.parse::<u16>().is_ok_and(|w| (100..=1000).contains(&w))
+                    {
+                        return Err(Error::new(format!(
+                            "Weight must be a number between 100 and 1000 or one of {}",
+                            WEIGHTS.join(", ")
+                        )));
+                    }
+                    ("weight", val)
+                }
+                "italic" => {
+                    let italic: bool = val.parse().error("Italic value must be a bool")?;
+                    ("style", if italic { "italic" } else { "normal" }.into())
+                }
+                "underline" => {
+                    if !UNDERLINES.contains(&val.as_str()) {
+                        return Err(Error::new(format!(
+                            "Underline must be one of {}",
+                            UNDERLINES.join(", ")
+                        )));
+                    }
+                    ("underline", val)
+                }
+                "strikethrough" => {
+                    let strikethrough: bool =
+                        val.parse().error("Strikethrough value must be a bool")?;
+                    ("strikethrough", strikethrough.to_string())
+                }
+                "size" => {
+                    let valid = match val.strip_suffix("pt").or_else(|| val.strip_suffix('%')) {
+                        Some(number) => number.parse::<f64>().is_ok(),
+                        // Plain numbers are in 1024ths of a point
+                        None => SIZES.contains(&val.as_str()) || val.parse::<u32>().is_ok(),
+                    };
+                    if !valid {
+                        return Err(Error::new(format!(
+                            "Size must be a number, a number followed by 'pt' or '%', or one of {}",
+                            SIZES.join(", ")
+                        )));
+                    }
+                    ("size", val)
+                }
+                other => {
+                    return Err(Error::new(format!(
+                        "Unknown argument for 'style': '{other}'"
+                    )));
+                }
+            };
+            let _ = write!(attributes, " {name}=\"{val}\"");
+        }
+        if attributes.is_empty() {
+            return Err(Error::new("'style' requires at least one argument"));
+        }
+        Ok(Self { attributes })
+    }
+
+    /// Wraps `text` in a pango span with the attributes of this style
+    pub fn apply(&self, text: String) -> String {
+        format!("<span{}>{text}</span>", self.attributes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn style() {
+        let style = |args: &[(&'static str, &'static str)]| {
+            let args: Vec<_> = args.iter().map(|&(key, val)| Arg { key, val }).collect();
+            Style::from_args(&args).map(|style| style.apply("x".into()))
+        };
+        assert_eq!(
+            style(&[("weight", "bold"), ("italic", "true")]).unwrap(),
+            "<span weight=\"bold\" style=\"italic\">x</span>"
+        );
+        assert_eq!(
+            style(&[("underline", "single"), ("size", "small")]).unwrap(),
+            "<span underline=\"single\" size=\"small\">x</span>"
+        );
+        assert_eq!(
+            style(&[("weight", "600"), ("size", "12.5pt")]).unwrap(),
+            "<span weight=\"600\" size=\"12.5pt\">x</span>"
+        );
+        assert!(style(&[]).is_err());
+        assert!(style(&[("weight", "\" foreground=\"red")]).is_err());
+        assert!(style(&[("underline", "wavy")]).is_err());
+        assert!(style(&[("size", "huge")]).is_err());
+        assert!(style(&[("color", "red")]).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! Global string interner
+//!
+//! Placeholder keys, icon names and instances are drawn from a small set of strings which are
+//! repeated in every update of every block. Interning them means each distinct string is allocated
+//! only once for the lifetime of the process, and values can refer to it with a 4 byte id.
+//!
+//! Interned strings are never freed, so only strings from a bounded set (names defined by a block,
+//! its config or its formats) may be interned. Strings from outside of the program, such as the
+//! output of a command or names chosen by a script, must not be. Use [`Istr::lookup`] to find the
+//! name of a placeholder which is used by a format.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::num::NonZeroU32;
+use std::sync::{LazyLock, OnceLock, RwLock};
+
+#[derive(Default)]
+struct Interner {
+    ids: HashMap<&'static str, Istr>,
+    len: u32,
+}
+
+static INTERNER: LazyLock<RwLock<Interner>> = LazyLock::new(Default::default);
+
+/// The interned strings, indexed by id. Segment `i` holds the ids `2^i..2^(i+1)`, so that it
+/// never moves and strings can be read without a lock while new ones are added.
+static STRINGS: [OnceLock<Box<[OnceLock<&'static str>]>>; 32] = [const { OnceLock::new() }; 32];
+
+fn slot(id: NonZeroU32) -> &'static OnceLock<&'static str> {
+    let segment = id.ilog2();
+    let first = 1 << segment;
+    let slots =
+        STRINGS[segment as usize].get_or_init(|| (0..first).map(|_| OnceLock::new()).collect());
+    &slots[(id.get() - first) as usize]
+}
+
+/// An interned string
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Istr(NonZeroU32);
+
+impl Istr {
+    pub fn new(s: &str) -> Self {
+        if let Some(id) = Self::lookup(s) {
+            return id;
+        }
+        let mut interner = INTERNER.write().unwrap();
+        // Another thread may have interned the string in the meantime
+        if let Some(id) = interner.ids.get(s) {
+            return *id;
+        }
+        let s: &'static str = Box::leak(s.into());
+        interner.len += 1;
+        let id = NonZeroU32::new(interner.len).expect("too many interned strings");
+        let _ = slot(id).set(s);
+        interner.ids.insert(s, Self(id));
+        Self(id)
+    }
+
+    /// Returns the interned copy of `s` without interning it if there is none
+    pub fn lookup(s: &str) -> Option<Self> {
+        INTERNER.read().unwrap().ids.get(s).copied()
+    }
+
+    pub fn as_str(self) -> &'static str {
+        slot(self.0)
+            .get()
+            .expect("strings are stored before their id is returned")
+    }
+}
+
+/// Intern a string and return a reference to its only copy
+pub fn intern(s: &str) -> &'static str {
+    Istr::new(s).as_str()
+}
+
+impl From<&str> for Istr {
+    fn from(s: &str) -> Self {
+        Self::new(s)
+    }
+}
+
+impl fmt::Debug for Istr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Istr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_string_same_id() {
+        let a = Istr::new("intern_test_a");
+        let b = Istr::new(&String::from("intern_test_a"));
+        assert_eq!(a, b);
+        assert_ne!(a, Istr::new("intern_test_b"));
+        assert_eq!(a.as_str(), "intern_test_a");
+        assert!(std::ptr::eq(intern("intern_test_a"), a.as_str()));
+        assert_eq!(Istr::lookup("intern_test_a"), Some(a));
+        assert_eq!(Istr::lookup("intern_test_never_interned"), None);
+    }
+
+    #[test]
+    fn many_strings() {
+        let ids: Vec<_> = (0..1000)
+            .map(|i| Istr::new(&format!("intern_test_{i}")))
+            .collect();
+        for (i, id) in ids.iter().enumerate() {
+            assert_eq!(id.as_str(), format!("intern_test_{i}"));
+        }
+    }
+}
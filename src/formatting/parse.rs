@@ -1,13 +1,15 @@
 use nom::{
     branch::alt,
     bytes::complete::{escaped_transform, tag, take_while, take_while1},
-    character::complete::{anychar, char},
+    character::complete::{anychar, char, one_of},
     combinator::{cut, eof, map, not, opt},
     multi::{many0, separated_list0},
+    number::complete::double,
     sequence::{preceded, separated_pair, terminated, tuple},
     IResult, Parser,
 };
 
+use super::expr::{Expr, Op};
 use crate::errors::*;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -25,21 +27,28 @@ pub struct Formatter<'a> {
 #[derive(Debug, PartialEq, Eq)]
 pub struct Placeholder<'a> {
     pub name: &'a str,
+    pub formatters: Vec<Formatter<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Expression<'a> {
+    pub expr: Expr,
     pub formatter: Option<Formatter<'a>>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq)]
 pub enum Token<'a> {
     Text(String),
     Placeholder(Placeholder<'a>),
     Icon(&'a str),
     Recursive(FormatTemplate<'a>),
+    Expression(Expression<'a>),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq)]
 pub struct TokenList<'a>(pub Vec<Token<'a>>);
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq)]
 pub struct FormatTemplate<'a>(pub Vec<TokenList<'a>>);
 
 #[derive(Debug, PartialEq, Eq)]
@@ -94,9 +103,11 @@ fn arg1(i: &str) -> IResult<&str, &str, PError> {
 }
 
 // `key:val`
+// `0.5:val`
 fn parse_arg(i: &str) -> IResult<&str, Arg, PError> {
+    let key = take_while1(|x: char| x.is_alphanumeric() || x == '_' || x == '-' || x == '.');
     map(
-        separated_pair(alphanum1, cut(char(':')), cut(arg1)),
+        separated_pair(key, cut(char(':')), cut(arg1)),
         |(key, val)| Arg { key, val },
     )(i)
 }
@@ -124,12 +135,76 @@ fn parse_formatter(i: &str) -> IResult<&str, Formatter, PError> {
 
 // `$var`
 // `$key.eng(unit:bits,bin)`
+// `$title.lower().str(max_w:10)`
 fn parse_placeholder(i: &str) -> IResult<&str, Placeholder, PError> {
-    preceded(char('$'), cut(tuple((alphanum1, opt(parse_formatter)))))
-        .map(|(name, formatter)| Placeholder { name, formatter })
+    preceded(char('$'), cut(tuple((alphanum1, many0(parse_formatter)))))
+        .map(|(name, formatters)| Placeholder { name, formatters })
         .parse(i)
 }
 
+// `$var`, `2.5`, `-$var` or `($a + $b)`
+fn parse_operand<'a>(i: &'a str) -> IResult<&'a str, Expr, PError<'a>> {
+    preceded(
+        spaces,
+        alt((
+            map(
+                preceded(
+                    char('$'),
+                    cut(take_while1(|x: char| x.is_alphanumeric() || x == '_')),
+                ),
+                |name: &str| Expr::Placeholder(name.to_owned()),
+            ),
+            map(double, Expr::Number),
+            map(preceded(char('-'), cut(parse_operand)), |expr| {
+                Expr::Neg(Box::new(expr))
+            }),
+            preceded(
+                char('('),
+                cut(terminated(parse_expr, preceded(spaces, char(')')))),
+            ),
+        )),
+    )(i)
+}
+
+// Left-associative chain of `operand` separated by one of `ops`
+fn parse_chain<'a>(
+    i: &'a str,
+    ops: &'static str,
+    operand: fn(&'a str) -> IResult<&'a str, Expr, PError<'a>>,
+) -> IResult<&'a str, Expr, PError<'a>> {
+    let (mut i, mut expr) = operand(i)?;
+    while let Ok((rest, op)) = preceded(spaces, one_of::<_, _, PError>(ops))(i) {
+        let (rest, rhs) = cut(operand)(rest)?;
+        let op = Op::from_char(op).unwrap();
+        expr = Expr::Binary(op, Box::new(expr), Box::new(rhs));
+        i = rest;
+    }
+    Ok((i, expr))
+}
+
+fn parse_product<'a>(i: &'a str) -> IResult<&'a str, Expr, PError<'a>> {
+    parse_chain(i, "*/", parse_operand)
+}
+
+// `$a / $b * 100`
+fn parse_expr<'a>(i: &'a str) -> IResult<&'a str, Expr, PError<'a>> {
+    parse_chain(i, "+-", parse_product)
+}
+
+// `$($a / $b * 100)`
+// `$($speed * 8).eng(w:3)`
+fn parse_expression<'a>(i: &'a str) -> IResult<&'a str, Expression<'a>, PError<'a>> {
+    preceded(
+        tag("$("),
+        cut(tuple((
+            terminated(parse_expr, preceded(spaces, char(')'))),
+            opt(parse_formatter),
+        ))),
+    )
+    .map(|(expr, formatter)| Expression { expr, formatter })
+    .parse(i)
+}
+
 // `just escaped \| text`
 fn parse_string(i: &str) -> IResult<&str, String, PError> {
     preceded(
@@ -156,6 +231,7 @@ fn parse_token_list(i: &str) -> IResult<&str, TokenList, PError> {
     map(
         many0(alt((
             map(parse_string, Token::Text),
+            map(parse_expression, Token::Expression),
             map(parse_placeholder, Token::Placeholder),
             map(parse_icon, Token::Icon),
             map(parse_recursive_template, Token::Recursive),
@@ -309,7 +385,7 @@ mod tests {
                 "",
                 Placeholder {
                     name: "key",
-                    formatter: None,
+                    formatters: vec![],
                 }
             ))
         );
@@ -319,10 +395,10 @@ mod tests {
                 "",
                 Placeholder {
                     name: "var",
-                    formatter: Some(Formatter {
+                    formatters: vec![Formatter {
                         name: "str",
                         args: vec![]
-                    }),
+                    }],
                 }
             ))
         );
@@ -332,16 +408,42 @@ mod tests {
                 "",
                 Placeholder {
                     name: "var",
-                    formatter: Some(Formatter {
+                    formatters: vec![Formatter {
                         name: "str",
                         args: vec![Arg { key: "a", val: "b" }, Arg { key: "c", val: "d" }]
-                    }),
+                    }],
                 }
             ))
         );
         assert!(parse_placeholder("$key.").is_err());
     }
 
+    #[test]
+    fn chained_formatters() {
+        assert_eq!(
+            parse_placeholder("$title.lower().str(max_w:5)"),
+            Ok((
+                "",
+                Placeholder {
+                    name: "title",
+                    formatters: vec![
+                        Formatter {
+                            name: "lower",
+                            args: vec![]
+                        },
+                        Formatter {
+                            name: "str",
+                            args: vec![Arg {
+                                key: "max_w",
+                                val: "5"
+                            }]
+                        },
+                    ],
+                }
+            ))
+        );
+    }
+
     #[test]
     fn icon() {
         assert_eq!(parse_icon("^icon_my_icon"), Ok(("", "my_icon")));
@@ -360,14 +462,14 @@ mod tests {
                     Token::Text(" abc $ ".into()),
                     Token::Placeholder(Placeholder {
                         name: "var",
-                        formatter: Some(Formatter {
+                        formatters: vec![Formatter {
                             name: "str",
                             args: vec![Arg { key: "a", val: "b" }]
-                        })
+                        }]
                     }),
                     Token::Placeholder(Placeholder {
                         name: "x",
-                        formatter: None,
+                        formatters: vec![],
                     }),
                     Token::Text(" ".into())
                 ])
@@ -393,10 +495,10 @@ mod tests {
                         Token::Text(" ".into()),
                         Token::Placeholder(Placeholder {
                             name: "x",
-                            formatter: Some(Formatter {
+                            formatters: vec![Formatter {
                                 name: "str",
                                 args: vec![]
-                            })
+                            }]
                         }),
                         Token::Text(" ".into()),
                     ]),
@@ -406,6 +508,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn expression() {
+        let placeholder = |name: &str| Box::new(Expr::Placeholder(name.into()));
+        assert_eq!(
+            parse_expression("$( $a/$b *100 ).eng(w:2)"),
+            Ok((
+                "",
+                Expression {
+                    expr: Expr::Binary(
+                        Op::Mul,
+                        Box::new(Expr::Binary(Op::Div, placeholder("a"), placeholder("b"))),
+                        Box::new(Expr::Number(100.0)),
+                    ),
+                    formatter: Some(Formatter {
+                        name: "eng",
+                        args: vec![Arg { key: "w", val: "2" }]
+                    }),
+                }
+            ))
+        );
+        assert_eq!(
+            parse_expression("$(-($a + 1))"),
+            Ok((
+                "",
+                Expression {
+                    expr: Expr::Neg(Box::new(Expr::Binary(
+                        Op::Add,
+                        placeholder("a"),
+                        Box::new(Expr::Number(1.0)),
+                    ))),
+                    formatter: None,
+                }
+            ))
+        );
+        assert!(parse_expression("$($a +)").is_err());
+        // Outside of `$()`, operators are just text
+        assert_eq!(
+            parse_token_list("$a/$b"),
+            Ok((
+                "",
+                TokenList(vec![
+                    Token::Placeholder(Placeholder {
+                        name: "a",
+                        formatters: vec![],
+                    }),
+                    Token::Text("/".into()),
+                    Token::Placeholder(Placeholder {
+                        name: "b",
+                        formatters: vec![],
+                    }),
+                ])
+            ))
+        );
+    }
+
     #[test]
     fn full() {
         assert_eq!(
@@ -419,10 +576,10 @@ mod tests {
                     Token::Recursive(FormatTemplate(vec![
                         TokenList(vec![Token::Placeholder(Placeholder {
                             name: "x",
-                            formatter: Some(Formatter {
+                            formatters: vec![Formatter {
                                 name: "str",
                                 args: vec![]
-                            })
+                            }]
                         })]),
                         TokenList(vec![Token::Text("N/A".into())]),
                     ])),
@@ -116,6 +116,31 @@ impl Prefix {
             Self::OneButBinary | Self::Kibi | Self::Mebi | Self::Gibi | Self::Tebi
         )
     }
+
+    /// Returns the binary prefix of the same magnitude, e.g. `Ki` for `K`. Prefixes smaller than
+    /// one have no binary equivalent and are returned as is.
+    pub fn to_binary(self) -> Self {
+        match self {
+            Self::One => Self::OneButBinary,
+            Self::Kilo => Self::Kibi,
+            Self::Mega => Self::Mebi,
+            Self::Giga => Self::Gibi,
+            Self::Tera => Self::Tebi,
+            other => other,
+        }
+    }
+
+    /// Returns the decimal prefix of the same magnitude, e.g. `K` for `Ki`
+    pub fn to_decimal(self) -> Self {
+        match self {
+            Self::OneButBinary => Self::One,
+            Self::Kibi => Self::Kilo,
+            Self::Mebi => Self::Mega,
+            Self::Gibi => Self::Giga,
+            Self::Tebi => Self::Tera,
+            other => other,
+        }
+    }
 }
 
 impl fmt::Display for Prefix {
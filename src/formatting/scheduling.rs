@@ -3,13 +3,39 @@ use futures::stream::StreamExt;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 
-pub fn manage_widgets_updates() -> (UnboundedSender<(usize, Vec<u64>)>, BoxedStream<Vec<usize>>) {
-    let (intervals_tx, intervals_rx) = unbounded_channel::<(usize, Vec<u64>)>();
+/// How often a widget has to be re-rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    /// The period in milliseconds
+    pub period: u64,
+    /// The instant the periods are counted from. Widgets with different epochs are re-rendered
+    /// at different times even if their periods are the same.
+    pub epoch: Option<Instant>,
+}
+
+pub fn manage_widgets_updates() -> (
+    UnboundedSender<(usize, Vec<Interval>)>,
+    BoxedStream<Vec<usize>>,
+) {
+    let (intervals_tx, intervals_rx) = unbounded_channel::<(usize, Vec<Interval>)>();
     struct State {
         time_anchor: Instant,
         last_update: u64,
-        intervals_rx: UnboundedReceiver<(usize, Vec<u64>)>,
-        intervals: Vec<(usize, Vec<u64>)>,
+        intervals_rx: UnboundedReceiver<(usize, Vec<Interval>)>,
+        /// The periods and phases of each widget in milliseconds
+        intervals: Vec<(usize, Vec<(u64, u64)>)>,
+    }
+    impl State {
+        fn set_intervals(&mut self, id: usize, new_intervals: Vec<Interval>) {
+            self.intervals.retain(|(i, _)| *i != id);
+            if !new_intervals.is_empty() {
+                let phases = new_intervals
+                    .iter()
+                    .map(|i| (i.period, phase(self.time_anchor, i)))
+                    .collect();
+                self.intervals.push((id, phases));
+            }
+        }
     }
     let stream = futures::stream::unfold(
         State {
@@ -22,10 +48,7 @@ pub fn manage_widgets_updates() -> (UnboundedSender<(usize, Vec<u64>)>, BoxedStr
             loop {
                 if state.intervals.is_empty() {
                     let (id, new_intervals) = state.intervals_rx.recv().await?;
-                    state.intervals.retain(|(i, _)| *i != id);
-                    if !new_intervals.is_empty() {
-                        state.intervals.push((id, new_intervals));
-                    }
+                    state.set_intervals(id, new_intervals);
                     continue;
                 }
 
@@ -53,10 +76,7 @@ pub fn manage_widgets_updates() -> (UnboundedSender<(usize, Vec<u64>)>, BoxedStr
                     tokio::time::timeout(Duration::from_millis(delay), state.intervals_rx.recv())
                         .await
                 {
-                    state.intervals.retain(|(i, _)| *i != id);
-                    if !new_intervals.is_empty() {
-                        state.intervals.push((id, new_intervals));
-                    }
+                    state.set_intervals(id, new_intervals);
                 }
             }
         },
@@ -65,16 +85,33 @@ pub fn manage_widgets_updates() -> (UnboundedSender<(usize, Vec<u64>)>, BoxedStr
     (intervals_tx, stream)
 }
 
-fn single_block_next_update(intervals: &[u64], time: u64, last_update: u64) -> u64 {
-    fn next_update(time: u64, interval: u64) -> u64 {
-        time + interval - time % interval
+/// Returns the offset of the epoch of `interval` from `time_anchor`, modulo its period. The
+/// phase is rounded so that updates never happen before a period of the epoch has passed.
+fn phase(time_anchor: Instant, interval: &Interval) -> u64 {
+    let period = interval.period;
+    match interval.epoch {
+        None => 0,
+        Some(epoch) if epoch <= time_anchor => {
+            let behind = (time_anchor - epoch).as_millis() as u64 % period;
+            (period - behind) % period
+        }
+        Some(epoch) => {
+            let ahead = (epoch - time_anchor).as_nanos().div_ceil(1_000_000) as u64;
+            ahead % period
+        }
+    }
+}
+
+fn single_block_next_update(intervals: &[(u64, u64)], time: u64, last_update: u64) -> u64 {
+    fn next_update(time: u64, interval: u64, phase: u64) -> u64 {
+        time + interval - (time + interval - phase) % interval
     }
     let mut time_to_next = u64::MAX;
-    for &interval in intervals {
-        if next_update(last_update, interval) <= time {
+    for &(interval, phase) in intervals {
+        if next_update(last_update, interval, phase) <= time {
             return 0;
         }
-        time_to_next = time_to_next.min(next_update(time, interval) - time);
+        time_to_next = time_to_next.min(next_update(time, interval, phase) - time);
     }
     time_to_next
 }
@@ -90,7 +127,7 @@ mod tests {
         // 200 x         x         x         x         x         x
         // 300 x              x              x              x
         // 500 x                        x                        x
-        let intervals = &[200, 300, 500];
+        let intervals = &[(200, 0), (300, 0), (500, 0)];
         assert_eq!(single_block_next_update(intervals, 0, 0), 200);
         assert_eq!(single_block_next_update(intervals, 50, 0), 150);
         assert_eq!(single_block_next_update(intervals, 210, 50), 0);
@@ -99,4 +136,36 @@ mod tests {
         assert_eq!(single_block_next_update(intervals, 300, 300), 100);
         assert_eq!(single_block_next_update(intervals, 800, 300), 0);
     }
+
+    #[test]
+    fn phases() {
+        //     0   100  200  300  400  500  600
+        //     |    |    |    |    |    |    |
+        // 200    x         x         x
+        // 200         x         x         x
+        let a = &[(200, 50)];
+        let b = &[(200, 150)];
+        assert_eq!(single_block_next_update(a, 0, 0), 50);
+        assert_eq!(single_block_next_update(b, 0, 0), 150);
+        assert_eq!(single_block_next_update(a, 50, 0), 0);
+        assert_eq!(single_block_next_update(a, 50, 50), 200);
+        assert_eq!(single_block_next_update(b, 50, 50), 100);
+        assert_eq!(single_block_next_update(b, 360, 150), 0);
+        assert_eq!(single_block_next_update(b, 360, 360), 190);
+
+        let anchor = Instant::now();
+        let interval = |epoch| Interval {
+            period: 200,
+            epoch: Some(epoch),
+        };
+        assert_eq!(phase(anchor, &interval(anchor)), 0);
+        assert_eq!(
+            phase(anchor, &interval(anchor - Duration::from_millis(450))),
+            150
+        );
+        assert_eq!(
+            phase(anchor, &interval(anchor + Duration::from_micros(50_400))),
+            51
+        );
+    }
 }
@@ -1,5 +1,10 @@
-use super::formatter::{new_formatter, Formatter};
-use super::{parse, FormatError, Fragment, Values};
+use super::cache::RenderCache;
+use super::expr::Expr;
+use super::formatter::{new_formatter, ColorThresholds, Filter, Formatter, Style};
+use super::intern::Istr;
+use super::scheduling::Interval;
+use super::value::Value;
+use super::{parse, FormatError, Fragment, Metadata, Values};
 use crate::config::SharedConfig;
 use crate::errors::*;
 
@@ -24,11 +29,18 @@ pub enum Token {
     Recursive(FormatTemplate),
     Placeholder {
         name: String,
+        filters: Vec<Filter>,
         formatter: Option<Box<dyn Formatter>>,
+        color: Option<ColorThresholds>,
+        style: Option<Style>,
     },
     Icon {
         name: String,
     },
+    Expression {
+        expr: Expr,
+        formatter: Option<Box<dyn Formatter>>,
+    },
 }
 
 impl FormatTemplate {
@@ -36,6 +48,7 @@ impl FormatTemplate {
         self.0.iter().any(|token_list| {
             token_list.0.iter().any(|token| match token {
                 Token::Placeholder { name, .. } => name == key,
+                Token::Expression { expr, .. } => expr.contains_key(key),
                 Token::Recursive(rec) => rec.contains_key(key),
                 _ => false,
             })
@@ -46,14 +59,16 @@ impl FormatTemplate {
         &self,
         values: &Values,
         config: &SharedConfig,
+        cache: &RenderCache,
     ) -> Result<Vec<Fragment>, FormatError> {
         for (i, token_list) in self.0.iter().enumerate() {
-            match token_list.render(values, config) {
+            match token_list.render(values, config, cache) {
                 Ok(res) => return Ok(res),
                 Err(
                     FormatError::PlaceholderNotFound(_)
                     | FormatError::IncompatibleFormatter { .. }
-                    | FormatError::NumberOutOfRange(_),
+                    | FormatError::NumberOutOfRange(_)
+                    | FormatError::NotANumber(_),
                 ) if i != self.0.len() - 1 => (),
                 Err(e) => return Err(e),
             }
@@ -61,16 +76,22 @@ impl FormatTemplate {
         Ok(Vec::new())
     }
 
-    pub fn init_intervals(&self, intervals: &mut Vec<u64>) {
+    pub fn init_intervals(&self, intervals: &mut Vec<Interval>) {
         for tl in self.0.iter() {
             for t in &tl.0 {
                 match t {
                     Token::Recursive(r) => r.init_intervals(intervals),
                     Token::Placeholder {
                         formatter: Some(f), ..
+                    }
+                    | Token::Expression {
+                        formatter: Some(f), ..
                     } => {
                         if let Some(i) = f.interval() {
-                            intervals.push(i.as_millis() as u64);
+                            intervals.push(Interval {
+                                period: i.as_millis() as u64,
+                                epoch: f.epoch(),
+                            });
                         }
                     }
                     _ => (),
@@ -85,6 +106,7 @@ impl TokenList {
         &self,
         values: &Values,
         config: &SharedConfig,
+        cache: &RenderCache,
     ) -> Result<Vec<Fragment>, FormatError> {
         let mut retval = Vec::new();
         let mut cur = Fragment::default();
@@ -104,32 +126,53 @@ impl TokenList {
                     if !cur.text.is_empty() {
                         retval.push(cur);
                     }
-                    retval.extend(rec.render(values, config)?);
+                    retval.extend(rec.render(values, config, cache)?);
                     cur = retval.pop().unwrap_or_default();
                 }
-                Token::Placeholder { name, formatter } => {
+                Token::Placeholder {
+                    name,
+                    filters,
+                    formatter,
+                    color,
+                    style,
+                } => {
                     let value = values
                         .get(name.as_str())
                         .ok_or_else(|| FormatError::PlaceholderNotFound(name.into()))?;
-                    let formatter = formatter
-                        .as_ref()
-                        .map(Box::as_ref)
-                        .unwrap_or_else(|| value.default_formatter());
-                    let formatted = formatter.format(&value.inner, config)?;
-                    if value.metadata == cur.metadata {
-                        cur.text.push_str(&formatted);
+                    let filtered;
+                    let value = if filters.is_empty() {
+                        value
                     } else {
-                        if !cur.text.is_empty() {
-                            retval.push(cur);
+                        let mut inner = value.inner.clone();
+                        for filter in filters {
+                            inner = filter.apply(&inner)?;
                         }
-                        cur = Fragment {
-                            text: formatted,
+                        filtered = Value {
+                            inner,
                             metadata: value.metadata,
                         };
+                        &filtered
+                    };
+                    let mut formatted = format_value(token, value, formatter, config, cache)?;
+                    if let Some(color) = color {
+                        formatted = color.apply(&value.inner, formatted, config)?;
                     }
+                    if let Some(style) = style {
+                        formatted = style.apply(formatted);
+                    }
+                    push_value(&mut retval, &mut cur, formatted, value.metadata);
+                }
+                Token::Expression { expr, formatter } => {
+                    let value = expr.eval(values)?;
+                    // The result depends on several values, so it is not cached
+                    let formatter = formatter
+                        .as_deref()
+                        .unwrap_or_else(|| value.default_formatter());
+                    let formatted = formatter.format(&value.inner, config)?;
+                    push_value(&mut retval, &mut cur, formatted, value.metadata);
                 }
                 Token::Icon { name } => {
-                    let icon = config.get_icon(name, None)?;
+                    let icon = cache.get_or_insert(token, || config.get_icon(name, None))?;
                     if cur.metadata.is_default() {
                         cur.text.push_str(&icon);
                     } else {
@@ -150,6 +193,43 @@ impl TokenList {
     }
 }
 
+fn format_value(
+    token: &Token,
+    value: &Value,
+    formatter: &Option<Box<dyn Formatter>>,
+    config: &SharedConfig,
+    cache: &RenderCache,
+) -> Result<String, FormatError> {
+    let formatter = formatter
+        .as_deref()
+        .unwrap_or_else(|| value.default_formatter());
+    // Time-dependent formatters must be re-evaluated on every render
+    if formatter.interval().is_some() {
+        formatter.format(&value.inner, config)
+    } else {
+        cache.get_or_insert(token, || formatter.format(&value.inner, config))
+    }
+}
+
+fn push_value(
+    retval: &mut Vec<Fragment>,
+    cur: &mut Fragment,
+    formatted: String,
+    metadata: Metadata,
+) {
+    if metadata == cur.metadata {
+        cur.text.push_str(&formatted);
+    } else {
+        if !cur.text.is_empty() {
+            retval.push(std::mem::take(cur));
+        }
+        *cur = Fragment {
+            text: formatted,
+            metadata,
+        };
+    }
+}
+
 impl FromStr for FormatTemplate {
     type Err = Error;
 
@@ -192,17 +272,67 @@ impl TryFrom<parse::Token<'_>> for Token {
     fn try_from(value: parse::Token) -> Result<Self, Self::Error> {
         Ok(match value {
             parse::Token::Text(text) => Self::Text(text),
-            parse::Token::Placeholder(placeholder) => Self::Placeholder {
-                name: placeholder.name.to_owned(),
-                formatter: placeholder
-                    .formatter
-                    .map(|fmt| new_formatter(fmt.name, &fmt.args))
-                    .transpose()?,
-            },
+            parse::Token::Placeholder(placeholder) => {
+                let mut filters = Vec::new();
+                let mut formatter = None;
+                let mut color = None;
+                let mut style = None;
+                for fmt in placeholder.formatters {
+                    match fmt.name {
+                        "color" if color.is_none() => {
+                            color = Some(ColorThresholds::from_args(&fmt.args)?);
+                            continue;
+                        }
+                        "style" if style.is_none() => {
+                            style = Some(Style::from_args(&fmt.args)?);
+                            continue;
+                        }
+                        _ => (),
+                    }
+                    if color.is_some() || style.is_some() {
+                        return Err(Error::new(format!(
+                            "'.color()' and '.style()' must come last and at most once in '${}'",
+                            placeholder.name
+                        )));
+                    }
+                    if formatter.is_some() {
+                        return Err(Error::new(format!(
+                            "'.{}' follows the formatter of '${}', but only filters can be chained before it",
+                            fmt.name, placeholder.name
+                        )));
+                    }
+                    match Filter::new(fmt.name, &fmt.args) {
+                        Some(filter) => filters.push(filter?),
+                        None => formatter = Some(new_formatter(fmt.name, &fmt.args)?),
+                    }
+                }
+                // Blocks whose placeholders are named at runtime (e.g. by a script) find the
+                // names used by formats with `Istr::lookup`
+                Istr::new(placeholder.name);
+                Self::Placeholder {
+                    name: placeholder.name.to_owned(),
+                    filters,
+                    formatter,
+                    color,
+                    style,
+                }
+            }
             parse::Token::Icon(icon) => Self::Icon {
                 name: icon.to_owned(),
             },
             parse::Token::Recursive(rec) => Self::Recursive(rec.try_into()?),
+            parse::Token::Expression(expression) => {
+                expression.expr.for_each_placeholder(&mut |name| {
+                    Istr::new(name);
+                });
+                Self::Expression {
+                    expr: expression.expr,
+                    formatter: expression
+                        .formatter
+                        .map(|fmt| new_formatter(fmt.name, &fmt.args))
+                        .transpose()?,
+                }
+            }
         })
     }
 }
@@ -1,9 +1,10 @@
-use std::borrow::Cow;
 use std::time::Duration;
 
 use super::formatter;
+use super::intern::Istr;
 use super::unit::Unit;
 use super::Metadata;
+use crate::widget::State;
 use chrono::{DateTime, Utc};
 use chrono_tz::Tz;
 
@@ -13,16 +14,36 @@ pub struct Value {
     pub metadata: Metadata,
 }
 
+// Values are created on every update of every block, so keep them small
+const _: () = assert!(std::mem::size_of::<Value>() <= 32);
+
 #[derive(Debug, Clone)]
 pub enum ValueInner {
-    Text(String),
-    Icon(Cow<'static, str>, Option<f64>),
+    Text(Box<str>),
+    Icon(IconName, Option<f32>),
     Number { val: f64, unit: Unit },
     Datetime(DateTime<Utc>, Option<Tz>),
     Duration(Duration),
     Flag,
 }
 
+/// The name of an icon. Names defined by blocks are interned, while names from outside of the
+/// program (e.g. the output of a command) are owned, so that they are freed again.
+#[derive(Debug, Clone)]
+pub enum IconName {
+    Interned(Istr),
+    Owned(Box<str>),
+}
+
+impl IconName {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Interned(name) => name.as_str(),
+            Self::Owned(name) => name,
+        }
+    }
+}
+
 impl ValueInner {
     pub fn type_name(&self) -> &'static str {
         match self {
@@ -74,28 +95,33 @@ impl Value {
         Self::new(ValueInner::Duration(duration))
     }
 
-    pub fn icon<S>(name: S) -> Self
-    where
-        S: Into<Cow<'static, str>>,
-    {
-        Self::new(ValueInner::Icon(name.into(), None))
+    pub fn icon(name: impl AsRef<str>) -> Self {
+        Self::new(ValueInner::Icon(
+            IconName::Interned(Istr::new(name.as_ref())),
+            None,
+        ))
+    }
+
+    /// An icon whose name comes from outside of the program, which must not be interned
+    pub fn icon_owned(name: String) -> Self {
+        Self::new(ValueInner::Icon(
+            IconName::Owned(name.into_boxed_str()),
+            None,
+        ))
     }
 
-    pub fn icon_progression<S>(name: S, value: f64) -> Self
-    where
-        S: Into<Cow<'static, str>>,
-    {
-        Self::new(ValueInner::Icon(name.into(), Some(value)))
+    pub fn icon_progression(name: impl AsRef<str>, value: f64) -> Self {
+        Self::new(ValueInner::Icon(
+            IconName::Interned(Istr::new(name.as_ref())),
+            Some(value as f32),
+        ))
     }
-    pub fn icon_progression_bound<S>(name: S, value: f64, low: f64, high: f64) -> Self
-    where
-        S: Into<Cow<'static, str>>,
-    {
+    pub fn icon_progression_bound(name: impl AsRef<str>, value: f64, low: f64, high: f64) -> Self {
         Self::icon_progression(name, (value.clamp(low, high) - low) / (high - low))
     }
 
     pub fn text(text: String) -> Self {
-        Self::new(ValueInner::Text(text))
+        Self::new(ValueInner::Text(text.into_boxed_str()))
     }
 
     pub fn number_unit(val: impl IntoF64, unit: Unit) -> Self {
@@ -133,8 +159,8 @@ impl Value {
 
 /// Set options
 impl Value {
-    pub fn with_instance(mut self, instance: &'static str) -> Self {
-        self.metadata.instance = Some(instance);
+    pub fn with_instance(mut self, instance: &str) -> Self {
+        self.metadata.instance = Some(Istr::new(instance));
         self
     }
 
@@ -148,6 +174,11 @@ impl Value {
         self
     }
 
+    pub fn with_state(mut self, state: State) -> Self {
+        self.metadata.state = Some(state);
+        self
+    }
+
     pub fn default_formatter(&self) -> &'static dyn formatter::Formatter {
         match &self.inner {
             ValueInner::Text(_) | ValueInner::Icon(..) => &formatter::DEFAULT_STRING_FORMATTER,
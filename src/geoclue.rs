@@ -0,0 +1,165 @@
+//! The location of the computer from [GeoClue](https://gitlab.freedesktop.org/geoclue/geoclue)
+//!
+//! GeoClue locates the computer using e.g. nearby WiFi networks or a GPS receiver, without sending
+//! requests to IP geolocation services from i3status-rs. Blocks get the location with
+//! `CommonApi::get_location`.
+//!
+//! GeoClue only gives the location to applications the user has agreed to. This is either done by
+//! a GeoClue agent (included in e.g. GNOME Shell, or `/usr/libexec/geoclue-2.0/demos/agent`),
+//! which asks the user, or by allowing i3status-rs in `/etc/geoclue/geoclue.conf`:
+//!
+//! ```ini
+//! [i3status-rs]
+//! allowed=true
+//! system=false
+//! users=
+//! ```
+
+use std::time::Duration;
+
+use futures::StreamExt;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+
+use crate::errors::*;
+use crate::util::new_system_dbus_connection;
+
+/// The ID under which i3status-rs asks for consent
+const DESKTOP_ID: &str = "i3status-rs";
+/// City level accuracy, which is enough for all blocks
+const ACCURACY_LEVEL: u32 = 4;
+/// How long to wait for the first location
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+static CLIENT: tokio::sync::OnceCell<ClientProxy<'static>> = tokio::sync::OnceCell::const_new();
+
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// The accuracy in meters
+    pub accuracy: f64,
+    /// A human-readable description, may be empty
+    pub description: String,
+}
+
+/// The current location. The GeoClue client is shared by all blocks and keeps running, so only the
+/// first call waits for a location to be found.
+pub async fn location() -> Result<Location> {
+    let client = CLIENT.get_or_try_init(start_client).await?;
+    let mut updates = client
+        .receive_location_updated()
+        .await
+        .error("Failed to subscribe to GeoClue location updates")?;
+    let mut path = client
+        .location()
+        .await
+        .error("Failed to get the GeoClue location")?;
+    // The path is "/" until the first location is found
+    if path.as_str() == "/" {
+        let update = tokio::time::timeout(TIMEOUT, updates.next())
+            .await
+            .error("GeoClue did not find the location in time")?
+            .error("GeoClue stopped")?;
+        path = update
+            .args()
+            .error("Invalid GeoClue location update")?
+            .new
+            .into();
+    }
+    let location = LocationProxy::builder(client.inner().connection())
+        .path(path)
+        .error("Invalid GeoClue location path")?
+        .build()
+        .await
+        .error("Failed to create LocationProxy")?;
+    Ok(Location {
+        latitude: location.latitude().await.error("Failed to get latitude")?,
+        longitude: location
+            .longitude()
+            .await
+            .error("Failed to get longitude")?,
+        accuracy: location.accuracy().await.error("Failed to get accuracy")?,
+        description: location.description().await.unwrap_or_default(),
+    })
+}
+
+async fn start_client() -> Result<ClientProxy<'static>> {
+    let dbus_conn = new_system_dbus_connection().await?;
+    let manager = ManagerProxy::new(&dbus_conn)
+        .await
+        .error("Failed to create GeoClue ManagerProxy")?;
+    let path = manager
+        .get_client()
+        .await
+        .error("Failed to get a GeoClue client. Is GeoClue installed?")?;
+    let client = ClientProxy::builder(&dbus_conn)
+        .path(path)
+        .error("Invalid GeoClue client path")?
+        .build()
+        .await
+        .error("Failed to create GeoClue ClientProxy")?;
+    client
+        .set_desktop_id(DESKTOP_ID)
+        .await
+        .error("Failed to set the GeoClue desktop ID")?;
+    client
+        .set_requested_accuracy_level(ACCURACY_LEVEL)
+        .await
+        .error("Failed to set the GeoClue accuracy level")?;
+    client.start().await.map_err(|error| match error {
+        zbus::Error::MethodError(name, ..) if name.as_str().ends_with("AccessDenied") => {
+            Error::new(format!(
+                "GeoClue denied access to the location. Run a GeoClue agent, or allow '{DESKTOP_ID}' in /etc/geoclue/geoclue.conf"
+            ))
+        }
+        error => Error::new(format!("Failed to start the GeoClue client: {error}")),
+    })?;
+    Ok(client)
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.GeoClue2.Manager",
+    default_service = "org.freedesktop.GeoClue2",
+    default_path = "/org/freedesktop/GeoClue2/Manager"
+)]
+trait Manager {
+    fn get_client(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.GeoClue2.Client",
+    default_service = "org.freedesktop.GeoClue2"
+)]
+trait Client {
+    fn start(&self) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn location(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn set_desktop_id(&self, id: &str) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn set_requested_accuracy_level(&self, level: u32) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn location_updated(&self, old: ObjectPath<'_>, new: ObjectPath<'_>) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.GeoClue2.Location",
+    default_service = "org.freedesktop.GeoClue2"
+)]
+trait Location {
+    #[zbus(property)]
+    fn latitude(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn longitude(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn accuracy(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn description(&self) -> zbus::Result<String>;
+}
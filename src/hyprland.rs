@@ -0,0 +1,76 @@
+//! The IPC of [Hyprland](https://wiki.hyprland.org/IPC/)
+//!
+//! Hyprland has two sockets in `$XDG_RUNTIME_DIR/hypr/$HYPRLAND_INSTANCE_SIGNATURE/` (in
+//! `/tmp/hypr/` before version 0.40): `.socket.sock` answers requests such as `j/activewindow`,
+//! and `.socket2.sock` sends events as lines of the form `name>>data`.
+
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::UnixStream;
+
+use crate::errors::*;
+
+const SIGNATURE: &str = "HYPRLAND_INSTANCE_SIGNATURE";
+
+/// Whether i3status-rs runs under Hyprland
+pub fn is_running() -> bool {
+    std::env::var_os(SIGNATURE).is_some()
+}
+
+fn socket_path(socket: &str) -> Result<PathBuf> {
+    let signature = std::env::var_os(SIGNATURE).or_error(|| format!("${SIGNATURE} is not set"))?;
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(|dir| PathBuf::from(dir).join("hypr").join(&signature))
+        .filter(|dir| dir.exists());
+    let dir = runtime_dir.unwrap_or_else(|| PathBuf::from("/tmp/hypr").join(&signature));
+    Ok(dir.join(socket))
+}
+
+/// Sends a JSON request, e.g. `activewindow`, and parses the reply
+pub async fn request<T: DeserializeOwned>(command: &str) -> Result<T> {
+    let mut stream = UnixStream::connect(socket_path(".socket.sock")?)
+        .await
+        .error("Failed to connect to the Hyprland socket")?;
+    stream
+        .write_all(format!("j/{command}").as_bytes())
+        .await
+        .error("Failed to send Hyprland request")?;
+    let mut reply = String::new();
+    stream
+        .read_to_string(&mut reply)
+        .await
+        .error("Failed to read Hyprland reply")?;
+    serde_json::from_str(&reply).or_error(|| format!("Invalid reply to Hyprland '{command}'"))
+}
+
+pub struct Events {
+    lines: Lines<BufReader<UnixStream>>,
+}
+
+impl Events {
+    pub async fn new() -> Result<Self> {
+        let stream = UnixStream::connect(socket_path(".socket2.sock")?)
+            .await
+            .error("Failed to connect to the Hyprland event socket")?;
+        Ok(Self {
+            lines: BufReader::new(stream).lines(),
+        })
+    }
+
+    /// Waits for the next event and returns its name and data
+    pub async fn next(&mut self) -> Result<(String, String)> {
+        loop {
+            let line = self
+                .lines
+                .next_line()
+                .await
+                .error("Failed to read Hyprland event")?
+                .error("Hyprland event socket closed")?;
+            if let Some((name, data)) = line.split_once(">>") {
+                return Ok((name.to_owned(), data.to_owned()));
+            }
+        }
+    }
+}
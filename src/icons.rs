@@ -12,6 +12,9 @@ pub struct Icons(pub HashMap<String, Icon>);
 pub enum Icon {
     Single(String),
     Progression(Vec<String>),
+    /// The values (in percent) at which the icons of the progression `<name>` switch, set as
+    /// `<name>_progression`
+    Breakpoints(Vec<f64>),
 }
 
 impl From<&'static str> for Icon {
@@ -37,6 +40,7 @@ impl Default for Icons {
             "bell" => "ON",
             "bell-slash" => "OFF",
             "bluetooth" => "BT",
+            "browser" => "WEB",
             "calendar" => "CAL",
             "cogs" => "LOAD",
             "cpu" => "CPU",
@@ -44,6 +48,7 @@ impl Default for Icons {
             "cpu_boost_off" => "BOOST OFF",
             "disk_drive" => "DISK",
             "docker" => "DOCKER",
+            "git" => "GIT",
             "github" => "GITHUB",
             "gpu" => "GPU",
             "headphones" => "HEAD",
@@ -74,7 +79,9 @@ impl Default for Icons {
             "net_vpn" => "VPN",
             "net_wired" => "ETH",
             "net_wireless" => "WLAN",
+            "nix" => "NIX",
             "notification" => "NOTIF",
+            "paused" => "||",
             "phone" => "PHONE",
             "phone_disconnected" => "PHONE",
             "ping" => "PING",
@@ -83,7 +90,11 @@ impl Default for Icons {
             "pomodoro_paused" => "PAUSED",
             "pomodoro_started" => "STARTED",
             "pomodoro_stopped" => "STOPPED",
+            "power_profile_balanced" => "BAL",
+            "power_profile_performance" => "PERF",
+            "power_profile_power_saver" => "SAVE",
             "resolution" => "RES",
+            "rgb" => "RGB",
             "scratchpad" => "[]",
             "tasks" => "TSK",
             "tea" => "TEA",
@@ -127,6 +138,62 @@ impl Icons {
         }
     }
 
+    /// Loads several icon sets, where icons missing in a set are taken from the next one
+    pub fn layered(files: &[String]) -> Result<Self> {
+        let sets = files
+            .iter()
+            .map(|file| Self::from_file(file))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Icons which no set but "none" provides are shown as text or not at all
+        let none = Self::default();
+        let mut unresolved: Vec<&str> = none
+            .0
+            .keys()
+            .filter(|name| {
+                files
+                    .iter()
+                    .zip(&sets)
+                    .all(|(file, set)| file == "none" || !set.0.contains_key(*name))
+            })
+            .map(String::as_str)
+            .collect();
+        if !unresolved.is_empty() {
+            unresolved.sort_unstable();
+            log::warn!(
+                "Icons not found in the icon sets {files:?}: {}",
+                unresolved.join(", ")
+            );
+        }
+
+        Ok(Self::merge(sets))
+    }
+
+    /// Merges icon sets, preferring the earlier ones
+    fn merge(sets: Vec<Self>) -> Self {
+        let mut icons = HashMap::new();
+        for set in sets {
+            // Breakpoints only apply to the progression of their own set
+            let foreign: Vec<String> = set
+                .0
+                .iter()
+                .filter(|(name, icon)| {
+                    matches!(icon, Icon::Breakpoints(_))
+                        && name
+                            .strip_suffix("_progression")
+                            .is_some_and(|base| icons.contains_key(base))
+                })
+                .map(|(name, _)| name.clone())
+                .collect();
+            for (name, icon) in set.0 {
+                if !foreign.contains(&name) {
+                    icons.entry(name).or_insert(icon);
+                }
+            }
+        }
+        Self(icons)
+    }
+
     pub fn apply_overrides(&mut self, overrides: HashMap<String, Icon>) {
         self.0.extend(overrides);
     }
@@ -134,11 +201,18 @@ impl Icons {
     pub fn get(&self, icon: &'_ str, value: Option<f64>) -> Option<&str> {
         match (self.0.get(icon)?, value) {
             (Icon::Single(icon), _) => Some(icon),
+            (Icon::Breakpoints(_), _) => None,
             (Icon::Progression(prog), _) if prog.is_empty() => None,
             (Icon::Progression(prog), None) => Some(prog.last().unwrap()),
             (Icon::Progression(prog), Some(value)) => {
-                let index = ((value * prog.len() as f64) as usize).clamp(0, prog.len() - 1);
-                Some(prog[index].as_str())
+                let index = match self.0.get(&format!("{icon}_progression")) {
+                    Some(Icon::Breakpoints(breakpoints)) => breakpoints
+                        .iter()
+                        .rposition(|&b| value * 100.0 >= b)
+                        .unwrap_or(0),
+                    _ => (value * prog.len() as f64) as usize,
+                };
+                Some(prog[index.min(prog.len() - 1)].as_str())
             }
         }
     }
@@ -147,15 +221,27 @@ impl Icons {
 #[derive(Deserialize, Default)]
 #[serde(deny_unknown_fields, default)]
 struct IconsConfigRaw {
-    icons: Option<String>,
+    icons: Option<IconSets>,
     overrides: Option<HashMap<String, Icon>>,
 }
 
+/// Either a single icon set or a list of sets to fall back through
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IconSets {
+    Single(String),
+    Layered(Vec<String>),
+}
+
 impl TryFrom<IconsConfigRaw> for Icons {
     type Error = Error;
 
     fn try_from(raw: IconsConfigRaw) -> Result<Self, Self::Error> {
-        let mut icons = Self::from_file(raw.icons.as_deref().unwrap_or("none"))?;
+        let mut icons = match raw.icons {
+            None => Self::default(),
+            Some(IconSets::Single(file)) => Self::from_file(&file)?,
+            Some(IconSets::Layered(files)) => Self::layered(&files)?,
+        };
         if let Some(overrides) = raw.overrides {
             for icon in overrides {
                 icons.0.insert(icon.0, icon.1);
@@ -164,3 +250,49 @@ impl TryFrom<IconsConfigRaw> for Icons {
         Ok(icons)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progression() {
+        let mut icons = Icons(map! {
+            "volume" => ["mute", "low", "mid", "high"],
+        });
+        let get = |icons: &Icons, value| icons.get("volume", Some(value)).unwrap().to_owned();
+        assert_eq!(get(&icons, 0.3), "low");
+        assert_eq!(get(&icons, 0.5), "mid");
+        assert_eq!(get(&icons, 1.0), "high");
+
+        icons.apply_overrides(map! {
+            "volume_progression" => Icon::Breakpoints(vec![0.0, 15.0, 40.0, 75.0]),
+        });
+        assert_eq!(get(&icons, 0.1), "mute");
+        assert_eq!(get(&icons, 0.2), "low");
+        assert_eq!(get(&icons, 0.5), "mid");
+        assert_eq!(get(&icons, 0.75), "high");
+        assert_eq!(get(&icons, -1.0), "mute");
+        assert_eq!(icons.get("volume_progression", Some(0.5)), None);
+    }
+
+    #[test]
+    fn merge() {
+        let first = Icons(map! {
+            "cpu" => "C",
+            "volume" => ["1", "2"],
+        });
+        let second = Icons(map! {
+            "volume" => ["a", "b", "c"],
+            "volume_progression" => Icon::Breakpoints(vec![0.0, 90.0, 95.0]),
+            "bat" => ["x", "y"],
+            "bat_progression" => Icon::Breakpoints(vec![0.0, 90.0]),
+        });
+        let icons = Icons::merge(vec![first, second, Icons::default()]);
+        assert_eq!(icons.get("cpu", None), Some("C"));
+        assert_eq!(icons.get("tea", None), Some("TEA"));
+        // The breakpoints of the second set don't apply to the volume icons of the first
+        assert_eq!(icons.get("volume", Some(0.6)), Some("2"));
+        assert_eq!(icons.get("bat", Some(0.6)), Some("x"));
+    }
+}
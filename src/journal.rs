@@ -0,0 +1,144 @@
+//! Following the systemd journal
+//!
+//! New entries are read from `journalctl --follow --output=json`, which prints one JSON object per
+//! line. journalctl takes care of finding the journal files, of their rotation and of the
+//! permissions of the user, which would all be needed to read the files directly.
+
+use std::process::Stdio;
+
+use serde_json::Value as JsonValue;
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdout, Command};
+
+use crate::errors::*;
+
+/// Which entries to follow
+#[derive(Debug, Default)]
+pub struct Filter<'a> {
+    /// The lowest priority, by name (e.g. `"err"`) or number, passed to `journalctl --priority`
+    pub priority: Option<&'a str>,
+    /// Only entries of these units
+    pub units: &'a [String],
+    /// The journal of the user instead of the system
+    pub user: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// From 0 (emerg) to 7 (debug)
+    pub priority: Option<u8>,
+    pub message: String,
+    /// The unit, or the syslog identifier of the program if the entry is not from a unit
+    pub source: Option<String>,
+}
+
+pub struct Journal {
+    lines: Lines<BufReader<ChildStdout>>,
+    _child: Child,
+}
+
+impl Journal {
+    /// Starts following the journal. Only entries written from now on are returned.
+    pub fn follow(filter: &Filter) -> Result<Self> {
+        let mut cmd = Command::new("journalctl");
+        cmd.args(["--follow", "--lines=0", "--output=json", "--quiet"]);
+        if filter.user {
+            cmd.arg("--user");
+        }
+        if let Some(priority) = filter.priority {
+            cmd.arg(format!("--priority={priority}"));
+        }
+        for unit in filter.units {
+            cmd.arg(format!("--unit={unit}"));
+        }
+        let mut child = cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .error("Failed to run journalctl")?;
+        let stdout = child
+            .stdout
+            .take()
+            .expect("child did not have a handle to stdout");
+        Ok(Self {
+            lines: BufReader::new(stdout).lines(),
+            _child: child,
+        })
+    }
+
+    /// Waits for the next entry
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancellation safe, because it only awaits `next_line`, which is.
+    pub async fn next(&mut self) -> Result<Entry> {
+        loop {
+            let line = self
+                .lines
+                .next_line()
+                .await
+                .error("Failed to read from journalctl")?
+                .error("journalctl exited unexpectedly")?;
+            if let Some(entry) = parse_entry(&line) {
+                return Ok(entry);
+            }
+        }
+    }
+}
+
+fn parse_entry(line: &str) -> Option<Entry> {
+    let json: JsonValue = serde_json::from_str(line).ok()?;
+    let field = |name| json.get(name).and_then(field_text);
+    Some(Entry {
+        priority: field("PRIORITY").and_then(|p| p.parse().ok()),
+        message: field("MESSAGE").unwrap_or_default(),
+        source: field("_SYSTEMD_UNIT")
+            .or_else(|| field("_SYSTEMD_USER_UNIT"))
+            .or_else(|| field("SYSLOG_IDENTIFIER")),
+    })
+}
+
+/// Fields are strings, or arrays of bytes if they are not valid UTF-8
+fn field_text(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::String(text) => Some(text.clone()),
+        JsonValue::Array(bytes) => {
+            let bytes: Vec<u8> = bytes
+                .iter()
+                .filter_map(|b| b.as_u64().and_then(|b| b.try_into().ok()))
+                .collect();
+            Some(String::from_utf8_lossy(&bytes).into_owned())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries() {
+        assert_eq!(
+            parse_entry(
+                r#"{"PRIORITY":"3","MESSAGE":"Failed to start foo","_SYSTEMD_UNIT":"foo.service","SYSLOG_IDENTIFIER":"systemd"}"#
+            ),
+            Some(Entry {
+                priority: Some(3),
+                message: "Failed to start foo".into(),
+                source: Some("foo.service".into()),
+            })
+        );
+        assert_eq!(
+            parse_entry(r#"{"PRIORITY":"2","MESSAGE":[104,105,255],"SYSLOG_IDENTIFIER":"kernel"}"#),
+            Some(Entry {
+                priority: Some(2),
+                message: "hi\u{fffd}".into(),
+                source: Some("kernel".into()),
+            })
+        );
+        assert_eq!(parse_entry("-- No entries --"), None);
+    }
+}
@@ -9,14 +9,26 @@ pub mod util;
 pub mod blocks;
 pub mod click;
 pub mod config;
+mod demo;
 pub mod errors;
 pub mod escape;
 pub mod formatting;
+mod geoclue;
+mod hyprland;
 pub mod icons;
+mod journal;
+#[cfg(target_os = "linux")]
 mod netlink;
+mod niri;
 pub mod protocol;
+mod secrets;
 mod signals;
 mod subprocess;
+#[cfg(target_os = "freebsd")]
+mod sysctl;
+mod systemd;
+#[cfg(feature = "termux")]
+mod termux;
 pub mod themes;
 pub mod widget;
 mod wrappers;
@@ -29,22 +41,26 @@ use std::borrow::Cow;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::{Arc, LazyLock};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use futures::stream::{FuturesUnordered, StreamExt};
 use futures::Stream;
 use tokio::process::Command;
-use tokio::sync::{mpsc, Notify};
+use tokio::sync::{mpsc, watch, Notify};
 
-use crate::blocks::{BlockAction, BlockError, CommonApi};
-use crate::click::{ClickHandler, MouseButton};
-use crate::config::{BlockConfigEntry, Config, SharedConfig};
+use crate::blocks::{BlockAction, BlockConfig, BlockError, CommonApi, Restart};
+use crate::click::{ClickHandler, MouseButton, ScrollHandler};
+use crate::config::{BarConfig, BlockConfigEntry, Config, SharedConfig, Urgent};
 use crate::errors::*;
 use crate::formatting::value::Value;
 use crate::formatting::Format;
 use crate::protocol::i3bar_block::I3BarBlock;
 use crate::protocol::i3bar_event::{self, I3BarEvent};
+use crate::protocol::SeparatorStyle;
 use crate::signals::Signal;
+use crate::themes::color::Color;
+use crate::themes::color_scheme::{self, ColorScheme};
+use crate::themes::ThemeOverrides;
 use crate::widget::{State, Widget};
 
 const APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
@@ -71,14 +87,14 @@ type BoxedFuture<T> = Pin<Box<dyn Future<Output = T>>>;
 
 type BoxedStream<T> = Pin<Box<dyn Stream<Item = T>>>;
 
-type WidgetUpdatesSender = mpsc::UnboundedSender<(usize, Vec<u64>)>;
+type WidgetUpdatesSender = mpsc::UnboundedSender<(usize, Vec<formatting::scheduling::Interval>)>;
 
 /// A feature-rich and resource-friendly replacement for i3status(1), written in Rust. The
 /// i3status-rs program writes a stream of configurable "blocks" of system information (time,
 /// battery status, volume, etc.) to standard output in the JSON format understood by i3bar(1) and
 /// sway-bar(5).
 #[derive(Debug, clap::Parser)]
-#[clap(author, about, long_about, version = env!("VERSION"))]
+#[clap(author, about, long_about, version = env!("VERSION"), args_conflicts_with_subcommands = true)]
 pub struct CliArgs {
     /// Sets a TOML config file
     ///
@@ -89,7 +105,13 @@ pub struct CliArgs {
     /// 3. Then look for it in `$XDG_DATA_HOME/i3status-rust`
     ///
     /// 4. Otherwise look for it in `/usr/share/i3status-rust`
-    #[clap(default_value = "config.toml")]
+    ///
+    /// Use `-` to read the config from stdin. Note that click events are not available then,
+    /// since i3bar sends them through stdin too. Pipes such as `/dev/fd/3` are read as well. In both
+    /// cases the config is kept in memory to survive in-place restarts.
+    ///
+    /// If the default config file is not found, a minimal built-in config is used.
+    #[clap(default_value = config::DEFAULT_CONFIG_FILE)]
     pub config: String,
     /// Ignore any attempts by i3 to pause the bar when hidden/fullscreen
     #[clap(long = "never-pause")]
@@ -100,6 +122,55 @@ pub struct CliArgs {
     /// The maximum number of blocking threads spawned by tokio
     #[clap(long = "threads", short = 'j', default_value = "2")]
     pub blocking_threads: usize,
+    /// Check the config file for errors and exit without starting the bar
+    #[clap(long = "check")]
+    pub check: bool,
+    /// Print a single line once every block has displayed something (or the timeout has passed)
+    /// and exit. Additional bars are ignored.
+    #[clap(long = "once")]
+    pub once: bool,
+    /// With `--once`, how long to wait for the blocks, in seconds
+    #[clap(long = "timeout", default_value = "5", requires = "once")]
+    pub timeout: u64,
+    /// With `--once`, print plain text instead of JSON
+    #[clap(long = "plain", requires = "once")]
+    pub plain: bool,
+    /// Print the config options, placeholders, actions and icons of a block and exit
+    #[clap(long = "explain", value_name = "BLOCK")]
+    pub explain: Option<String>,
+    /// Show plausible fake values in all blocks instead of running them, e.g. for screenshots or
+    /// to develop themes. Blocks use their `format` (or the default one) and do not touch the
+    /// system or the network. `if_command`s are not run.
+    #[clap(long = "demo")]
+    pub demo: bool,
+    /// Print a built-in example config, either a `minimal` or a `full` one, and exit
+    #[clap(
+        long = "print-default-config",
+        value_name = "KIND",
+        value_parser = ["minimal", "full"],
+        num_args = 0..=1,
+        default_missing_value = "minimal"
+    )]
+    pub print_default_config: Option<String>,
+    #[clap(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum CliCommand {
+    /// Run only the blocks named BLOCK from the config (or BLOCK with its default config if the
+    /// config has none) with debug logging, print every widget they produce until the timeout has
+    /// passed and exit. Fails if no widget was produced. Helps to reproduce issues of a block.
+    TestBlock {
+        #[clap(value_name = "BLOCK")]
+        block: String,
+        /// The config file, found like the CONFIG argument of the bar, which it overrides
+        #[clap(long = "config")]
+        config: Option<String>,
+        /// How long to run the blocks, in seconds
+        #[clap(long = "timeout", default_value = "5")]
+        timeout: u64,
+    },
 }
 
 pub struct BarState {
@@ -107,6 +178,11 @@ pub struct BarState {
 
     blocks: Vec<Block>,
     fullscreen_block: Option<usize>,
+    /// The block which was just clicked and is drawn in the pressed colors
+    pressed_block: Option<usize>,
+    pressed_timeout: Pin<Box<tokio::time::Sleep>>,
+    /// Whether the blocks collapsed due to `max_width` are shown
+    overflow_expanded: bool,
     running_blocks: FuturesUnordered<BoxedFuture<()>>,
 
     widget_updates_sender: WidgetUpdatesSender,
@@ -115,11 +191,28 @@ pub struct BarState {
     request_sender: mpsc::UnboundedSender<Request>,
     request_receiver: mpsc::UnboundedReceiver<Request>,
 
+    output: protocol::output::Output,
+
     widget_updates_stream: BoxedStream<Vec<usize>>,
     signals_stream: BoxedStream<Signal>,
     events_stream: BoxedStream<I3BarEvent>,
+    color_scheme_stream: BoxedStream<ColorScheme>,
+
+    /// Set only for the main bar when running as a systemd service
+    systemd: Option<systemd::Notifier>,
+    systemd_events: BoxedStream<systemd::Event>,
 }
 
+/// How long a clicked block is drawn in the pressed colors
+const PRESSED_DURATION: Duration = Duration::from_millis(150);
+
+/// The built-in click action which hides or shows a block
+const TOGGLE_VISIBILITY: &str = "toggle_visibility";
+/// The built-in click action which freezes or unfreezes the output of a block
+const PAUSE_UPDATES: &str = "pause_updates";
+/// The placeholder which shows the output of a click command
+const CMD_OUTPUT: &str = "cmd_output";
+
 #[derive(Debug)]
 struct Request {
     block_id: usize,
@@ -133,12 +226,23 @@ enum RequestCmd {
     SetError(Error),
     SetDefaultActions(&'static [(MouseButton, Option<&'static str>, &'static str)]),
     SubscribeToActions(mpsc::UnboundedSender<BlockAction>),
+    SetExpanded(bool),
+    /// Shows the output of a click command as `$cmd_output` until the given time
+    SetCmdOutput(String, Instant),
+    /// Removes `$cmd_output` if its time is over
+    ExpireCmdOutput,
 }
 
 #[derive(Debug, Clone)]
 struct RenderedBlock {
     pub segments: Vec<I3BarBlock>,
     pub merge_with_next: bool,
+    pub priority: i32,
+    /// The separator to the left of the block if the block overrides it
+    pub separator: Option<SeparatorStyle>,
+    /// The segments rendered with the narrower `formats` of the block, from the widest to the
+    /// narrowest
+    pub alternatives: Vec<Vec<I3BarBlock>>,
 }
 
 #[derive(Debug)]
@@ -150,13 +254,31 @@ pub struct Block {
     action_sender: Option<mpsc::UnboundedSender<BlockAction>>,
 
     click_handler: ClickHandler,
+    scroll_handler: ScrollHandler,
     default_actions: &'static [(MouseButton, Option<&'static str>, &'static str)],
     signal: Option<i32>,
+    toggle_signal: Option<i32>,
     shared_config: SharedConfig,
+    /// Reapplied when the theme changes with the color scheme
+    theme_overrides: Option<ThemeOverrides>,
 
     error_format: Format,
     error_fullscreen_format: Format,
 
+    /// The ID of the group this block belongs to
+    group: Option<usize>,
+    /// Whether this block is a group which is currently expanded
+    expanded: bool,
+    /// Hidden blocks keep running, but are not displayed
+    hidden: bool,
+    urgent: Urgent,
+    /// Paused blocks wait in their timers and update requests, and their updates are not rendered
+    paused: watch::Sender<bool>,
+    /// Whether the block has displayed something (or nothing, or an error) since it was spawned
+    reported: bool,
+    /// The output of a click command and until when it is shown
+    cmd_output: Option<(String, Instant)>,
+
     state: BlockState,
 }
 
@@ -168,10 +290,22 @@ enum BlockState {
 }
 
 impl Block {
-    fn notify_intervals(&self, tx: &WidgetUpdatesSender) {
+    fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Schedules the updates of the widget. Blocks which are hidden by the `fullscreen` block are
+    /// not updated.
+    fn notify_intervals(&self, tx: &WidgetUpdatesSender, fullscreen: Option<usize>) {
         let intervals = match &self.state {
+            _ if self.is_paused() => Vec::new(),
+            _ if fullscreen.is_some_and(|id| id != self.id) => Vec::new(),
             BlockState::None => Vec::new(),
-            BlockState::Normal { widget } | BlockState::Error { widget } => widget.intervals(),
+            BlockState::Normal { widget } | BlockState::Error { widget } => {
+                let mut intervals = widget.intervals();
+                intervals.extend(self.shared_config.theme.blink_interval(widget.state));
+                intervals
+            }
         };
         let _ = tx.send((self.id, intervals));
     }
@@ -206,14 +340,92 @@ impl Block {
     }
 }
 
+/// Runs a click command in the background and shows its output as `$cmd_output` of the block for
+/// `duration`
+fn show_cmd_output(
+    request_sender: mpsc::UnboundedSender<Request>,
+    block_id: usize,
+    cmd: String,
+    duration: Duration,
+) {
+    tokio::spawn(async move {
+        let output = match subprocess::shell_output(&cmd).await {
+            Ok(output) => output,
+            Err(error) => format!("Failed to run '{cmd}': {error}"),
+        };
+        let _ = request_sender.send(Request {
+            block_id,
+            cmd: RequestCmd::SetCmdOutput(output, Instant::now() + duration),
+        });
+        tokio::time::sleep(duration).await;
+        let _ = request_sender.send(Request {
+            block_id,
+            cmd: RequestCmd::ExpireCmdOutput,
+        });
+    });
+}
+
 impl BarState {
+    /// Creates the main bar, which writes to stdout and reads click events from stdin. If running
+    /// as a systemd service, it notifies systemd when ready and pings the watchdog.
     pub fn new(config: Config) -> Self {
+        let mut bar = Self::with_output(
+            config,
+            protocol::output::Output::Stdout,
+            i3bar_event::stdin_lines(),
+        );
+        if let Some(notifier) = systemd::Notifier::from_env() {
+            bar.systemd_events = notifier.events();
+            bar.systemd = Some(notifier);
+        }
+        bar
+    }
+
+    /// Creates a bar which is rendered only once (see [`BarState::render_once`]) and therefore
+    /// does not read click events.
+    pub fn new_once(config: Config) -> Self {
+        Self::with_output(
+            config,
+            protocol::output::Output::Stdout,
+            futures::stream::pending().boxed_local(),
+        )
+    }
+
+    /// Creates an additional bar defined by a `[[bar]]` section.
+    pub fn new_secondary(mut config: Config, bar: &BarConfig, never_pause: bool) -> Result<Self> {
+        if bar.max_width.is_some() {
+            config.max_width = bar.max_width;
+        }
+        let (output, events) = match (&bar.socket, &bar.fifo) {
+            (Some(socket), None) => {
+                protocol::output::Output::socket(socket.expand()?.into_owned().into(), never_pause)?
+            }
+            (None, Some(fifo)) => {
+                protocol::output::Output::fifo(fifo.expand()?.into_owned().into(), never_pause)
+            }
+            _ => {
+                return Err(Error::new(
+                    "Exactly one of 'socket' or 'fifo' must be set for a bar",
+                ))
+            }
+        };
+        Ok(Self::with_output(config, output, events))
+    }
+
+    fn with_output(
+        config: Config,
+        output: protocol::output::Output,
+        events: BoxedStream<String>,
+    ) -> Self {
         let (request_sender, request_receiver) = mpsc::unbounded_channel();
         let (widget_updates_sender, widget_updates_stream) =
             formatting::scheduling::manage_widgets_updates();
         Self {
             blocks: Vec::new(),
             fullscreen_block: None,
+            pressed_block: None,
+            pressed_timeout: Box::pin(tokio::time::sleep(Duration::ZERO)),
+            overflow_expanded: false,
             running_blocks: FuturesUnordered::new(),
 
             widget_updates_sender,
@@ -222,52 +434,115 @@ impl BarState {
             request_sender,
             request_receiver,
 
+            output,
+
             widget_updates_stream,
             signals_stream: signals::signals_stream(),
             events_stream: i3bar_event::events_stream(
+                events,
                 config.invert_scrolling,
                 Duration::from_millis(config.double_click_delay),
             ),
+            color_scheme_stream: if config.shared.theme.follows_color_scheme() {
+                color_scheme::color_scheme_stream()
+            } else {
+                futures::stream::pending().boxed_local()
+            },
+
+            systemd: None,
+            systemd_events: futures::stream::pending().boxed_local(),
 
             config,
         }
     }
 
-    pub async fn spawn_block(&mut self, block_config: BlockConfigEntry) -> Result<()> {
-        if let Some(cmd) = &block_config.common.if_command {
-            // TODO: async
-            if !Command::new("sh")
-                .args(["-c", cmd])
-                .output()
-                .await
-                .error("failed to run if_command")?
-                .status
-                .success()
-            {
-                return Ok(());
+    /// Spawns all blocks. `if_command`s are evaluated concurrently, the display order of the
+    /// blocks is preserved.
+    pub async fn spawn_blocks(&mut self, blocks: Vec<BlockConfigEntry>) -> Result<()> {
+        // Flatten groups. Each entry is paired with the index of its group (if any).
+        let mut flat: Vec<(BlockConfigEntry, Option<usize>)> = Vec::new();
+        for mut block_config in blocks {
+            let children = match &mut block_config.config {
+                BlockConfig::group(group) => {
+                    group.count = group.blocks.len();
+                    std::mem::take(&mut group.blocks)
+                }
+                _ => Vec::new(),
+            };
+            let group_index = flat.len();
+            flat.push((block_config, None));
+            for child in children {
+                if matches!(child.config, BlockConfig::group(_)) {
+                    return Err(Error::new("Groups cannot be nested"));
+                }
+                flat.push((child, Some(group_index)));
             }
         }
 
+        let enabled = futures::future::try_join_all(flat.iter().map(|(block_config, _)| async {
+            match &block_config.common.if_command {
+                Some(_) if self.config.demo => Ok(true),
+                Some(cmd) => Command::new("sh")
+                    .args(["-c", cmd])
+                    .output()
+                    .await
+                    .error("failed to run if_command")
+                    .map(|output| output.status.success()),
+                None => Ok(true),
+            }
+        }))
+        .await?;
+
+        // Maps indices in `flat` to block IDs
+        let mut ids = vec![None; flat.len()];
+        for (i, ((block_config, group_index), enabled)) in flat.into_iter().zip(enabled).enumerate()
+        {
+            let group = match group_index {
+                // The group itself is disabled
+                Some(group_index) if ids[group_index].is_none() => continue,
+                Some(group_index) => ids[group_index],
+                None => None,
+            };
+            if enabled {
+                ids[i] = Some(self.blocks.len());
+                self.spawn_block(block_config, group)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn spawn_block(&mut self, block_config: BlockConfigEntry, group: Option<usize>) -> Result<()> {
         let mut shared_config = self.config.shared.clone();
 
         // Overrides
         if let Some(icons_format) = block_config.common.icons_format {
             shared_config.icons_format = Arc::new(icons_format);
         }
-        if let Some(theme_overrides) = block_config.common.theme_overrides {
-            Arc::make_mut(&mut shared_config.theme).apply_overrides(theme_overrides)?;
+        if let Some(theme_overrides) = &block_config.common.theme_overrides {
+            Arc::make_mut(&mut shared_config.theme).apply_overrides(theme_overrides.clone())?;
         }
         if let Some(icons_overrides) = block_config.common.icons_overrides {
             Arc::make_mut(&mut shared_config.icons).apply_overrides(icons_overrides);
         }
 
         let update_request = Arc::new(Notify::new());
+        let (paused, _) = watch::channel(false);
 
         let api = CommonApi {
             id: self.blocks.len(),
             update_request: update_request.clone(),
             request_sender: self.request_sender.clone(),
             error_interval: Duration::from_secs(block_config.common.error_interval),
+            restart: Restart {
+                policy: block_config.common.restart_policy,
+                max_restarts: block_config.common.max_restarts,
+                backoff: block_config.common.error_backoff,
+            },
+            updated: Arc::new(Notify::new()),
+            watchdog: block_config.common.watchdog_secs.map(Duration::from_secs),
+            watchdog_restart: block_config.common.watchdog_restart,
+            paused: paused.subscribe(),
         };
 
         let error_format = block_config
@@ -287,22 +562,57 @@ impl BarState {
             action_sender: None,
 
             click_handler: block_config.common.click,
+            scroll_handler: ScrollHandler::new(
+                Duration::from_millis(
+                    block_config
+                        .common
+                        .scroll_interval
+                        .unwrap_or(self.config.scroll_interval),
+                ),
+                block_config
+                    .common
+                    .scroll_acceleration
+                    .unwrap_or(self.config.scroll_acceleration),
+            ),
             default_actions: &[],
             signal: block_config.common.signal,
+            toggle_signal: block_config.common.toggle_signal,
             shared_config,
+            theme_overrides: block_config.common.theme_overrides,
 
             error_format,
             error_fullscreen_format,
 
+            group,
+            expanded: false,
+            hidden: block_config.common.hidden,
+            urgent: block_config.common.urgent,
+            paused,
+            reported: false,
+            cmd_output: None,
+
             state: BlockState::None,
         };
 
-        block_config.config.spawn(api, &mut self.running_blocks);
+        if self.config.demo {
+            block_config
+                .config
+                .spawn_demo(block_config.demo.format, api, &mut self.running_blocks);
+        } else {
+            block_config.config.spawn(api, &mut self.running_blocks);
+        }
 
+        let separator = block
+            .theme_overrides
+            .as_ref()
+            .map(|_| SeparatorStyle::new(&block.shared_config.theme));
         self.blocks.push(block);
         self.blocks_render_cache.push(RenderedBlock {
             segments: Vec::new(),
             merge_with_next: block_config.common.merge_with_next,
+            priority: block_config.common.priority,
+            separator,
+            alternatives: Vec::new(),
         });
 
         Ok(())
@@ -310,8 +620,17 @@ impl BarState {
 
     fn process_request(&mut self, request: Request) {
         let block = &mut self.blocks[request.block_id];
+        if matches!(
+            request.cmd,
+            RequestCmd::SetWidget(_) | RequestCmd::UnsetWidget | RequestCmd::SetError(_)
+        ) {
+            block.reported = true;
+        }
         match request.cmd {
-            RequestCmd::SetWidget(widget) => {
+            RequestCmd::SetWidget(mut widget) => {
+                if let Some((output, _)) = &block.cmd_output {
+                    widget.set_value(CMD_OUTPUT, Value::text(output.clone()));
+                }
                 block.state = BlockState::Normal { widget };
                 if self.fullscreen_block == Some(request.block_id) {
                     self.fullscreen_block = None;
@@ -332,38 +651,220 @@ impl BarState {
             RequestCmd::SubscribeToActions(action_sender) => {
                 block.action_sender = Some(action_sender);
             }
+            RequestCmd::SetExpanded(expanded) => {
+                block.expanded = expanded;
+            }
+            RequestCmd::SetCmdOutput(output, until) => {
+                if let BlockState::Normal { widget } = &mut block.state {
+                    widget.set_value(CMD_OUTPUT, Value::text(output.clone()));
+                }
+                block.cmd_output = Some((output, until));
+            }
+            RequestCmd::ExpireCmdOutput => {
+                if block
+                    .cmd_output
+                    .as_ref()
+                    .is_some_and(|(_, until)| *until <= Instant::now())
+                {
+                    block.cmd_output = None;
+                    if let BlockState::Normal { widget } = &mut block.state {
+                        widget.unset_value(CMD_OUTPUT);
+                    }
+                }
+            }
+        }
+        block.notify_intervals(&self.widget_updates_sender, self.fullscreen_block);
+    }
+
+    /// Whether the block is drawn, i.e. no other block is shown fullscreen
+    fn is_drawn(&self, id: usize) -> bool {
+        self.fullscreen_block
+            .is_none_or(|fullscreen| fullscreen == id)
+    }
+
+    /// Reschedules the updates of all blocks and renders the drawn ones after a block entered or
+    /// left fullscreen mode
+    fn fullscreen_changed(&mut self) -> Result<(), BlockError> {
+        for block in &self.blocks {
+            block.notify_intervals(&self.widget_updates_sender, self.fullscreen_block);
+        }
+        for id in 0..self.blocks.len() {
+            if self.is_drawn(id) {
+                self.render_block(id)?;
+            }
         }
-        block.notify_intervals(&self.widget_updates_sender);
+        Ok(())
     }
 
     fn render_block(&mut self, id: usize) -> Result<(), BlockError> {
         let block = &mut self.blocks[id];
-        let data = &mut self.blocks_render_cache[id].segments;
+        // Paused blocks keep showing what they showed when they were paused
+        if block.is_paused() {
+            return Ok(());
+        }
+        let rendered = &mut self.blocks_render_cache[id];
+        rendered.alternatives.clear();
         match &block.state {
             BlockState::None => {
-                data.clear();
+                rendered.segments.clear();
             }
             BlockState::Normal { widget } | BlockState::Error { widget, .. } => {
-                *data = widget
+                let block_error = |error| BlockError {
+                    block_id: id,
+                    block_name: block.name,
+                    error,
+                };
+                rendered.segments = widget
                     .get_data(&block.shared_config, id)
+                    .map_err(block_error)?;
+                if let BlockState::Normal { widget } = &block.state {
+                    for full in widget.narrower_formats() {
+                        let mut widget = widget.clone();
+                        widget.set_full_format(full.clone());
+                        rendered.alternatives.push(
+                            widget
+                                .get_data(&block.shared_config, id)
+                                .map_err(block_error)?,
+                        );
+                    }
+                }
+                if block.urgent.contains(widget.state) {
+                    for segment in rendered
+                        .segments
+                        .iter_mut()
+                        .chain(rendered.alternatives.iter_mut().flatten())
+                    {
+                        segment.urgent = Some(true);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws the frozen output of a paused block in the idle colors, prefixed with the `paused`
+    /// icon
+    fn mark_paused(&mut self, id: usize) {
+        let shared_config = &self.blocks[id].shared_config;
+        let icon = shared_config.get_icon("paused", None).unwrap_or_default();
+        let (background, color) = shared_config.theme.get_colors(State::Idle);
+        let rendered = &mut self.blocks_render_cache[id];
+        for segments in std::iter::once(&mut rendered.segments).chain(&mut rendered.alternatives) {
+            let Some(first) = segments.first() else {
+                continue;
+            };
+            let indicator = I3BarBlock {
+                full_text: format!(" {icon}"),
+                short_text: format!(" {icon}"),
+                ..first.clone()
+            };
+            segments.insert(0, indicator);
+            for segment in segments {
+                segment.background = background;
+                segment.color = color;
+                segment.background_gradient = Color::None;
+            }
+        }
+    }
+
+    /// Switches the theme of the bar and of all blocks to the one for `scheme`
+    fn set_color_scheme(&mut self, scheme: ColorScheme) -> Result<(), BlockError> {
+        let theme = Arc::new(self.config.shared.theme.for_color_scheme(scheme));
+        self.config.shared.theme = theme.clone();
+        for (block, rendered) in self.blocks.iter_mut().zip(&mut self.blocks_render_cache) {
+            block.shared_config.theme = theme.clone();
+            if let Some(theme_overrides) = &block.theme_overrides {
+                Arc::make_mut(&mut block.shared_config.theme)
+                    .apply_overrides(theme_overrides.clone())
                     .map_err(|error| BlockError {
-                        block_id: id,
+                        block_id: block.id,
                         block_name: block.name,
                         error,
                     })?;
+                rendered.separator = Some(SeparatorStyle::new(&block.shared_config.theme));
+            }
+        }
+        for id in 0..self.blocks.len() {
+            if self.is_drawn(id) {
+                self.render_block(id)?;
             }
         }
         Ok(())
     }
 
-    fn render(&self) {
+    /// The rendered block `id`, in the pressed colors of its theme while it is pressed
+    fn rendered_block(&self, id: usize) -> Cow<'_, RenderedBlock> {
+        let rendered = &self.blocks_render_cache[id];
+        if self.pressed_block != Some(id) {
+            return Cow::Borrowed(rendered);
+        }
+        let theme = &self.blocks[id].shared_config.theme;
+        let mut rendered = rendered.clone();
+        for segment in rendered
+            .segments
+            .iter_mut()
+            .chain(rendered.alternatives.iter_mut().flatten())
+        {
+            if let Some(bg) = theme.pressed_bg {
+                segment.background = bg;
+                segment.background_gradient = Color::None;
+            }
+            if let Some(fg) = theme.pressed_fg {
+                segment.color = fg;
+            }
+        }
+        Cow::Owned(rendered)
+    }
+
+    fn rendered(&self) -> Vec<I3BarBlock> {
         if let Some(id) = self.fullscreen_block {
-            protocol::print_blocks(&[&self.blocks_render_cache[id]], &self.config.shared);
+            protocol::render_blocks(&[self.rendered_block(id)], &self.config.shared, None)
         } else {
-            protocol::print_blocks(&self.blocks_render_cache, &self.config.shared);
+            // Hidden blocks and blocks in folded groups are rendered as empty blocks, so that they
+            // still end a run of merged blocks
+            let hidden = RenderedBlock {
+                segments: Vec::new(),
+                merge_with_next: false,
+                priority: 0,
+                separator: None,
+                alternatives: Vec::new(),
+            };
+            let mut visible: Vec<_> = self
+                .blocks
+                .iter()
+                .enumerate()
+                .map(|(id, block)| {
+                    let folded = block.group.is_some_and(|g| !self.blocks[g].expanded);
+                    if block.hidden || folded {
+                        Cow::Borrowed(&hidden)
+                    } else {
+                        self.rendered_block(id)
+                    }
+                })
+                .collect();
+            if let Some(max_width) = self.config.max_width {
+                protocol::degrade(&mut visible, max_width, &self.config.shared);
+            }
+            let overflow = self.config.max_width.map(|max_width| protocol::Overflow {
+                max_width,
+                expanded: self.overflow_expanded,
+            });
+            protocol::render_blocks(&visible, &self.config.shared, overflow)
         }
     }
 
+    fn render(&self) {
+        self.output
+            .write(serde_json::to_string(&self.rendered()).unwrap());
+    }
+
+    /// Whether every visible block has displayed something since it was spawned
+    fn all_blocks_reported(&self) -> bool {
+        self.blocks
+            .iter()
+            .all(|block| block.hidden || block.reported)
+    }
+
     async fn process_event(&mut self, restart: fn() -> !) -> Result<(), BlockError> {
         tokio::select! {
             // Poll blocks
@@ -371,42 +872,107 @@ impl BarState {
             // Receive messages from blocks
             Some(request) = self.request_receiver.recv() => {
                 let id = request.block_id;
+                let fullscreen = self.fullscreen_block;
                 self.process_request(request);
-                self.render_block(id)?;
-                self.render();
+                // Blocks hidden by a fullscreen block are rendered when it is left
+                if self.fullscreen_block != fullscreen {
+                    self.fullscreen_changed()?;
+                    self.render();
+                } else if self.is_drawn(id) && !self.blocks[id].is_paused() {
+                    self.render_block(id)?;
+                    self.render();
+                }
+                let all_reported = self.all_blocks_reported();
+                if let Some(systemd) = &mut self.systemd {
+                    if all_reported {
+                        systemd.notify_ready();
+                    }
+                }
+            }
+            // Keep systemd informed that the event loop is running
+            Some(event) = self.systemd_events.next() => {
+                if let Some(systemd) = &mut self.systemd {
+                    match event {
+                        systemd::Event::StartupTimeout => systemd.notify_ready(),
+                        systemd::Event::Watchdog => systemd.notify_watchdog(),
+                    }
+                }
             }
             // Handle scheduled updates
             Some(ids) = self.widget_updates_stream.next() => {
+                // Only redraw the bar if the output of a block changed, e.g. a rotating text
+                // which fits into its width does not move
+                let mut changed = false;
                 for id in ids {
+                    let old = std::mem::take(&mut self.blocks_render_cache[id].segments);
                     self.render_block(id)?;
+                    changed |= self.blocks_render_cache[id].segments != old;
+                }
+                if changed {
+                    self.render();
                 }
-                self.render();
             }
             // Handle clicks
             Some(event) = self.events_stream.next() => {
-                let block = self.blocks.get_mut(event.id).expect("Events receiver: ID out of bounds");
+                if event.id == protocol::OVERFLOW_ID {
+                    self.overflow_expanded = !self.overflow_expanded;
+                    self.render();
+                    return Ok(());
+                }
+                let Some(block) = self.blocks.get_mut(event.id) else {
+                    return Ok(());
+                };
+                let steps = block.scroll_handler.steps(event.button, Instant::now());
+                if steps == 0 {
+                    return Ok(());
+                }
+                // Give feedback that the click was registered
+                let theme = &block.shared_config.theme;
+                if theme.pressed_bg.is_some() || theme.pressed_fg.is_some() {
+                    self.pressed_block = Some(event.id);
+                    self.pressed_timeout
+                        .as_mut()
+                        .reset(tokio::time::Instant::now() + PRESSED_DURATION);
+                    self.render();
+                }
+                let block = &mut self.blocks[event.id];
+                let mut visibility_changed = false;
+                let mut pause_toggled = false;
                 match &mut block.state {
                     BlockState::None => (),
                     BlockState::Normal { .. } => {
-                        let result = block.click_handler.handle(&event).await.map_err(|error| BlockError {
-                            block_id: event.id,
-                            block_name: block.name,
-                            error,
-                        })?;
-                        match result {
-                            Some(post_actions) => {
-                                if let Some(action) = post_actions.action {
-                                    block.send_action(Cow::Owned(action));
+                        // Fast scrolling is handled like several wheel events
+                        for _ in 0..steps {
+                            let result = block.click_handler.handle(&event).await.map_err(|error| BlockError {
+                                block_id: event.id,
+                                block_name: block.name,
+                                error,
+                            })?;
+                            match result {
+                                Some(post_actions) => {
+                                    if post_actions.action.as_deref() == Some(TOGGLE_VISIBILITY) {
+                                        block.hidden = !block.hidden;
+                                        visibility_changed = true;
+                                    } else if post_actions.action.as_deref() == Some(PAUSE_UPDATES) {
+                                        block.paused.send_modify(|paused| *paused = !*paused);
+                                        block.notify_intervals(&self.widget_updates_sender, self.fullscreen_block);
+                                        pause_toggled = !pause_toggled;
+                                    } else if let Some(action) = post_actions.action {
+                                        block.send_action(Cow::Owned(action));
+                                    }
+                                    if post_actions.update {
+                                        block.update_request.notify_one();
+                                    }
+                                    if let Some((cmd, duration)) = post_actions.cmd_output {
+                                        show_cmd_output(self.request_sender.clone(), event.id, cmd, duration);
+                                    }
                                 }
-                                if post_actions.update {
-                                    block.update_request.notify_one();
-                                }
-                            }
-                            None => {
-                                if let Some((_, _, action)) = block.default_actions
-                                    .iter()
-                                    .find(|(btn, widget, _)| *btn == event.button && *widget == event.instance.as_deref()) {
-                                    block.send_action(Cow::Borrowed(action));
+                                None => {
+                                    if let Some((_, _, action)) = block.default_actions
+                                        .iter()
+                                        .find(|(btn, widget, _)| *btn == event.button && *widget == event.instance.as_deref()) {
+                                        block.send_action(Cow::Borrowed(action));
+                                    }
                                 }
                             }
                         }
@@ -419,11 +985,30 @@ impl BarState {
                             self.fullscreen_block = Some(event.id);
                             widget.set_format(block.error_fullscreen_format.clone());
                         }
-                        block.notify_intervals(&self.widget_updates_sender);
-                        self.render_block(event.id)?;
+                        self.fullscreen_changed()?;
                         self.render();
                     }
                 }
+                if pause_toggled {
+                    if self.blocks[event.id].is_paused() {
+                        self.mark_paused(event.id);
+                    } else {
+                        self.render_block(event.id)?;
+                    }
+                }
+                if visibility_changed || pause_toggled {
+                    self.render();
+                }
+            }
+            // Draw the clicked block in its normal colors again
+            _ = &mut self.pressed_timeout, if self.pressed_block.is_some() => {
+                self.pressed_block = None;
+                self.render();
+            }
+            // Switch between the light and dark theme
+            Some(scheme) = self.color_scheme_stream.next() => {
+                self.set_color_scheme(scheme)?;
+                self.render();
             }
             // Handle signals
             Some(signal) = self.signals_stream.next() => match signal {
@@ -434,10 +1019,18 @@ impl BarState {
                 }
                 Signal::Usr2 => restart(),
                 Signal::Custom(signal) => {
-                    for block in &self.blocks {
+                    let mut visibility_changed = false;
+                    for block in &mut self.blocks {
                         if block.signal == Some(signal) {
                             block.update_request.notify_one();
                         }
+                        if block.toggle_signal == Some(signal) {
+                            block.hidden = !block.hidden;
+                            visibility_changed = true;
+                        }
+                    }
+                    if visibility_changed {
+                        self.render();
                     }
                 }
             }
@@ -445,6 +1038,70 @@ impl BarState {
         Ok(())
     }
 
+    /// Waits until every visible block has sent its first update or `timeout` has passed, and
+    /// returns the rendered line. Blocks which have not displayed anything by then are omitted.
+    pub async fn render_once(mut self, timeout: Duration) -> Result<Vec<I3BarBlock>, BlockError> {
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+        while !self.all_blocks_reported() {
+            tokio::select! {
+                _ = &mut deadline => break,
+                Some(()) = self.running_blocks.next() => (),
+                Some(request) = self.request_receiver.recv() => self.process_request(request),
+            }
+        }
+
+        for id in 0..self.blocks.len() {
+            if let Err(error) = self.render_block(id) {
+                self.blocks[id].set_error(false, error.error);
+                self.render_block(id)?;
+            }
+        }
+        Ok(self.rendered())
+    }
+
+    /// Runs the blocks until `timeout` has passed and prints each change of their widgets as plain
+    /// text and JSON, for `test-block`. Returns whether any block displayed a widget.
+    pub async fn run_test(mut self, timeout: Duration) -> Result<bool, BlockError> {
+        let start = Instant::now();
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+        let mut displayed = false;
+        loop {
+            tokio::select! {
+                _ = &mut deadline => return Ok(displayed),
+                Some(()) = self.running_blocks.next() => (),
+                Some(request) = self.request_receiver.recv() => {
+                    let id = request.block_id;
+                    let change = match &request.cmd {
+                        RequestCmd::SetWidget(widget) => format!("{:?}", widget.state),
+                        RequestCmd::UnsetWidget => "hidden".into(),
+                        RequestCmd::SetError(error) => format!("error: {error}"),
+                        _ => {
+                            self.process_request(request);
+                            continue;
+                        }
+                    };
+                    displayed |= matches!(request.cmd, RequestCmd::SetWidget(_));
+                    self.process_request(request);
+                    self.render_block(id)?;
+                    let rendered = protocol::render_blocks(
+                        &[self.rendered_block(id)],
+                        &self.config.shared,
+                        None,
+                    );
+                    println!(
+                        "[{:7.2}s] {} ({change}): {}",
+                        start.elapsed().as_secs_f64(),
+                        self.blocks[id].name,
+                        protocol::plain_text(&rendered)
+                    );
+                    println!("{}", serde_json::to_string(&rendered).unwrap());
+                }
+            }
+        }
+    }
+
     pub async fn run_event_loop(mut self, restart: fn() -> !) -> Result<(), BlockError> {
         loop {
             if let Err(error) = self.process_event(restart).await {
@@ -457,10 +1114,12 @@ impl BarState {
                 }
 
                 block.set_error(self.fullscreen_block == Some(block.id), error.error);
-                block.notify_intervals(&self.widget_updates_sender);
+                block.notify_intervals(&self.widget_updates_sender, self.fullscreen_block);
 
-                self.render_block(error.block_id)?;
-                self.render();
+                if self.is_drawn(error.block_id) {
+                    self.render_block(error.block_id)?;
+                    self.render();
+                }
             }
         }
     }
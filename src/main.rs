@@ -1,11 +1,23 @@
+use std::io::Read as _;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
 use clap::Parser;
 
-use i3status_rs::blocks::BlockError;
-use i3status_rs::config::Config;
+use i3status_rs::blocks::{self, BlockConfig, BlockError};
+use i3status_rs::config::{self, BlockConfigEntry, Config};
 use i3status_rs::errors::*;
 use i3status_rs::escape::Escaped;
 use i3status_rs::widget::{State, Widget};
-use i3status_rs::{protocol, util, BarState};
+use i3status_rs::{protocol, util, BarState, CliCommand};
+
+/// The value of `--config` which reads the configuration from stdin
+const STDIN: &str = "-";
+/// Passes a piped configuration on to the restarted process, because a pipe cannot be read again
+const PIPED_CONFIG_VAR: &str = "I3STATUS_RS_PIPED_CONFIG";
+/// The configuration read from stdin or another pipe (e.g. `/dev/fd/3`), if any
+static PIPED_CONFIG: OnceLock<String> = OnceLock::new();
 
 #[derive(Debug, thiserror::Error)]
 enum ErrorMaybeInBlock {
@@ -16,11 +28,56 @@ enum ErrorMaybeInBlock {
 }
 
 fn main() {
-    env_logger::init();
+    let mut args = i3status_rs::CliArgs::parse();
+    if let Some(CliCommand::TestBlock {
+        config: Some(config),
+        ..
+    }) = &args.command
+    {
+        args.config = config.clone();
+    }
 
-    let args = i3status_rs::CliArgs::parse();
+    // `test-block` logs debug messages unless `RUST_LOG` says otherwise
+    let default_filter = if args.command.is_some() {
+        "debug"
+    } else {
+        "error"
+    };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_filter))
+        .init();
     let blocking_threads = args.blocking_threads;
 
+    if is_pipe(&args.config) {
+        read_piped_config(&args.config);
+    }
+
+    if let Some(block) = &args.explain {
+        explain(block);
+    }
+
+    if let Some(kind) = &args.print_default_config {
+        print!(
+            "{}",
+            match kind.as_str() {
+                "full" => config::FULL_CONFIG,
+                _ => config::MINIMAL_CONFIG,
+            }
+        );
+        std::process::exit(0);
+    }
+
+    if args.check {
+        check_config(&args.config, blocking_threads);
+    }
+
+    if args.once {
+        print_once(&args);
+    }
+
+    if let Some(CliCommand::TestBlock { block, timeout, .. }) = &args.command {
+        test_block(&args, block, Duration::from_secs(*timeout));
+    }
+
     if !args.no_init {
         protocol::init(args.never_pause);
     }
@@ -31,15 +88,25 @@ fn main() {
         .build()
         .unwrap()
         .block_on(async move {
-            let config_path = util::find_file(&args.config, None, Some("toml"))
-                .or_error(|| format!("Configuration file '{}' not found", args.config))?;
-            let mut config: Config = util::deserialize_toml_file(&config_path)?;
+            let (_, mut config) = load_config(&args.config).await?;
+            config.demo = args.demo;
             let blocks = std::mem::take(&mut config.blocks);
-            let mut bar = BarState::new(config);
-            for block_config in blocks {
-                bar.spawn_block(block_config).await?;
+            let mut bars = Vec::new();
+            for mut bar_config in std::mem::take(&mut config.bars) {
+                let blocks = std::mem::take(&mut bar_config.blocks);
+                let mut bar = BarState::new_secondary(
+                    config.without_blocks(),
+                    &bar_config,
+                    args.never_pause,
+                )?;
+                bar.spawn_blocks(blocks).await?;
+                bars.push(bar);
             }
-            bar.run_event_loop(restart).await?;
+            let mut bar = BarState::new(config);
+            bar.spawn_blocks(blocks).await?;
+            bars.insert(0, bar);
+            futures::future::try_join_all(bars.into_iter().map(|bar| bar.run_event_loop(restart)))
+                .await?;
             Ok(())
         });
     if let Err(error) = result {
@@ -64,6 +131,186 @@ fn main() {
     }
 }
 
+/// Whether the configuration is read from stdin or from an absolute path which is not a regular
+/// file, such as `/dev/fd/3`
+fn is_pipe(config: &str) -> bool {
+    let path = Path::new(config);
+    config == STDIN
+        || (path.is_absolute() && path.metadata().is_ok_and(|meta| !meta.is_file()))
+        || std::env::var_os(PIPED_CONFIG_VAR).is_some()
+}
+
+/// Read the piped configuration, or take it over from the process which restarted us
+fn read_piped_config(config: &str) {
+    let contents = match std::env::var(PIPED_CONFIG_VAR) {
+        Ok(contents) => {
+            // Do not leak the configuration to the commands run by the blocks
+            std::env::remove_var(PIPED_CONFIG_VAR);
+            Ok(contents)
+        }
+        Err(_) if config == STDIN => {
+            let mut contents = String::new();
+            std::io::stdin()
+                .read_to_string(&mut contents)
+                .map(|_| contents)
+        }
+        Err(_) => std::fs::read_to_string(config),
+    };
+    match contents {
+        Ok(contents) => PIPED_CONFIG.set(contents).unwrap(),
+        Err(error) => {
+            eprintln!("Failed to read the configuration from '{config}': {error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Find and load the configuration, returning its path along with it
+async fn load_config(config: &str) -> Result<(PathBuf, Config)> {
+    if let Some(contents) = PIPED_CONFIG.get() {
+        // Relative includes of stdin are resolved against the current directory
+        let path = Path::new(if config == STDIN { "<stdin>" } else { config });
+        return Ok((path.into(), Config::load_str(contents, path).await?));
+    }
+    let Some(config_path) = util::find_file(config, None, Some("toml")) else {
+        if config == config::DEFAULT_CONFIG_FILE {
+            log::warn!(
+                "Configuration file '{config}' not found, using the built-in minimal config"
+            );
+            let path = Path::new("<built-in minimal config>");
+            return Ok((
+                path.into(),
+                Config::load_str(config::MINIMAL_CONFIG, path).await?,
+            ));
+        }
+        return Err(Error::new(format!(
+            "Configuration file '{config}' not found"
+        )));
+    };
+    let config = Config::load(&config_path).await?;
+    Ok((config_path, config))
+}
+
+/// Describe a block and exit
+fn explain(block: &str) -> ! {
+    match blocks::explain(block) {
+        Some(doc) => {
+            println!("{doc}");
+            std::process::exit(0);
+        }
+        None => {
+            let names: Vec<_> = blocks::block_names().collect();
+            eprintln!(
+                "Unknown block '{block}'. Available blocks: {}",
+                names.join(", ")
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Check the config and exit
+fn check_config(config: &str, blocking_threads: usize) -> ! {
+    let result = tokio::runtime::Builder::new_current_thread()
+        .max_blocking_threads(blocking_threads)
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async {
+            let (config_path, config) = load_config(config).await?;
+            config.check()?;
+            Ok::<_, Error>(config_path)
+        });
+    match result {
+        Ok(path) => {
+            println!("{}: OK", path.display());
+            std::process::exit(0);
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Render the bar once, print it and exit
+fn print_once(args: &i3status_rs::CliArgs) -> ! {
+    let result: Result<_, ErrorMaybeInBlock> = tokio::runtime::Builder::new_current_thread()
+        .max_blocking_threads(args.blocking_threads)
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async {
+            let (_, mut config) = load_config(&args.config).await?;
+            config.demo = args.demo;
+            let blocks = std::mem::take(&mut config.blocks);
+            let mut bar = BarState::new_once(config);
+            bar.spawn_blocks(blocks).await?;
+            Ok(bar.render_once(Duration::from_secs(args.timeout)).await?)
+        });
+    match result {
+        Ok(line) if args.plain => {
+            println!("{}", protocol::plain_text(&line));
+            std::process::exit(0);
+        }
+        Ok(line) => {
+            println!("{}", serde_json::to_string(&line).unwrap());
+            std::process::exit(0);
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Run the blocks with the given name, print what they display and exit
+fn test_block(args: &i3status_rs::CliArgs, name: &str, timeout: Duration) -> ! {
+    let result: Result<_, ErrorMaybeInBlock> = tokio::runtime::Builder::new_current_thread()
+        .max_blocking_threads(args.blocking_threads)
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async {
+            let (_, mut config) = load_config(&args.config).await?;
+            config.demo = args.demo;
+            let mut blocks: Vec<BlockConfigEntry> = std::mem::take(&mut config.blocks)
+                .into_iter()
+                .flat_map(|block| match block.config {
+                    BlockConfig::group(group) => group.blocks,
+                    _ => vec![block],
+                })
+                .filter(|block| block.config.name() == name)
+                .collect();
+            if blocks.is_empty() {
+                if !blocks::block_names().any(|block| block == name) {
+                    return Err(Error::new(format!("Unknown block '{name}'")).into());
+                }
+                log::info!("'{name}' is not in the config, using its default config");
+                let block = toml::from_str(&format!("block = {}", toml::Value::from(name)))
+                    .error("Failed to create the default config")?;
+                blocks.push(block);
+            }
+            let mut bar = BarState::new_once(config);
+            bar.spawn_blocks(blocks).await?;
+            Ok(bar.run_test(timeout).await?)
+        });
+    match result {
+        Ok(true) => std::process::exit(0),
+        Ok(false) => {
+            eprintln!(
+                "'{name}' did not display anything within {} seconds",
+                timeout.as_secs()
+            );
+            std::process::exit(1);
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+    }
+}
+
 /// Restart in-place
 fn restart() -> ! {
     use std::env;
@@ -84,6 +331,11 @@ fn restart() -> ! {
         arg.push(no_init_arg);
     }
 
+    // The new process cannot read the pipe again
+    if let Some(contents) = PIPED_CONFIG.get() {
+        env::set_var(PIPED_CONFIG_VAR, contents);
+    }
+
     // Restart
     nix::unistd::execvp(&exe, &arg).unwrap();
     unreachable!();
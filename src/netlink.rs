@@ -40,6 +40,16 @@ pub struct WifiInfo {
 
 impl NetDevice {
     pub async fn new(iface_re: Option<&Regex>) -> Result<Option<Self>> {
+        let result = Self::from_netlink(iface_re).await;
+        // Android does not allow apps to use netlink
+        #[cfg(feature = "termux")]
+        if result.is_err() && crate::termux::is_termux() {
+            return Self::from_procfs(iface_re).await;
+        }
+        result
+    }
+
+    async fn from_netlink(iface_re: Option<&Regex>) -> Result<Option<Self>> {
         let mut sock = NlSocket::new(
             NlSocketHandle::connect(NlFamily::Route, None, &[]).error("Socket error")?,
         )
@@ -105,6 +115,110 @@ impl NetDevice {
         }))
     }
 
+    /// Reads the interfaces from `/proc/net/dev` and WiFi info from Termux:API
+    #[cfg(feature = "termux")]
+    async fn from_procfs(iface_re: Option<&Regex>) -> Result<Option<Self>> {
+        #[derive(serde::Deserialize, Default)]
+        #[serde(default)]
+        struct WifiConnectionInfo {
+            ssid: Option<String>,
+            ip: Option<Ipv4Addr>,
+            rssi: Option<f64>,
+            frequency_mhz: Option<f64>,
+            link_speed_mbps: Option<f64>,
+            supplicant_state: Option<String>,
+        }
+
+        let dev = util::read_file("/proc/net/dev")
+            .await
+            .error("Failed to read /proc/net/dev")?;
+
+        let mut ifaces = Vec::new();
+        // The first two lines are headers
+        for (index, line) in dev.lines().skip(2).enumerate() {
+            let Some((name, data)) = line.split_once(':') else {
+                continue;
+            };
+            let name = name.trim();
+            if !iface_re.is_none_or(|re| re.is_match(name)) {
+                continue;
+            }
+            let fields: Vec<u64> = data
+                .split_whitespace()
+                .filter_map(|x| x.parse().ok())
+                .collect();
+            let operstate = match util::read_file(format!("/sys/class/net/{name}/operstate"))
+                .await
+                .as_deref()
+            {
+                Ok("up") => Operstate::Up,
+                Ok("down") => Operstate::Down,
+                _ => Operstate::Unknown,
+            };
+            ifaces.push(Interface {
+                index: index as i32,
+                operstate,
+                name: name.to_owned(),
+                stats: (fields.len() > 8).then(|| InterfaceStats {
+                    rx_bytes: fields[0],
+                    tx_bytes: fields[8],
+                }),
+            });
+        }
+
+        // There is no default route to look at, so prefer active non-loopback interfaces
+        let Some(iface_position) = ifaces
+            .iter()
+            .position(|i| i.name != "lo" && i.operstate == Operstate::Up)
+            .or_else(|| ifaces.iter().position(|i| i.name != "lo"))
+            .or((!ifaces.is_empty()).then_some(0))
+        else {
+            return Ok(None);
+        };
+        let iface = ifaces.swap_remove(iface_position);
+
+        let mut ip = None;
+        let mut wifi_info = None;
+        if iface.name.starts_with("wlan") {
+            let info: WifiConnectionInfo = crate::termux::api("termux-wifi-connectioninfo")
+                .await
+                .unwrap_or_default();
+            if info.supplicant_state.as_deref() == Some("COMPLETED") {
+                ip = info.ip;
+                wifi_info = Some(WifiInfo {
+                    ssid: info.ssid,
+                    // Map [-100, -50] dBm to [0, 100] %
+                    signal: info
+                        .rssi
+                        .map(|rssi| ((rssi + 100.0) * 2.0).clamp(0.0, 100.0)),
+                    frequency: info.frequency_mhz.map(|f| f * 1e6),
+                    bitrate: info.link_speed_mbps.map(|b| b * 1e6),
+                });
+            }
+        }
+
+        let tun = iface.name.starts_with("tun");
+        let icon = if wifi_info.is_some() {
+            "net_wireless"
+        } else if tun {
+            "net_vpn"
+        } else if iface.name == "lo" {
+            "net_loopback"
+        } else {
+            "net_wired"
+        };
+
+        Ok(Some(Self {
+            iface,
+            wifi_info,
+            ip,
+            ipv6: None,
+            icon,
+            tun_wg_ppp: tun,
+            nameservers: read_nameservers().await.unwrap_or_default(),
+        }))
+    }
+
     pub fn is_up(&self) -> bool {
         self.tun_wg_ppp
             || self.iface.operstate == Operstate::Up
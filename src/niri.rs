@@ -0,0 +1,241 @@
+//! The IPC of [niri](https://github.com/YaLTeR/niri/wiki/IPC)
+//!
+//! niri listens on the socket in `$NIRI_SOCKET`. Each request and reply is a line of JSON. After
+//! the `EventStream` request, niri sends the current state as events, followed by an event for
+//! every change. [`State`] keeps track of the parts of the state blocks are interested in.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::UnixStream;
+
+use crate::errors::*;
+
+const SOCKET: &str = "NIRI_SOCKET";
+
+/// Whether i3status-rs runs under niri
+pub fn is_running() -> bool {
+    std::env::var_os(SOCKET).is_some()
+}
+
+/// Sends a request and returns the lines of the connection, starting with the reply
+async fn connect(request: &impl Serialize) -> Result<Lines<BufReader<UnixStream>>> {
+    let path = std::env::var_os(SOCKET).or_error(|| format!("${SOCKET} is not set"))?;
+    let mut stream = UnixStream::connect(path)
+        .await
+        .error("Failed to connect to the niri socket")?;
+    let mut request = serde_json::to_string(request).error("Failed to serialize request")?;
+    request.push('\n');
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .error("Failed to send niri request")?;
+    let mut lines = BufReader::new(stream).lines();
+    let reply = lines
+        .next_line()
+        .await
+        .error("Failed to read niri reply")?
+        .error("niri closed the connection")?;
+    // `{"Ok": ...}` or `{"Err": "..."}`
+    let reply: std::result::Result<serde::de::IgnoredAny, String> =
+        serde_json::from_str(&reply).error("Invalid niri reply")?;
+    reply.map_err(|err| Error::new(format!("niri: {err}")))?;
+    Ok(lines)
+}
+
+/// Performs an action, e.g. `{"FocusWorkspaceDown": {}}`
+pub async fn action(action: serde_json::Value) -> Result<()> {
+    connect(&json!({ "Action": action })).await.map(drop)
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Workspace {
+    pub id: u64,
+    pub idx: u8,
+    pub name: Option<String>,
+    pub output: Option<String>,
+    #[serde(default)]
+    pub is_urgent: bool,
+    pub is_active: bool,
+    pub is_focused: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Window {
+    pub id: u64,
+    pub title: Option<String>,
+    pub is_focused: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct KeyboardLayouts {
+    pub names: Vec<String>,
+    pub current_idx: u8,
+}
+
+/// The events blocks are interested in. Others are skipped by [`EventStream::next`].
+#[derive(Deserialize, Debug)]
+pub enum Event {
+    WorkspacesChanged { workspaces: Vec<Workspace> },
+    WorkspaceActivated { id: u64, focused: bool },
+    WorkspaceUrgencyChanged { id: u64, urgent: bool },
+    WindowsChanged { windows: Vec<Window> },
+    WindowOpenedOrChanged { window: Window },
+    WindowClosed { id: u64 },
+    WindowFocusChanged { id: Option<u64> },
+    KeyboardLayoutsChanged { keyboard_layouts: KeyboardLayouts },
+    KeyboardLayoutSwitched { idx: u8 },
+}
+
+pub struct EventStream {
+    lines: Lines<BufReader<UnixStream>>,
+}
+
+impl EventStream {
+    pub async fn new() -> Result<Self> {
+        Ok(Self {
+            lines: connect(&"EventStream").await?,
+        })
+    }
+
+    pub async fn next(&mut self) -> Result<Event> {
+        loop {
+            let line = self
+                .lines
+                .next_line()
+                .await
+                .error("Failed to read niri event")?
+                .error("niri event stream closed")?;
+            // Unknown events do not parse
+            if let Ok(event) = serde_json::from_str(&line) {
+                return Ok(event);
+            }
+        }
+    }
+}
+
+/// The state of niri, as far as it is known from the events
+#[derive(Debug, Default)]
+pub struct State {
+    pub workspaces: Vec<Workspace>,
+    pub windows: HashMap<u64, Window>,
+    pub keyboard_layouts: Vec<String>,
+    pub keyboard_layout: usize,
+}
+
+impl State {
+    pub fn apply(&mut self, event: Event) {
+        match event {
+            Event::WorkspacesChanged { workspaces } => {
+                self.workspaces = workspaces;
+                self.workspaces
+                    .sort_by(|a, b| (&a.output, a.idx).cmp(&(&b.output, b.idx)));
+            }
+            Event::WorkspaceActivated { id, focused } => {
+                let Some(output) = self.workspace(id).map(|ws| ws.output.clone()) else {
+                    return;
+                };
+                for ws in &mut self.workspaces {
+                    if ws.output == output {
+                        ws.is_active = ws.id == id;
+                    }
+                    if focused {
+                        ws.is_focused = ws.id == id;
+                    }
+                }
+            }
+            Event::WorkspaceUrgencyChanged { id, urgent } => {
+                if let Some(ws) = self.workspaces.iter_mut().find(|ws| ws.id == id) {
+                    ws.is_urgent = urgent;
+                }
+            }
+            Event::WindowsChanged { windows } => {
+                self.windows = windows.into_iter().map(|w| (w.id, w)).collect();
+            }
+            Event::WindowOpenedOrChanged { window } => {
+                if window.is_focused {
+                    for w in self.windows.values_mut() {
+                        w.is_focused = false;
+                    }
+                }
+                self.windows.insert(window.id, window);
+            }
+            Event::WindowClosed { id } => {
+                self.windows.remove(&id);
+            }
+            Event::WindowFocusChanged { id } => {
+                for w in self.windows.values_mut() {
+                    w.is_focused = Some(w.id) == id;
+                }
+            }
+            Event::KeyboardLayoutsChanged { keyboard_layouts } => {
+                self.keyboard_layouts = keyboard_layouts.names;
+                self.keyboard_layout = keyboard_layouts.current_idx.into();
+            }
+            Event::KeyboardLayoutSwitched { idx } => {
+                self.keyboard_layout = idx.into();
+            }
+        }
+    }
+
+    fn workspace(&self, id: u64) -> Option<&Workspace> {
+        self.workspaces.iter().find(|ws| ws.id == id)
+    }
+
+    pub fn focused_window(&self) -> Option<&Window> {
+        self.windows.values().find(|w| w.is_focused)
+    }
+
+    pub fn keyboard_layout(&self) -> Option<&str> {
+        self.keyboard_layouts
+            .get(self.keyboard_layout)
+            .map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(json: &str) -> Event {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn state() {
+        let mut state = State::default();
+        state.apply(event(
+            r#"{"WorkspacesChanged":{"workspaces":[
+                {"id":2,"idx":2,"name":"web","output":"DP-1","is_urgent":false,"is_active":false,"is_focused":false,"active_window_id":null},
+                {"id":1,"idx":1,"name":null,"output":"DP-1","is_urgent":false,"is_active":true,"is_focused":true,"active_window_id":7}
+            ]}}"#,
+        ));
+        assert_eq!(state.workspaces[0].id, 1);
+        state.apply(event(r#"{"WorkspaceActivated":{"id":2,"focused":true}}"#));
+        assert!(!state.workspaces[0].is_active && !state.workspaces[0].is_focused);
+        assert!(state.workspaces[1].is_active && state.workspaces[1].is_focused);
+
+        state.apply(event(
+            r#"{"WindowsChanged":{"windows":[{"id":7,"title":"vim","app_id":"foot","pid":1,"workspace_id":1,"is_focused":true,"is_floating":false,"is_urgent":false}]}}"#,
+        ));
+        assert_eq!(
+            state.focused_window().unwrap().title.as_deref(),
+            Some("vim")
+        );
+        state.apply(event(r#"{"WindowFocusChanged":{"id":null}}"#));
+        assert!(state.focused_window().is_none());
+
+        state.apply(event(
+            r#"{"KeyboardLayoutsChanged":{"keyboard_layouts":{"names":["English (US)","German"],"current_idx":0}}}"#,
+        ));
+        state.apply(event(r#"{"KeyboardLayoutSwitched":{"idx":1}}"#));
+        assert_eq!(state.keyboard_layout(), Some("German"));
+
+        assert!(
+            serde_json::from_str::<Event>(r#"{"OverviewOpenedOrClosed":{"is_open":true}}"#)
+                .is_err()
+        );
+    }
+}
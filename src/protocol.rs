@@ -1,129 +1,228 @@
 pub mod i3bar_block;
 pub mod i3bar_event;
+pub(crate) mod output;
 
-use std::borrow::Borrow;
+use std::borrow::{Borrow, Cow};
+
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::config::SharedConfig;
 use crate::themes::color::Color;
 use crate::themes::separator::Separator;
+use crate::themes::Theme;
 use crate::RenderedBlock;
 
 use i3bar_block::I3BarBlock;
 
+/// The block ID used in the `instance` of the ellipsis widget which replaces collapsed blocks
+pub(crate) const OVERFLOW_ID: usize = usize::MAX;
+
+/// How to handle a bar which is wider than `max_width`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Overflow {
+    /// The maximum width of the bar in characters
+    pub max_width: usize,
+    /// Whether the collapsed blocks are currently shown
+    pub expanded: bool,
+}
+
+/// The separator to the left of a block. Blocks can change it in their `theme_overrides`.
+#[derive(Debug, Clone)]
+pub(crate) struct SeparatorStyle {
+    pub separator: Separator,
+    pub bg: Color,
+    pub fg: Color,
+    /// The separator between the widgets of the block
+    pub thin: Option<String>,
+    pub thin_fg: Option<Color>,
+}
+
+impl SeparatorStyle {
+    pub fn new(theme: &Theme) -> Self {
+        Self {
+            separator: theme.separator.clone(),
+            bg: theme.separator_bg,
+            fg: theme.separator_fg,
+            thin: theme.thin_separator.clone(),
+            thin_fg: theme.thin_separator_fg,
+        }
+    }
+}
+
 pub fn init(never_pause: bool) {
+    print!("{}", header(never_pause));
+}
+
+/// The header and the opening bracket of the infinite array
+pub(crate) fn header(never_pause: bool) -> String {
     if never_pause {
-        println!("{{\"version\": 1, \"click_events\": true, \"stop_signal\": 0}}\n[");
+        "{\"version\": 1, \"click_events\": true, \"stop_signal\": 0}\n[\n".into()
     } else {
-        println!("{{\"version\": 1, \"click_events\": true}}\n[");
+        "{\"version\": 1, \"click_events\": true}\n[\n".into()
     }
 }
 
-pub(crate) fn print_blocks<B>(blocks: &[B], config: &SharedConfig)
+/// Renders the blocks into a single line of the i3bar protocol, adding separators and the tint of
+/// alternating blocks
+pub(crate) fn render_blocks<B>(
+    blocks: &[B],
+    config: &SharedConfig,
+    overflow: Option<Overflow>,
+) -> Vec<I3BarBlock>
 where
     B: Borrow<RenderedBlock>,
 {
     let mut prev_last_bg = Color::None;
-    let mut rendered_blocks = vec![];
+    let mut rendered_blocks: Vec<I3BarBlock> = vec![];
+    let default_style = SeparatorStyle::new(&config.theme);
 
-    // The right most block should never be alternated
-    let mut alt = blocks
-        .iter()
-        .map(|x| x.borrow())
-        .filter(|x| !x.segments.is_empty() && !x.merge_with_next)
-        .count()
-        % 2
-        == 0;
+    // Blocks merged with `merge_with_next` are treated as a single block from here on
+    let merged = merge_blocks(blocks);
+    let mut blocks: Vec<&RenderedBlock> = merged.iter().collect();
 
-    let mut logical_block_i = 0;
+    let ellipsis;
+    if let Some(overflow) = overflow {
+        if let Some((pos, collapsed)) = collapse(&blocks, overflow.max_width, config) {
+            ellipsis = RenderedBlock {
+                segments: vec![I3BarBlock {
+                    full_text: if overflow.expanded { " » " } else { " … " }.into(),
+                    background: config.theme.idle_bg,
+                    color: config.theme.idle_fg,
+                    instance: format!("{OVERFLOW_ID}:"),
+                    ..Default::default()
+                }],
+                merge_with_next: false,
+                priority: i32::MAX,
+                separator: None,
+                alternatives: Vec::new(),
+            };
+            if !overflow.expanded {
+                blocks = blocks
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(i, _)| !collapsed.contains(i))
+                    .map(|(_, block)| block)
+                    .collect();
+            }
+            blocks.insert(pos, &ellipsis);
+        }
+    }
 
-    let mut prev_merge_with_next = false;
+    // The right most block should never be alternated
+    let mut alt = blocks.len().is_multiple_of(2);
 
-    for (i, widgets) in blocks
-        .iter()
-        .map(|x| x.borrow())
-        .filter(|x| !x.segments.is_empty())
-        .cloned()
-        .enumerate()
-    {
-        let RenderedBlock {
-            mut segments,
-            merge_with_next,
-        } = widgets;
+    for (i, block) in blocks.into_iter().enumerate() {
+        let mut segments = block.segments.clone();
 
         for segment in &mut segments {
-            segment.name = Some(logical_block_i.to_string());
+            segment.name = Some(i.to_string());
 
             // Apply tint for all widgets of every second block
-            // TODO: Allow for other non-additive tints
             if alt {
-                segment.background = segment.background + config.theme.alternating_tint_bg;
-                segment.color = segment.color + config.theme.alternating_tint_fg;
+                let mode = config.theme.alternating_tint_mode;
+                segment.background = segment
+                    .background
+                    .tint(config.theme.alternating_tint_bg, mode);
+                if !segment.background_gradient.skip_ser() {
+                    segment.background_gradient = segment
+                        .background_gradient
+                        .tint(config.theme.alternating_tint_bg, mode);
+                }
+                segment.color = segment.color.tint(config.theme.alternating_tint_fg, mode);
             }
         }
 
-        if !merge_with_next {
-            alt = !alt;
+        alt = !alt;
+
+        // The BGs at the edges of the block, which the separators connect to
+        let first_bg = segments.first().unwrap().background;
+        let last = segments.last().unwrap();
+        let last_bg = if last.background_gradient.skip_ser() {
+            last.background
+        } else {
+            last.background_gradient
+        };
+        let style = block.separator.as_ref().unwrap_or(&default_style);
+        if let Some(thin) = &style.thin {
+            // The gradient already runs across the widgets
+            if segments.iter().all(|s| s.background_gradient.skip_ser()) {
+                segments = add_thin_separators(segments, thin, style);
+            }
         }
+        apply_gradients(&mut segments);
 
         let separator = match &config.theme.start_separator {
             Separator::Custom(_) if i == 0 => &config.theme.start_separator,
-            _ => &config.theme.separator,
+            _ => &style.separator,
         };
 
         if let Separator::Custom(separator) = separator {
-            if !prev_merge_with_next {
-                // The first widget's BG is used to get the FG color for the current separator
-                let sep_fg = if config.theme.separator_fg == Color::Auto {
-                    segments.first().unwrap().background
-                } else {
-                    config.theme.separator_fg
-                };
+            let (theme_fg, theme_bg) = if i == 0 {
+                (
+                    config.theme.start_separator_fg,
+                    config.theme.start_separator_bg,
+                )
+            } else {
+                (None, None)
+            };
 
-                // The separator's BG is the last block's last widget's BG
-                let sep_bg = if config.theme.separator_bg == Color::Auto {
-                    prev_last_bg
-                } else {
-                    config.theme.separator_bg
-                };
+            // The first widget's BG is used to get the FG color for the current separator
+            let sep_fg = match theme_fg.unwrap_or(style.fg) {
+                Color::Auto => first_bg,
+                color => color,
+            };
 
-                let separator = I3BarBlock {
-                    full_text: separator.clone(),
-                    background: sep_bg,
-                    color: sep_fg,
-                    ..Default::default()
-                };
+            // The separator's BG is the last block's last widget's BG
+            let sep_bg = match theme_bg.unwrap_or(style.bg) {
+                Color::Auto => prev_last_bg,
+                color => color,
+            };
 
-                rendered_blocks.push(separator);
-            }
-        } else if !merge_with_next {
-            // Re-add native separator on last widget for native theme
-            segments.last_mut().unwrap().separator = None;
-            segments.last_mut().unwrap().separator_block_width = None;
-        }
+            let separator = I3BarBlock {
+                full_text: separator.clone(),
+                background: sep_bg,
+                color: sep_fg,
+                ..Default::default()
+            };
 
-        if !merge_with_next {
-            logical_block_i += 1;
+            rendered_blocks.push(separator);
+        } else if let Some(prev) = rendered_blocks.last_mut() {
+            // Re-add native separator on the previous block's last widget
+            prev.separator = None;
+            prev.separator_block_width = None;
         }
 
-        prev_merge_with_next = merge_with_next;
-        prev_last_bg = segments.last().unwrap().background;
+        prev_last_bg = last_bg;
 
         rendered_blocks.extend(segments);
     }
 
+    if config.theme.separator == Separator::Native {
+        if let Some(last) = rendered_blocks.last_mut() {
+            last.separator = None;
+            last.separator_block_width = None;
+        }
+    }
+
     if let Separator::Custom(end_separator) = &config.theme.end_separator {
         // The separator's FG is the last block's last widget's BG
-        let sep_fg = if config.theme.separator_fg == Color::Auto {
-            prev_last_bg
-        } else {
-            config.theme.separator_fg
+        let sep_fg = match config
+            .theme
+            .end_separator_fg
+            .unwrap_or(config.theme.separator_fg)
+        {
+            Color::Auto => prev_last_bg,
+            color => color,
         };
 
         // The separator has no background color
-        let sep_bg = if config.theme.separator_bg == Color::Auto {
-            Color::None
-        } else {
-            config.theme.separator_bg
+        let sep_bg = match config
+            .theme
+            .end_separator_bg
+            .unwrap_or(config.theme.separator_bg)
+        {
+            Color::Auto => Color::None,
+            color => color,
         };
 
         let separator = I3BarBlock {
@@ -136,5 +235,550 @@ where
         rendered_blocks.push(separator);
     }
 
-    println!("{},", serde_json::to_string(&rendered_blocks).unwrap());
+    rendered_blocks
+}
+
+/// Separates the widgets of a block with `thin` if they have the same background, and with the
+/// full separator otherwise. Widgets without text, including those which are only shown in short
+/// mode, are not separated.
+fn add_thin_separators(
+    segments: Vec<I3BarBlock>,
+    thin: &str,
+    style: &SeparatorStyle,
+) -> Vec<I3BarBlock> {
+    let mut out: Vec<I3BarBlock> = Vec::with_capacity(segments.len() * 2);
+    let mut prev: Option<usize> = None;
+    for segment in segments {
+        if segment.full_text.is_empty() || segment.full_text == "<span/>" {
+            out.push(segment);
+            continue;
+        }
+        if let Some(prev) = prev.map(|i| &out[i]) {
+            let separator = if prev.background == segment.background {
+                Some((thin, style.thin_fg.unwrap_or(prev.color)))
+            } else if let Separator::Custom(separator) = &style.separator {
+                Some((separator.as_str(), segment.background))
+            } else {
+                None
+            };
+            if let Some((text, color)) = separator {
+                let separator = I3BarBlock {
+                    full_text: text.into(),
+                    background: prev.background,
+                    color,
+                    // Clicks on the separator go to the block
+                    name: prev.name.clone(),
+                    instance: prev.instance.clone(),
+                    // Hidden in short mode along with the widgets
+                    short_text: prev.short_text.clone(),
+                    ..Default::default()
+                };
+                out.push(separator);
+            }
+        }
+        prev = Some(out.len());
+        out.push(segment);
+    }
+    out
+}
+
+/// Draws the gradient backgrounds of the segments of a block with pango markup. The gradients run
+/// across the whole block, so a gradient continues where the previous segment ends. The BG of
+/// each segment is set to the color in its middle, which fills the parts of the block that the
+/// text does not cover.
+fn apply_gradients(segments: &mut [I3BarBlock]) {
+    if segments.iter().all(|s| s.background_gradient.skip_ser()) {
+        return;
+    }
+    let mut middles = vec![None; segments.len()];
+    for short in [false, true] {
+        let widths: Vec<usize> = segments
+            .iter()
+            .map(|s| estimate_width(if short { &s.short_text } else { &s.full_text }))
+            .collect();
+        let total = widths.iter().sum::<usize>() as f64;
+        if total == 0.0 {
+            continue;
+        }
+        let mut pos = 0;
+        for ((segment, width), middle) in segments.iter_mut().zip(widths).zip(&mut middles) {
+            let (from, to) = (segment.background, segment.background_gradient);
+            if width > 0 && !to.skip_ser() {
+                let color_at = |i: usize| from.blend(to, (pos + i) as f64 / total + 0.5 / total);
+                let text = if short {
+                    &mut segment.short_text
+                } else {
+                    &mut segment.full_text
+                };
+                *text = gradient_markup(text, color_at);
+                if !short {
+                    *middle = Some(color_at(width / 2));
+                }
+            }
+            pos += width;
+        }
+    }
+    for (segment, middle) in segments.iter_mut().zip(middles) {
+        if let Some(middle) = middle {
+            segment.background = middle;
+        }
+    }
+}
+
+/// Wraps each visible character of a pango-formatted text in a span with the background
+/// `color_at` its index. Runs of characters with the same color share a span.
+fn gradient_markup(text: &str, color_at: impl Fn(usize) -> Color) -> String {
+    let mut out = String::with_capacity(text.len() * 4);
+    let mut span: Option<String> = None;
+    let mut index = 0;
+    let mut rest = text;
+    let close = |out: &mut String, span: &mut Option<String>| {
+        if span.take().is_some() {
+            out.push_str("</span>");
+        }
+    };
+    while let Some(c) = rest.chars().next() {
+        let unit = match c {
+            // Tags are kept as they are, outside of the spans
+            '<' => {
+                let end = rest.find('>').map_or(rest.len(), |end| end + 1);
+                close(&mut out, &mut span);
+                out.push_str(&rest[..end]);
+                rest = &rest[end..];
+                continue;
+            }
+            // Entities such as `&amp;` are displayed as a single character
+            '&' => &rest[..rest.find(';').map_or(1, |end| end + 1)],
+            _ => rest.graphemes(true).next().unwrap_or(rest),
+        };
+        rest = &rest[unit.len()..];
+        let color = color_at(index).to_hex();
+        index += 1;
+        if color != span {
+            close(&mut out, &mut span);
+            if let Some(color) = &color {
+                out.push_str(&format!("<span background=\"{color}\">"));
+            }
+            span = color;
+        }
+        out.push_str(unit);
+    }
+    close(&mut out, &mut span);
+    out
+}
+
+/// Joins each run of blocks connected with `merge_with_next` into a single block, so that merged
+/// blocks share tinting and separators. Empty (e.g. hidden) blocks still end a run, so a block
+/// merged with a hidden block is not merged with the block after it. Empty runs are removed.
+fn merge_blocks<B>(blocks: &[B]) -> Vec<RenderedBlock>
+where
+    B: Borrow<RenderedBlock>,
+{
+    let mut merged = Vec::new();
+    let mut current: Option<RenderedBlock> = None;
+    for block in blocks.iter().map(Borrow::borrow) {
+        let run = current.get_or_insert_with(|| RenderedBlock {
+            segments: Vec::new(),
+            merge_with_next: false,
+            priority: i32::MIN,
+            separator: None,
+            alternatives: Vec::new(),
+        });
+        if !block.segments.is_empty() {
+            // The separator to the left of the run is the one of its first block
+            if run.segments.is_empty() {
+                run.separator = block.separator.clone();
+            }
+            run.segments.extend_from_slice(&block.segments);
+            run.priority = run.priority.max(block.priority);
+        }
+        if !block.merge_with_next {
+            merged.extend(current.take().filter(|run| !run.segments.is_empty()));
+        }
+    }
+    merged.extend(current.filter(|run| !run.segments.is_empty()));
+    merged
+}
+
+/// Converts a rendered line to plain text. Pango markup is removed and native separators are
+/// replaced with `|`.
+pub fn plain_text(blocks: &[I3BarBlock]) -> String {
+    let mut text = String::new();
+    for (i, block) in blocks.iter().enumerate() {
+        if block.markup.as_deref() == Some("pango") {
+            strip_markup(&block.full_text, &mut text);
+        } else {
+            text.push_str(&block.full_text);
+        }
+        if block.separator != Some(false) && i + 1 < blocks.len() {
+            text.push('|');
+        }
+    }
+    text
+}
+
+/// Removes pango tags and replaces entities with the characters they represent
+fn strip_markup(markup: &str, out: &mut String) {
+    let mut rest = markup;
+    while let Some(pos) = rest.find(['<', '&']) {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if rest.starts_with('<') {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            continue;
+        }
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        match &rest[1..end] {
+            "amp" => out.push('&'),
+            "lt" => out.push('<'),
+            "gt" => out.push('>'),
+            "quot" => out.push('"'),
+            "apos" => out.push('\''),
+            entity => match entity
+                .strip_prefix('#')
+                .and_then(|code| code.parse().ok())
+                .and_then(char::from_u32)
+            {
+                Some(c) => out.push(c),
+                None => out.push_str(&rest[..=end]),
+            },
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+}
+
+/// Chooses the blocks to collapse so that the bar fits into `max_width` characters. Blocks with
+/// the lowest priority are collapsed first, and among blocks with equal priority the leftmost
+/// ones. Returns the position at which the ellipsis should be inserted (the position of the
+/// leftmost collapsed block) and the indices of the collapsed blocks, or `None` if the bar fits.
+fn collapse(
+    blocks: &[&RenderedBlock],
+    max_width: usize,
+    config: &SharedConfig,
+) -> Option<(usize, Vec<usize>)> {
+    let separator_width = separator_width(config);
+    let widths: Vec<usize> = blocks
+        .iter()
+        .map(|block| {
+            let text: usize = block
+                .segments
+                .iter()
+                .map(|s| estimate_width(&s.full_text))
+                .sum();
+            text + separator_width
+        })
+        .collect();
+
+    let mut total: usize = widths.iter().sum();
+    if total <= max_width {
+        return None;
+    }
+    total += estimate_width(" … ") + separator_width;
+
+    let mut order: Vec<usize> = (0..blocks.len()).collect();
+    order.sort_by_key(|&i| blocks[i].priority);
+
+    let mut collapsed = Vec::new();
+    for i in order {
+        if total <= max_width {
+            break;
+        }
+        total -= widths[i];
+        collapsed.push(i);
+    }
+
+    let first = *collapsed.iter().min()?;
+    Some((first, collapsed))
+}
+
+/// Switches blocks to the narrower formats in their `alternatives` until the bar fits into
+/// `max_width` characters. Like with collapsing, the blocks with the lowest priority are degraded
+/// first, and among blocks with equal priority the leftmost ones. Each block is degraded as far as
+/// needed before the next one is.
+pub(crate) fn degrade(
+    blocks: &mut [Cow<'_, RenderedBlock>],
+    max_width: usize,
+    config: &SharedConfig,
+) {
+    let separator_width = separator_width(config);
+    let width = |block: &RenderedBlock| {
+        if block.segments.is_empty() {
+            return 0;
+        }
+        let text: usize = block
+            .segments
+            .iter()
+            .map(|s| estimate_width(&s.full_text))
+            .sum();
+        // Merged blocks have no separator between them
+        text + if block.merge_with_next {
+            0
+        } else {
+            separator_width
+        }
+    };
+
+    let mut total: usize = blocks.iter().map(|block| width(block)).sum();
+    let mut order: Vec<usize> = (0..blocks.len())
+        .filter(|&i| !blocks[i].alternatives.is_empty())
+        .collect();
+    order.sort_by_key(|&i| blocks[i].priority);
+
+    for i in order {
+        for level in 0..blocks[i].alternatives.len() {
+            if total <= max_width {
+                return;
+            }
+            let old = width(&blocks[i]);
+            let block = blocks[i].to_mut();
+            block.segments = block.alternatives[level].clone();
+            total = total - old + width(&blocks[i]);
+        }
+    }
+}
+
+fn separator_width(config: &SharedConfig) -> usize {
+    match &config.theme.separator {
+        Separator::Custom(s) => estimate_width(s),
+        // Native separators are a few pixels wide
+        Separator::Native => 1,
+    }
+}
+
+/// Estimates the width of a (possibly pango-formatted) text in characters
+fn estimate_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            // Skip markup tags
+            '<' => match rest.find('>') {
+                Some(end) => rest = &rest[end + 1..],
+                None => break,
+            },
+            // Entities such as `&amp;` are displayed as a single character
+            '&' => {
+                width += 1;
+                rest = match rest.find(';') {
+                    Some(end) => &rest[end + 1..],
+                    None => &rest[1..],
+                };
+            }
+            _ => {
+                let end = rest.find(['<', '&']).unwrap_or(rest.len());
+                width += rest[..end].graphemes(true).count();
+                rest = &rest[end..];
+            }
+        }
+    }
+    width
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn width_of_plain_text() {
+        assert_eq!(estimate_width(""), 0);
+        assert_eq!(estimate_width(" CPU 12% "), 9);
+        assert_eq!(estimate_width("é…"), 2);
+    }
+
+    #[test]
+    fn width_of_pango_text() {
+        assert_eq!(estimate_width("<span/>"), 0);
+        assert_eq!(estimate_width("<i><u>abc</u></i>"), 3);
+        assert_eq!(estimate_width("a &amp; b"), 5);
+        assert_eq!(estimate_width("<span font_family='x'>ab</span>c"), 3);
+    }
+
+    fn block(text: &str, merge_with_next: bool) -> RenderedBlock {
+        RenderedBlock {
+            segments: vec![I3BarBlock {
+                full_text: text.into(),
+                background: "#000000".parse().unwrap(),
+                ..Default::default()
+            }],
+            merge_with_next,
+            priority: 0,
+            separator: None,
+            alternatives: Vec::new(),
+        }
+    }
+
+    fn powerline_config() -> SharedConfig {
+        let mut config = SharedConfig::default();
+        let theme = std::sync::Arc::make_mut(&mut config.theme);
+        theme.separator = Separator::Custom("<".into());
+        theme.start_separator = Separator::Native;
+        theme.end_separator = Separator::Native;
+        theme.separator_bg = Color::Auto;
+        theme.separator_fg = Color::Auto;
+        theme.alternating_tint_bg = "#111111".parse().unwrap();
+        config
+    }
+
+    fn texts(rendered: &[I3BarBlock]) -> Vec<&str> {
+        rendered.iter().map(|b| b.full_text.as_str()).collect()
+    }
+
+    #[test]
+    fn merged_blocks_are_one_block() {
+        let config = powerline_config();
+        let blocks = [block("a", true), block("b", false), block("c", false)];
+        let rendered = render_blocks(&blocks, &config, None);
+        assert_eq!(texts(&rendered), ["<", "a", "b", "<", "c"]);
+
+        let tinted: Color = "#111111".parse().unwrap();
+        let (a, b, sep, c) = (&rendered[1], &rendered[2], &rendered[3], &rendered[4]);
+        assert_eq!(a.name.as_deref(), Some("0"));
+        assert_eq!(b.name.as_deref(), Some("0"));
+        assert_eq!(c.name.as_deref(), Some("1"));
+        assert_eq!(a.background, tinted);
+        assert_eq!(b.background, tinted);
+        assert_eq!(c.background, blocks[2].segments[0].background);
+        // The separator joins the end of the merged block with the next block
+        assert_eq!(sep.background, b.background);
+        assert_eq!(sep.color, c.background);
+    }
+
+    #[test]
+    fn gradient() {
+        let config = powerline_config();
+        let mut blocks = [block("ab", true), block("<b>c</b>&amp;", false)];
+        for block in &mut blocks {
+            block.segments[0].background = "#000000".parse().unwrap();
+            block.segments[0].background_gradient = "#ff0000".parse().unwrap();
+        }
+        let rendered = render_blocks(&blocks[..1], &config, None);
+        let black: Color = "#000000".parse().unwrap();
+        assert_eq!(
+            rendered[1].full_text,
+            "<span background=\"#400000FF\">a</span><span background=\"#BF0000FF\">b</span>"
+        );
+        assert_eq!(
+            rendered[1].background,
+            black.blend("#ff0000".parse().unwrap(), 0.75)
+        );
+
+        // The gradient runs across merged blocks
+        let rendered = render_blocks(&blocks, &config, None);
+        assert_eq!(
+            texts(&rendered)[2],
+            "<b><span background=\"#9F0000FF\">c</span></b><span background=\"#DF0000FF\">&amp;</span>"
+        );
+    }
+
+    #[test]
+    fn block_separator_override() {
+        let config = powerline_config();
+        let red: Color = "#ff0000".parse().unwrap();
+        let mut blocks = [block("a", false), block("b", false), block("c", false)];
+        blocks[1].separator = Some(SeparatorStyle {
+            separator: Separator::Custom(">".into()),
+            bg: Color::Auto,
+            fg: red,
+            thin: None,
+            thin_fg: None,
+        });
+        let rendered = render_blocks(&blocks, &config, None);
+        assert_eq!(texts(&rendered), ["<", "a", ">", "b", "<", "c"]);
+        assert_eq!(rendered[2].color, red);
+
+        // Native separators only separate the blocks which use them
+        blocks[1].separator = Some(SeparatorStyle {
+            separator: Separator::Native,
+            bg: Color::Auto,
+            fg: Color::Auto,
+            thin: None,
+            thin_fg: None,
+        });
+        let rendered = render_blocks(&blocks, &config, None);
+        assert_eq!(texts(&rendered), ["<", "a", "b", "<", "c"]);
+        assert_eq!(rendered[1].separator, None);
+        assert_eq!(rendered[2].separator, Some(false));
+    }
+
+    #[test]
+    fn thin_separators() {
+        let mut config = powerline_config();
+        std::sync::Arc::make_mut(&mut config.theme).thin_separator = Some("|".into());
+        let red: Color = "#ff0000".parse().unwrap();
+        let mut blocks = [block("a", false)];
+        let segment = |text: &str, background| I3BarBlock {
+            full_text: text.into(),
+            background,
+            color: red,
+            ..Default::default()
+        };
+        let black = blocks[0].segments[0].background;
+        blocks[0].segments = vec![
+            segment("a", black),
+            segment("", black),
+            segment("b", black),
+            segment("c", red),
+        ];
+        let rendered = render_blocks(&blocks, &config, None);
+        assert_eq!(texts(&rendered), ["<", "a", "", "|", "b", "<", "c"]);
+        // Thin separators have the text color, full ones connect the backgrounds
+        assert_eq!((rendered[3].background, rendered[3].color), (black, red));
+        assert_eq!((rendered[5].background, rendered[5].color), (black, red));
+        assert_eq!(rendered[3].name.as_deref(), Some("0"));
+    }
+
+    #[test]
+    fn empty_block_ends_merge() {
+        let config = powerline_config();
+        let hidden = RenderedBlock {
+            segments: Vec::new(),
+            merge_with_next: false,
+            priority: 0,
+            separator: None,
+            alternatives: Vec::new(),
+        };
+        let blocks = [block("a", true), hidden, block("c", false)];
+        let rendered = render_blocks(&blocks, &config, None);
+        assert_eq!(texts(&rendered), ["<", "a", "<", "c"]);
+        assert_eq!(rendered[3].name.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn degrade_to_fit() {
+        let config = powerline_config();
+        let with_alternatives = |text, alternatives: &[&str], priority| RenderedBlock {
+            alternatives: alternatives
+                .iter()
+                .map(|t| block(t, false).segments)
+                .collect(),
+            priority,
+            ..block(text, false)
+        };
+        let blocks = [
+            with_alternatives("aaaaaa", &["aaa", "a"], 0),
+            with_alternatives("bbbbbb", &["bb"], 1),
+        ];
+        // The `<` separators are taken for markup and have no width
+        let degraded = |max_width| {
+            let mut blocks: Vec<_> = blocks.iter().map(Cow::Borrowed).collect();
+            degrade(&mut blocks, max_width, &config);
+            texts(&render_blocks(&blocks, &config, None))
+                .into_iter()
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(degraded(12), ["<", "aaaaaa", "<", "bbbbbb"]);
+        assert_eq!(degraded(9), ["<", "aaa", "<", "bbbbbb"]);
+        assert_eq!(degraded(4), ["<", "a", "<", "bb"]);
+    }
+
+    #[test]
+    fn plain_text_of_pango() {
+        let mut text = String::new();
+        strip_markup("<b>a</b> &amp; &lt;b&gt; &#39;c&#39;", &mut text);
+        assert_eq!(text, "a & <b> 'c'");
+    }
 }
@@ -2,7 +2,7 @@ use crate::themes::color::Color;
 use serde::Serialize;
 
 /// Represent block as described in <https://i3wm.org/docs/i3bar-protocol.html>
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, PartialEq)]
 pub struct I3BarBlock {
     pub full_text: String,
     #[serde(skip_serializing_if = "String::is_empty")]
@@ -44,6 +44,10 @@ pub struct I3BarBlock {
     pub separator_block_width: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub markup: Option<String>,
+    /// The background color at the right edge of the block. If set, the background is a gradient
+    /// from `background`, which is drawn with pango markup when the bar is rendered.
+    #[serde(skip)]
+    pub background_gradient: Color,
 }
 
 impl Default for I3BarBlock {
@@ -70,11 +74,12 @@ impl Default for I3BarBlock {
             separator: Some(false),
             separator_block_width: Some(0),
             markup: Some("pango".to_string()),
+            background_gradient: Color::None,
         }
     }
 }
 
-#[derive(Serialize, Debug, Clone, Copy)]
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(dead_code)]
 #[serde(rename_all = "lowercase")]
 pub enum I3BarBlockAlign {
@@ -83,7 +88,7 @@ pub enum I3BarBlockAlign {
     Left,
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
 #[allow(dead_code)]
 #[serde(untagged)]
 pub enum I3BarBlockMinWidth {
@@ -7,7 +7,7 @@ use futures::StreamExt;
 use tokio::fs::File;
 use tokio::io::{AsyncBufReadExt, BufReader};
 
-use crate::click::MouseButton;
+use crate::click::{Modifier, MouseButton};
 use crate::BoxedStream;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -15,18 +15,35 @@ pub struct I3BarEvent {
     pub id: usize,
     pub instance: Option<String>,
     pub button: MouseButton,
+    pub modifiers: Vec<Modifier>,
 }
 
-fn unprocessed_events_stream(invert_scrolling: bool) -> BoxedStream<I3BarEvent> {
+/// Returns the stream of lines read from stdin
+pub(crate) fn stdin_lines() -> BoxedStream<String> {
     // Avoid spawning a blocking therad (why doesn't tokio do this too?)
     // This should be safe given that this function is called only once
     let stdin = unsafe { File::from_raw_fd(0) };
     let lines = BufReader::new(stdin).lines();
 
+    futures::stream::unfold(lines, |mut lines| async move {
+        lines
+            .next_line()
+            .await
+            .ok()
+            .flatten()
+            .map(|line| (line, lines))
+    })
+    .boxed_local()
+}
+
+fn unprocessed_events_stream(
+    lines: BoxedStream<String>,
+    invert_scrolling: bool,
+) -> BoxedStream<I3BarEvent> {
     futures::stream::unfold(lines, move |mut lines| async move {
         loop {
             // Take only the valid JSON object between curly braces (cut off leading bracket, commas and whitespace)
-            let line = lines.next_line().await.ok().flatten()?;
+            let line = lines.next().await?;
             let line = line.trim_start_matches(|c| c != '{');
             let line = line.trim_end_matches(|c| c != '}');
 
@@ -38,6 +55,8 @@ fn unprocessed_events_stream(invert_scrolling: bool) -> BoxedStream<I3BarEvent>
             struct I3BarEventRaw {
                 instance: Option<String>,
                 button: MouseButton,
+                #[serde(default)]
+                modifiers: Vec<String>,
             }
 
             let event: I3BarEventRaw = match serde_json::from_str(line) {
@@ -48,15 +67,19 @@ fn unprocessed_events_stream(invert_scrolling: bool) -> BoxedStream<I3BarEvent>
                 }
             };
 
-            let (id, instance) = match event.instance {
-                Some(name) => {
-                    let (id, instance) = name.split_once(':').unwrap();
+            let (id, instance) = match event
+                .instance
+                .as_deref()
+                .and_then(|name| name.split_once(':'))
+                .and_then(|(id, instance)| Some((id.parse().ok()?, instance)))
+            {
+                Some((id, instance)) => {
                     let instance = if instance.is_empty() {
                         None
                     } else {
                         Some(instance.to_owned())
                     };
-                    (id.parse().unwrap(), instance)
+                    (id, instance)
                 }
                 None => continue,
             };
@@ -68,10 +91,18 @@ fn unprocessed_events_stream(invert_scrolling: bool) -> BoxedStream<I3BarEvent>
                 (other, _) => other,
             };
 
+            // Lock keys such as Num Lock are not of interest
+            let modifiers = event
+                .modifiers
+                .iter()
+                .filter_map(|name| name.parse().ok())
+                .collect();
+
             let event = I3BarEvent {
                 id,
                 instance,
                 button,
+                modifiers,
             };
 
             break Some((event, lines));
@@ -81,10 +112,11 @@ fn unprocessed_events_stream(invert_scrolling: bool) -> BoxedStream<I3BarEvent>
 }
 
 pub fn events_stream(
+    lines: BoxedStream<String>,
     invert_scrolling: bool,
     double_click_delay: Duration,
 ) -> BoxedStream<I3BarEvent> {
-    let events = unprocessed_events_stream(invert_scrolling);
+    let events = unprocessed_events_stream(lines, invert_scrolling);
     futures::stream::unfold((events, None), move |(mut events, pending)| async move {
         if let Some(pending) = pending {
             return Some((pending, (events, None)));
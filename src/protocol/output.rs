@@ -0,0 +1,158 @@
+//! Destinations for the stream of rendered blocks
+//!
+//! The main bar writes to stdout and reads click events from stdin. Additional bars (defined with
+//! `[[bar]]`) write either to a named pipe or to a UNIX socket. Sockets are bidirectional, so bars
+//! connected with e.g. `status_command socat UNIX-CONNECT:/path/to/socket -` also receive click
+//! events.
+
+use std::os::unix::fs::FileTypeExt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use futures::StreamExt;
+use tokio::io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::unix::pipe;
+use tokio::net::UnixListener;
+use tokio::sync::{mpsc, watch};
+
+use crate::errors::*;
+use crate::BoxedStream;
+
+/// How long to wait before reopening a named pipe, doubled after every failure
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+pub(crate) enum Output {
+    Stdout,
+    /// The latest rendered line, forwarded to the bar(s) by a background task
+    Latest(watch::Sender<String>),
+}
+
+impl Output {
+    pub fn write(&self, line: String) {
+        match self {
+            Self::Stdout => println!("{line},"),
+            Self::Latest(tx) => {
+                tx.send_replace(line);
+            }
+        }
+    }
+
+    /// Write to a named pipe. The pipe is (re)opened whenever a reader is available. Click events
+    /// are not supported.
+    pub fn fifo(path: PathBuf, never_pause: bool) -> (Self, BoxedStream<String>) {
+        let (tx, mut rx) = watch::channel(String::new());
+        tokio::spawn(async move {
+            let mut backoff = MIN_BACKOFF;
+            let mut waiting = false;
+            loop {
+                match pipe::OpenOptions::new().open_sender(&path) {
+                    Ok(mut fifo) => {
+                        backoff = MIN_BACKOFF;
+                        waiting = false;
+                        if let Err(err) = forward(&mut rx, &mut fifo, never_pause).await {
+                            log::debug!("Failed to write to '{}': {err}", path.display());
+                        }
+                        if rx.has_changed().is_err() {
+                            return;
+                        }
+                    }
+                    // Opening fails with ENXIO until there is a reader
+                    Err(err) if err.raw_os_error() == Some(libc::ENXIO) => {
+                        if !waiting {
+                            log::debug!("Waiting for a reader of '{}'", path.display());
+                            waiting = true;
+                        }
+                        tokio::time::sleep(MIN_BACKOFF).await;
+                    }
+                    Err(err) => {
+                        log::warn!(
+                            "Failed to open '{}': {err}, retrying in {}s",
+                            path.display(),
+                            backoff.as_secs()
+                        );
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(MAX_BACKOFF);
+                    }
+                }
+            }
+        });
+        (Self::Latest(tx), futures::stream::pending().boxed_local())
+    }
+
+    /// Listen on a UNIX socket. Each connected client receives the full stream and may send click
+    /// events.
+    pub fn socket(path: PathBuf, never_pause: bool) -> Result<(Self, BoxedStream<String>)> {
+        // Remove a stale socket left from the previous run, but nothing else
+        match std::fs::symlink_metadata(&path) {
+            Ok(meta) if meta.file_type().is_socket() => std::fs::remove_file(&path)
+                .or_error(|| format!("Failed to remove stale socket '{}'", path.display()))?,
+            Ok(_) => {
+                return Err(Error::new(format!(
+                    "'{}' exists and is not a socket",
+                    path.display()
+                )));
+            }
+            Err(_) => (),
+        }
+        let listener = UnixListener::bind(&path)
+            .or_error(|| format!("Failed to bind to '{}'", path.display()))?;
+
+        let (tx, rx) = watch::channel(String::new());
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (reader, mut writer) = stream.into_split();
+
+                let events_tx = events_tx.clone();
+                tokio::spawn(async move {
+                    let mut lines = BufReader::new(reader).lines();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        if events_tx.send(line).is_err() {
+                            break;
+                        }
+                    }
+                });
+
+                let mut rx = rx.clone();
+                tokio::spawn(async move {
+                    // The client disconnected
+                    let _ = forward(&mut rx, &mut writer, never_pause).await;
+                });
+            }
+        });
+
+        let events = futures::stream::unfold(events_rx, |mut rx| async move {
+            rx.recv().await.map(|line| (line, rx))
+        })
+        .boxed_local();
+
+        Ok((Self::Latest(tx), events))
+    }
+}
+
+/// Write the protocol header followed by every new line until either the writer fails or the
+/// sender is dropped
+async fn forward(
+    rx: &mut watch::Receiver<String>,
+    writer: &mut (impl AsyncWrite + Unpin),
+    never_pause: bool,
+) -> std::io::Result<()> {
+    rx.mark_changed();
+    let mut data = super::header(never_pause);
+    while rx.changed().await.is_ok() {
+        {
+            let line = rx.borrow_and_update();
+            if line.is_empty() {
+                continue;
+            }
+            data.push_str(&line);
+        }
+        data.push_str(",\n");
+        writer.write_all(data.as_bytes()).await?;
+        data.clear();
+    }
+    Ok(())
+}
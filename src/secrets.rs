@@ -0,0 +1,230 @@
+//! Secrets from the system keyring
+//!
+//! Any string in the config of the form `secret:<attribute>=<value>[,<attribute>=<value>...]` is
+//! replaced on startup with the secret whose attributes match, looked up using the
+//! [Secret Service API](https://specifications.freedesktop.org/secret-service-spec/latest/)
+//! (implemented by e.g. GNOME Keyring and KeePassXC). Secrets can be stored with `secret-tool`:
+//!
+//! ```sh
+//! secret-tool store --label="OpenWeatherMap" service openweathermap
+//! ```
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+
+use futures::StreamExt;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+use crate::errors::*;
+use crate::util::new_dbus_connection;
+
+const PREFIX: &str = "secret:";
+
+/// Whether any string in `table` refers to a secret
+pub fn has_secrets(table: &toml::Table) -> bool {
+    let mut found = false;
+    visit(table, &mut |s| found |= s.starts_with(PREFIX));
+    found
+}
+
+/// Replace all references to secrets in `table` with the secrets
+pub async fn resolve(table: &mut toml::Table) -> Result<()> {
+    let mut specs = Vec::new();
+    visit(table, &mut |s| {
+        if let Some(spec) = s.strip_prefix(PREFIX) {
+            specs.push(spec.to_owned());
+        }
+    });
+    if specs.is_empty() {
+        return Ok(());
+    }
+
+    let dbus_conn = new_dbus_connection().await?;
+    let service = ServiceProxy::new(&dbus_conn)
+        .await
+        .error("Failed to create Secret Service proxy")?;
+    let (_, session) = service
+        .open_session("plain", &Value::from(""))
+        .await
+        .error("Failed to open Secret Service session")?;
+
+    let mut secrets = HashMap::new();
+    for spec in specs {
+        if let Entry::Vacant(entry) = secrets.entry(spec) {
+            let secret = lookup(&dbus_conn, &service, &session, entry.key()).await?;
+            entry.insert(secret);
+        }
+    }
+
+    visit_mut(table, &mut |s| {
+        if let Some(secret) = s.strip_prefix(PREFIX).and_then(|spec| secrets.get(spec)) {
+            *s = secret.clone();
+        }
+    });
+    Ok(())
+}
+
+async fn lookup(
+    dbus_conn: &zbus::Connection,
+    service: &ServiceProxy<'_>,
+    session: &ObjectPath<'_>,
+    spec: &str,
+) -> Result<String> {
+    let attributes = parse_attributes(spec)?;
+    let (unlocked, locked) = service
+        .search_items(attributes)
+        .await
+        .error("Failed to search for secrets")?;
+
+    let item = match (unlocked.into_iter().next(), locked.into_iter().next()) {
+        (Some(item), _) => item,
+        (None, Some(item)) => {
+            unlock(dbus_conn, service, &item).await?;
+            item
+        }
+        (None, None) => return Err(Error::new(format!("Secret '{spec}' not found"))),
+    };
+
+    let item = ItemProxy::builder(dbus_conn)
+        .path(item)
+        .error("Could not set path")?
+        .build()
+        .await
+        .error("Failed to create Secret Service item proxy")?;
+    let (_, _, value, _) = item
+        .get_secret(session)
+        .await
+        .or_error(|| format!("Failed to get secret '{spec}'"))?;
+    String::from_utf8(value).or_error(|| format!("Secret '{spec}' is not valid UTF-8"))
+}
+
+/// Unlock an item, prompting the user if needed
+async fn unlock(
+    dbus_conn: &zbus::Connection,
+    service: &ServiceProxy<'_>,
+    item: &ObjectPath<'_>,
+) -> Result<()> {
+    let (_, prompt) = service
+        .unlock(&[item])
+        .await
+        .error("Failed to unlock the keyring")?;
+    // "/" means that no prompt is necessary
+    if prompt.as_str() == "/" {
+        return Ok(());
+    }
+
+    let prompt = PromptProxy::builder(dbus_conn)
+        .path(prompt)
+        .error("Could not set path")?
+        .build()
+        .await
+        .error("Failed to create Secret Service prompt proxy")?;
+    let mut completed = prompt
+        .receive_completed()
+        .await
+        .error("Failed to subscribe to the prompt")?;
+    prompt
+        .prompt("")
+        .await
+        .error("Failed to prompt for unlocking the keyring")?;
+    let completed = completed.next().await.error("Prompt closed unexpectedly")?;
+    let dismissed = completed.args().error("Invalid prompt result")?.dismissed;
+    if dismissed {
+        return Err(Error::new("Unlocking the keyring was dismissed"));
+    }
+    Ok(())
+}
+
+/// Parse `a=b,c=d` into attributes
+fn parse_attributes(spec: &str) -> Result<BTreeMap<&str, &str>> {
+    spec.split(',')
+        .map(|pair| {
+            pair.split_once('=').or_error(|| {
+                format!("Invalid secret '{spec}', expected 'secret:<attribute>=<value>'")
+            })
+        })
+        .collect()
+}
+
+fn visit(table: &toml::Table, f: &mut impl FnMut(&str)) {
+    fn visit_value(value: &toml::Value, f: &mut impl FnMut(&str)) {
+        match value {
+            toml::Value::String(s) => f(s),
+            toml::Value::Array(array) => array.iter().for_each(|v| visit_value(v, f)),
+            toml::Value::Table(table) => visit(table, f),
+            _ => (),
+        }
+    }
+    table.values().for_each(|v| visit_value(v, f));
+}
+
+fn visit_mut(table: &mut toml::Table, f: &mut impl FnMut(&mut String)) {
+    fn visit_value(value: &mut toml::Value, f: &mut impl FnMut(&mut String)) {
+        match value {
+            toml::Value::String(s) => f(s),
+            toml::Value::Array(array) => array.iter_mut().for_each(|v| visit_value(v, f)),
+            toml::Value::Table(table) => visit_mut(table, f),
+            _ => (),
+        }
+    }
+    table.iter_mut().for_each(|(_, v)| visit_value(v, f));
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Secret.Service",
+    default_service = "org.freedesktop.secrets",
+    default_path = "/org/freedesktop/secrets"
+)]
+trait Service {
+    fn open_session(
+        &self,
+        algorithm: &str,
+        input: &Value<'_>,
+    ) -> zbus::Result<(OwnedValue, OwnedObjectPath)>;
+
+    fn search_items(
+        &self,
+        attributes: BTreeMap<&str, &str>,
+    ) -> zbus::Result<(Vec<OwnedObjectPath>, Vec<OwnedObjectPath>)>;
+
+    fn unlock(
+        &self,
+        objects: &[&ObjectPath<'_>],
+    ) -> zbus::Result<(Vec<OwnedObjectPath>, OwnedObjectPath)>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Secret.Item",
+    default_service = "org.freedesktop.secrets"
+)]
+trait Item {
+    fn get_secret(
+        &self,
+        session: &ObjectPath<'_>,
+    ) -> zbus::Result<(OwnedObjectPath, Vec<u8>, Vec<u8>, String)>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Secret.Prompt",
+    default_service = "org.freedesktop.secrets"
+)]
+trait Prompt {
+    fn prompt(&self, window_id: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn completed(&self, dismissed: bool, result: Value<'_>) -> zbus::Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes() {
+        let attrs = parse_attributes("service=github,user=me").unwrap();
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(attrs["service"], "github");
+        assert_eq!(attrs["user"], "me");
+        assert!(parse_attributes("github").is_err());
+    }
+}
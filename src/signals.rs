@@ -1,5 +1,4 @@
 use futures::stream::StreamExt;
-use libc::{SIGRTMAX, SIGRTMIN};
 use signal_hook::consts::{SIGUSR1, SIGUSR2};
 use signal_hook_tokio::Signals;
 
@@ -12,9 +11,21 @@ pub enum Signal {
     Custom(i32),
 }
 
+/// The range of real-time signals
+#[cfg(target_os = "linux")]
+fn realtime_signals() -> (i32, i32) {
+    (libc::SIGRTMIN(), libc::SIGRTMAX())
+}
+
+/// The range of real-time signals, which `libc` does not define on the BSDs
+#[cfg(target_os = "freebsd")]
+fn realtime_signals() -> (i32, i32) {
+    (65, 126)
+}
+
 /// Returns an infinite stream of `Signal`s
 pub fn signals_stream() -> BoxedStream<Signal> {
-    let (sigmin, sigmax) = (SIGRTMIN(), SIGRTMAX());
+    let (sigmin, sigmax) = realtime_signals();
     let signals = Signals::new((sigmin..sigmax).chain([SIGUSR1, SIGUSR2])).unwrap();
     signals
         .map(move |signal| match signal {
@@ -37,3 +37,14 @@ pub async fn spawn_shell_sync(cmd: &str) -> io::Result<()> {
         .await?;
     Ok(())
 }
+
+/// Run a shell command and return its trimmed standard output
+pub async fn shell_output(cmd: &str) -> io::Result<String> {
+    let output = tokio::process::Command::new("sh")
+        .args(["-c", cmd])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
@@ -0,0 +1,41 @@
+//! Reading kernel state with `sysctl(3)`
+//!
+//! This is what `/proc` and `/sys` are used for on Linux.
+
+use std::ffi::CString;
+use std::mem::size_of;
+
+use crate::errors::*;
+
+/// Reads a fixed-size value, e.g. `u64` or `[c_long; 5]`
+pub fn read<T: Copy + Default>(name: &str) -> Result<T> {
+    let mut val = T::default();
+    let mut len = size_of::<T>();
+    sysctlbyname(name, (&mut val as *mut T).cast(), &mut len)?;
+    if len != size_of::<T>() {
+        return Err(Error::new(format!("sysctl '{name}' has unexpected size")));
+    }
+    Ok(val)
+}
+
+/// Reads an array of values of unknown length
+pub fn read_vec<T: Copy + Default>(name: &str) -> Result<Vec<T>> {
+    let mut len = 0;
+    sysctlbyname(name, std::ptr::null_mut(), &mut len)?;
+    let mut vals = vec![T::default(); len / size_of::<T>()];
+    len = vals.len() * size_of::<T>();
+    sysctlbyname(name, vals.as_mut_ptr().cast(), &mut len)?;
+    vals.truncate(len / size_of::<T>());
+    Ok(vals)
+}
+
+fn sysctlbyname(name: &str, buf: *mut libc::c_void, len: &mut usize) -> Result<()> {
+    let c_name = CString::new(name).error("sysctl name contains a nul byte")?;
+    // SAFETY: `buf` is either null or points to at least `len` writable bytes
+    let ret = unsafe { libc::sysctlbyname(c_name.as_ptr(), buf, len, std::ptr::null(), 0) };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error()).or_error(|| format!("sysctl '{name}' failed"))
+    }
+}
@@ -0,0 +1,108 @@
+//! Service supervision by systemd, using the `sd_notify` protocol
+//!
+//! When i3status-rs runs as a `Type=notify` service, it reports `READY=1` once every block has
+//! been rendered and, if `WatchdogSec` is set, sends `WATCHDOG=1` from the event loop. A bar whose
+//! event loop hangs is thus restarted by systemd. See sd_notify(3).
+
+#[cfg(target_os = "linux")]
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::time::Duration;
+
+use futures::StreamExt;
+
+use crate::BoxedStream;
+
+/// Send `READY=1` after this time even if some blocks have not displayed anything yet, so that
+/// systemd does not consider the startup failed
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    StartupTimeout,
+    Watchdog,
+}
+
+#[derive(Debug)]
+pub struct Notifier {
+    socket: UnixDatagram,
+    addr: SocketAddr,
+    ready: bool,
+}
+
+impl Notifier {
+    /// Connects to the socket in `$NOTIFY_SOCKET`. Returns `None` if the variable is not set, i.e.
+    /// if not running as a `Type=notify` service.
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var_os("NOTIFY_SOCKET")?;
+        let addr = match path.as_encoded_bytes().strip_prefix(b"@") {
+            #[cfg(target_os = "linux")]
+            Some(name) => SocketAddr::from_abstract_name(name),
+            #[cfg(not(target_os = "linux"))]
+            Some(_) => Err(std::io::Error::other("abstract sockets are not supported")),
+            None => SocketAddr::from_pathname(&path),
+        };
+        let result = addr.and_then(|addr| Ok((UnixDatagram::unbound()?, addr)));
+        match result {
+            Ok((socket, addr)) => Some(Self {
+                socket,
+                addr,
+                ready: false,
+            }),
+            Err(err) => {
+                log::warn!("Failed to connect to the systemd notification socket: {err}");
+                None
+            }
+        }
+    }
+
+    fn notify(&self, state: &str) {
+        if let Err(err) = self.socket.send_to_addr(state.as_bytes(), &self.addr) {
+            log::warn!("Failed to notify systemd: {err}");
+        }
+    }
+
+    /// Sends `READY=1`, unless it was already sent
+    pub fn notify_ready(&mut self) {
+        if !self.ready {
+            self.ready = true;
+            self.notify("READY=1");
+        }
+    }
+
+    pub fn notify_watchdog(&self) {
+        self.notify("WATCHDOG=1");
+    }
+
+    /// Returns the stream of events at which a notification is due
+    pub fn events(&self) -> BoxedStream<Event> {
+        let timeout = futures::stream::once(tokio::time::sleep(STARTUP_TIMEOUT))
+            .map(|()| Event::StartupTimeout);
+        match watchdog_interval() {
+            Some(interval) => {
+                let pings = futures::stream::unfold(
+                    tokio::time::interval(interval),
+                    |mut interval| async {
+                        interval.tick().await;
+                        Some((Event::Watchdog, interval))
+                    },
+                );
+                futures::stream::select(timeout, pings).boxed_local()
+            }
+            None => timeout.boxed_local(),
+        }
+    }
+}
+
+/// Returns how often the watchdog must be notified, which is half of the timeout recommended by
+/// sd_watchdog_enabled(3)
+fn watchdog_interval() -> Option<Duration> {
+    if let Some(pid) = std::env::var_os("WATCHDOG_PID") {
+        // The watchdog is meant for another process
+        if pid.to_str()?.parse::<u32>().ok()? != std::process::id() {
+            return None;
+        }
+    }
+    let usec: u64 = std::env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    (usec > 0).then(|| Duration::from_micros(usec) / 2)
+}
@@ -0,0 +1,28 @@
+//! Data sources for Android devices running Termux
+//!
+//! Android does not allow apps to use netlink or to read most of `/sys/class/power_supply`, so the
+//! `battery` and `net` blocks fall back to [Termux:API](https://wiki.termux.com/wiki/Termux:API).
+//! This requires the `termux-api` package and the Termux:API app.
+
+use std::process::Stdio;
+
+use serde::de::DeserializeOwned;
+use tokio::process::Command;
+
+use crate::errors::*;
+
+/// Whether i3status-rs is running under Termux
+pub fn is_termux() -> bool {
+    std::env::var_os("TERMUX_VERSION").is_some()
+}
+
+/// Runs a Termux:API command (e.g. `termux-battery-status`) and parses its JSON output
+pub async fn api<T: DeserializeOwned>(command: &str) -> Result<T> {
+    let output = Command::new(command)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .or_error(|| format!("Failed to run {command}"))?;
+    serde_json::from_slice(&output.stdout)
+        .or_error(|| format!("Failed to parse the output of {command}"))
+}
@@ -1,38 +1,65 @@
 pub mod color;
+pub mod color_scheme;
+pub mod palette;
 pub mod separator;
 pub mod xresources;
 
 use std::fmt;
 use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+use std::time::Instant;
 
 use serde::{de, Deserialize};
 
 use crate::errors::*;
+use crate::formatting::scheduling::Interval;
 use crate::util;
 use crate::widget::State;
-use color::Color;
+use crate::wrappers::{Seconds, ShellString};
+use color::{Color, TintMode};
+use color_scheme::ColorScheme;
 use separator::Separator;
 
+/// The default theme, used if it is not installed
+const PLAIN_THEME: &str = include_str!("../files/themes/plain.toml");
+
+/// The instant blinking blocks count their periods from, so that they blink in sync
+static BLINK_EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+
 #[derive(Debug, Clone)]
-pub struct Theme(pub ThemeInner);
+pub struct Theme {
+    inner: ThemeInner,
+    /// The themes used with a light or dark desktop, if configured
+    color_schemes: Option<Arc<ColorSchemeThemes>>,
+}
+
+#[derive(Debug)]
+struct ColorSchemeThemes {
+    light: ThemeInner,
+    dark: ThemeInner,
+}
 
 impl Default for Theme {
     fn default() -> Self {
         ThemeUserConfig::default()
             .try_into()
-            .unwrap_or_else(|_| Self(ThemeInner::default()))
+            .unwrap_or_else(|_| Self {
+                inner: ThemeInner::default(),
+                color_schemes: None,
+            })
     }
 }
 
 impl Deref for Theme {
     type Target = ThemeInner;
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.inner
     }
 }
 impl DerefMut for Theme {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.inner
     }
 }
 
@@ -54,11 +81,69 @@ pub struct ThemeInner {
     pub separator_fg: Color,
     pub alternating_tint_bg: Color,
     pub alternating_tint_fg: Color,
+    pub alternating_tint_mode: TintMode,
     pub end_separator: Separator,
     pub start_separator: Separator,
+    pub idle_border: Color,
+    pub info_border: Color,
+    pub good_border: Color,
+    pub warning_border: Color,
+    pub critical_border: Color,
+    /// The colors at the right edge of blocks whose background is a gradient
+    pub idle_bg_gradient: Color,
+    pub info_bg_gradient: Color,
+    pub good_bg_gradient: Color,
+    pub warning_bg_gradient: Color,
+    pub critical_bg_gradient: Color,
+    /// The colors of the separators at the edges of the bar. `separator_bg` and `separator_fg`
+    /// are used if unset.
+    pub start_separator_bg: Option<Color>,
+    pub start_separator_fg: Option<Color>,
+    pub end_separator_bg: Option<Color>,
+    pub end_separator_fg: Option<Color>,
+    /// The widths of the borders in pixels. The bar draws borders of one pixel if unset.
+    pub border_top: Option<usize>,
+    pub border_right: Option<usize>,
+    pub border_bottom: Option<usize>,
+    pub border_left: Option<usize>,
+    /// The separator between the widgets of a block. Its color is the text color of the widget if
+    /// `thin_separator_fg` is unset.
+    pub thin_separator: Option<String>,
+    pub thin_separator_fg: Option<Color>,
+    /// Blocks in the critical state alternate between their colors and the blink colors, which
+    /// are the critical colors swapped if unset
+    pub critical_blink_interval: Option<Seconds<false>>,
+    pub critical_blink_bg: Option<Color>,
+    pub critical_blink_fg: Option<Color>,
+    /// The colors of a block right after it was clicked
+    pub pressed_bg: Option<Color>,
+    pub pressed_fg: Option<Color>,
+    /// The number of spaces at the edges of a block and between its widgets. They replace the
+    /// whitespace there, e.g. the spaces at the start and the end of formats.
+    pub padding: Option<usize>,
+    pub spacing: Option<usize>,
 }
 
 impl Theme {
+    /// Whether this theme changes with the color scheme of the desktop
+    pub fn follows_color_scheme(&self) -> bool {
+        self.color_schemes.is_some()
+    }
+
+    /// The theme for `scheme`, without any overrides applied to this theme afterwards
+    pub fn for_color_scheme(&self, scheme: ColorScheme) -> Self {
+        match &self.color_schemes {
+            Some(themes) => Self {
+                inner: match scheme {
+                    ColorScheme::Light => themes.light.clone(),
+                    ColorScheme::Dark => themes.dark.clone(),
+                },
+                color_schemes: Some(themes.clone()),
+            },
+            None => self.clone(),
+        }
+    }
+
     pub fn get_colors(&self, state: State) -> (Color, Color) {
         match state {
             State::Idle => (self.idle_bg, self.idle_fg),
@@ -69,6 +154,52 @@ impl Theme {
         }
     }
 
+    /// How often blocks in `state` have to be redrawn to blink, if they blink
+    pub fn blink_interval(&self, state: State) -> Option<Interval> {
+        match state {
+            State::Critical => self.critical_blink_interval.map(|interval| Interval {
+                period: (interval.0.as_millis() as u64).max(1),
+                epoch: Some(*BLINK_EPOCH),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Same as `get_colors`, but with the blink colors every other period of a blinking state
+    pub fn get_colors_now(&self, state: State) -> (Color, Color) {
+        if let Some(interval) = self.blink_interval(state) {
+            // Rounded, since the redraws happen at about the start of each period
+            let elapsed = BLINK_EPOCH.elapsed().as_millis() as u64;
+            if (elapsed + interval.period / 2) / interval.period % 2 == 1 {
+                return (
+                    self.critical_blink_bg.unwrap_or(self.critical_fg),
+                    self.critical_blink_fg.unwrap_or(self.critical_bg),
+                );
+            }
+        }
+        self.get_colors(state)
+    }
+
+    pub fn get_bg_gradient(&self, state: State) -> Color {
+        match state {
+            State::Idle => self.idle_bg_gradient,
+            State::Info => self.info_bg_gradient,
+            State::Good => self.good_bg_gradient,
+            State::Warning => self.warning_bg_gradient,
+            State::Critical => self.critical_bg_gradient,
+        }
+    }
+
+    pub fn get_border(&self, state: State) -> Color {
+        match state {
+            State::Idle => self.idle_border,
+            State::Info => self.info_border,
+            State::Good => self.good_border,
+            State::Warning => self.warning_border,
+            State::Critical => self.critical_border,
+        }
+    }
+
     pub fn apply_overrides(&mut self, overrides: ThemeOverrides) -> Result<()> {
         let copy = self.clone();
 
@@ -81,6 +212,33 @@ impl Theme {
         if let Some(start_separator) = overrides.start_separator {
             self.start_separator = start_separator;
         }
+        if let Some(thin_separator) = overrides.thin_separator {
+            self.thin_separator = Some(thin_separator);
+        }
+        if let Some(interval) = overrides.critical_blink_interval {
+            self.critical_blink_interval = Some(interval);
+        }
+        if let Some(mode) = overrides.alternating_tint_mode {
+            self.alternating_tint_mode = mode;
+        }
+        if let Some(width) = overrides.border_top {
+            self.border_top = Some(width);
+        }
+        if let Some(width) = overrides.border_right {
+            self.border_right = Some(width);
+        }
+        if let Some(width) = overrides.border_bottom {
+            self.border_bottom = Some(width);
+        }
+        if let Some(width) = overrides.border_left {
+            self.border_left = Some(width);
+        }
+        if let Some(padding) = overrides.padding {
+            self.padding = Some(padding);
+        }
+        if let Some(spacing) = overrides.spacing {
+            self.spacing = Some(spacing);
+        }
 
         macro_rules! apply {
             ($prop:tt) => {
@@ -103,6 +261,69 @@ impl Theme {
         apply!(separator_fg);
         apply!(alternating_tint_bg);
         apply!(alternating_tint_fg);
+        apply!(idle_border);
+        apply!(info_border);
+        apply!(good_border);
+        apply!(warning_border);
+        apply!(critical_border);
+        apply!(idle_bg_gradient);
+        apply!(info_bg_gradient);
+        apply!(good_bg_gradient);
+        apply!(warning_bg_gradient);
+        apply!(critical_bg_gradient);
+
+        macro_rules! apply_optional {
+            ($prop:tt) => {
+                if let Some(color) = overrides.$prop {
+                    self.$prop = Some(color.eval(&copy)?);
+                }
+            };
+        }
+        apply_optional!(start_separator_bg);
+        apply_optional!(start_separator_fg);
+        apply_optional!(end_separator_bg);
+        apply_optional!(end_separator_fg);
+        apply_optional!(thin_separator_fg);
+        apply_optional!(critical_blink_bg);
+        apply_optional!(critical_blink_fg);
+        apply_optional!(pressed_bg);
+        apply_optional!(pressed_fg);
+
+        macro_rules! apply_state {
+            ($state:tt, $bg:tt, $fg:tt, $border:tt, $bg_gradient:tt) => {
+                if let Some(state) = overrides.$state {
+                    if let Some(color) = state.bg {
+                        self.$bg = color.eval(&copy)?;
+                    }
+                    if let Some(color) = state.fg {
+                        self.$fg = color.eval(&copy)?;
+                    }
+                    if let Some(color) = state.border {
+                        self.$border = color.eval(&copy)?;
+                    }
+                    if let Some(color) = state.bg_gradient {
+                        self.$bg_gradient = color.eval(&copy)?;
+                    }
+                }
+            };
+        }
+        apply_state!(idle, idle_bg, idle_fg, idle_border, idle_bg_gradient);
+        apply_state!(info, info_bg, info_fg, info_border, info_bg_gradient);
+        apply_state!(good, good_bg, good_fg, good_border, good_bg_gradient);
+        apply_state!(
+            warning,
+            warning_bg,
+            warning_fg,
+            warning_border,
+            warning_bg_gradient
+        );
+        apply_state!(
+            critical,
+            critical_bg,
+            critical_fg,
+            critical_border,
+            critical_bg_gradient
+        );
 
         Ok(())
     }
@@ -112,6 +333,20 @@ impl Theme {
 #[serde(deny_unknown_fields, default)]
 pub struct ThemeUserConfig {
     pub theme: Option<String>,
+    /// A base16 or pywal color scheme to use instead of `theme`
+    pub from_file: Option<ShellString>,
+    pub overrides: Option<ThemeOverrides>,
+    pub light: Option<ColorSchemeConfig>,
+    pub dark: Option<ColorSchemeConfig>,
+}
+
+/// The theme used with a light or dark desktop. `theme` and `from_file` default to the main
+/// theme, and `overrides` are applied after the main overrides.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct ColorSchemeConfig {
+    pub theme: Option<String>,
+    pub from_file: Option<ShellString>,
     pub overrides: Option<ThemeOverrides>,
 }
 
@@ -132,23 +367,119 @@ pub struct ThemeOverrides {
     pub separator_fg: Option<ColorOrLink>,
     pub alternating_tint_bg: Option<ColorOrLink>,
     pub alternating_tint_fg: Option<ColorOrLink>,
+    pub alternating_tint_mode: Option<TintMode>,
     pub end_separator: Option<Separator>,
     pub start_separator: Option<Separator>,
+    pub idle_border: Option<ColorOrLink>,
+    pub info_border: Option<ColorOrLink>,
+    pub good_border: Option<ColorOrLink>,
+    pub warning_border: Option<ColorOrLink>,
+    pub critical_border: Option<ColorOrLink>,
+    pub idle_bg_gradient: Option<ColorOrLink>,
+    pub info_bg_gradient: Option<ColorOrLink>,
+    pub good_bg_gradient: Option<ColorOrLink>,
+    pub warning_bg_gradient: Option<ColorOrLink>,
+    pub critical_bg_gradient: Option<ColorOrLink>,
+    pub start_separator_bg: Option<ColorOrLink>,
+    pub start_separator_fg: Option<ColorOrLink>,
+    pub end_separator_bg: Option<ColorOrLink>,
+    pub end_separator_fg: Option<ColorOrLink>,
+    pub border_top: Option<usize>,
+    pub border_right: Option<usize>,
+    pub border_bottom: Option<usize>,
+    pub border_left: Option<usize>,
+    pub thin_separator: Option<String>,
+    pub thin_separator_fg: Option<ColorOrLink>,
+    pub critical_blink_interval: Option<Seconds<false>>,
+    pub critical_blink_bg: Option<ColorOrLink>,
+    pub critical_blink_fg: Option<ColorOrLink>,
+    pub pressed_bg: Option<ColorOrLink>,
+    pub pressed_fg: Option<ColorOrLink>,
+    pub padding: Option<usize>,
+    pub spacing: Option<usize>,
+    pub idle: Option<StateOverrides>,
+    pub info: Option<StateOverrides>,
+    pub good: Option<StateOverrides>,
+    pub warning: Option<StateOverrides>,
+    pub critical: Option<StateOverrides>,
+}
+
+/// The overrides of a single state, e.g. `warning = { bg = "#ff0000" }`. They take precedence
+/// over the flat keys such as `warning_bg`.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct StateOverrides {
+    pub bg: Option<ColorOrLink>,
+    pub fg: Option<ColorOrLink>,
+    pub border: Option<ColorOrLink>,
+    pub bg_gradient: Option<ColorOrLink>,
 }
 
 impl TryFrom<ThemeUserConfig> for Theme {
     type Error = Error;
 
     fn try_from(user_config: ThemeUserConfig) -> Result<Self, Self::Error> {
-        let name = user_config.theme.as_deref().unwrap_or("plain");
-        let file = util::find_file(name, Some("themes"), Some("toml"))
-            .or_error(|| format!("Theme '{name}' not found"))?;
-        let theme: ThemeInner = util::deserialize_toml_file(file)?;
-        let mut theme = Theme(theme);
-        if let Some(overrides) = user_config.overrides {
-            theme.apply_overrides(overrides)?;
-        }
-        Ok(theme)
+        let load = |name: Option<&str>,
+                    from_file: Option<&ShellString>,
+                    overrides: [Option<ThemeOverrides>; 2]|
+         -> Result<ThemeInner> {
+            let theme = match (name, from_file) {
+                (Some(_), Some(_)) => {
+                    return Err(Error::new("Only one of 'theme' and 'from_file' can be set"));
+                }
+                (_, Some(file)) => palette::load(Path::new(&*file.expand()?))?,
+                (name, None) => {
+                    let name = name.unwrap_or("plain");
+                    match util::find_file(name, Some("themes"), Some("toml")) {
+                        Some(file) => util::deserialize_toml_file(file)?,
+                        // The default theme works even if the theme files are not installed
+                        None if name == "plain" => util::deserialize_toml_str(
+                            PLAIN_THEME,
+                            Path::new("<built-in plain theme>"),
+                        )?,
+                        None => return Err(Error::new(format!("Theme '{name}' not found"))),
+                    }
+                }
+            };
+            let mut theme = Theme {
+                inner: theme,
+                color_schemes: None,
+            };
+            for overrides in overrides.into_iter().flatten() {
+                theme.apply_overrides(overrides)?;
+            }
+            Ok(theme.inner)
+        };
+
+        let name = user_config.theme.as_deref();
+        let from_file = user_config.from_file.as_ref();
+        let inner = load(name, from_file, [user_config.overrides.clone(), None])?;
+        let color_schemes = if user_config.light.is_some() || user_config.dark.is_some() {
+            let scheme = |config: Option<ColorSchemeConfig>| match config {
+                // A scheme either picks its own theme or uses the main one
+                Some(config) if config.theme.is_some() || config.from_file.is_some() => load(
+                    config.theme.as_deref(),
+                    config.from_file.as_ref(),
+                    [user_config.overrides.clone(), config.overrides],
+                ),
+                Some(config) => load(
+                    name,
+                    from_file,
+                    [user_config.overrides.clone(), config.overrides],
+                ),
+                None => Ok(inner.clone()),
+            };
+            Some(Arc::new(ColorSchemeThemes {
+                light: scheme(user_config.light)?,
+                dark: scheme(user_config.dark)?,
+            }))
+        } else {
+            None
+        };
+        Ok(Theme {
+            inner,
+            color_schemes,
+        })
     }
 }
 
@@ -177,6 +508,16 @@ impl ColorOrLink {
                 "separator_fg" => theme.separator_fg,
                 "alternating_tint_bg" => theme.alternating_tint_bg,
                 "alternating_tint_fg" => theme.alternating_tint_fg,
+                "idle_border" => theme.idle_border,
+                "info_border" => theme.info_border,
+                "good_border" => theme.good_border,
+                "warning_border" => theme.warning_border,
+                "critical_border" => theme.critical_border,
+                "idle_bg_gradient" => theme.idle_bg_gradient,
+                "info_bg_gradient" => theme.info_bg_gradient,
+                "good_bg_gradient" => theme.good_bg_gradient,
+                "warning_bg_gradient" => theme.warning_bg_gradient,
+                "critical_bg_gradient" => theme.critical_bg_gradient,
                 _ => return Err(Error::new(format!("{link} is not a correct theme color"))),
             },
         })
@@ -165,6 +165,21 @@ pub fn approx(a: f64, b: f64) -> bool {
     }
 }
 
+/// How the alternating tint is applied to the colors of every second block
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TintMode {
+    /// Add the tint to each channel, which always brightens the color
+    #[default]
+    Add,
+    /// Multiply each channel with the tint, which always darkens the color
+    Multiply,
+    /// Darken dark colors and brighten light colors, increasing the contrast
+    Overlay,
+    /// Use the tint instead of the color
+    Replace,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, SmartDefault)]
 pub enum Color {
     #[default]
@@ -178,6 +193,74 @@ impl Color {
     pub fn skip_ser(&self) -> bool {
         matches!(self, Self::None | Self::Auto)
     }
+
+    /// Returns the color in the `#RRGGBBAA` notation, or `None` if it is not set
+    pub fn to_hex(self) -> Option<String> {
+        let rgba = match self {
+            Self::None | Self::Auto => return None,
+            Self::Rgba(rgba) => rgba,
+            Self::Hsva(hsva) => hsva.into(),
+        };
+        Some(format!(
+            "#{:02X}{:02X}{:02X}{:02X}",
+            rgba.r, rgba.g, rgba.b, rgba.a
+        ))
+    }
+
+    /// Applies `tint` to this color. Except in `add` mode, the alpha channel of this color is
+    /// preserved and colors which are not set are not tinted.
+    pub fn tint(self, tint: Color, mode: TintMode) -> Color {
+        let blend = |f: fn(u8, u8) -> u8| match (self, tint) {
+            (Self::Rgba(_) | Self::Hsva(_), Self::Rgba(_) | Self::Hsva(_)) => {
+                let (base, tint) = (self.to_rgba(), tint.to_rgba());
+                Color::Rgba(Rgba::new(
+                    f(base.r, tint.r),
+                    f(base.g, tint.g),
+                    f(base.b, tint.b),
+                    base.a,
+                ))
+            }
+            _ => self,
+        };
+        match mode {
+            TintMode::Add => self + tint,
+            TintMode::Multiply => blend(|base, tint| (base as u16 * tint as u16 / 255) as u8),
+            TintMode::Overlay => blend(|base, tint| {
+                let (base, tint) = (base as u16, tint as u16);
+                if base < 128 {
+                    (2 * base * tint / 255) as u8
+                } else {
+                    (255 - 2 * (255 - base) * (255 - tint) / 255) as u8
+                }
+            }),
+            TintMode::Replace if tint.skip_ser() => self,
+            TintMode::Replace => tint,
+        }
+    }
+
+    /// Returns the color at `t` (between `0` and `1`) of a gradient from this color to `other`.
+    /// Colors which are not set are not blended.
+    pub fn blend(self, other: Color, t: f64) -> Color {
+        if self.skip_ser() || other.skip_ser() {
+            return self;
+        }
+        let (a, b) = (self.to_rgba(), other.to_rgba());
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Color::Rgba(Rgba::new(
+            mix(a.r, b.r),
+            mix(a.g, b.g),
+            mix(a.b, b.b),
+            mix(a.a, b.a),
+        ))
+    }
+
+    fn to_rgba(self) -> Rgba {
+        match self {
+            Self::Hsva(hsva) => hsva.into(),
+            Self::Rgba(rgba) => rgba,
+            Self::None | Self::Auto => Rgba::default(),
+        }
+    }
 }
 
 impl Add for Color {
@@ -239,12 +322,9 @@ impl Serialize for Color {
     where
         S: Serializer,
     {
-        let format_rgba =
-            |rgba: Rgba| format!("#{:02X}{:02X}{:02X}{:02X}", rgba.r, rgba.g, rgba.b, rgba.a);
-        match *self {
-            Self::None | Self::Auto => serializer.serialize_none(),
-            Self::Rgba(rgba) => serializer.serialize_str(&format_rgba(rgba)),
-            Self::Hsva(hsva) => serializer.serialize_str(&format_rgba(hsva.into())),
+        match self.to_hex() {
+            Some(hex) => serializer.serialize_str(&hex),
+            None => serializer.serialize_none(),
         }
     }
 }
@@ -274,3 +354,41 @@ impl<'de> Deserialize<'de> for Color {
         deserializer.deserialize_any(ColorVisitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tint_modes() {
+        let base = Color::Rgba(Rgba::from_hex(0xf0f0_10ff));
+        let tint = Color::Rgba(Rgba::from_hex(0x8080_8000));
+        let rgba = |color: Color| color.to_rgba();
+
+        assert_eq!(
+            rgba(base.tint(tint, TintMode::Add)),
+            Rgba::from_hex(0xffff_90ff)
+        );
+        assert_eq!(
+            rgba(base.tint(tint, TintMode::Multiply)),
+            Rgba::from_hex(0x7878_08ff)
+        );
+        assert_eq!(
+            rgba(base.tint(tint, TintMode::Overlay)),
+            Rgba::from_hex(0xf1f1_10ff)
+        );
+        assert_eq!(base.tint(tint, TintMode::Replace), tint);
+        assert_eq!(base.tint(Color::None, TintMode::Replace), base);
+        assert_eq!(Color::None.tint(tint, TintMode::Multiply), Color::None);
+    }
+
+    #[test]
+    fn blend() {
+        let a = Color::Rgba(Rgba::from_hex(0x0000_00ff));
+        let b = Color::Rgba(Rgba::from_hex(0xff80_2000));
+        assert_eq!(a.blend(b, 0.0), a);
+        assert_eq!(a.blend(b, 1.0), b);
+        assert_eq!(a.blend(b, 0.5).to_rgba(), Rgba::from_hex(0x8040_1080));
+        assert_eq!(a.blend(Color::None, 0.5), a);
+    }
+}
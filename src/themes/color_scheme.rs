@@ -0,0 +1,93 @@
+//! The color scheme preferred by the desktop
+//!
+//! The preference is read from the `org.freedesktop.appearance` `color-scheme` setting of the
+//! [XDG desktop portal](https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.Settings.html).
+
+use futures::{stream, StreamExt};
+use log::debug;
+use zbus::zvariant::{OwnedValue, Value};
+
+use crate::errors::*;
+use crate::util::new_dbus_connection;
+use crate::BoxedStream;
+
+const NAMESPACE: &str = "org.freedesktop.appearance";
+const KEY: &str = "color-scheme";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    /// Interprets a value of the `color-scheme` setting. Both "no preference" (0) and "prefer
+    /// light" (2) mean light.
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        match value {
+            // `Read` wraps the value in another variant
+            Value::Value(value) => Self::from_value(value),
+            Value::U32(1) => Some(Self::Dark),
+            Value::U32(_) => Some(Self::Light),
+            _ => None,
+        }
+    }
+}
+
+/// A stream which yields the current color scheme and then every change. It ends immediately if
+/// the portal is not available.
+pub fn color_scheme_stream() -> BoxedStream<ColorScheme> {
+    stream::once(async {
+        match subscribe().await {
+            Ok(changes) => changes,
+            Err(error) => {
+                debug!("could not read the color scheme: {error}");
+                stream::empty().boxed_local()
+            }
+        }
+    })
+    .flatten()
+    .boxed_local()
+}
+
+async fn subscribe() -> Result<BoxedStream<ColorScheme>> {
+    let dbus_conn = new_dbus_connection().await?;
+    let proxy = SettingsProxy::new(&dbus_conn)
+        .await
+        .error("Failed to create SettingsProxy")?;
+    // Subscribe before reading, so that no change is missed
+    let changes = proxy
+        .receive_setting_changed_with_args(&[(0, NAMESPACE), (1, KEY)])
+        .await
+        .error("Failed to subscribe to SettingChanged")?;
+    let current = match proxy.read_one(NAMESPACE, KEY).await {
+        Ok(value) => value,
+        // `ReadOne` was added in version 2 of the interface
+        Err(_) => proxy
+            .read(NAMESPACE, KEY)
+            .await
+            .error("Failed to read the color scheme")?,
+    };
+    let current = ColorScheme::from_value(&current);
+    let changes = changes.filter_map(|signal| async move {
+        signal
+            .args()
+            .ok()
+            .and_then(|args| ColorScheme::from_value(&args.value))
+    });
+    Ok(stream::iter(current).chain(changes).boxed_local())
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.portal.Settings",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait Settings {
+    fn read(&self, namespace: &str, key: &str) -> zbus::Result<OwnedValue>;
+
+    fn read_one(&self, namespace: &str, key: &str) -> zbus::Result<OwnedValue>;
+
+    #[zbus(signal)]
+    fn setting_changed(&self, namespace: &str, key: &str, value: Value<'_>) -> zbus::Result<()>;
+}
@@ -0,0 +1,195 @@
+//! Themes generated from the color schemes of other programs
+//!
+//! Two formats are supported:
+//! - [base16](https://github.com/tinted-theming/home) schemes in YAML, both the legacy format
+//!   (`base00: "282828"`) and the current one with a `palette` table
+//! - the `colors.json` written by [pywal](https://github.com/dylanaraps/pywal) (and compatible
+//!   tools such as wallust), usually at `~/.cache/wal/colors.json`
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use super::color::Color;
+use super::ThemeInner;
+use crate::errors::*;
+
+/// The colors of a scheme which the theme uses
+#[derive(Debug, PartialEq)]
+struct Palette {
+    background: Color,
+    foreground: Color,
+    red: Color,
+    green: Color,
+    yellow: Color,
+    blue: Color,
+}
+
+impl Palette {
+    fn from_base16(yaml: &str) -> Result<Self> {
+        // The scheme is a flat list of `baseXX: "rrggbb"`, either at the top level or in `palette`
+        let colors: HashMap<&str, &str> = yaml
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(key, value)| {
+                let value = value.split(" #").next().unwrap_or_default().trim();
+                (
+                    key.trim(),
+                    value.trim_matches(['"', '\'']).trim_start_matches('#'),
+                )
+            })
+            .filter(|(key, _)| key.starts_with("base"))
+            .collect();
+        let color = |key: &str| -> Result<Color> {
+            let value = colors
+                .get(key)
+                .or_error(|| format!("'{key}' is missing in the base16 scheme"))?;
+            format!("#{value}")
+                .parse()
+                .or_error(|| format!("Invalid color '{value}' for '{key}'"))
+        };
+        Ok(Self {
+            background: color("base00")?,
+            foreground: color("base05")?,
+            red: color("base08")?,
+            green: color("base0B")?,
+            yellow: color("base0A")?,
+            blue: color("base0D")?,
+        })
+    }
+
+    fn from_pywal(json: &str) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Pywal {
+            special: Special,
+            colors: HashMap<String, Color>,
+        }
+        #[derive(Deserialize)]
+        struct Special {
+            background: Color,
+            foreground: Color,
+        }
+        let pywal: Pywal = serde_json::from_str(json).error("Invalid pywal colors")?;
+        let color = |key: &str| -> Result<Color> {
+            pywal
+                .colors
+                .get(key)
+                .copied()
+                .or_error(|| format!("'{key}' is missing in the pywal colors"))
+        };
+        Ok(Self {
+            background: pywal.special.background,
+            foreground: pywal.special.foreground,
+            red: color("color1")?,
+            green: color("color2")?,
+            yellow: color("color3")?,
+            blue: color("color4")?,
+        })
+    }
+
+    /// Idle blocks use the colors of the terminal, the other states the accent colors with the
+    /// background color as text
+    fn into_theme(self) -> ThemeInner {
+        ThemeInner {
+            idle_bg: self.background,
+            idle_fg: self.foreground,
+            info_bg: self.blue,
+            info_fg: self.background,
+            good_bg: self.green,
+            good_fg: self.background,
+            warning_bg: self.yellow,
+            warning_fg: self.background,
+            critical_bg: self.red,
+            critical_fg: self.background,
+            separator_bg: Color::Auto,
+            separator_fg: Color::Auto,
+            ..Default::default()
+        }
+    }
+}
+
+/// Loads a base16 (`.yaml` or `.yml`) or pywal (`.json`) color scheme as a theme
+pub fn load(path: &Path) -> Result<ThemeInner> {
+    let contents = std::fs::read_to_string(path)
+        .or_error(|| format!("Failed to read color scheme '{}'", path.display()))?;
+    let palette = match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml" | "yml") => Palette::from_base16(&contents),
+        Some("json") => Palette::from_pywal(&contents),
+        _ => Err(Error::new(
+            "Unknown color scheme format, expected a base16 '.yaml' or pywal '.json' file",
+        )),
+    }
+    .or_error(|| format!("Failed to load color scheme '{}'", path.display()))?;
+    Ok(palette.into_theme())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gruvbox() -> Palette {
+        let color = |c: &str| c.parse().unwrap();
+        Palette {
+            background: color("#282828"),
+            foreground: color("#d5c4a1"),
+            red: color("#fb4934"),
+            green: color("#b8bb26"),
+            yellow: color("#fabd2f"),
+            blue: color("#83a598"),
+        }
+    }
+
+    #[test]
+    fn base16() {
+        let legacy = r#"
+scheme: "Gruvbox dark, medium"
+author: "Dawid Kurek (dawikur@gmail.com)"
+base00: "282828" # ----
+base01: "3c3836" # ---
+base05: "d5c4a1" # +
+base08: "fb4934" # red
+base0A: "fabd2f" # yellow
+base0B: "b8bb26" # green
+base0D: "83a598" # blue
+"#;
+        assert_eq!(Palette::from_base16(legacy).unwrap(), gruvbox());
+
+        let current = r##"
+system: "base16"
+name: "Gruvbox dark, medium"
+variant: "dark"
+palette:
+  base00: "#282828"
+  base05: "#d5c4a1"
+  base08: "#fb4934"
+  base0A: "#fabd2f"
+  base0B: "#b8bb26"
+  base0D: "#83a598"
+"##;
+        assert_eq!(Palette::from_base16(current).unwrap(), gruvbox());
+
+        assert!(Palette::from_base16("base00: \"282828\"").is_err());
+    }
+
+    #[test]
+    fn pywal() {
+        let json = r##"{
+            "wallpaper": "/home/user/wallpaper.png",
+            "alpha": "100",
+            "special": {
+                "background": "#282828",
+                "foreground": "#d5c4a1",
+                "cursor": "#d5c4a1"
+            },
+            "colors": {
+                "color0": "#282828",
+                "color1": "#fb4934",
+                "color2": "#b8bb26",
+                "color3": "#fabd2f",
+                "color4": "#83a598"
+            }
+        }"##;
+        assert_eq!(Palette::from_pywal(json).unwrap(), gruvbox());
+    }
+}
@@ -73,15 +73,23 @@ fn exists_with_opt_extension(file: &Path, extension: Option<&str>) -> Option<Pat
     None
 }
 
+// Share DBus connections between all blocks (and bars)
+static DBUS_SESSION: tokio::sync::OnceCell<zbus::Connection> = tokio::sync::OnceCell::const_new();
+static DBUS_SYSTEM: tokio::sync::OnceCell<zbus::Connection> = tokio::sync::OnceCell::const_new();
+
 pub async fn new_dbus_connection() -> Result<zbus::Connection> {
-    zbus::Connection::session()
+    DBUS_SESSION
+        .get_or_try_init(zbus::Connection::session)
         .await
+        .cloned()
         .error("Failed to open DBus session connection")
 }
 
 pub async fn new_system_dbus_connection() -> Result<zbus::Connection> {
-    zbus::Connection::system()
+    DBUS_SYSTEM
+        .get_or_try_init(zbus::Connection::system)
         .await
+        .cloned()
         .error("Failed to open DBus system connection")
 }
 
@@ -95,7 +103,16 @@ where
     let contents = std::fs::read_to_string(path)
         .or_error(|| format!("Failed to read file: {}", path.display()))?;
 
-    toml::from_str(&contents).map_err(|err| {
+    deserialize_toml_str(&contents, path)
+}
+
+/// Like [`deserialize_toml_file`], but for contents which are already read. `path` is used in
+/// error messages only.
+pub fn deserialize_toml_str<T>(contents: &str, path: &Path) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    toml::from_str(contents).map_err(|err| {
         let location_msg = err
             .span()
             .map(|span| {
@@ -1,7 +1,12 @@
 use crate::config::SharedConfig;
 use crate::errors::*;
+use crate::formatting::cache::RenderCache;
+use crate::formatting::scheduling::Interval;
+use crate::formatting::template::FormatTemplate;
+use crate::formatting::value::Value;
 use crate::formatting::{Format, Fragment, Values};
 use crate::protocol::i3bar_block::I3BarBlock;
+use crate::themes::Theme;
 use serde::Deserialize;
 use smart_default::SmartDefault;
 
@@ -10,6 +15,7 @@ pub struct Widget {
     pub state: State,
     source: Source,
     values: Values,
+    cache: RenderCache,
 }
 
 impl Widget {
@@ -50,13 +56,43 @@ impl Widget {
 
     pub fn set_format(&mut self, format: Format) {
         self.source = Source::Format(format);
+        self.cache.clear();
+    }
+
+    /// Overrides the full text of a formatted widget
+    pub fn set_full_format(&mut self, full: FormatTemplate) {
+        if let Source::Format(format) = &mut self.source {
+            format.set_full(full);
+            self.cache.clear();
+        }
+    }
+
+    /// The narrower alternatives of the full text of a formatted widget
+    pub fn narrower_formats(&self) -> &[FormatTemplate] {
+        match &self.source {
+            Source::Format(format) => format.narrower(),
+            _ => &[],
+        }
     }
 
     pub fn set_values(&mut self, new_values: Values) {
         self.values = new_values;
+        self.cache.clear();
+    }
+
+    /// Sets a single value in addition to the values of the block
+    pub fn set_value(&mut self, key: &'static str, value: Value) {
+        self.values.insert(key, value);
+        self.cache.clear();
     }
 
-    pub fn intervals(&self) -> Vec<u64> {
+    pub fn unset_value(&mut self, key: &'static str) {
+        if self.values.remove(key).is_some() {
+            self.cache.clear();
+        }
+    }
+
+    pub fn intervals(&self) -> Vec<Interval> {
         match &self.source {
             Source::Format(f) => f.intervals(),
             _ => Vec::new(),
@@ -66,14 +102,19 @@ impl Widget {
     /// Construct `I3BarBlock` from this widget
     pub fn get_data(&self, shared_config: &SharedConfig, id: usize) -> Result<Vec<I3BarBlock>> {
         // Create a "template" block
-        let (key_bg, key_fg) = shared_config.theme.get_colors(self.state);
-        let (full, short) = self.source.render(shared_config, &self.values)?;
+        let theme = &shared_config.theme;
+        let (key_bg, key_fg) = theme.get_colors_now(self.state);
+        let (full, short) = self
+            .source
+            .render(shared_config, &self.values, &self.cache)?;
         let mut template = I3BarBlock {
             instance: format!("{id}:"),
             background: key_bg,
             color: key_fg,
+            background_gradient: theme.get_bg_gradient(self.state),
             ..I3BarBlock::default()
         };
+        set_border(&mut template, theme, self.state);
 
         // Collect all the pieces into "parts"
         let mut parts = Vec::new();
@@ -93,8 +134,13 @@ impl Widget {
         parts.extend(full.into_iter().map(|w| {
             let mut data = template.clone();
             data.full_text = w.formatted_text();
-            if let Some(i) = &w.metadata.instance {
-                data.instance.push_str(i);
+            if let Some(i) = w.metadata.instance {
+                data.instance.push_str(i.as_str());
+            }
+            if let Some(state) = w.metadata.state {
+                (data.background, data.color) = theme.get_colors(state);
+                data.background_gradient = theme.get_bg_gradient(state);
+                set_border(&mut data, theme, state);
             }
             data
         }));
@@ -103,16 +149,98 @@ impl Widget {
         parts.extend(short.into_iter().map(|w| {
             let mut data = template.clone();
             data.short_text = w.formatted_text();
-            if let Some(i) = &w.metadata.instance {
-                data.instance.push_str(i);
+            if let Some(i) = w.metadata.instance {
+                data.instance.push_str(i.as_str());
+            }
+            if let Some(state) = w.metadata.state {
+                (data.background, data.color) = theme.get_colors(state);
+                data.background_gradient = theme.get_bg_gradient(state);
+                set_border(&mut data, theme, state);
             }
             data
         }));
 
+        apply_spacing(&mut parts, theme, |part| &mut part.full_text);
+        apply_spacing(&mut parts, theme, |part| &mut part.short_text);
+
         Ok(parts)
     }
 }
 
+/// Replaces the whitespace at the edges of a block with the `padding` of the theme and the
+/// whitespace between its widgets with the `spacing`. With a thin separator, the spacing is added
+/// on both of its sides. Widgets which only consist of whitespace are removed where the theme
+/// controls the spacing.
+fn apply_spacing(
+    parts: &mut [I3BarBlock],
+    theme: &Theme,
+    text: fn(&mut I3BarBlock) -> &mut String,
+) {
+    if theme.padding.is_none() && theme.spacing.is_none() {
+        return;
+    }
+    let is_widget = |text: &str| !text.trim().is_empty() && text != "<span/>";
+    let widgets: Vec<usize> = (0..parts.len())
+        .filter(|&i| is_widget(text(&mut parts[i])))
+        .collect();
+    let (Some(&first), Some(&last)) = (widgets.first(), widgets.last()) else {
+        return;
+    };
+
+    for (i, part) in parts.iter_mut().enumerate() {
+        let text = text(part);
+        let outside = i < first || i > last;
+        let controlled = if outside {
+            theme.padding.is_some()
+        } else {
+            theme.spacing.is_some()
+        };
+        if controlled && !text.is_empty() && text.trim().is_empty() {
+            text.clear();
+        }
+    }
+
+    if let Some(spacing) = theme.spacing {
+        let spacing = " ".repeat(spacing);
+        let start = if theme.thin_separator.is_some() {
+            spacing.as_str()
+        } else {
+            ""
+        };
+        for pair in widgets.windows(2) {
+            set_end(text(&mut parts[pair[0]]), &spacing);
+            set_start(text(&mut parts[pair[1]]), start);
+        }
+    }
+    if let Some(padding) = theme.padding {
+        let padding = " ".repeat(padding);
+        set_start(text(&mut parts[first]), &padding);
+        set_end(text(&mut parts[last]), &padding);
+    }
+}
+
+fn set_start(text: &mut String, start: &str) {
+    let whitespace = text.len() - text.trim_start().len();
+    text.replace_range(..whitespace, start);
+}
+
+fn set_end(text: &mut String, end: &str) {
+    text.truncate(text.trim_end().len());
+    text.push_str(end);
+}
+
+/// Sets the border of `block` to the border of `state`, if the theme has one. Widths without a
+/// border color would draw the default border of the bar, so they are only set with a color.
+fn set_border(block: &mut I3BarBlock, theme: &Theme, state: State) {
+    if let Some(border) = theme.get_border(state).to_hex() {
+        block.border = Some(border);
+        block.border_top = theme.border_top;
+        block.border_right = theme.border_right;
+        block.border_bottom = theme.border_bottom;
+        block.border_left = theme.border_left;
+    }
+}
+
 /// State of the widget. Affects the theming.
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, SmartDefault)]
 pub enum State {
@@ -146,11 +274,50 @@ impl Source {
         &self,
         config: &SharedConfig,
         values: &Values,
+        cache: &RenderCache,
     ) -> Result<(Vec<Fragment>, Vec<Fragment>)> {
         match self {
             Self::Text(text) => Ok((vec![text.clone().into()], vec![])),
-            Self::Format(format) => format.render(values, config),
+            Self::Format(format) => format.render(values, config, cache),
             Self::None => Ok((vec![], vec![])),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spaced(texts: &[&str], padding: Option<usize>, spacing: Option<usize>) -> Vec<String> {
+        let mut theme = Theme::default();
+        theme.padding = padding;
+        theme.spacing = spacing;
+        let mut parts: Vec<_> = texts
+            .iter()
+            .map(|text| I3BarBlock {
+                full_text: (*text).into(),
+                ..Default::default()
+            })
+            .collect();
+        apply_spacing(&mut parts, &theme, |part| &mut part.full_text);
+        parts.into_iter().map(|part| part.full_text).collect()
+    }
+
+    #[test]
+    fn spacing() {
+        let texts = [" ", "a ", " ", "b", "  c ", " "];
+        assert_eq!(spaced(&texts, None, None), texts);
+        assert_eq!(
+            spaced(&texts, Some(2), None),
+            ["", "  a ", " ", "b", "  c  ", ""]
+        );
+        assert_eq!(
+            spaced(&texts, Some(0), Some(1)),
+            ["", "a ", "", "b ", "c", ""]
+        );
+        assert_eq!(
+            spaced(&["<span/>", "  "], Some(1), Some(1)),
+            ["<span/>", "  "]
+        );
+    }
+}
@@ -22,10 +22,19 @@ impl<const ALLOW_ONCE: bool> Seconds<ALLOW_ONCE> {
         Self(Duration::from_secs(value))
     }
 
-    pub fn timer(self) -> tokio::time::Interval {
-        let mut timer = tokio::time::interval_at(tokio::time::Instant::now() + self.0, self.0);
-        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
-        timer
+    pub fn timer(self) -> Timer {
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + self.0, self.0);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        Timer {
+            interval,
+            ticked: false,
+        }
+    }
+
+    /// Sleeps for this duration, and then for as long as the block is paused
+    pub async fn sleep(self) {
+        tokio::time::sleep(self.0).await;
+        let _ = crate::blocks::wait_while_paused().await;
     }
 
     pub fn seconds(self) -> u64 {
@@ -33,6 +42,34 @@ impl<const ALLOW_ONCE: bool> Seconds<ALLOW_ONCE> {
     }
 }
 
+/// An interval whose ticks are held back while the block is paused by the `pause_updates` action
+#[derive(Debug)]
+pub struct Timer {
+    interval: tokio::time::Interval,
+    /// Whether the interval has ticked, but the tick is held back, so that it is not lost if
+    /// [`Timer::tick`] is cancelled
+    ticked: bool,
+}
+
+impl Timer {
+    pub async fn tick(&mut self) {
+        if !self.ticked {
+            self.interval.tick().await;
+            self.ticked = true;
+        }
+        if crate::blocks::wait_while_paused().await {
+            // Count the next interval from the update after resuming
+            self.interval.reset();
+        }
+        self.ticked = false;
+    }
+
+    pub fn reset(&mut self) {
+        self.interval.reset();
+        self.ticked = false;
+    }
+}
+
 impl<'de, const ALLOW_ONCE: bool> Deserialize<'de> for Seconds<ALLOW_ONCE> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where